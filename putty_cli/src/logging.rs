@@ -1,9 +1,18 @@
+use env_logger::WriteStyle;
 use log::LevelFilter;
 
 /// Initialize logging using env_logger.
 /// By default, this reads the RUST_LOG environment variable for filtering.
+/// Log lines are colored only on a terminal and never when `NO_COLOR` is set
+/// to a non-empty value (<https://no-color.org>).
 pub fn init_logging() {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
     env_logger::Builder::from_default_env()
         .filter(None, LevelFilter::Info)
+        .write_style(if no_color {
+            WriteStyle::Never
+        } else {
+            WriteStyle::Auto
+        })
         .init();
 }
@@ -1,15 +1,22 @@
 mod logging;
+mod runtime;
 mod ui;
 
 use crate::logging::init_logging;
 use crate::ui::cli;
 use clap::Parser;
 
-#[tokio::main]
-async fn main() {
+fn main() {
     init_logging();
     let args = cli::Args::parse();
-    if let Err(e) = cli::run_cli(args).await {
+    let runtime = match args.runtime.build() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("CLI error: cannot start the async runtime: {e}");
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = runtime.block_on(cli::run_cli(args)) {
         eprintln!("CLI error: {e:?}");
         std::process::exit(1);
     }
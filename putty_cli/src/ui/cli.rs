@@ -1,84 +1,352 @@
+#[cfg(feature = "storage")]
+use clap::ValueEnum;
 use clap::{Parser, Subcommand};
-#[cfg(any(feature = "serial", feature = "ssh"))]
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
-#[cfg(any(feature = "serial", feature = "ssh"))]
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
 use log::info;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use log::warn;
 use putty_core::connections::errors::ConnectionError;
 #[cfg(feature = "serial")]
-use putty_core::connections::serial::SerialConnection;
+use putty_core::connections::serial::{
+    list_ports, InitString, ResetSequence, SerialConnection, SerialPortInfo,
+};
 #[cfg(feature = "ssh")]
-use putty_core::connections::ssh::SshConnection;
-#[cfg(any(feature = "serial", feature = "ssh"))]
+use putty_core::connections::ssh::{SshConnection, SshProbe, X11Display};
+#[cfg(feature = "telnet")]
+use putty_core::connections::telnet::{TelnetConnection, TELNET_PORT};
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
 use putty_core::connections::Connection;
-#[cfg(any(feature = "serial", feature = "ssh"))]
+#[cfg(feature = "serial")]
+use putty_core::connections::{Baud, DataBits, FlowControl, Framing, Parity, StopBits};
+#[cfg(feature = "ssh")]
+use putty_core::connections::{LocalForward, RemoteForward};
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
 use putty_core::core::connection_manager::ConnectionManager;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use putty_core::utils::escape::unescape;
+#[cfg(feature = "serial")]
+use putty_core::utils::hex::{parse_hex, to_hex};
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use putty_core::ConnectionEventKind;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use putty_core::ConnectionOptions;
+#[cfg(feature = "serial")]
+use putty_core::KeepAliveAction;
+#[cfg(feature = "serial")]
+use putty_core::SubscriptionItem;
 #[cfg(feature = "storage")]
 use putty_storage::{Profile, ProfileStore};
-#[cfg(any(feature = "serial", feature = "ssh"))]
-use std::io::{stdout, Write};
-#[cfg(any(feature = "serial", feature = "ssh"))]
-use tokio::io::{self, AsyncReadExt};
-
-/// Enable raw mode via crossterm, throwing an error if it fails.
-/// This disables line-buffering and echo on all supported platforms.
-#[cfg(any(feature = "serial", feature = "ssh"))]
-fn set_raw_mode() -> Result<(), ConnectionError> {
-    enable_raw_mode().map_err(|e| ConnectionError::Other(format!("Failed to enable raw mode: {e}")))
-}
-
-/// Restore normal terminal mode.
-/// crossterm internally remembers the previous mode and restores it.
-#[cfg(any(feature = "serial", feature = "ssh"))]
-fn restore_mode() {
-    let _ = disable_raw_mode();
-}
-
-#[cfg(all(feature = "serial", feature = "ssh", feature = "storage"))]
-const CLI_ABOUT: &str = "Terminal client with serial, SSH, and saved profile support";
-#[cfg(all(feature = "serial", feature = "ssh", not(feature = "storage")))]
-const CLI_ABOUT: &str = "Terminal client with serial and SSH support";
-#[cfg(all(feature = "serial", not(feature = "ssh"), feature = "storage"))]
-const CLI_ABOUT: &str = "Terminal client with serial and saved profile support";
-#[cfg(all(feature = "serial", not(feature = "ssh"), not(feature = "storage")))]
-const CLI_ABOUT: &str = "Terminal client with serial support";
-#[cfg(all(not(feature = "serial"), feature = "ssh", feature = "storage"))]
-const CLI_ABOUT: &str = "Terminal client with SSH and saved profile support";
-#[cfg(all(not(feature = "serial"), feature = "ssh", not(feature = "storage")))]
-const CLI_ABOUT: &str = "Terminal client with SSH support";
-#[cfg(all(not(feature = "serial"), not(feature = "ssh"), feature = "storage"))]
-const CLI_ABOUT: &str = "Terminal client with saved profile support";
+
+use crate::runtime::RuntimeArgs;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use crate::ui::echo::LocalEcho;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use crate::ui::keys::EscapeKeys;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use crate::ui::terminal::{run_session, Render, Terminal};
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use putty_core::ConnectionEvent;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use putty_core::{LogFormat, LogRotation, SessionLogger};
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use std::io::stdout;
+#[cfg(any(
+    feature = "serial",
+    feature = "ssh",
+    feature = "telnet",
+    feature = "storage"
+))]
+use std::path::PathBuf;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use std::time::Duration;
+#[cfg(feature = "ssh")]
+use tokio::sync::broadcast::error::RecvError;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use tokio::sync::broadcast::{self, error::TryRecvError};
 #[cfg(all(
-    not(feature = "serial"),
-    not(feature = "ssh"),
-    not(feature = "storage")
+    feature = "storage",
+    any(feature = "serial", feature = "ssh", feature = "telnet")
 ))]
-const CLI_ABOUT: &str = "Terminal client";
+use tokio::sync::oneshot;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+use tokio::task::JoinHandle;
+
+/// The `--help` summary, naming what this build was compiled with.
+fn cli_about() -> String {
+    let parts: Vec<&str> = [
+        (cfg!(feature = "serial"), "serial"),
+        (cfg!(feature = "ssh"), "SSH"),
+        (cfg!(feature = "telnet"), "Telnet"),
+        (cfg!(feature = "storage"), "saved profile"),
+    ]
+    .into_iter()
+    .filter_map(|(enabled, part)| enabled.then_some(part))
+    .collect();
+    match parts.as_slice() {
+        [] => "Terminal client".into(),
+        [only] => format!("Terminal client with {only} support"),
+        [first, second] => format!("Terminal client with {first} and {second} support"),
+        [rest @ .., last] => format!(
+            "Terminal client with {}, and {last} support",
+            rest.join(", ")
+        ),
+    }
+}
+
+/// Chunks the terminal or a session log may fall behind the connection.
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+const SESSION_FAN_OUT_CAPACITY: usize = 256;
 
 /// Command-line arguments.
 #[derive(Parser, Debug)]
 #[command(
     name = "putty-rs",
     version,
-    about = CLI_ABOUT,
+    about = cli_about(),
     long_about = None,
     subcommand_required = true
 )]
 pub struct Args {
     #[command(subcommand)]
     pub protocol: Protocol,
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+    #[command(flatten)]
+    pub session: SessionArgs,
+    #[command(flatten)]
+    pub runtime: RuntimeArgs,
+}
+
+/// One `--log` file and the format written to it.
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogTarget {
+    /// `None` falls back to `--log-format` or `--strip-ansi`.
+    pub format: Option<LogFormat>,
+    pub path: PathBuf,
+}
+
+/// `timestamped:session.txt` or just `session.log`. A prefix that is not a
+/// format name is kept as part of the path.
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+fn parse_log_target(s: &str) -> Result<LogTarget, String> {
+    if let Some((prefix, path)) = s.split_once(':') {
+        if let Ok(format) = prefix.parse::<LogFormat>() {
+            if path.is_empty() {
+                return Err(format!("missing path after {prefix}:"));
+            }
+            return Ok(LogTarget {
+                format: Some(format),
+                path: path.into(),
+            });
+        }
+    }
+    Ok(LogTarget {
+        format: None,
+        path: s.into(),
+    })
+}
+
+/// One ASCII key, either as itself (`q`) or in caret notation (`^B` for
+/// Ctrl+B).
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+fn parse_escape_key(s: &str) -> Result<char, String> {
+    let invalid = || format!("expected one ASCII key such as q or ^B, got {s:?}");
+    let mut chars = s.chars();
+    let key = match (chars.next(), chars.next(), chars.next()) {
+        (Some(key), None, _) => key,
+        (Some('^'), Some(letter @ ('@'..='_' | 'a'..='z')), None) => {
+            char::from(letter.to_ascii_uppercase() as u8 - b'@')
+        }
+        _ => return Err(invalid()),
+    };
+    if key.is_ascii() {
+        Ok(key)
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Character format and flow control of a serial port, 8N1 without flow
+/// control unless given.
+#[cfg(feature = "serial")]
+#[derive(clap::Args, Debug, Clone, Copy, Default)]
+pub struct FramingArgs {
+    /// Data bits per character (5 to 8)
+    #[arg(long, default_value_t = DataBits::Eight)]
+    pub data_bits: DataBits,
+    /// Parity bit (none, odd or even)
+    #[arg(long, default_value_t = Parity::None)]
+    pub parity: Parity,
+    /// Stop bits (1 or 2)
+    #[arg(long, default_value_t = StopBits::One)]
+    pub stop_bits: StopBits,
+    /// Flow control (none, software or hardware)
+    #[arg(long, default_value_t = FlowControl::None)]
+    pub flow_control: FlowControl,
+}
+
+#[cfg(feature = "serial")]
+impl From<FramingArgs> for Framing {
+    fn from(args: FramingArgs) -> Self {
+        Framing {
+            data_bits: args.data_bits,
+            parity: args.parity,
+            stop_bits: args.stop_bits,
+            flow_control: args.flow_control,
+        }
+    }
+}
+
+/// Settings of the interactive terminal session, shared by every protocol.
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct SessionArgs {
+    /// Local echo of typed characters
+    #[arg(long, value_enum, global = true, default_value_t = LocalEcho::Off)]
+    pub local_echo: LocalEcho,
+    /// Append the session output to this file. Repeat to write several logs;
+    /// prefix with raw:, plain:, timestamped: or asciinema: to pick each
+    /// file's format
+    #[arg(long, global = true, value_name = "[FORMAT:]PATH", value_parser = parse_log_target)]
+    pub log: Vec<LogTarget>,
+    /// Format of log files without a format prefix: raw, plain, timestamped
+    /// or asciinema (a .cast recording)
+    #[arg(long, global = true, value_name = "FORMAT", requires = "log")]
+    pub log_format: Option<LogFormat>,
+    /// Remove ANSI escape sequences from log files without a format prefix
+    /// (the terminal keeps them). Same as --log-format plain
+    #[arg(long, global = true, requires = "log", conflicts_with = "log_format")]
+    pub strip_ansi: bool,
+    /// Start a new log file once the current one would exceed this many bytes
+    #[arg(long, global = true, value_name = "BYTES", requires = "log")]
+    pub log_rotate_size: Option<u64>,
+    /// Number of rotated log files to keep (session.log.1, .2, ...)
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        default_value_t = 5,
+        requires = "log_rotate_size"
+    )]
+    pub log_keep: usize,
+    /// Draw output only in complete lines, for line-oriented devices whose
+    /// partial updates flicker. Not meant for interactive shells
+    #[arg(long, global = true)]
+    pub flush_on_newline: bool,
+    /// With --flush-on-newline, draw a partial line (e.g. a prompt) after it
+    /// has waited this long
+    #[arg(
+        long,
+        global = true,
+        value_name = "MS",
+        default_value_t = 100,
+        requires = "flush_on_newline"
+    )]
+    pub flush_timeout_ms: u64,
+    /// Disconnect after this many seconds, however busy the session is.
+    /// Overrides the limit saved in a profile
+    #[arg(long, global = true, value_name = "SECS")]
+    pub max_session_secs: Option<u64>,
+    /// Show this line when the session starts, e.g. '=== rack1 ===\r\n'.
+    /// Only displayed, never sent. Overrides the banner saved in a profile
+    #[arg(long, global = true, value_name = "STRING")]
+    pub banner: Option<String>,
+    /// Key that starts the exit sequence, e.g. ^B for Ctrl+B [default: ^A].
+    /// Overrides the key saved in a profile
+    #[arg(long, global = true, value_name = "KEY", value_parser = parse_escape_key)]
+    pub escape_char: Option<char>,
+    /// Key that ends the session after --escape-char [default: x].
+    /// Overrides the key saved in a profile
+    #[arg(long, global = true, value_name = "KEY", value_parser = parse_escape_key)]
+    pub escape_exit: Option<char>,
+    /// Treat the connection as a data pipe (firmware, sensor streams): bytes
+    /// are printed and logged exactly as received, and piped stdin is sent
+    /// untouched, without watching for the exit sequence
+    #[arg(
+        long,
+        global = true,
+        conflicts_with_all = ["strip_ansi", "log_format", "flush_on_newline", "banner"]
+    )]
+    pub binary: bool,
+}
+
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+impl SessionArgs {
+    /// Clap can only refuse `--log-format` with `--binary`; a format prefix
+    /// on a `--log` target is checked here, as binary logs must stay raw.
+    fn check_binary_logs(&self) -> Result<(), ConnectionError> {
+        if !self.binary {
+            return Ok(());
+        }
+        match self
+            .log
+            .iter()
+            .find(|target| target.format.is_some_and(|format| format != LogFormat::Raw))
+        {
+            Some(target) => Err(ConnectionError::Other(format!(
+                "--binary logs exactly as received; --log {} asks for another format",
+                target.path.display()
+            ))),
+            None => Ok(()),
+        }
+    }
 }
 
 #[derive(Subcommand, Debug)]
 pub enum Protocol {
     #[cfg(feature = "serial")]
     /// Open an interactive serial terminal session
+    #[command(args_conflicts_with_subcommands = true)]
     Serial {
+        #[command(subcommand)]
+        action: Option<SerialAction>,
         /// Serial device path, for example /dev/ttyUSB0
         #[arg(long, default_value = "/dev/pts/3")]
         port: String,
         /// Serial baud rate
-        #[arg(long, default_value_t = 115200)]
+        #[arg(long, default_value_t = 115200, value_parser = parse_baud)]
         baud: u32,
+        #[command(flatten)]
+        framing: FramingArgs,
+        /// Delay in milliseconds inserted between transmitted characters
+        #[arg(long, default_value_t = 0)]
+        char_delay_ms: u64,
+        /// Warn if the first bytes received look like noise from a wrong baud rate
+        #[arg(long)]
+        detect_baud_mismatch: bool,
+        /// Re-assert DTR/RTS after this many idle milliseconds, for devices
+        /// that reset when the host goes quiet
+        #[arg(long, value_name = "MS")]
+        keepalive_ms: Option<u64>,
+        /// With --keepalive-ms, send this byte (hex, e.g. 00) instead of
+        /// touching the control lines
+        #[arg(long, value_name = "HEX", requires = "keepalive_ms", value_parser = parse_hex_byte)]
+        keepalive_byte: Option<u8>,
+        /// DTR/RTS reset sequence to run after opening the port
+        /// (esp32, arduino or none)
+        #[arg(long, default_value_t = ResetSequence::None)]
+        reset: ResetSequence,
+        /// Send this string after opening the port, e.g. 'ATZ\r'
+        /// (escapes: \r \n \t \0 \\ \xNN)
+        #[arg(long, value_name = "STRING")]
+        init: Option<String>,
+        /// Wait this long after opening the port before sending --init
+        #[arg(long, default_value_t = 0, requires = "init")]
+        init_delay_ms: u64,
+        /// Fail unless the device answers --init with this string (e.g. OK)
+        #[arg(long, value_name = "STRING", requires = "init")]
+        init_expect: Option<String>,
+        /// Write these bytes once instead of starting a session, e.g.
+        /// "AA BB 01 FF", and print the reply as hex
+        #[arg(long, value_name = "HEX")]
+        send_hex: Option<String>,
+        /// How long to wait for a reply to --send-hex
+        #[arg(long, default_value_t = 1000, requires = "send_hex")]
+        reply_timeout_ms: u64,
+        /// Save these settings as a profile once the connection is up
+        #[cfg(feature = "storage")]
+        #[arg(long, value_name = "NAME")]
+        save_as: Option<String>,
     },
     #[cfg(feature = "ssh")]
     /// Open an interactive SSH terminal session
@@ -90,11 +358,59 @@ pub enum Protocol {
         #[arg(long, default_value_t = 22)]
         port: u16,
         /// Username for SSH authentication
-        #[arg(long)]
-        username: String,
+        #[arg(long, required_unless_present = "probe")]
+        username: Option<String>,
         /// Password for SSH authentication
         #[arg(long, default_value = "")]
         password: String,
+        /// Only connect if the server's host key has this SHA-256
+        /// fingerprint, as printed by `--probe` or `ssh-keygen -lf`
+        #[arg(long, value_name = "SHA256:...")]
+        host_key: Option<String>,
+        /// Forward X11 to the local display named by $DISPLAY, so remote GUI
+        /// programs can open windows here
+        #[arg(long, conflicts_with = "probe")]
+        x11: bool,
+        /// Tunnel a local port through the server, like `ssh -L`, e.g.
+        /// 8080:internal:80; IPv6 addresses go in brackets. May be given
+        /// several times
+        #[arg(
+            long,
+            value_name = "[BIND:]PORT:HOST:HOSTPORT",
+            conflicts_with = "probe"
+        )]
+        local_forward: Vec<LocalForward>,
+        /// Let the server side reach a local port, like `ssh -R`, e.g.
+        /// 9000:localhost:3000; IPv6 addresses go in brackets. May be given
+        /// several times
+        #[arg(
+            long,
+            value_name = "[BIND:]PORT:HOST:HOSTPORT",
+            conflicts_with = "probe"
+        )]
+        remote_forward: Vec<RemoteForward>,
+        /// Only run the handshake and print the server's host key and
+        /// negotiated algorithms, without authenticating
+        #[arg(long)]
+        probe: bool,
+        /// Save these settings as a profile once the connection is up
+        #[cfg(feature = "storage")]
+        #[arg(long, value_name = "NAME", conflicts_with = "probe")]
+        save_as: Option<String>,
+    },
+    #[cfg(feature = "telnet")]
+    /// Open an interactive Telnet session, e.g. to older lab equipment
+    Telnet {
+        /// Telnet server host name or IP address
+        #[arg(long)]
+        host: String,
+        /// Telnet server port
+        #[arg(long, default_value_t = TELNET_PORT)]
+        port: u16,
+        /// Save these settings as a profile once the connection is up
+        #[cfg(feature = "storage")]
+        #[arg(long, value_name = "NAME")]
+        save_as: Option<String>,
     },
     #[cfg(feature = "storage")]
     /// Manage saved connection presets
@@ -102,26 +418,92 @@ pub enum Protocol {
         #[command(subcommand)]
         action: StorageAction,
     },
+    #[cfg(feature = "storage")]
+    /// Open the default profile (see `storage set-default`)
+    Connect,
+}
+
+/// Accept only baud rates `putty_core::connections::Baud` considers valid.
+#[cfg(feature = "serial")]
+fn parse_baud(s: &str) -> Result<u32, String> {
+    s.parse::<Baud>()
+        .map(u32::from)
+        .map_err(|_| format!("expected a baud rate in {}..={}", Baud::MIN, Baud::MAX))
+}
+
+/// One byte in hex, with or without a `0x` prefix.
+#[cfg(feature = "serial")]
+fn parse_hex_byte(s: &str) -> Result<u8, String> {
+    let digits = s.strip_prefix("0x").unwrap_or(s);
+    u8::from_str_radix(digits, 16).map_err(|_| format!("expected one byte in hex, got {s:?}"))
+}
+
+/// Output format of `putty_rs storage list`.
+#[cfg(feature = "storage")]
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ListFormat {
+    /// One debug-formatted profile per line
+    #[default]
+    Human,
+    /// A JSON array of profiles, for scripts
+    Json,
+}
+
+/// Actions in `putty_rs serial <action>`, instead of opening a session
+#[cfg(feature = "serial")]
+#[derive(Subcommand, Debug)]
+pub enum SerialAction {
+    /// List the serial ports of this machine, with USB vendor/product ids
+    List,
 }
 
 /// Actions in `putty_rs storage <action>`
 #[cfg(feature = "storage")]
 #[derive(Subcommand, Debug)]
+// Parsed once from the command line, so the size of `SaveSsh` does not matter.
+#[allow(clippy::large_enum_variant)]
 pub enum StorageAction {
     /// List saved profiles
-    List,
+    List {
+        /// Output format
+        #[arg(long, value_enum, default_value_t = ListFormat::Human)]
+        format: ListFormat,
+        /// Only list the profiles of this group
+        #[arg(long)]
+        group: Option<String>,
+    },
     #[cfg(feature = "serial")]
     /// Save a serial profile
     SaveSerial {
         /// Profile name
         #[arg(long)]
         name: String,
+        /// Group to file the profile under, e.g. prod
+        #[arg(long)]
+        group: Option<String>,
         /// Serial device path
         #[arg(long, default_value = "/dev/pts/3")]
         port: String,
         /// Serial baud rate
-        #[arg(long, default_value_t = 115200)]
+        #[arg(long, default_value_t = 115200, value_parser = parse_baud)]
         baud: u32,
+        #[command(flatten)]
+        framing: FramingArgs,
+        /// String sent after opening the port, e.g. 'ATZ\r'
+        #[arg(long, value_name = "STRING")]
+        init: Option<String>,
+        /// Disconnect sessions after this many seconds
+        #[arg(long, value_name = "SECS")]
+        max_session_secs: Option<u64>,
+        /// Line shown when a session starts, e.g. '=== rack1 ===\r\n'
+        #[arg(long, value_name = "STRING")]
+        banner: Option<String>,
+        /// Key that starts the exit sequence, e.g. ^B
+        #[arg(long, value_name = "KEY", value_parser = parse_escape_key)]
+        escape_char: Option<char>,
+        /// Key that ends the session after --escape-char
+        #[arg(long, value_name = "KEY", value_parser = parse_escape_key)]
+        escape_exit: Option<char>,
     },
     #[cfg(feature = "ssh")]
     /// Save an SSH profile
@@ -129,6 +511,9 @@ pub enum StorageAction {
         /// Profile name
         #[arg(long)]
         name: String,
+        /// Group to file the profile under, e.g. prod
+        #[arg(long)]
+        group: Option<String>,
         /// SSH server host name or IP address
         #[arg(long)]
         host: String,
@@ -141,29 +526,242 @@ pub enum StorageAction {
         /// Password for SSH authentication
         #[arg(long, default_value = "")]
         password: String,
+        /// Private key to log in with instead of the password
+        #[arg(long, value_name = "PATH")]
+        key: Option<PathBuf>,
+        /// Passphrase of the private key given with --key
+        #[arg(long, requires = "key")]
+        key_passphrase: Option<String>,
+        /// Pinned SHA-256 host-key fingerprint
+        #[arg(long, value_name = "SHA256:...")]
+        host_key: Option<String>,
+        /// Disconnect sessions after this many seconds
+        #[arg(long, value_name = "SECS")]
+        max_session_secs: Option<u64>,
+        /// Line shown when a session starts, e.g. '=== rack1 ===\r\n'
+        #[arg(long, value_name = "STRING")]
+        banner: Option<String>,
+        /// Key that starts the exit sequence, e.g. ^B
+        #[arg(long, value_name = "KEY", value_parser = parse_escape_key)]
+        escape_char: Option<char>,
+        /// Key that ends the session after --escape-char
+        #[arg(long, value_name = "KEY", value_parser = parse_escape_key)]
+        escape_exit: Option<char>,
+    },
+    #[cfg(feature = "telnet")]
+    /// Save a Telnet profile
+    SaveTelnet {
+        /// Profile name
+        #[arg(long)]
+        name: String,
+        /// Group to file the profile under, e.g. prod
+        #[arg(long)]
+        group: Option<String>,
+        /// Telnet server host name or IP address
+        #[arg(long)]
+        host: String,
+        /// Telnet server port
+        #[arg(long, default_value_t = TELNET_PORT)]
+        port: u16,
+        /// Disconnect sessions after this many seconds
+        #[arg(long, value_name = "SECS")]
+        max_session_secs: Option<u64>,
+        /// Line shown when a session starts, e.g. '=== rack1 ===\r\n'
+        #[arg(long, value_name = "STRING")]
+        banner: Option<String>,
+        /// Key that starts the exit sequence, e.g. ^B
+        #[arg(long, value_name = "KEY", value_parser = parse_escape_key)]
+        escape_char: Option<char>,
+        /// Key that ends the session after --escape-char
+        #[arg(long, value_name = "KEY", value_parser = parse_escape_key)]
+        escape_exit: Option<char>,
     },
     /// Delete a saved profile
     Delete {
-        /// Profile name
+        /// Profile name, as group/name for a profile in a group
         #[arg(long)]
         name: String,
     },
     /// Open a saved profile by name
     UseProfile {
-        /// Profile name
+        /// Profile name, as group/name for a profile in a group
         #[arg(long)]
         profile: String,
     },
+    /// Make a saved profile the one opened by `putty-rs connect`
+    SetDefault {
+        /// Profile name, as group/name for a profile in a group
+        #[arg(long)]
+        name: String,
+    },
+    /// Forget the default profile
+    ClearDefault,
+    /// Write every profile into one bundle file
+    Export {
+        /// Bundle file to write
+        #[arg(long)]
+        file: PathBuf,
+    },
+    /// Add the profiles of a bundle file written by `storage export`
+    Import {
+        /// Bundle file to read
+        #[arg(long)]
+        file: PathBuf,
+        /// Replace profiles that already exist instead of skipping them
+        #[arg(long)]
+        overwrite: bool,
+    },
 }
 
 pub async fn run_cli(args: Args) -> Result<(), ConnectionError> {
-    #[cfg(any(feature = "serial", feature = "ssh"))]
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
     let connection_manager = ConnectionManager::new();
+    #[cfg(all(
+        feature = "storage",
+        any(feature = "serial", feature = "ssh", feature = "telnet")
+    ))]
+    let save = match session_profile(&args.protocol, &args.session) {
+        Some(profile) => {
+            let store =
+                ProfileStore::from_env().map_err(|e| ConnectionError::Other(e.to_string()))?;
+            Some(spawn_save_on_open(&connection_manager, store, profile))
+        }
+        None => None,
+    };
+
+    let result = run_protocol(
+        args,
+        #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+        &connection_manager,
+    )
+    .await;
+
+    #[cfg(all(
+        feature = "storage",
+        any(feature = "serial", feature = "ssh", feature = "telnet")
+    ))]
+    if let Some((session_over, task)) = save {
+        drop(session_over);
+        let saved = task
+            .await
+            .map_err(|e| ConnectionError::Other(e.to_string()))
+            .and_then(|saved| saved);
+        return result.and(saved);
+    }
+    result
+}
+
+/// Save the `--save-as` profile as soon as the connection opens, so that
+/// it is kept even if the session ends in an error, and not saved if the
+/// connection never came up. Dropping the returned sender tells the task
+/// that the session is over; an open already reported is still saved.
+#[cfg(all(
+    feature = "storage",
+    any(feature = "serial", feature = "ssh", feature = "telnet")
+))]
+fn spawn_save_on_open(
+    connection_manager: &ConnectionManager,
+    store: ProfileStore,
+    profile: Profile,
+) -> (oneshot::Sender<()>, JoinHandle<Result<(), ConnectionError>>) {
+    let mut events = connection_manager.events();
+    let (session_over_tx, mut session_over) = oneshot::channel();
+    let task = tokio::spawn(async move {
+        let opened = loop {
+            // Biased so that events already sent are seen before the end
+            // of the session is.
+            tokio::select! {
+                biased;
+                event = events.recv() => match event {
+                    Ok(event) if matches!(event.kind, ConnectionEventKind::Opened(_)) => {
+                        break true
+                    }
+                    Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+                    Err(broadcast::error::RecvError::Closed) => break false,
+                },
+                _ = &mut session_over => break false,
+            }
+        };
+        if opened {
+            store.save(&profile)?;
+            info!("Saved profile '{}'", profile.qualified_name());
+        }
+        Ok(())
+    });
+    (session_over_tx, task)
+}
+
+async fn run_protocol(
+    args: Args,
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+    connection_manager: &ConnectionManager,
+) -> Result<(), ConnectionError> {
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+    let session = &args.session;
 
     match args.protocol {
         #[cfg(feature = "serial")]
-        Protocol::Serial { port, baud } => {
-            run_serial_protocol(port, baud, &connection_manager).await?;
+        Protocol::Serial {
+            action: Some(SerialAction::List),
+            ..
+        } => {
+            let ports = list_ports().map_err(port_list_error)?;
+            if ports.is_empty() {
+                eprintln!("No serial ports found");
+            }
+            print!("{}", render_ports(&ports));
+        }
+        #[cfg(feature = "serial")]
+        Protocol::Serial {
+            port,
+            baud,
+            framing,
+            char_delay_ms,
+            detect_baud_mismatch,
+            keepalive_ms,
+            keepalive_byte,
+            reset,
+            init,
+            init_delay_ms,
+            init_expect,
+            send_hex,
+            reply_timeout_ms,
+            ..
+        } => {
+            let mut options = ConnectionOptions::new()
+                .with_char_delay(Duration::from_millis(char_delay_ms))
+                .with_baud_mismatch_detection(detect_baud_mismatch);
+            if let Some(ms) = keepalive_ms {
+                let action = match keepalive_byte {
+                    Some(byte) => KeepAliveAction::SendByte(byte),
+                    None => KeepAliveAction::AssertLines,
+                };
+                options = options.with_line_keepalive(Duration::from_millis(ms), action);
+            }
+            let init = match init {
+                Some(text) => {
+                    let mut init = InitString::from_escaped(&text)?
+                        .with_delay(Duration::from_millis(init_delay_ms));
+                    if let Some(expect) = init_expect {
+                        let timeout = init.timeout;
+                        init = init.expecting(unescape(&expect)?, timeout);
+                    }
+                    Some(init)
+                }
+                None => None,
+            };
+            info!("Opening serial port: {port} at {baud} baud");
+            let conn = serial_connection(&port, baud, framing.into(), reset, init);
+            match send_hex {
+                Some(hex) => {
+                    let bytes = parse_hex(&hex)?;
+                    let reply_timeout = Duration::from_millis(reply_timeout_ms);
+                    send_once(connection_manager, port, conn, &bytes, reply_timeout).await?;
+                }
+                None => {
+                    run_cli_loop(connection_manager, port, Box::new(conn), options, session).await?
+                }
+            }
         }
         #[cfg(feature = "ssh")]
         Protocol::Ssh {
@@ -171,55 +769,59 @@ pub async fn run_cli(args: Args) -> Result<(), ConnectionError> {
             port,
             username,
             password,
+            host_key,
+            x11,
+            local_forward,
+            remote_forward,
+            probe,
+            ..
         } => {
-            run_ssh_protocol(host, port, username, password, &connection_manager).await?;
+            if probe {
+                print_ssh_probe(&SshConnection::probe(&host, port).await?);
+            } else {
+                let username = username.unwrap_or_default();
+                let mut conn = ssh_connection(&host, port, username, password, None, host_key);
+                if x11 {
+                    conn = conn.with_x11_forwarding(X11Display::from_env()?);
+                }
+                for forward in remote_forward {
+                    conn = conn.with_remote_forward(forward);
+                }
+                run_ssh_protocol(host, conn, &local_forward, session, connection_manager).await?;
+            }
+        }
+        #[cfg(feature = "telnet")]
+        Protocol::Telnet { host, port, .. } => {
+            info!("Connecting to Telnet server {host}:{port}");
+            let conn = TelnetConnection::new(host.clone(), port);
+            run_cli_loop(
+                connection_manager,
+                host,
+                Box::new(conn),
+                ConnectionOptions::default(),
+                session,
+            )
+            .await?;
         }
         #[cfg(feature = "storage")]
         Protocol::Storage { action } => match action {
             // open by profile name
             StorageAction::UseProfile { profile } => {
                 let store =
-                    ProfileStore::new().map_err(|e| ConnectionError::Other(e.to_string()))?;
-                let preset = store
-                    .list()?
-                    .into_iter()
-                    .find(|p| p.name() == profile)
-                    .ok_or_else(|| {
-                        ConnectionError::Other(format!("preset not found: {profile}"))
-                    })?;
-
-                match preset {
-                    #[cfg(feature = "serial")]
-                    Profile::Serial { port, baud, .. } => {
-                        run_serial_protocol(port, baud, &connection_manager).await?
-                    }
-                    #[cfg(not(feature = "serial"))]
-                    Profile::Serial { .. } => {
-                        return Err(ConnectionError::Other(
-                            "This CLI was built without serial support".into(),
-                        ));
-                    }
-                    #[cfg(feature = "ssh")]
-                    Profile::Ssh {
-                        host,
-                        port,
-                        username,
-                        password,
-                        ..
-                    } => {
-                        run_ssh_protocol(host, port, username, password, &connection_manager)
-                            .await?
-                    }
-                    #[cfg(not(feature = "ssh"))]
-                    Profile::Ssh { .. } => {
-                        return Err(ConnectionError::Other(
-                            "This CLI was built without SSH support".into(),
-                        ));
-                    }
-                }
+                    ProfileStore::from_env().map_err(|e| ConnectionError::Other(e.to_string()))?;
+                let profile = find_profile(&store, &profile)?;
+                run_profile(
+                    &store,
+                    &profile,
+                    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+                    session,
+                    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+                    connection_manager,
+                )
+                .await?;
             }
 
-            StorageAction::List => {
+            StorageAction::List { .. } => {
                 handle_storage_cmd(action).await?;
             }
             #[cfg(feature = "serial")]
@@ -230,36 +832,487 @@ pub async fn run_cli(args: Args) -> Result<(), ConnectionError> {
             StorageAction::SaveSsh { .. } => {
                 handle_storage_cmd(action).await?;
             }
-            StorageAction::Delete { .. } => {
+            #[cfg(feature = "telnet")]
+            StorageAction::SaveTelnet { .. } => {
+                handle_storage_cmd(action).await?;
+            }
+            StorageAction::Delete { .. }
+            | StorageAction::SetDefault { .. }
+            | StorageAction::ClearDefault
+            | StorageAction::Export { .. }
+            | StorageAction::Import { .. } => {
                 handle_storage_cmd(action).await?;
             }
         },
+        #[cfg(feature = "storage")]
+        Protocol::Connect => {
+            let store =
+                ProfileStore::from_env().map_err(|e| ConnectionError::Other(e.to_string()))?;
+            let profile = store.default_profile()?.ok_or_else(|| {
+                ConnectionError::Other(
+                    "No default profile set. Choose one with `putty-rs storage set-default --name <profile>`".into(),
+                )
+            })?;
+            run_profile(
+                &store,
+                &profile,
+                #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+                session,
+                #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+                connection_manager,
+            )
+            .await?;
+        }
     }
+
     Ok(())
 }
 
+/// The profile requested with `--save-as`, built from the session's flags.
+#[cfg(all(
+    feature = "storage",
+    any(feature = "serial", feature = "ssh", feature = "telnet")
+))]
+fn session_profile(protocol: &Protocol, session: &SessionArgs) -> Option<Profile> {
+    match protocol {
+        #[cfg(feature = "serial")]
+        Protocol::Serial {
+            port,
+            baud,
+            framing,
+            init,
+            save_as: Some(name),
+            ..
+        } => Some(Profile::Serial {
+            name: saved_name(name),
+            group: saved_group(name),
+            port: port.clone(),
+            baud: *baud,
+            data_bits: framing.data_bits,
+            parity: framing.parity,
+            stop_bits: framing.stop_bits,
+            flow_control: framing.flow_control,
+            init_string: init.clone(),
+            max_session_secs: session.max_session_secs,
+            banner: session.banner.clone(),
+            escape_char: session.escape_char,
+            escape_exit: session.escape_exit,
+        }),
+        #[cfg(feature = "ssh")]
+        Protocol::Ssh {
+            host,
+            port,
+            username,
+            password,
+            host_key,
+            save_as: Some(name),
+            ..
+        } => Some(Profile::Ssh {
+            name: saved_name(name),
+            group: saved_group(name),
+            host: host.clone(),
+            port: *port,
+            username: username.clone().unwrap_or_default(),
+            password: password.clone(),
+            keyring_id: None,
+            key_path: None,
+            passphrase: None,
+            expected_host_key: host_key.clone(),
+            max_session_secs: session.max_session_secs,
+            banner: session.banner.clone(),
+            escape_char: session.escape_char,
+            escape_exit: session.escape_exit,
+        }),
+        #[cfg(feature = "telnet")]
+        Protocol::Telnet {
+            host,
+            port,
+            save_as: Some(name),
+        } => Some(Profile::Telnet {
+            name: saved_name(name),
+            group: saved_group(name),
+            host: host.clone(),
+            port: *port,
+            max_session_secs: session.max_session_secs,
+            banner: session.banner.clone(),
+            escape_char: session.escape_char,
+            escape_exit: session.escape_exit,
+        }),
+        _ => None,
+    }
+}
+
+/// The name part of `--save-as group/name`, or all of `--save-as name`.
+#[cfg(all(
+    feature = "storage",
+    any(feature = "serial", feature = "ssh", feature = "telnet")
+))]
+fn saved_name(save_as: &str) -> String {
+    Profile::split_qualified_name(save_as).1.to_owned()
+}
+
+/// The group part of `--save-as group/name`.
+#[cfg(all(
+    feature = "storage",
+    any(feature = "serial", feature = "ssh", feature = "telnet")
+))]
+fn saved_group(save_as: &str) -> Option<String> {
+    Profile::split_qualified_name(save_as).0.map(str::to_owned)
+}
+
+/// The saved profile `query` refers to: the profile of that exact name if
+/// there is one, otherwise the single match of [`match_profile_name`].
+#[cfg(feature = "storage")]
+fn find_profile(store: &ProfileStore, query: &str) -> Result<String, ConnectionError> {
+    match store.resolve(query) {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        // A profile of that name exists; let `run_profile` report any error.
+        _ => return Ok(query.to_owned()),
+    }
+    let names: Vec<String> = store.list()?.iter().map(Profile::qualified_name).collect();
+    let name = match_profile_name(query, &names)?;
+    log::info!("Using profile '{name}' for '{query}'");
+    Ok(name.to_owned())
+}
+
+/// Pick the profile name `query` stands for. An exact name wins; otherwise
+/// names containing `query` (ignoring case) are candidates, and failing
+/// those, names containing its characters in order. Errors unless exactly
+/// one candidate is left, listing them when there are several.
+#[cfg(feature = "storage")]
+fn match_profile_name<'a>(query: &str, names: &'a [String]) -> Result<&'a str, ConnectionError> {
+    if let Some(name) = names.iter().find(|name| *name == query) {
+        return Ok(name);
+    }
+    let needle = query.to_lowercase();
+    let mut candidates: Vec<&str> = names
+        .iter()
+        .filter(|name| name.to_lowercase().contains(&needle))
+        .map(String::as_str)
+        .collect();
+    if candidates.is_empty() {
+        candidates = names
+            .iter()
+            .filter(|name| is_subsequence(&needle, &name.to_lowercase()))
+            .map(String::as_str)
+            .collect();
+    }
+    candidates.sort_unstable();
+    match candidates.as_slice() {
+        [name] => Ok(name),
+        [] => Err(ConnectionError::Other(format!("no such profile: {query}"))),
+        _ => Err(ConnectionError::Other(format!(
+            "'{query}' matches several profiles: {}",
+            candidates.join(", ")
+        ))),
+    }
+}
+
+/// Whether the characters of `needle` appear in `haystack` in order.
+#[cfg(feature = "storage")]
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
+
+/// `session` with the settings saved in `preset` filled in: the profile's
+/// session limit, banner and escape keys apply unless the command line sets
+/// them.
+#[cfg(all(
+    feature = "storage",
+    any(feature = "serial", feature = "ssh", feature = "telnet")
+))]
+fn profile_session(preset: &Profile, session: &SessionArgs) -> SessionArgs {
+    let (escape_char, escape_exit) = preset.escape_keys();
+    SessionArgs {
+        max_session_secs: session
+            .max_session_secs
+            .or(preset.max_session().map(|limit| limit.as_secs())),
+        banner: session
+            .banner
+            .clone()
+            .or(preset.banner().map(str::to_owned)),
+        escape_char: session.escape_char.or(escape_char),
+        escape_exit: session.escape_exit.or(escape_exit),
+        ..session.clone()
+    }
+}
+
+/// Open the saved profile `name` in an interactive session.
+#[cfg(feature = "storage")]
+async fn run_profile(
+    store: &ProfileStore,
+    name: &str,
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))] session: &SessionArgs,
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+    connection_manager: &ConnectionManager,
+) -> Result<(), ConnectionError> {
+    let preset = store.resolve(name)?;
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+    let session = &profile_session(&preset, session);
+
+    match preset {
+        #[cfg(feature = "serial")]
+        Profile::Serial {
+            port,
+            baud,
+            data_bits,
+            parity,
+            stop_bits,
+            flow_control,
+            init_string,
+            ..
+        } => {
+            let init = init_string
+                .as_deref()
+                .map(InitString::from_escaped)
+                .transpose()?;
+            let framing = Framing {
+                data_bits,
+                parity,
+                stop_bits,
+                flow_control,
+            };
+            info!("Opening serial port: {port} at {baud} baud");
+            let conn = serial_connection(&port, baud, framing, ResetSequence::None, init);
+            run_cli_loop(
+                connection_manager,
+                port,
+                Box::new(conn),
+                ConnectionOptions::default(),
+                session,
+            )
+            .await
+        }
+        #[cfg(not(feature = "serial"))]
+        Profile::Serial { .. } => Err(ConnectionError::Other(
+            "This CLI was built without serial support".into(),
+        )),
+        #[cfg(feature = "ssh")]
+        Profile::Ssh {
+            host,
+            port,
+            username,
+            password,
+            key_path,
+            passphrase,
+            expected_host_key,
+            ..
+        } => {
+            let key = key_path.map(|path| (path, passphrase));
+            let conn = ssh_connection(&host, port, username, password, key, expected_host_key);
+            run_ssh_protocol(host, conn, &[], session, connection_manager).await
+        }
+        #[cfg(not(feature = "ssh"))]
+        Profile::Ssh { .. } => Err(ConnectionError::Other(
+            "This CLI was built without SSH support".into(),
+        )),
+        #[cfg(feature = "telnet")]
+        Profile::Telnet { host, port, .. } => {
+            info!("Connecting to Telnet server {host}:{port}");
+            let conn = TelnetConnection::new(host.clone(), port);
+            run_cli_loop(
+                connection_manager,
+                host,
+                Box::new(conn),
+                ConnectionOptions::default(),
+                session,
+            )
+            .await
+        }
+        #[cfg(not(feature = "telnet"))]
+        Profile::Telnet { .. } => Err(ConnectionError::Other(
+            "This CLI was built without Telnet support".into(),
+        )),
+    }
+}
+
 #[cfg(feature = "serial")]
-async fn run_serial_protocol(
-    port: String,
+fn serial_connection(
+    port: &str,
     baud: u32,
+    framing: Framing,
+    reset: ResetSequence,
+    init: Option<InitString>,
+) -> SerialConnection {
+    let conn = SerialConnection::new(port.to_string(), baud)
+        .with_framing(framing)
+        .with_reset(reset);
+    match init {
+        Some(init) => conn.with_init(init),
+        None => conn,
+    }
+}
+
+/// Write `bytes` once, print whatever arrives within `reply_timeout` as hex
+/// and close the connection again.
+#[cfg(feature = "serial")]
+async fn send_once(
     connection_manager: &ConnectionManager,
+    id: String,
+    conn: SerialConnection,
+    bytes: &[u8],
+    reply_timeout: Duration,
 ) -> Result<(), ConnectionError> {
-    info!("Opening serial port: {port} at {baud} baud");
-    let conn = SerialConnection::new(port.clone(), baud);
-    run_cli_loop(connection_manager, port, Box::new(conn)).await
+    connection_manager
+        .add_connection(id.clone(), Box::new(conn))
+        .await?;
+    let mut replies = connection_manager.subscribe_lossy(&id).await.unwrap();
+    connection_manager.write_raw(&id, bytes).await?;
+    println!("> {}", to_hex(bytes));
+
+    let deadline = tokio::time::Instant::now() + reply_timeout;
+    while let Ok(Some(item)) = tokio::time::timeout_at(deadline, replies.recv()).await {
+        match item {
+            SubscriptionItem::Data(chunk) => println!("< {}", to_hex(&chunk)),
+            SubscriptionItem::Lagged { missed } => println!("< ... {missed} chunks dropped"),
+            SubscriptionItem::Reconnected => {}
+        }
+    }
+    connection_manager.stop_connection(&id).await
 }
 
+/// An SSH connection logging in with `key` and its passphrase if given,
+/// with `password` otherwise.
 #[cfg(feature = "ssh")]
-async fn run_ssh_protocol(
-    host: String,
+fn ssh_connection(
+    host: &str,
     port: u16,
     username: String,
     password: String,
+    key: Option<(PathBuf, Option<String>)>,
+    expected_host_key: Option<String>,
+) -> SshConnection {
+    info!("Connecting to SSH server {host}:{port} as user {username}");
+    let conn = match key {
+        Some((path, passphrase)) => {
+            SshConnection::with_key(host.to_string(), port, username, path, passphrase)
+        }
+        None => SshConnection::new(host.to_string(), port, username, password),
+    };
+    match expected_host_key {
+        Some(fingerprint) => conn.with_expected_host_key(fingerprint),
+        None => conn,
+    }
+}
+
+/// Run an interactive session on `conn`, registered under `host`.
+#[cfg(feature = "ssh")]
+async fn run_ssh_protocol(
+    host: String,
+    mut conn: SshConnection,
+    forwards: &[LocalForward],
+    session: &SessionArgs,
     connection_manager: &ConnectionManager,
 ) -> Result<(), ConnectionError> {
-    info!("Connecting to SSH server {host}:{port} as user {username}");
-    let conn = SshConnection::new(host.clone(), port, username, password);
-    run_cli_loop(connection_manager, host, Box::new(conn)).await
+    if let Some((cols, rows)) = Terminal::detect().size {
+        conn = conn.with_pty_size(cols, rows);
+    }
+    if !forwards.is_empty() {
+        spawn_local_forwards(connection_manager, host.clone(), forwards.to_vec());
+    }
+    run_cli_loop(
+        connection_manager,
+        host,
+        Box::new(conn),
+        ConnectionOptions::default(),
+        session,
+    )
+    .await
+}
+
+/// Set up `forwards` on connection `id` as soon as it opens. Like `ssh`, a
+/// forward that cannot be set up is reported without ending the session.
+#[cfg(feature = "ssh")]
+fn spawn_local_forwards(
+    connection_manager: &ConnectionManager,
+    id: String,
+    forwards: Vec<LocalForward>,
+) {
+    let manager = connection_manager.clone();
+    let mut events = manager.events();
+    tokio::spawn(async move {
+        loop {
+            match events.recv().await {
+                Ok(event)
+                    if event.id == id && matches!(event.kind, ConnectionEventKind::Opened(_)) =>
+                {
+                    break
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return,
+            }
+        }
+        for forward in forwards {
+            match manager.local_forward(&id, forward.clone()).await {
+                Ok(local) => info!("Forwarding {local} to {}:{}", forward.host, forward.port),
+                Err(e) => warn!("Cannot forward {forward}: {e}"),
+            }
+        }
+    });
+}
+
+/// One line per port: its name and, for USB adapters, `vid:pid` and what
+/// the adapter says about itself.
+#[cfg(feature = "serial")]
+fn render_ports(ports: &[SerialPortInfo]) -> String {
+    let mut out = String::new();
+    for port in ports {
+        out.push_str(&port.name);
+        if let Some(usb) = &port.usb {
+            out.push_str(&format!("  USB {:04x}:{:04x}", usb.vid, usb.pid));
+            for text in [&usb.manufacturer, &usb.product].into_iter().flatten() {
+                out.push(' ');
+                out.push_str(text);
+            }
+            if let Some(serial) = &usb.serial_number {
+                out.push_str(&format!(" (serial {serial})"));
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// Point at the usual fix when the port list was off limits.
+#[cfg(feature = "serial")]
+fn port_list_error(err: ConnectionError) -> ConnectionError {
+    match err {
+        ConnectionError::IoError(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            ConnectionError::Other(format!(
+                "Permission denied listing serial ports ({e}); on Linux, add yourself to the dialout group"
+            ))
+        }
+        err => err,
+    }
+}
+
+#[cfg(feature = "ssh")]
+fn print_ssh_probe(probe: &SshProbe) {
+    println!("server version:  {}", probe.server_version);
+    println!(
+        "host key:        {} {}",
+        probe.host_key_algorithm, probe.host_key_fingerprint
+    );
+    println!("kex:             {}", probe.kex);
+    println!("cipher:          {}", probe.cipher);
+    println!("mac:             {}", probe.mac);
+    println!("compression:     {}", probe.compression);
+
+    let offered = &probe.server_algorithms;
+    println!("server offers:");
+    for (category, names) in [
+        ("kex", &offered.kex),
+        ("host key", &offered.host_key),
+        ("cipher c->s", &offered.cipher_client_to_server),
+        ("cipher s->c", &offered.cipher_server_to_client),
+        ("mac c->s", &offered.mac_client_to_server),
+        ("mac s->c", &offered.mac_server_to_client),
+        ("compression c->s", &offered.compression_client_to_server),
+        ("compression s->c", &offered.compression_server_to_client),
+    ] {
+        println!("  {category:<17} {}", names.join(","));
+    }
 }
 
 /// Runs the CLI loop for a given connection.
@@ -267,88 +1320,257 @@ async fn run_ssh_protocol(
 /// This function registers a connection by passing ownership of the Connection trait object
 /// (via `Box<dyn Connection + Send + Unpin>`)
 /// to the connection manager, enables raw terminal mode, and reads user input to write to the connection.
-/// It exits when the user types Ctrl+A followed by 'x',
-#[cfg(any(feature = "serial", feature = "ssh"))]
+/// It exits when the user types the escape key followed by the exit key (Ctrl+A then 'x' unless
+/// `session` picks others),
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
 async fn run_cli_loop(
     connection_manager: &ConnectionManager,
     id: String,
     conn: Box<dyn Connection + Send + Unpin>,
+    options: ConnectionOptions,
+    session: &SessionArgs,
 ) -> Result<(), ConnectionError> {
-    connection_manager.add_connection(id.clone(), conn).await?;
+    session.check_binary_logs()?;
+    let escape = EscapeKeys::new(session.escape_char, session.escape_exit)?;
+    let options = match session.max_session_secs {
+        Some(secs) => options.with_max_session(Duration::from_secs(secs)),
+        None => options,
+    };
+    let options = match &session.banner {
+        Some(banner) => options.with_banner(unescape(banner)?),
+        None => options,
+    };
+    let options = options.with_binary(session.binary);
+    let mut events = connection_manager.events();
+    connection_manager
+        .add_connection_with_options(id.clone(), conn, options)
+        .await?;
+    #[cfg(unix)]
+    connection_manager.dump_on_sigusr1();
 
     // Subscribe to messages from the new connection
-    let mut connection_receiver = connection_manager.subscribe(&id).await.unwrap();
+    let connection_receiver = connection_manager.subscribe(&id).await.unwrap();
 
-    // -> echo to the user’s terminal
-    tokio::spawn(async move {
-        while let Ok(chunk) = connection_receiver.recv().await {
-            let _ = stdout().write_all(&chunk);
-            let _ = stdout().flush();
-        }
-    });
+    // -> optionally mirror to transcript files
+    let size = match connection_manager.pty_size(&id).await {
+        Some(size) => size,
+        None => Terminal::detect().size.unwrap_or((80, 24)),
+    };
+    let (connection_receiver, logger_tasks) =
+        start_session_logs(connection_manager, &id, connection_receiver, session, size).await?;
 
-    info!("Enable raw mode. Press Ctrl+A then 'x' to exit the program.");
-    set_raw_mode()?;
+    // -> forward between the user's terminal and the connection
+    let mut terminal = Terminal::detect();
+    terminal.escape = escape;
+    terminal.binary = session.binary;
+    if session.flush_on_newline {
+        terminal.render = Render::LineBuffered {
+            flush_after: Duration::from_millis(session.flush_timeout_ms),
+        };
+    }
+    let result = run_session(
+        connection_manager,
+        &id,
+        connection_receiver,
+        terminal,
+        session.local_echo,
+        tokio::io::stdin(),
+        &mut stdout(),
+    )
+    .await;
+    report_remote_close(&mut events, &id);
+    let _ = connection_manager.stop_connection(&id).await;
+    for logger_task in logger_tasks {
+        // The broadcast channel is closed now, so each logger drains and exits.
+        let _ = logger_task.await;
+    }
+    if terminal.interactive {
+        info!("Terminal mode restored.");
+    }
+    result
+}
 
-    let mut last_was_ctrl_a = false;
-    let mut buf = [0u8; 1];
-    let mut stdin = io::stdin();
-    loop {
-        if stdin.read_exact(&mut buf).await.is_err() {
-            break;
+/// Start a transcript writer for every `--log` target of `session`.
+///
+/// The terminal and all logs are fed from `receiver`, the connection's first
+/// subscription, by one forwarding task that only starts once every logger
+/// is ready. Each file therefore starts with the first byte of the session,
+/// e.g. the login banner. Asciinema logs also record the resizes of
+/// connection `id`. Returns the receiver for the terminal and the logger
+/// tasks, which end once the connection is stopped.
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+async fn start_session_logs(
+    connection_manager: &ConnectionManager,
+    id: &str,
+    receiver: broadcast::Receiver<Vec<u8>>,
+    session: &SessionArgs,
+    (cols, rows): (u16, u16),
+) -> Result<
+    (
+        broadcast::Receiver<Vec<u8>>,
+        Vec<JoinHandle<std::io::Result<()>>>,
+    ),
+    ConnectionError,
+> {
+    if session.log.is_empty() {
+        return Ok((receiver, Vec::new()));
+    }
+    let default_format = match session.log_format {
+        Some(format) => format,
+        None if session.strip_ansi => LogFormat::Plain,
+        None => LogFormat::Raw,
+    };
+    let mut loggers = Vec::with_capacity(session.log.len());
+    for target in &session.log {
+        let format = target.format.unwrap_or(default_format);
+        let mut logger = SessionLogger::create(&target.path)
+            .await?
+            .with_format(format)
+            .with_terminal_size(cols, rows);
+        if format == LogFormat::Asciinema {
+            logger = logger.with_resize_events(connection_manager.events(), id);
         }
-        let ch = buf[0];
-        if ch == 0x01 {
-            last_was_ctrl_a = true;
-            continue;
+        if let Some(max_size) = session.log_rotate_size {
+            logger = logger.with_rotation(LogRotation::by_size(max_size, session.log_keep));
         }
-        if last_was_ctrl_a && ch == b'x' {
-            restore_mode();
-            info!("Exiting...");
-            break;
-        } else {
-            last_was_ctrl_a = false;
+        loggers.push(logger);
+    }
+
+    let (fan_out_tx, terminal_receiver) = broadcast::channel(SESSION_FAN_OUT_CAPACITY);
+    let logger_tasks = loggers
+        .into_iter()
+        .map(|logger| logger.spawn(fan_out_tx.subscribe()))
+        .collect();
+    let mut receiver = receiver;
+    tokio::spawn(async move {
+        loop {
+            match receiver.recv().await {
+                Ok(chunk) => {
+                    let _ = fan_out_tx.send(chunk);
+                }
+                Err(broadcast::error::RecvError::Lagged(n)) => {
+                    warn!("Session output lagged; {n} chunks missing from the terminal and logs");
+                }
+                // Dropping the sender closes the terminal's and the logs' receivers.
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
         }
-        if ch == b'\r' {
-            let _ = connection_manager.write_bytes(&id, b"\r").await;
-        } else {
-            let _ = connection_manager.write_bytes(&id, &[ch]).await;
+    });
+    Ok((terminal_receiver, logger_tasks))
+}
+
+/// Tell the user if the session ended because the remote side closed it,
+/// e.g. after `exit` in an SSH shell.
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+fn report_remote_close(events: &mut broadcast::Receiver<ConnectionEvent>, id: &str) {
+    loop {
+        match events.try_recv() {
+            Ok(ConnectionEvent {
+                id: event_id,
+                kind: ConnectionEventKind::RemoteClosed { exit_status },
+            }) if event_id == id => {
+                info!("{}", ConnectionError::RemoteClosed { exit_status });
+            }
+            Ok(_) | Err(TryRecvError::Lagged(_)) => {}
+            Err(_) => break,
         }
     }
-    let _ = connection_manager.stop_connection(&id).await;
-    info!("Terminal mode restored.");
-    Ok(())
 }
 
 #[cfg(feature = "storage")]
 async fn handle_storage_cmd(action: StorageAction) -> Result<(), ConnectionError> {
-    let store = ProfileStore::new().map_err(|e| ConnectionError::Other(e.to_string()))?;
+    let store = ProfileStore::from_env().map_err(|e| ConnectionError::Other(e.to_string()))?;
 
     match action {
-        StorageAction::List => {
-            for p in store.list()? {
-                println!("{p:?}");
-            }
+        StorageAction::List { format, group } => {
+            let profiles = match group {
+                Some(group) => store.list_group(Some(&group))?,
+                None => store.list()?,
+            };
+            print!("{}", render_profiles(&profiles, format)?);
         }
         #[cfg(feature = "serial")]
-        StorageAction::SaveSerial { name, port, baud } => {
-            store.save(&Profile::Serial { name, port, baud })?;
+        StorageAction::SaveSerial {
+            name,
+            group,
+            port,
+            baud,
+            framing,
+            init,
+            max_session_secs,
+            banner,
+            escape_char,
+            escape_exit,
+        } => {
+            store.save(&Profile::Serial {
+                name,
+                group,
+                port,
+                baud,
+                data_bits: framing.data_bits,
+                parity: framing.parity,
+                stop_bits: framing.stop_bits,
+                flow_control: framing.flow_control,
+                init_string: init,
+                max_session_secs,
+                banner,
+                escape_char,
+                escape_exit,
+            })?;
         }
         #[cfg(feature = "ssh")]
         StorageAction::SaveSsh {
             name,
+            group,
             host,
             port,
             username,
             password,
+            key,
+            key_passphrase,
+            host_key,
+            max_session_secs,
+            banner,
+            escape_char,
+            escape_exit,
         } => {
             store.save(&Profile::Ssh {
                 name,
+                group,
                 host,
                 port,
                 username,
                 password,
                 keyring_id: None, // not needed here
+                key_path: key,
+                passphrase: key_passphrase,
+                expected_host_key: host_key,
+                max_session_secs,
+                banner,
+                escape_char,
+                escape_exit,
+            })?;
+        }
+        #[cfg(feature = "telnet")]
+        StorageAction::SaveTelnet {
+            name,
+            group,
+            host,
+            port,
+            max_session_secs,
+            banner,
+            escape_char,
+            escape_exit,
+        } => {
+            store.save(&Profile::Telnet {
+                name,
+                group,
+                host,
+                port,
+                max_session_secs,
+                banner,
+                escape_char,
+                escape_exit,
             })?;
         }
         StorageAction::Delete { name } => {
@@ -356,7 +1578,782 @@ async fn handle_storage_cmd(action: StorageAction) -> Result<(), ConnectionError
                 eprintln!("No such profile: {name}");
             }
         }
+        StorageAction::SetDefault { name } => {
+            store.set_default_profile(&name)?;
+        }
+        StorageAction::ClearDefault => {
+            store.clear_default_profile()?;
+        }
+        StorageAction::Export { file } => {
+            let count = store.export(&file)?;
+            println!("Exported {count} profiles to {}", file.display());
+        }
+        StorageAction::Import { file, overwrite } => {
+            let summary = store.import(&file, overwrite)?;
+            println!(
+                "Imported {} profiles, skipped {} that already exist",
+                summary.imported, summary.skipped
+            );
+        }
         StorageAction::UseProfile { .. } => unreachable!(), // handled above
     }
     Ok(())
 }
+
+/// Render profiles for `storage list`. Secrets never appear in the JSON form
+/// because `Profile` skips them when serializing. The human form lists the
+/// profiles without a group first, then each group under a `[group]` header.
+#[cfg(feature = "storage")]
+fn render_profiles(profiles: &[Profile], format: ListFormat) -> Result<String, ConnectionError> {
+    match format {
+        ListFormat::Human => {
+            let mut sorted: Vec<&Profile> = profiles.iter().collect();
+            sorted.sort_by(|a, b| (a.group(), a.name()).cmp(&(b.group(), b.name())));
+            let mut out = String::new();
+            let mut current = None;
+            for profile in sorted {
+                if let Some(group) = profile.group() {
+                    if current != Some(group) {
+                        out += &format!("[{group}]\n");
+                        current = Some(group);
+                    }
+                    out += "  ";
+                }
+                out += &format!("{profile:?}\n");
+            }
+            Ok(out)
+        }
+        ListFormat::Json => serde_json::to_string_pretty(profiles)
+            .map(|json| json + "\n")
+            .map_err(|e| ConnectionError::Other(e.to_string())),
+    }
+}
+
+#[cfg(all(test, feature = "storage"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_list_round_trips() {
+        let profiles = vec![
+            Profile::Serial {
+                name: "lab".into(),
+                group: None,
+                port: "/dev/ttyUSB0".into(),
+                baud: 115_200,
+                data_bits: Default::default(),
+                parity: Default::default(),
+                stop_bits: Default::default(),
+                flow_control: Default::default(),
+                init_string: None,
+                max_session_secs: Some(3600),
+                banner: None,
+                escape_char: None,
+                escape_exit: None,
+            },
+            Profile::Ssh {
+                name: "pi".into(),
+                group: None,
+                host: "192.168.1.20".into(),
+                port: 22,
+                username: "simon".into(),
+                password: String::new(),
+                keyring_id: Some("putty_rs:pi".into()),
+                key_path: Some("/home/simon/.ssh/id_ed25519".into()),
+                passphrase: None,
+                expected_host_key: Some(
+                    "SHA256:Hw0L3k2pJt7cQq6V8m3mJxkQm1a3P6pDqJ0rX9b1c2E".into(),
+                ),
+                max_session_secs: None,
+                banner: None,
+                escape_char: None,
+                escape_exit: None,
+            },
+        ];
+
+        let json = render_profiles(&profiles, ListFormat::Json).unwrap();
+        let parsed: Vec<Profile> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, profiles);
+    }
+
+    #[test]
+    fn human_list_groups_the_profiles() {
+        let serial = |name: &str, group: Option<&str>| Profile::Serial {
+            name: name.into(),
+            group: group.map(Into::into),
+            port: "/dev/ttyUSB0".into(),
+            baud: 115_200,
+            data_bits: Default::default(),
+            parity: Default::default(),
+            stop_bits: Default::default(),
+            flow_control: Default::default(),
+            init_string: None,
+            max_session_secs: None,
+            banner: None,
+            escape_char: None,
+            escape_exit: None,
+        };
+        let profiles = vec![
+            serial("web", Some("prod")),
+            serial("lab", None),
+            serial("db", Some("prod")),
+            serial("bench", Some("lab")),
+        ];
+
+        let lines: Vec<String> = render_profiles(&profiles, ListFormat::Human)
+            .unwrap()
+            .lines()
+            .map(|line| match line.split_once(", group") {
+                Some((head, _)) => head.to_owned(),
+                None => line.to_owned(),
+            })
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "Serial { name: \"lab\"",
+                "[lab]",
+                "  Serial { name: \"bench\"",
+                "[prod]",
+                "  Serial { name: \"db\"",
+                "  Serial { name: \"web\"",
+            ]
+        );
+    }
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn profile_name_matches_a_unique_fragment() {
+        let names = names(&["lab-bench", "Prod-Router", "pi"]);
+
+        assert_eq!(match_profile_name("router", &names).unwrap(), "Prod-Router");
+        assert_eq!(match_profile_name("lbb", &names).unwrap(), "lab-bench");
+        assert_eq!(match_profile_name("pi", &names).unwrap(), "pi");
+    }
+
+    #[test]
+    fn ambiguous_profile_name_lists_the_candidates() {
+        let names = names(&["lab-uart", "lab-bench", "pi"]);
+
+        let err = match_profile_name("LAB", &names).unwrap_err().to_string();
+        assert!(err.contains("lab-bench, lab-uart"), "{err}");
+    }
+
+    #[test]
+    fn unknown_profile_name_is_an_error() {
+        let names = names(&["lab-bench", "pi"]);
+
+        let err = match_profile_name("router", &names)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("no such profile"), "{err}");
+    }
+
+    #[cfg(feature = "serial")]
+    #[test]
+    fn save_as_captures_the_session_flags() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "serial",
+            "--port",
+            "/dev/ttyUSB1",
+            "--baud",
+            "9600",
+            "--save-as",
+            "bench",
+        ])
+        .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::in_dir(dir.path().join("profiles")).unwrap();
+        store
+            .save(&session_profile(&args.protocol, &args.session).unwrap())
+            .unwrap();
+
+        assert_eq!(
+            store.list().unwrap(),
+            vec![Profile::Serial {
+                name: "bench".into(),
+                group: None,
+                port: "/dev/ttyUSB1".into(),
+                baud: 9600,
+                data_bits: Default::default(),
+                parity: Default::default(),
+                stop_bits: Default::default(),
+                flow_control: Default::default(),
+                init_string: None,
+                max_session_secs: None,
+                banner: None,
+                escape_char: None,
+                escape_exit: None,
+            }]
+        );
+    }
+
+    #[cfg(feature = "serial")]
+    #[test]
+    fn serial_list_is_a_subcommand() {
+        let args = Args::try_parse_from(["putty-rs", "serial", "list"]).unwrap();
+        assert!(matches!(
+            args.protocol,
+            Protocol::Serial {
+                action: Some(SerialAction::List),
+                ..
+            }
+        ));
+        assert!(Args::try_parse_from(["putty-rs", "serial", "--baud", "9600", "list"]).is_err());
+    }
+
+    #[cfg(feature = "serial")]
+    #[test]
+    fn ports_render_with_their_usb_details() {
+        use putty_core::connections::serial::UsbInfo;
+
+        let ports = [
+            SerialPortInfo {
+                name: "/dev/ttyS0".into(),
+                usb: None,
+            },
+            SerialPortInfo {
+                name: "/dev/ttyUSB0".into(),
+                usb: Some(UsbInfo {
+                    vid: 0x0403,
+                    pid: 0x6001,
+                    manufacturer: Some("FTDI".into()),
+                    product: Some("FT232R USB UART".into()),
+                    serial_number: Some("A12345".into()),
+                }),
+            },
+        ];
+        assert_eq!(
+            render_ports(&ports),
+            "/dev/ttyS0\n/dev/ttyUSB0  USB 0403:6001 FTDI FT232R USB UART (serial A12345)\n"
+        );
+        assert_eq!(render_ports(&[]), "");
+    }
+
+    #[cfg(feature = "serial")]
+    #[test]
+    fn save_as_keeps_the_serial_framing() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "serial",
+            "--port",
+            "/dev/ttyUSB1",
+            "--data-bits",
+            "7",
+            "--parity",
+            "even",
+            "--stop-bits",
+            "2",
+            "--flow-control",
+            "hardware",
+            "--save-as",
+            "modem",
+        ])
+        .unwrap();
+
+        let profile = session_profile(&args.protocol, &args.session).unwrap();
+        assert_eq!(
+            profile.framing(),
+            Some(Framing {
+                data_bits: DataBits::Seven,
+                parity: Parity::Even,
+                stop_bits: StopBits::Two,
+                flow_control: FlowControl::Hardware,
+            })
+        );
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn save_as_keeps_the_pinned_host_key() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "ssh",
+            "--host",
+            "10.0.0.5",
+            "--username",
+            "ops",
+            "--host-key",
+            "SHA256:Hw0L3k2pJt7cQq6V8m3mJxkQm1a3P6pDqJ0rX9b1c2E",
+            "--save-as",
+            "prodbox",
+        ])
+        .unwrap();
+
+        match session_profile(&args.protocol, &args.session) {
+            Some(Profile::Ssh {
+                expected_host_key, ..
+            }) => assert_eq!(
+                expected_host_key.as_deref(),
+                Some("SHA256:Hw0L3k2pJt7cQq6V8m3mJxkQm1a3P6pDqJ0rX9b1c2E")
+            ),
+            other => panic!("expected an SSH profile, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "telnet")]
+    #[test]
+    fn telnet_defaults_to_port_23_and_saves_as_a_profile() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "telnet",
+            "--host",
+            "10.0.0.9",
+            "--escape-char",
+            "^B",
+            "--save-as",
+            "lab/switch",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            session_profile(&args.protocol, &args.session),
+            Some(Profile::Telnet {
+                name: "switch".into(),
+                group: Some("lab".into()),
+                host: "10.0.0.9".into(),
+                port: 23,
+                max_session_secs: None,
+                banner: None,
+                escape_char: Some('\u{2}'),
+                escape_exit: None,
+            })
+        );
+    }
+
+    #[cfg(feature = "serial")]
+    #[test]
+    fn save_as_keeps_the_max_session() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "serial",
+            "--port",
+            "/dev/ttyUSB1",
+            "--max-session-secs",
+            "3600",
+            "--save-as",
+            "lab",
+        ])
+        .unwrap();
+
+        let profile = session_profile(&args.protocol, &args.session).unwrap();
+        assert_eq!(profile.max_session(), Some(Duration::from_secs(3600)));
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn save_as_keeps_the_banner() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "ssh",
+            "--host",
+            "10.0.0.5",
+            "--username",
+            "ops",
+            "--banner",
+            r"=== rack1 ===\r\n",
+            "--save-as",
+            "rack1",
+        ])
+        .unwrap();
+
+        let profile = session_profile(&args.protocol, &args.session).unwrap();
+        assert_eq!(profile.banner(), Some(r"=== rack1 ===\r\n"));
+    }
+
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+    #[test]
+    fn escape_keys_parse_as_caret_notation_or_a_single_key() {
+        assert_eq!(parse_escape_key("^B"), Ok('\x02'));
+        assert_eq!(parse_escape_key("^]"), Ok('\x1d'));
+        assert_eq!(parse_escape_key("q"), Ok('q'));
+        assert!(parse_escape_key("^").is_ok(), "a lone caret is a key too");
+        assert!(parse_escape_key("qq").is_err());
+        assert!(parse_escape_key("ü").is_err());
+    }
+
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+    #[tokio::test]
+    async fn every_log_starts_with_the_data_sent_right_after_connect() {
+        use crate::ui::terminal::tests::ChannelConnection;
+
+        let dir = tempfile::tempdir().unwrap();
+        let raw = dir.path().join("raw.log");
+        let plain = dir.path().join("plain.log");
+        let session = SessionArgs {
+            log: vec![
+                LogTarget {
+                    format: None,
+                    path: raw.clone(),
+                },
+                LogTarget {
+                    format: Some(LogFormat::Plain),
+                    path: plain.clone(),
+                },
+            ],
+            ..SessionArgs::default()
+        };
+
+        let connection_manager = ConnectionManager::new();
+        let (device_tx, incoming) = tokio::sync::mpsc::channel(8);
+        connection_manager
+            .add_connection("board".into(), Box::new(ChannelConnection { incoming }))
+            .await
+            .unwrap();
+        // The banner is read before anyone subscribed or opened a log.
+        device_tx
+            .send(b"U-Boot 2024.01\r\n".to_vec())
+            .await
+            .unwrap();
+        while connection_manager.metrics("board").await.unwrap().bytes_in == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        let receiver = connection_manager.subscribe("board").await.unwrap();
+        let (mut terminal_receiver, logger_tasks) =
+            start_session_logs(&connection_manager, "board", receiver, &session, (80, 24))
+                .await
+                .unwrap();
+        device_tx.send(b"=> ".to_vec()).await.unwrap();
+
+        assert_eq!(
+            terminal_receiver.recv().await.unwrap(),
+            b"U-Boot 2024.01\r\n"
+        );
+        assert_eq!(terminal_receiver.recv().await.unwrap(), b"=> ");
+        connection_manager.stop_connection("board").await.unwrap();
+        for task in logger_tasks {
+            task.await.unwrap().unwrap();
+        }
+        assert_eq!(std::fs::read(&raw).unwrap(), b"U-Boot 2024.01\r\n=> ");
+        assert!(std::fs::read_to_string(&plain)
+            .unwrap()
+            .starts_with("U-Boot 2024.01"));
+    }
+
+    #[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+    #[tokio::test]
+    async fn profile_escape_keys_drive_the_exit_detection() {
+        use crate::ui::terminal::tests::{ChannelConnection, SharedOutput};
+        use tokio::io::AsyncWriteExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::in_dir(dir.path().join("profiles")).unwrap();
+        store
+            .save(&Profile::Serial {
+                name: "console".into(),
+                group: None,
+                port: "/dev/ttyUSB0".into(),
+                baud: 115_200,
+                data_bits: Default::default(),
+                parity: Default::default(),
+                stop_bits: Default::default(),
+                flow_control: Default::default(),
+                init_string: None,
+                max_session_secs: None,
+                banner: None,
+                escape_char: Some('\x02'),
+                escape_exit: Some('q'),
+            })
+            .unwrap();
+        let session = profile_session(&store.resolve("console").unwrap(), &SessionArgs::default());
+        let mut terminal = Terminal::from_parts(false, false);
+        terminal.escape = EscapeKeys::new(session.escape_char, session.escape_exit).unwrap();
+
+        let connection_manager = ConnectionManager::new();
+        let (_device_tx, incoming) = tokio::sync::mpsc::channel(8);
+        connection_manager
+            .add_connection("console".into(), Box::new(ChannelConnection { incoming }))
+            .await
+            .unwrap();
+        let connection_receiver = connection_manager.subscribe("console").await.unwrap();
+        let (mut keyboard, input) = tokio::io::duplex(16);
+        let output = SharedOutput::default();
+        let run = {
+            let connection_manager = connection_manager.clone();
+            let mut output = output.clone();
+            tokio::spawn(async move {
+                run_session(
+                    &connection_manager,
+                    "console",
+                    connection_receiver,
+                    terminal,
+                    LocalEcho::Immediate,
+                    input,
+                    &mut output,
+                )
+                .await
+            })
+        };
+
+        // Ctrl+A then 'x' is ordinary input for this profile.
+        keyboard.write_all(b"\x01x").await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while output.0.lock().unwrap().as_slice() != b"\x01x" {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("Ctrl+A then 'x' should be forwarded");
+
+        keyboard.write_all(b"\x02q").await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), run)
+            .await
+            .expect("Ctrl+B then 'q' should end the session")
+            .unwrap()
+            .unwrap();
+        assert_eq!(output.0.lock().unwrap().as_slice(), b"\x01x");
+    }
+
+    #[cfg(feature = "serial")]
+    #[test]
+    fn log_is_repeatable_with_a_format_per_file() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "serial",
+            "--port",
+            "/dev/ttyUSB1",
+            "--log",
+            "raw:capture.bin",
+            "--log",
+            "timestamped:session.txt",
+            "--log",
+            "C:/logs/plain.log",
+        ])
+        .unwrap();
+
+        assert_eq!(
+            args.session.log,
+            vec![
+                LogTarget {
+                    format: Some(LogFormat::Raw),
+                    path: "capture.bin".into(),
+                },
+                LogTarget {
+                    format: Some(LogFormat::Timestamped),
+                    path: "session.txt".into(),
+                },
+                LogTarget {
+                    format: None,
+                    path: "C:/logs/plain.log".into(),
+                },
+            ]
+        );
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn local_forward_is_repeatable() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "ssh",
+            "--host",
+            "bastion",
+            "--username",
+            "ops",
+            "--local-forward",
+            "8080:internal:80",
+            "--local-forward",
+            "0.0.0.0:8443:10.1.2.3:443",
+        ])
+        .unwrap();
+
+        match args.protocol {
+            Protocol::Ssh { local_forward, .. } => assert_eq!(
+                local_forward,
+                vec![
+                    LocalForward::parse("127.0.0.1:8080:internal:80").unwrap(),
+                    LocalForward::parse("0.0.0.0:8443:10.1.2.3:443").unwrap(),
+                ]
+            ),
+            other => panic!("expected the ssh command, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn remote_forward_binds_to_localhost_by_default() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "ssh",
+            "--host",
+            "bastion",
+            "--username",
+            "ops",
+            "--remote-forward",
+            "9000:localhost:3000",
+        ])
+        .unwrap();
+
+        match args.protocol {
+            Protocol::Ssh { remote_forward, .. } => assert_eq!(
+                remote_forward,
+                vec![RemoteForward::parse("localhost:9000:localhost:3000").unwrap()]
+            ),
+            other => panic!("expected the ssh command, got {other:?}"),
+        }
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn runtime_flags_are_accepted_after_the_subcommand() {
+        use crate::runtime::RuntimeFlavor;
+        use std::num::NonZeroUsize;
+
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "ssh",
+            "--host",
+            "bastion",
+            "--username",
+            "ops",
+            "--runtime",
+            "current-thread",
+            "--worker-threads",
+            "3",
+        ])
+        .unwrap();
+
+        assert_eq!(args.runtime.runtime, RuntimeFlavor::CurrentThread);
+        assert_eq!(args.runtime.worker_threads, NonZeroUsize::new(3));
+        let zero_workers = Args::try_parse_from([
+            "putty-rs",
+            "--worker-threads",
+            "0",
+            "ssh",
+            "--host",
+            "bastion",
+            "--username",
+            "ops",
+        ]);
+        assert!(zero_workers.is_err());
+    }
+
+    #[cfg(feature = "serial")]
+    #[test]
+    fn log_format_applies_to_unprefixed_targets() {
+        let args = Args::try_parse_from([
+            "putty-rs",
+            "serial",
+            "--port",
+            "/dev/ttyUSB1",
+            "--log",
+            "session.cast",
+            "--log-format",
+            "asciinema",
+        ])
+        .unwrap();
+        assert_eq!(args.session.log_format, Some(LogFormat::Asciinema));
+
+        let conflicting = Args::try_parse_from([
+            "putty-rs",
+            "serial",
+            "--port",
+            "/dev/ttyUSB1",
+            "--log",
+            "session.log",
+            "--log-format",
+            "plain",
+            "--strip-ansi",
+        ]);
+        assert!(conflicting.is_err());
+    }
+
+    #[cfg(feature = "serial")]
+    #[test]
+    fn binary_logs_must_stay_raw() {
+        let parse = |log: &str| {
+            Args::try_parse_from([
+                "putty-rs",
+                "serial",
+                "--port",
+                "/dev/ttyUSB1",
+                "--binary",
+                "--log",
+                log,
+            ])
+            .unwrap()
+        };
+        for log in ["plain:fw.log", "timestamped:fw.log", "asciinema:fw.cast"] {
+            let err = parse(log).session.check_binary_logs().unwrap_err();
+            assert!(err.to_string().contains("--binary"), "{err}");
+        }
+        parse("raw:fw.bin").session.check_binary_logs().unwrap();
+        parse("fw.bin").session.check_binary_logs().unwrap();
+    }
+
+    #[cfg(all(
+        feature = "storage",
+        any(feature = "serial", feature = "ssh", feature = "telnet")
+    ))]
+    #[tokio::test]
+    async fn save_as_saves_once_the_connection_opens() {
+        use crate::ui::terminal::tests::ChannelConnection;
+
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::in_dir(dir.path().join("profiles")).unwrap();
+        let profile = Profile::Telnet {
+            name: "rack1".into(),
+            group: None,
+            host: "10.0.0.7".into(),
+            port: 23,
+            max_session_secs: None,
+            banner: None,
+            escape_char: None,
+            escape_exit: None,
+        };
+
+        // A session that never connects saves nothing.
+        let connection_manager = ConnectionManager::new();
+        let (session_over, task) =
+            spawn_save_on_open(&connection_manager, store.clone(), profile.clone());
+        drop(session_over);
+        task.await.unwrap().unwrap();
+        assert!(store.list().unwrap().is_empty());
+
+        let (session_over, task) =
+            spawn_save_on_open(&connection_manager, store.clone(), profile.clone());
+        let (_device_tx, incoming) = tokio::sync::mpsc::channel(8);
+        connection_manager
+            .add_connection("rack1".into(), Box::new(ChannelConnection { incoming }))
+            .await
+            .unwrap();
+        // Saved while the session is still running.
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while store.list().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("profile not saved while connected");
+        drop(session_over);
+        task.await.unwrap().unwrap();
+        assert_eq!(store.list().unwrap(), vec![profile]);
+    }
+
+    #[cfg(all(
+        feature = "serial",
+        feature = "ssh",
+        feature = "telnet",
+        feature = "storage"
+    ))]
+    #[test]
+    fn about_names_every_enabled_feature() {
+        assert_eq!(
+            cli_about(),
+            "Terminal client with serial, SSH, Telnet, and saved profile support"
+        );
+    }
+
+    #[cfg(feature = "serial")]
+    #[test]
+    fn without_save_as_nothing_is_captured() {
+        let args = Args::try_parse_from(["putty-rs", "serial", "--port", "/dev/ttyUSB1"]).unwrap();
+        assert!(session_profile(&args.protocol, &args.session).is_none());
+    }
+}
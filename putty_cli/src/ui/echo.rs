@@ -0,0 +1,125 @@
+use clap::ValueEnum;
+use putty_core::core::connection_manager::ConnectionManager;
+use std::io::Write;
+
+/// How typed bytes are echoed to the local terminal.
+#[derive(ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LocalEcho {
+    /// Leave echoing to the remote side
+    #[default]
+    Off,
+    /// Echo each byte as soon as it is typed
+    Immediate,
+    /// Echo each byte only after the connection confirmed the write
+    Acked,
+}
+
+/// Send user input to a connection, echoing it locally according to `local_echo`.
+///
+/// In `Acked` mode the echo is tied to the acknowledged write path, so a
+/// failed write never shows up as phantom input on the local display.
+pub async fn send_input(
+    connection_manager: &ConnectionManager,
+    id: &str,
+    data: &[u8],
+    local_echo: LocalEcho,
+    out: &mut impl Write,
+) {
+    match local_echo {
+        LocalEcho::Off => {
+            let _ = connection_manager.write_bytes(id, data).await;
+        }
+        LocalEcho::Immediate => {
+            echo(out, data);
+            let _ = connection_manager.write_bytes(id, data).await;
+        }
+        LocalEcho::Acked => {
+            if connection_manager.write_bytes_acked(id, data).await.is_ok() {
+                echo(out, data);
+            }
+        }
+    }
+}
+
+fn echo(out: &mut impl Write, data: &[u8]) {
+    let _ = out.write_all(data);
+    let _ = out.flush();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use putty_core::connections::{connection::Connection, errors::ConnectionError};
+
+    /// Connection whose writes succeed or fail depending on `fail_writes`.
+    struct StubConnection {
+        fail_writes: bool,
+    }
+
+    #[async_trait]
+    impl Connection for StubConnection {
+        async fn connect(&mut self) -> Result<(), ConnectionError> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<(), ConnectionError> {
+            Ok(())
+        }
+
+        async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError> {
+            if self.fail_writes {
+                Err(ConnectionError::Other("write failed".into()))
+            } else {
+                Ok(data.len())
+            }
+        }
+
+        async fn read(&mut self, _buffer: &mut [u8]) -> Result<usize, ConnectionError> {
+            std::future::pending().await
+        }
+    }
+
+    async fn manager_with(fail_writes: bool) -> ConnectionManager {
+        let connection_manager = ConnectionManager::new();
+        connection_manager
+            .add_connection("stub".into(), Box::new(StubConnection { fail_writes }))
+            .await
+            .expect("add_connection should succeed");
+        connection_manager
+    }
+
+    #[tokio::test]
+    async fn acked_echo_skips_failed_writes() {
+        let connection_manager = manager_with(true).await;
+        let mut out = Vec::new();
+
+        send_input(
+            &connection_manager,
+            "stub",
+            b"a",
+            LocalEcho::Acked,
+            &mut out,
+        )
+        .await;
+
+        assert!(out.is_empty(), "failed write must not be echoed");
+    }
+
+    #[tokio::test]
+    async fn acked_echo_shows_successful_writes() {
+        let connection_manager = manager_with(false).await;
+        let mut out = Vec::new();
+
+        send_input(
+            &connection_manager,
+            "stub",
+            b"a",
+            LocalEcho::Acked,
+            &mut out,
+        )
+        .await;
+
+        assert_eq!(out, b"a");
+    }
+}
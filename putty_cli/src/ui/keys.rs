@@ -0,0 +1,296 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use putty_core::connections::errors::ConnectionError;
+use std::fmt;
+
+/// Ctrl+A, the default first half of the exit sequence.
+const CTRL_A: u8 = 0x01;
+
+/// Typed after the escape key: send a BREAK, toggle DTR, toggle RTS.
+const BREAK_KEY: u8 = b'b';
+const DTR_KEY: u8 = b'd';
+const RTS_KEY: u8 = b'r';
+
+/// Bytes a terminal sends for `key`, or `None` for keys without a standard
+/// encoding and for key releases (reported on Windows).
+pub fn key_to_bytes(key: KeyEvent) -> Option<Vec<u8>> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+    let bytes: Vec<u8> = match key.code {
+        KeyCode::Char(c) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            vec![control_byte(c)?]
+        }
+        KeyCode::Char(c) => c.to_string().into_bytes(),
+        KeyCode::Enter => b"\r".to_vec(),
+        KeyCode::Tab => b"\t".to_vec(),
+        KeyCode::BackTab => b"\x1b[Z".to_vec(),
+        KeyCode::Backspace => vec![0x7f],
+        KeyCode::Esc => vec![0x1b],
+        KeyCode::Up => b"\x1b[A".to_vec(),
+        KeyCode::Down => b"\x1b[B".to_vec(),
+        KeyCode::Right => b"\x1b[C".to_vec(),
+        KeyCode::Left => b"\x1b[D".to_vec(),
+        KeyCode::Home => b"\x1b[H".to_vec(),
+        KeyCode::End => b"\x1b[F".to_vec(),
+        KeyCode::Insert => b"\x1b[2~".to_vec(),
+        KeyCode::Delete => b"\x1b[3~".to_vec(),
+        KeyCode::PageUp => b"\x1b[5~".to_vec(),
+        KeyCode::PageDown => b"\x1b[6~".to_vec(),
+        KeyCode::F(n @ 1..=4) => vec![0x1b, b'O', b'P' + (n - 1)],
+        KeyCode::F(n @ 5..=12) => {
+            let code = [15, 17, 18, 19, 20, 21, 23, 24][usize::from(n - 5)];
+            format!("\x1b[{code}~").into_bytes()
+        }
+        _ => return None,
+    };
+    // Alt sends the key prefixed with ESC, like xterm's default.
+    if key.modifiers.contains(KeyModifiers::ALT) {
+        return Some([&[0x1b], &bytes[..]].concat());
+    }
+    Some(bytes)
+}
+
+/// Ctrl+letter and the few punctuation keys with a control code.
+fn control_byte(c: char) -> Option<u8> {
+    match c {
+        'a'..='z' | 'A'..='Z' => Some(c.to_ascii_lowercase() as u8 - b'a' + 1),
+        '@' | ' ' | '2' => Some(0x00),
+        '[' | '3' => Some(0x1b),
+        '\\' | '4' => Some(0x1c),
+        ']' | '5' => Some(0x1d),
+        '^' | '6' => Some(0x1e),
+        '_' | '7' => Some(0x1f),
+        _ => None,
+    }
+}
+
+/// What to do with typed input once the exit sequence is filtered out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Input {
+    /// Forward these bytes to the connection (may be empty).
+    Send(Vec<u8>),
+    /// The user typed the escape key then the exit key.
+    Exit,
+    /// The escape key then 'b': send a BREAK.
+    Break,
+    /// The escape key then 'd': flip the DTR line.
+    ToggleDtr,
+    /// The escape key then 'r': flip the RTS line.
+    ToggleRts,
+}
+
+/// The two keys that end a session: the escape key, then the exit key.
+/// Ctrl+A then 'x' unless a profile or the command line picks others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EscapeKeys {
+    escape: u8,
+    exit: u8,
+}
+
+impl EscapeKeys {
+    /// `None` keeps the default key. Both keys have to be ASCII, since they
+    /// are matched against single typed bytes, and they have to differ, or
+    /// the exit key would only ever start another sequence.
+    pub fn new(escape: Option<char>, exit: Option<char>) -> Result<Self, ConnectionError> {
+        let byte = |key: Option<char>, default: u8| match key {
+            None => Ok(default),
+            Some(key) => u8::try_from(key).ok().filter(u8::is_ascii).ok_or_else(|| {
+                ConnectionError::Other(format!("Escape keys must be ASCII, got {key:?}"))
+            }),
+        };
+        let keys = Self {
+            escape: byte(escape, CTRL_A)?,
+            exit: byte(exit, b'x')?,
+        };
+        if keys.escape == keys.exit {
+            return Err(ConnectionError::Other(format!(
+                "Escape and exit key must differ, both are {}",
+                key_name(keys.escape)
+            )));
+        }
+        Ok(keys)
+    }
+
+    /// One line on the keys for the serial lines, e.g. for the start of a
+    /// session.
+    pub fn line_help(&self) -> String {
+        format!(
+            "{} then {:?} sends a BREAK, {:?}/{:?} toggle DTR/RTS",
+            key_name(self.escape),
+            BREAK_KEY as char,
+            DTR_KEY as char,
+            RTS_KEY as char
+        )
+    }
+}
+
+impl Default for EscapeKeys {
+    fn default() -> Self {
+        Self {
+            escape: CTRL_A,
+            exit: b'x',
+        }
+    }
+}
+
+impl fmt::Display for EscapeKeys {
+    /// `Ctrl+A then 'x'`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} then {:?}", key_name(self.escape), self.exit as char)
+    }
+}
+
+/// `Ctrl+A` for control keys, `'q'` for the rest.
+fn key_name(key: u8) -> String {
+    match key {
+        0x01..=0x1a => format!("Ctrl+{}", (key + b'@') as char),
+        key => format!("{:?}", key as char),
+    }
+}
+
+/// Watches typed bytes for the escape key followed by the exit key or one of
+/// the line keys. The escape key itself is never forwarded.
+#[derive(Debug, Clone, Default)]
+pub struct ExitSequence {
+    keys: EscapeKeys,
+    after_escape: bool,
+}
+
+impl ExitSequence {
+    pub fn new(keys: EscapeKeys) -> Self {
+        Self {
+            keys,
+            after_escape: false,
+        }
+    }
+
+    pub fn feed(&mut self, bytes: &[u8]) -> Input {
+        let mut send = Vec::with_capacity(bytes.len());
+        for &byte in bytes {
+            if byte == self.keys.escape {
+                self.after_escape = true;
+                continue;
+            }
+            if self.after_escape {
+                // The exit key wins if it was set to one of the line keys.
+                let command = match byte {
+                    _ if byte == self.keys.exit => Some(Input::Exit),
+                    BREAK_KEY => Some(Input::Break),
+                    DTR_KEY => Some(Input::ToggleDtr),
+                    RTS_KEY => Some(Input::ToggleRts),
+                    _ => None,
+                };
+                if let Some(command) = command {
+                    self.after_escape = false;
+                    return command;
+                }
+            }
+            self.after_escape = false;
+            send.push(byte);
+        }
+        Input::Send(send)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+        KeyEvent::new(code, modifiers)
+    }
+
+    #[test]
+    fn ctrl_a_then_x_exits_from_key_events() {
+        let mut exit = ExitSequence::default();
+        let ctrl_a = key_to_bytes(press(KeyCode::Char('a'), KeyModifiers::CONTROL)).unwrap();
+        let x = key_to_bytes(press(KeyCode::Char('x'), KeyModifiers::NONE)).unwrap();
+
+        assert_eq!(exit.feed(&ctrl_a), Input::Send(Vec::new()));
+        assert_eq!(exit.feed(&x), Input::Exit);
+    }
+
+    #[test]
+    fn ctrl_a_followed_by_anything_else_is_swallowed() {
+        let mut exit = ExitSequence::default();
+
+        assert_eq!(exit.feed(b"\x01y"), Input::Send(b"y".to_vec()));
+        // The sequence has to be consecutive.
+        assert_eq!(exit.feed(b"x"), Input::Send(b"x".to_vec()));
+        assert_eq!(exit.feed(b"ls\r"), Input::Send(b"ls\r".to_vec()));
+    }
+
+    #[test]
+    fn custom_escape_keys_replace_ctrl_a_x() {
+        let keys = EscapeKeys::new(Some('\x02'), Some('q')).unwrap();
+        assert_eq!(keys.to_string(), "Ctrl+B then 'q'");
+        let mut exit = ExitSequence::new(keys);
+
+        assert_eq!(exit.feed(b"\x01x"), Input::Send(b"\x01x".to_vec()));
+        assert_eq!(exit.feed(b"\x02"), Input::Send(Vec::new()));
+        assert_eq!(exit.feed(b"q"), Input::Exit);
+
+        let err = EscapeKeys::new(None, Some('ü')).unwrap_err().to_string();
+        assert!(err.contains("ASCII"), "{err}");
+        assert_eq!(EscapeKeys::new(None, None).unwrap(), EscapeKeys::default());
+    }
+
+    #[test]
+    fn escape_and_exit_key_must_differ() {
+        let err = EscapeKeys::new(Some('q'), Some('q'))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("differ"), "{err}");
+        assert!(EscapeKeys::new(Some('x'), None).is_err());
+        assert!(EscapeKeys::new(None, Some('\x01')).is_err());
+    }
+
+    #[test]
+    fn escape_then_line_keys_are_commands() {
+        let mut exit = ExitSequence::default();
+
+        assert_eq!(exit.feed(b"\x01b"), Input::Break);
+        assert_eq!(exit.feed(b"\x01"), Input::Send(Vec::new()));
+        assert_eq!(exit.feed(b"d"), Input::ToggleDtr);
+        assert_eq!(exit.feed(b"\x01r"), Input::ToggleRts);
+        // Without the escape key they are ordinary input.
+        assert_eq!(exit.feed(b"bdr"), Input::Send(b"bdr".to_vec()));
+
+        let keys = EscapeKeys::new(None, Some('b')).unwrap();
+        assert_eq!(ExitSequence::new(keys).feed(b"\x01b"), Input::Exit);
+        assert_eq!(
+            keys.line_help(),
+            "Ctrl+A then 'b' sends a BREAK, 'd'/'r' toggle DTR/RTS"
+        );
+    }
+
+    #[test]
+    fn keys_map_to_terminal_bytes() {
+        let none = KeyModifiers::NONE;
+        let cases: [(KeyEvent, &[u8]); 9] = [
+            (press(KeyCode::Char('ü'), none), "ü".as_bytes()),
+            (press(KeyCode::Char('c'), KeyModifiers::CONTROL), b"\x03"),
+            (press(KeyCode::Char('b'), KeyModifiers::ALT), b"\x1bb"),
+            (press(KeyCode::Enter, none), b"\r"),
+            (press(KeyCode::Backspace, none), b"\x7f"),
+            (press(KeyCode::Up, none), b"\x1b[A"),
+            (press(KeyCode::Delete, none), b"\x1b[3~"),
+            (press(KeyCode::F(1), none), b"\x1bOP"),
+            (press(KeyCode::F(12), none), b"\x1b[24~"),
+        ];
+        for (key, expected) in cases {
+            assert_eq!(key_to_bytes(key).as_deref(), Some(expected), "{key:?}");
+        }
+    }
+
+    #[test]
+    fn releases_and_unmapped_keys_send_nothing() {
+        let mut release = press(KeyCode::Char('a'), KeyModifiers::NONE);
+        release.kind = KeyEventKind::Release;
+        assert_eq!(key_to_bytes(release), None);
+        assert_eq!(
+            key_to_bytes(press(KeyCode::CapsLock, KeyModifiers::NONE)),
+            None
+        );
+    }
+}
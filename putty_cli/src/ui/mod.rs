@@ -1 +1,7 @@
 pub mod cli;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+pub mod echo;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+pub mod keys;
+#[cfg(any(feature = "serial", feature = "ssh", feature = "telnet"))]
+pub mod terminal;
@@ -0,0 +1,546 @@
+use crate::ui::echo::{send_input, LocalEcho};
+use crate::ui::keys::{key_to_bytes, EscapeKeys, ExitSequence, Input};
+use crossterm::event::{self, Event};
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use log::{info, warn};
+use putty_core::connections::errors::ConnectionError;
+use putty_core::core::connection_manager::ConnectionManager;
+use putty_core::utils::ansi::AnsiStripper;
+use putty_core::utils::line_buffer::LineBuffer;
+use std::io::{IsTerminal, Write};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// How long the BREAK sent by the escape key then 'b' lasts; long enough for
+/// every baud rate in use, like `tcsendbreak`.
+const BREAK_DURATION: Duration = Duration::from_millis(250);
+
+/// When output from the connection is drawn.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Render {
+    /// Every chunk as soon as it arrives; right for interactive shells.
+    #[default]
+    Immediate,
+    /// Only complete lines, to avoid flicker from partial updates on
+    /// line-oriented devices. A partial line (e.g. a prompt) is drawn once
+    /// it has waited `flush_after`.
+    LineBuffered { flush_after: Duration },
+}
+
+/// What the local terminal supports, detected once at startup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Terminal {
+    /// stdin and stdout are both a TTY, so raw mode makes sense.
+    pub interactive: bool,
+    /// stdout is not a TTY (piped to `tee`, a file, ...); escape sequences
+    /// from the remote side are removed so the output stays readable.
+    pub plain_output: bool,
+    /// Columns and rows of the local terminal, when it has a size.
+    pub size: Option<(u16, u16)>,
+    /// Chosen by the user rather than detected; immediate by default.
+    pub render: Render,
+    /// The keys that end the session; also chosen by the user.
+    pub escape: EscapeKeys,
+    /// Output is drawn exactly as received, even when stdout is not a TTY,
+    /// and piped input is sent exactly as read. An interactive terminal
+    /// still watches for the exit sequence, or there would be no way out.
+    pub binary: bool,
+}
+
+impl Terminal {
+    /// Inspect the real stdin/stdout and environment.
+    pub fn detect() -> Self {
+        let mut terminal = Self::from_parts(
+            std::io::stdin().is_terminal(),
+            std::io::stdout().is_terminal(),
+        );
+        if terminal.interactive {
+            terminal.size = crossterm::terminal::size().ok();
+        }
+        terminal
+    }
+
+    /// Derive the settings from the individual checks.
+    pub fn from_parts(stdin_tty: bool, stdout_tty: bool) -> Self {
+        Self {
+            interactive: stdin_tty && stdout_tty,
+            plain_output: !stdout_tty,
+            size: None,
+            render: Render::Immediate,
+            escape: EscapeKeys::default(),
+            binary: false,
+        }
+    }
+}
+
+/// Puts the terminal into raw mode and restores it when dropped, on every
+/// way out of the session.
+struct RawMode;
+
+impl RawMode {
+    /// Enable raw mode via crossterm. This disables line-buffering and echo
+    /// on all supported platforms.
+    fn enable() -> Result<Self, ConnectionError> {
+        enable_raw_mode()
+            .map_err(|e| ConnectionError::Other(format!("Failed to enable raw mode: {e}")))?;
+        Ok(Self)
+    }
+}
+
+impl Drop for RawMode {
+    /// crossterm internally remembers the previous mode and restores it.
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+    }
+}
+
+/// Read terminal events on a thread, since crossterm's reader blocks. The
+/// thread polls so it notices when the session drops the receiver.
+fn spawn_event_reader() -> mpsc::Receiver<Event> {
+    let (tx, rx) = mpsc::channel(64);
+    std::thread::spawn(move || {
+        while !tx.is_closed() {
+            match event::poll(Duration::from_millis(50)) {
+                Ok(true) => match event::read() {
+                    Ok(event) => {
+                        if tx.blocking_send(event).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                },
+                Ok(false) => {}
+                Err(_) => break,
+            }
+        }
+    });
+    rx
+}
+
+/// Forward typed input to connection `id` and what arrives on
+/// `connection_receiver` to `output`, until the user types the exit sequence
+/// of `terminal` (Ctrl+A then 'x' by default), the input ends or the
+/// connection closes. In binary mode piped input is never checked for the
+/// exit sequence.
+///
+/// The escape key followed by 'b', 'd' or 'r' sends a BREAK or toggles DTR
+/// or RTS instead; both lines count as raised when the session starts, as
+/// after opening a serial port.
+///
+/// An interactive terminal is read as crossterm key events, which works the
+/// same on every platform, and local resizes are passed on to the
+/// connection. Otherwise `input` (piped stdin) is read byte by byte.
+pub async fn run_session(
+    connection_manager: &ConnectionManager,
+    id: &str,
+    mut connection_receiver: broadcast::Receiver<Vec<u8>>,
+    terminal: Terminal,
+    local_echo: LocalEcho,
+    mut input: impl AsyncRead + Unpin,
+    output: &mut impl Write,
+) -> Result<(), ConnectionError> {
+    let mut stripper = (terminal.plain_output && !terminal.binary).then(AnsiStripper::new);
+    let (mut line_buffer, flush_after) = match terminal.render {
+        Render::Immediate => (None, Duration::ZERO),
+        Render::LineBuffered { flush_after } => (Some(LineBuffer::new()), flush_after),
+    };
+    // When the partial line held in `line_buffer` is drawn anyway.
+    let mut flush_deadline = Instant::now();
+
+    let _raw_mode = if terminal.interactive {
+        info!(
+            "Enable raw mode. Press {} to exit the program.",
+            terminal.escape
+        );
+        info!("{}.", terminal.escape.line_help());
+        Some(RawMode::enable()?)
+    } else {
+        None
+    };
+
+    let mut events = terminal.interactive.then(spawn_event_reader);
+    let mut exit =
+        (terminal.interactive || !terminal.binary).then(|| ExitSequence::new(terminal.escape));
+    let (mut dtr, mut rts) = (true, true);
+    let mut buf = [0u8; 1];
+    loop {
+        let typed = tokio::select! {
+            read = input.read(&mut buf), if events.is_none() => {
+                if !matches!(read, Ok(1)) {
+                    break;
+                }
+                buf.to_vec()
+            }
+            event = async { events.as_mut().expect("checked by the guard").recv().await },
+                if events.is_some() =>
+            {
+                match event {
+                    Some(Event::Key(key)) => match key_to_bytes(key) {
+                        Some(bytes) => bytes,
+                        None => continue,
+                    },
+                    Some(Event::Resize(cols, rows)) => {
+                        let _ = connection_manager.resize(id, cols, rows).await;
+                        continue;
+                    }
+                    Some(_) => continue,
+                    None => break,
+                }
+            }
+            chunk = connection_receiver.recv() => match chunk {
+                Ok(chunk) => {
+                    let chunk = match stripper.as_mut() {
+                        Some(stripper) => stripper.strip(&chunk),
+                        None => chunk,
+                    };
+                    let chunk = match line_buffer.as_mut() {
+                        Some(line_buffer) => {
+                            let was_pending = line_buffer.has_pending();
+                            let ready = line_buffer.push(&chunk);
+                            // The timer runs from the start of the partial line.
+                            if line_buffer.has_pending() && (!was_pending || !ready.is_empty()) {
+                                flush_deadline = Instant::now() + flush_after;
+                            }
+                            ready
+                        }
+                        None => chunk,
+                    };
+                    draw(output, &chunk);
+                    continue;
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            },
+            _ = tokio::time::sleep_until(flush_deadline),
+                if line_buffer.as_ref().is_some_and(LineBuffer::has_pending) =>
+            {
+                if let Some(partial) = line_buffer.as_mut().and_then(LineBuffer::take_pending) {
+                    draw(output, &partial);
+                }
+                continue;
+            },
+        };
+        let typed = match exit.as_mut() {
+            Some(exit) => exit.feed(&typed),
+            None => Input::Send(typed),
+        };
+        match typed {
+            Input::Exit => {
+                info!("Exiting...");
+                break;
+            }
+            Input::Send(bytes) if !bytes.is_empty() => {
+                send_input(connection_manager, id, &bytes, local_echo, output).await;
+            }
+            Input::Send(_) => {}
+            Input::Break => match connection_manager.send_break(id, BREAK_DURATION).await {
+                Ok(()) => info!("Sent BREAK"),
+                Err(e) => warn!("BREAK failed: {e}"),
+            },
+            Input::ToggleDtr => match connection_manager.set_dtr(id, !dtr).await {
+                Ok(()) => {
+                    dtr = !dtr;
+                    info!("DTR {}", if dtr { "high" } else { "low" });
+                }
+                Err(e) => warn!("Setting DTR failed: {e}"),
+            },
+            Input::ToggleRts => match connection_manager.set_rts(id, !rts).await {
+                Ok(()) => {
+                    rts = !rts;
+                    info!("RTS {}", if rts { "high" } else { "low" });
+                }
+                Err(e) => warn!("Setting RTS failed: {e}"),
+            },
+        }
+    }
+    if let Some(partial) = line_buffer.as_mut().and_then(LineBuffer::take_pending) {
+        draw(output, &partial);
+    }
+    Ok(())
+}
+
+fn draw(output: &mut impl Write, bytes: &[u8]) {
+    if bytes.is_empty() {
+        return;
+    }
+    let _ = output.write_all(bytes);
+    let _ = output.flush();
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use putty_core::connections::connection::Connection;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use tokio::io::AsyncWriteExt;
+    use tokio::sync::mpsc;
+
+    /// Connection that reads whatever the test sends on a channel.
+    pub(crate) struct ChannelConnection {
+        pub(crate) incoming: mpsc::Receiver<Vec<u8>>,
+    }
+
+    #[async_trait]
+    impl Connection for ChannelConnection {
+        async fn connect(&mut self) -> Result<(), ConnectionError> {
+            Ok(())
+        }
+
+        async fn disconnect(&mut self) -> Result<(), ConnectionError> {
+            Ok(())
+        }
+
+        async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError> {
+            Ok(data.len())
+        }
+
+        async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ConnectionError> {
+            match self.incoming.recv().await {
+                Some(chunk) => {
+                    buffer[..chunk.len()].copy_from_slice(&chunk);
+                    Ok(chunk.len())
+                }
+                None => std::future::pending().await,
+            }
+        }
+    }
+
+    /// `Write` handle the test can inspect while the session still owns it.
+    #[derive(Clone, Default)]
+    pub(crate) struct SharedOutput(pub(crate) Arc<Mutex<Vec<u8>>>);
+
+    impl Write for SharedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn piped_stdout_is_not_interactive_and_plain() {
+        let terminal = Terminal::from_parts(true, false);
+        assert!(!terminal.interactive);
+        assert!(terminal.plain_output);
+
+        let terminal = Terminal::from_parts(false, true);
+        assert!(
+            !terminal.interactive,
+            "piped stdin cannot be put in raw mode"
+        );
+        assert!(!terminal.plain_output);
+
+        let terminal = Terminal::from_parts(true, true);
+        assert!(terminal.interactive);
+        assert!(!terminal.plain_output);
+    }
+
+    #[tokio::test]
+    async fn non_tty_session_skips_raw_mode_and_strips_escapes() {
+        let connection_manager = ConnectionManager::new();
+        let (device_tx, incoming) = mpsc::channel(8);
+        connection_manager
+            .add_connection("piped".into(), Box::new(ChannelConnection { incoming }))
+            .await
+            .expect("add_connection should succeed");
+
+        let (mut keyboard, input) = tokio::io::duplex(16);
+        let output = SharedOutput::default();
+        let connection_receiver = connection_manager.subscribe("piped").await.unwrap();
+        let session = {
+            let connection_manager = connection_manager.clone();
+            let mut output = output.clone();
+            tokio::spawn(async move {
+                run_session(
+                    &connection_manager,
+                    "piped",
+                    connection_receiver,
+                    Terminal::from_parts(false, false),
+                    LocalEcho::Off,
+                    input,
+                    &mut output,
+                )
+                .await
+            })
+        };
+
+        device_tx
+            .send(b"\x1b[1;32mgreen\x1b[0m\r\n".to_vec())
+            .await
+            .unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while output.0.lock().unwrap().is_empty() {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("no output reached the terminal");
+        assert!(!crossterm::terminal::is_raw_mode_enabled().unwrap_or(false));
+
+        keyboard.write_all(b"\x01x").await.unwrap();
+        session.await.unwrap().unwrap();
+        assert_eq!(output.0.lock().unwrap().as_slice(), b"green\r\n");
+    }
+
+    #[tokio::test]
+    async fn line_keys_on_a_transport_without_lines_keep_the_session() {
+        let connection_manager = ConnectionManager::new();
+        let (_device_tx, incoming) = mpsc::channel(8);
+        connection_manager
+            .add_connection("shell".into(), Box::new(ChannelConnection { incoming }))
+            .await
+            .expect("add_connection should succeed");
+
+        let (mut keyboard, input) = tokio::io::duplex(16);
+        let connection_receiver = connection_manager.subscribe("shell").await.unwrap();
+        let session = {
+            let connection_manager = connection_manager.clone();
+            tokio::spawn(async move {
+                run_session(
+                    &connection_manager,
+                    "shell",
+                    connection_receiver,
+                    Terminal::from_parts(false, false),
+                    LocalEcho::Off,
+                    input,
+                    &mut SharedOutput::default(),
+                )
+                .await
+            })
+        };
+
+        // Refused by the transport, then the session still exits normally.
+        keyboard.write_all(b"\x01b\x01d\x01r\x01x").await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), session)
+            .await
+            .expect("the session hung")
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn binary_session_passes_bytes_through_untouched() {
+        const PAYLOAD: &[u8] = b"\x01x\r\n\x1b[1;32m\n\x00\xff";
+
+        let connection_manager = ConnectionManager::new();
+        let (device_tx, incoming) = mpsc::channel(8);
+        connection_manager
+            .add_connection("pipe".into(), Box::new(ChannelConnection { incoming }))
+            .await
+            .expect("add_connection should succeed");
+
+        let (mut keyboard, input) = tokio::io::duplex(16);
+        let output = SharedOutput::default();
+        let connection_receiver = connection_manager.subscribe("pipe").await.unwrap();
+        // stdout is not a TTY, which would normally strip escapes.
+        let mut terminal = Terminal::from_parts(false, false);
+        terminal.binary = true;
+        let session = {
+            let connection_manager = connection_manager.clone();
+            let mut output = output.clone();
+            tokio::spawn(async move {
+                run_session(
+                    &connection_manager,
+                    "pipe",
+                    connection_receiver,
+                    terminal,
+                    LocalEcho::Acked,
+                    input,
+                    &mut output,
+                )
+                .await
+            })
+        };
+        let shown_until = |expected: Vec<u8>| {
+            let output = output.clone();
+            async move {
+                tokio::time::timeout(Duration::from_secs(1), async {
+                    while *output.0.lock().unwrap() != expected {
+                        tokio::time::sleep(Duration::from_millis(5)).await;
+                    }
+                })
+                .await
+            }
+        };
+
+        // ── Read: drawn as received ──────────────────────────────────────
+        device_tx.send(PAYLOAD.to_vec()).await.unwrap();
+        shown_until(PAYLOAD.to_vec())
+            .await
+            .expect("device bytes were changed on the way out");
+
+        // ── Write: Ctrl+A then 'x' is data, the echo shows the acked bytes ─
+        keyboard.write_all(PAYLOAD).await.unwrap();
+        shown_until([PAYLOAD, PAYLOAD].concat())
+            .await
+            .expect("typed bytes were changed on the way in");
+
+        drop(keyboard);
+        session.await.unwrap().unwrap();
+    }
+
+    #[tokio::test]
+    async fn line_buffered_session_draws_whole_lines() {
+        let connection_manager = ConnectionManager::new();
+        let (device_tx, incoming) = mpsc::channel(8);
+        connection_manager
+            .add_connection("lines".into(), Box::new(ChannelConnection { incoming }))
+            .await
+            .expect("add_connection should succeed");
+
+        let (mut keyboard, input) = tokio::io::duplex(16);
+        let output = SharedOutput::default();
+        let connection_receiver = connection_manager.subscribe("lines").await.unwrap();
+        let mut terminal = Terminal::from_parts(false, true);
+        terminal.render = Render::LineBuffered {
+            flush_after: Duration::from_millis(150),
+        };
+        let session = {
+            let connection_manager = connection_manager.clone();
+            let mut output = output.clone();
+            tokio::spawn(async move {
+                run_session(
+                    &connection_manager,
+                    "lines",
+                    connection_receiver,
+                    terminal,
+                    LocalEcho::Off,
+                    input,
+                    &mut output,
+                )
+                .await
+            })
+        };
+        let shown = || output.0.lock().unwrap().clone();
+
+        // ── A partial line is held back ──────────────────────────────────
+        device_tx.send(b"boo".to_vec()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(shown().is_empty(), "{:?}", shown());
+
+        // ── Completing it draws the line, the next partial waits ─────────
+        device_tx.send(b"t\r\nlogin: ".to_vec()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(shown(), b"boot\r\n");
+
+        // ── ... until the flush timeout draws it anyway ──────────────────
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while shown() != b"boot\r\nlogin: " {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("the prompt was never drawn");
+
+        keyboard.write_all(b"\x01x").await.unwrap();
+        session.await.unwrap().unwrap();
+    }
+}
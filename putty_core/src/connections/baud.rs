@@ -0,0 +1,120 @@
+use crate::connections::errors::ConnectionError;
+use std::fmt;
+use std::str::FromStr;
+
+/// A validated serial baud rate.
+///
+/// Standard rates have their own variant; anything else within
+/// [`Baud::MIN`]..=[`Baud::MAX`] is kept as `Other`, for devices such as 3D
+/// printers (250000) or custom UARTs. Values outside that range are rejected
+/// up front instead of failing cryptically in the driver.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Baud {
+    B300,
+    B1200,
+    B2400,
+    B4800,
+    B9600,
+    B19200,
+    B38400,
+    B57600,
+    #[default]
+    B115200,
+    B230400,
+    B460800,
+    B921600,
+    Other(u32),
+}
+
+impl Baud {
+    /// Lowest rate accepted as `Other`.
+    pub const MIN: u32 = 50;
+    /// Highest rate accepted as `Other` (fast USB-UART bridges reach 12 Mbaud).
+    pub const MAX: u32 = 12_000_000;
+
+    /// The standard rates, slowest first, e.g. for a dropdown.
+    pub const COMMON: [Baud; 12] = [
+        Baud::B300,
+        Baud::B1200,
+        Baud::B2400,
+        Baud::B4800,
+        Baud::B9600,
+        Baud::B19200,
+        Baud::B38400,
+        Baud::B57600,
+        Baud::B115200,
+        Baud::B230400,
+        Baud::B460800,
+        Baud::B921600,
+    ];
+
+    /// Validate `rate`, mapping standard values to their named variant.
+    pub fn new(rate: u32) -> Result<Self, ConnectionError> {
+        if let Some(baud) = Self::COMMON.into_iter().find(|b| b.as_u32() == rate) {
+            return Ok(baud);
+        }
+        if (Self::MIN..=Self::MAX).contains(&rate) {
+            Ok(Baud::Other(rate))
+        } else {
+            Err(ConnectionError::Other(format!(
+                "Invalid baud rate {rate}: expected {}..={}",
+                Self::MIN,
+                Self::MAX
+            )))
+        }
+    }
+
+    pub fn as_u32(self) -> u32 {
+        match self {
+            Baud::B300 => 300,
+            Baud::B1200 => 1200,
+            Baud::B2400 => 2400,
+            Baud::B4800 => 4800,
+            Baud::B9600 => 9600,
+            Baud::B19200 => 19200,
+            Baud::B38400 => 38400,
+            Baud::B57600 => 57600,
+            Baud::B115200 => 115_200,
+            Baud::B230400 => 230_400,
+            Baud::B460800 => 460_800,
+            Baud::B921600 => 921_600,
+            Baud::Other(rate) => rate,
+        }
+    }
+
+    /// Whether this is one of the [`COMMON`](Self::COMMON) rates.
+    pub fn is_standard(self) -> bool {
+        !matches!(self, Baud::Other(_))
+    }
+}
+
+impl TryFrom<u32> for Baud {
+    type Error = ConnectionError;
+
+    fn try_from(rate: u32) -> Result<Self, Self::Error> {
+        Baud::new(rate)
+    }
+}
+
+impl From<Baud> for u32 {
+    fn from(baud: Baud) -> Self {
+        baud.as_u32()
+    }
+}
+
+impl FromStr for Baud {
+    type Err = ConnectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let rate = s
+            .parse()
+            .map_err(|_| ConnectionError::Other(format!("Invalid baud rate '{s}'")))?;
+        Baud::new(rate)
+    }
+}
+
+impl fmt::Display for Baud {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_u32())
+    }
+}
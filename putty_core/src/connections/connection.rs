@@ -1,5 +1,34 @@
 use crate::connections::errors::ConnectionError;
+use crate::connections::forward::LocalForward;
+use crate::connections::framing::ModemStatus;
+#[cfg(feature = "ssh")]
+use crate::connections::ssh::SftpClient;
 use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+/// Parameters actually in effect after `connect`, as reported by the transport
+/// (e.g. the SSH server version and cipher, or the applied serial settings).
+pub type NegotiatedParams = BTreeMap<String, String>;
+
+/// Milestones reached while `connect` runs, so UIs can show e.g.
+/// "Authenticating...". Transports report the ones that apply to them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectProgress {
+    /// The TCP connection to the server is up.
+    TcpConnected,
+    /// The protocol handshake (SSH key exchange) finished.
+    Handshaked,
+    /// The server accepted the credentials.
+    Authenticated,
+    /// The remote shell is running; the connection is usable.
+    ShellReady,
+}
+
+/// Where a transport sends its [`ConnectProgress`].
+pub type ProgressSender = mpsc::UnboundedSender<ConnectProgress>;
 
 /// A trait representing a generic connection (serial, SSH, etc.).
 #[async_trait]
@@ -9,4 +38,109 @@ pub trait Connection {
 
     async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError>;
     async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ConnectionError>;
+
+    /// Wait until everything passed to `write` has left the transport's
+    /// buffers. Transports that hand each write straight to the socket
+    /// have nothing to do.
+    async fn flush(&mut self) -> Result<(), ConnectionError> {
+        Ok(())
+    }
+
+    /// Whether the transport is currently usable: `connect` succeeded and
+    /// neither side has closed it since. Cheap and non-blocking, so it only
+    /// reflects what the transport already knows; a peer that vanished
+    /// without a word may still count as connected until the next read or
+    /// write fails. Transports that cannot tell always count as connected.
+    fn is_connected(&self) -> bool {
+        true
+    }
+
+    /// Report [`ConnectProgress`] to `progress` during the next `connect`.
+    /// Transports without distinct phases ignore it.
+    fn set_progress(&mut self, _progress: ProgressSender) {}
+
+    /// Short transport name used in diagnostics, e.g. `"serial"` or `"ssh"`.
+    fn kind(&self) -> &'static str {
+        "unknown"
+    }
+
+    /// Parameters negotiated by the last successful `connect`.
+    fn negotiated(&self) -> NegotiatedParams {
+        NegotiatedParams::new()
+    }
+
+    /// Current PTY size as `(cols, rows)`, or `None` if the transport has no PTY.
+    fn pty_size(&self) -> Option<(u16, u16)> {
+        None
+    }
+
+    /// Resize the PTY. Transports without a PTY ignore the request.
+    async fn resize(&mut self, _cols: u16, _rows: u16) -> Result<(), ConnectionError> {
+        Ok(())
+    }
+
+    /// Raise the DTR and RTS modem-control lines again. Transports without
+    /// control lines ignore the request.
+    async fn assert_modem_lines(&mut self) -> Result<(), ConnectionError> {
+        Ok(())
+    }
+
+    /// Drive the DTR modem-control line high (`true`) or low. Transports
+    /// without control lines refuse.
+    async fn set_dtr(&mut self, _level: bool) -> Result<(), ConnectionError> {
+        Err(ConnectionError::Other(format!(
+            "{} connections have no DTR line",
+            self.kind()
+        )))
+    }
+
+    /// Drive the RTS modem-control line high (`true`) or low. Transports
+    /// without control lines refuse.
+    async fn set_rts(&mut self, _level: bool) -> Result<(), ConnectionError> {
+        Err(ConnectionError::Other(format!(
+            "{} connections have no RTS line",
+            self.kind()
+        )))
+    }
+
+    /// Hold the line in the break condition for `duration`, after what was
+    /// written before has been sent. Transports without a line refuse.
+    async fn send_break(&mut self, _duration: Duration) -> Result<(), ConnectionError> {
+        Err(ConnectionError::Other(format!(
+            "{} connections cannot send a break",
+            self.kind()
+        )))
+    }
+
+    /// Read the CTS, DSR, DCD and RI input lines. Transports without
+    /// control lines refuse.
+    async fn modem_status(&mut self) -> Result<ModemStatus, ConnectionError> {
+        Err(ConnectionError::Other(format!(
+            "{} connections have no modem status lines",
+            self.kind()
+        )))
+    }
+
+    /// Start listening for `forward` and tunnel every accepted TCP connection
+    /// through this connection until it disconnects. Returns the address
+    /// actually listened on. Transports that cannot tunnel refuse.
+    async fn local_forward(
+        &mut self,
+        _forward: &LocalForward,
+    ) -> Result<SocketAddr, ConnectionError> {
+        Err(ConnectionError::Other(format!(
+            "{} connections cannot forward ports",
+            self.kind()
+        )))
+    }
+
+    /// Start an SFTP client next to the session. Transports without file
+    /// transfer refuse.
+    #[cfg(feature = "ssh")]
+    async fn sftp(&mut self) -> Result<SftpClient, ConnectionError> {
+        Err(ConnectionError::Other(format!(
+            "{} connections cannot transfer files",
+            self.kind()
+        )))
+    }
 }
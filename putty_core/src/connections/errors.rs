@@ -5,6 +5,34 @@ use std::fmt::{self, Display};
 pub enum ConnectionError {
     IoError(std::io::Error),
     PortError(String),
+    /// A verified write was echoed back differently than it was sent.
+    EchoMismatch {
+        /// Offset of the first differing byte.
+        offset: usize,
+        expected: u8,
+        actual: u8,
+    },
+    /// The host name could not be resolved.
+    DnsError(String),
+    /// The host resolved, but no TCP connection could be made (refused,
+    /// unreachable, timed out).
+    TcpConnectError(String),
+    /// TCP connected, but the protocol handshake (e.g. SSH key exchange or
+    /// host key check) failed.
+    HandshakeError(String),
+    /// The server rejected our credentials.
+    AuthError(String),
+    /// The connection already has `limit` writes outstanding; try again once
+    /// some of them have completed.
+    Busy {
+        limit: usize,
+    },
+    /// The remote side ended the session, e.g. because the user typed `exit`
+    /// in the shell. `exit_status` is what the remote command reported, if
+    /// it did.
+    RemoteClosed {
+        exit_status: Option<u32>,
+    },
     Other(String),
 }
 
@@ -22,11 +50,42 @@ impl From<tokio_serial::Error> for ConnectionError {
     }
 }
 
+#[cfg(feature = "websocket")]
+impl From<tokio_tungstenite::tungstenite::Error> for ConnectionError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        match err {
+            tokio_tungstenite::tungstenite::Error::Io(e) => ConnectionError::IoError(e),
+            other => ConnectionError::Other(format!("WebSocket: {other}")),
+        }
+    }
+}
+
 impl Display for ConnectionError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             ConnectionError::IoError(e) => write!(f, "IO error: {e}"),
             ConnectionError::PortError(msg) => write!(f, "Port error: {msg}"),
+            ConnectionError::EchoMismatch {
+                offset,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "Echo mismatch at byte {offset}: expected {expected:#04x}, got {actual:#04x}"
+            ),
+            ConnectionError::DnsError(msg) => write!(f, "DNS error: {msg}"),
+            ConnectionError::TcpConnectError(msg) => write!(f, "TCP connect error: {msg}"),
+            ConnectionError::HandshakeError(msg) => write!(f, "Handshake error: {msg}"),
+            ConnectionError::AuthError(msg) => write!(f, "Authentication error: {msg}"),
+            ConnectionError::Busy { limit } => {
+                write!(f, "Busy: {limit} writes already in flight")
+            }
+            ConnectionError::RemoteClosed {
+                exit_status: Some(status),
+            } => write!(f, "Remote closed the connection (status {status})"),
+            ConnectionError::RemoteClosed { exit_status: None } => {
+                write!(f, "Remote closed the connection")
+            }
             ConnectionError::Other(msg) => write!(f, "Other error: {msg}"),
         }
     }
@@ -0,0 +1,165 @@
+use crate::connections::errors::ConnectionError;
+use std::fmt;
+use std::str::FromStr;
+
+/// A local port forward in the form of `ssh -L [bind_address:]port:host:hostport`:
+/// TCP connections accepted on `bind_address:bind_port` are tunnelled to
+/// `host:port`, which is resolved and connected to by the remote side.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalForward {
+    /// Local address to listen on, `127.0.0.1` unless given.
+    pub bind_address: String,
+    /// Local port to listen on; `0` picks a free one.
+    pub bind_port: u16,
+    pub host: String,
+    pub port: u16,
+}
+
+impl LocalForward {
+    /// Parse `[bind_address:]port:host:hostport`, e.g. `8080:internal:80`.
+    /// IPv6 addresses go in brackets, as in `[::1]:8080:[fd00::7]:80`.
+    pub fn parse(spec: &str) -> Result<Self, ConnectionError> {
+        let (bind_address, bind_port, host, port) = parse_spec(spec)?;
+        Ok(Self {
+            bind_address: bind_address.unwrap_or("127.0.0.1").to_owned(),
+            bind_port,
+            host,
+            port,
+        })
+    }
+}
+
+impl FromStr for LocalForward {
+    type Err = ConnectionError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        Self::parse(spec)
+    }
+}
+
+impl fmt::Display for LocalForward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            Bracketed(&self.bind_address),
+            self.bind_port,
+            Bracketed(&self.host),
+            self.port
+        )
+    }
+}
+
+/// A remote port forward in the form of `ssh -R [bind_address:]port:host:hostport`:
+/// the server listens on `bind_address:bind_port` and every connection it
+/// accepts there is tunnelled back and connected to `host:port` from here.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteForward {
+    /// Address for the server to listen on, `localhost` unless given. Other
+    /// addresses need `GatewayPorts` enabled on the server.
+    pub bind_address: String,
+    /// Port for the server to listen on; `0` lets it pick one.
+    pub bind_port: u16,
+    pub host: String,
+    pub port: u16,
+}
+
+impl RemoteForward {
+    /// Parse `[bind_address:]port:host:hostport`, e.g. `9000:localhost:3000`.
+    /// IPv6 addresses go in brackets, as in `[::]:9000:[::1]:3000`.
+    pub fn parse(spec: &str) -> Result<Self, ConnectionError> {
+        let (bind_address, bind_port, host, port) = parse_spec(spec)?;
+        Ok(Self {
+            bind_address: bind_address.unwrap_or("localhost").to_owned(),
+            bind_port,
+            host,
+            port,
+        })
+    }
+}
+
+impl FromStr for RemoteForward {
+    type Err = ConnectionError;
+
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        Self::parse(spec)
+    }
+}
+
+impl fmt::Display for RemoteForward {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}:{}:{}",
+            Bracketed(&self.bind_address),
+            self.bind_port,
+            Bracketed(&self.host),
+            self.port
+        )
+    }
+}
+
+/// Split `[bind_address:]port:host:hostport` into its parts.
+fn parse_spec(spec: &str) -> Result<(Option<&str>, u16, String, u16), ConnectionError> {
+    let invalid = || {
+        ConnectionError::Other(format!(
+            "Invalid forward {spec:?}, expected [bind_address:]port:host:hostport"
+        ))
+    };
+    let parts = split_fields(spec).ok_or_else(invalid)?;
+    let (bind_address, bind_port, host, port) = match parts.as_slice() {
+        [bind_port, host, port] => (None, bind_port, host, port),
+        [bind_address, bind_port, host, port] => (Some(*bind_address), bind_port, host, port),
+        _ => return Err(invalid()),
+    };
+    if bind_address == Some("") || host.is_empty() {
+        return Err(invalid());
+    }
+    Ok((
+        bind_address,
+        bind_port.parse().map_err(|_| invalid())?,
+        (*host).to_owned(),
+        port.parse().map_err(|_| invalid())?,
+    ))
+}
+
+/// Split `spec` at colons, taking a `[...]` field whole and without its
+/// brackets so that IPv6 addresses can be given. `None` if a bracket is not
+/// closed or is not followed by a colon or the end.
+fn split_fields(spec: &str) -> Option<Vec<&str>> {
+    let mut fields = Vec::new();
+    let mut rest = spec;
+    loop {
+        let field;
+        if let Some(bracketed) = rest.strip_prefix('[') {
+            let end = bracketed.find(']')?;
+            field = &bracketed[..end];
+            rest = &bracketed[end + 1..];
+            if !rest.is_empty() && !rest.starts_with(':') {
+                return None;
+            }
+        } else {
+            let end = rest.find(':').unwrap_or(rest.len());
+            field = &rest[..end];
+            rest = &rest[end..];
+        }
+        fields.push(field);
+        match rest.strip_prefix(':') {
+            Some(next) => rest = next,
+            None => return Some(fields),
+        }
+    }
+}
+
+/// An address as written in a forward spec: in brackets if it has colons.
+struct Bracketed<'a>(&'a str);
+
+impl fmt::Display for Bracketed<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.contains(':') {
+            write!(f, "[{}]", self.0)
+        } else {
+            f.write_str(self.0)
+        }
+    }
+}
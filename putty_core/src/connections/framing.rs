@@ -0,0 +1,216 @@
+use crate::connections::errors::ConnectionError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Number of data bits per character.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum DataBits {
+    Five,
+    Six,
+    Seven,
+    #[default]
+    Eight,
+}
+
+/// Parity bit appended to every character.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Parity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+/// Number of stop bits after every character.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum StopBits {
+    #[default]
+    One,
+    Two,
+}
+
+/// How the two ends of a serial line throttle each other.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FlowControl {
+    /// Send as fast as the baud rate allows; a device with small buffers
+    /// drops what it cannot take.
+    #[default]
+    None,
+    /// XON/XOFF (0x11/0x13) in the data stream. Needs no extra wires, but
+    /// those two bytes cannot be sent as data, so it is unfit for binary
+    /// protocols.
+    Software,
+    /// RTS/CTS: the driver stops sending while the device drops CTS. Works
+    /// for any data, but only when both lines are wired through; on a
+    /// cable without them the port may never send at all.
+    Hardware,
+}
+
+/// The input lines of a serial port, as raised (`true`) by the device.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct ModemStatus {
+    /// Clear To Send: the device can take data.
+    pub cts: bool,
+    /// Data Set Ready: the device is powered and present.
+    pub dsr: bool,
+    /// Data Carrier Detect: a modem has a carrier.
+    pub dcd: bool,
+    /// Ring Indicator: a modem sees an incoming call.
+    pub ri: bool,
+}
+
+/// Character format and flow control of a serial line. The default is the
+/// common 8N1 without flow control.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Framing {
+    pub data_bits: DataBits,
+    pub parity: Parity,
+    pub stop_bits: StopBits,
+    pub flow_control: FlowControl,
+}
+
+impl TryFrom<u8> for DataBits {
+    type Error = ConnectionError;
+
+    fn try_from(bits: u8) -> Result<Self, Self::Error> {
+        match bits {
+            5 => Ok(DataBits::Five),
+            6 => Ok(DataBits::Six),
+            7 => Ok(DataBits::Seven),
+            8 => Ok(DataBits::Eight),
+            _ => Err(ConnectionError::Other(format!(
+                "Invalid data bits {bits}: expected 5..=8"
+            ))),
+        }
+    }
+}
+
+impl From<DataBits> for u8 {
+    fn from(bits: DataBits) -> Self {
+        match bits {
+            DataBits::Five => 5,
+            DataBits::Six => 6,
+            DataBits::Seven => 7,
+            DataBits::Eight => 8,
+        }
+    }
+}
+
+impl TryFrom<u8> for StopBits {
+    type Error = ConnectionError;
+
+    fn try_from(bits: u8) -> Result<Self, Self::Error> {
+        match bits {
+            1 => Ok(StopBits::One),
+            2 => Ok(StopBits::Two),
+            _ => Err(ConnectionError::Other(format!(
+                "Invalid stop bits {bits}: expected 1 or 2"
+            ))),
+        }
+    }
+}
+
+impl From<StopBits> for u8 {
+    fn from(bits: StopBits) -> Self {
+        match bits {
+            StopBits::One => 1,
+            StopBits::Two => 2,
+        }
+    }
+}
+
+impl FromStr for DataBits {
+    type Err = ConnectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bits = s
+            .parse::<u8>()
+            .map_err(|_| ConnectionError::Other(format!("Invalid data bits '{s}'")))?;
+        DataBits::try_from(bits)
+    }
+}
+
+impl FromStr for StopBits {
+    type Err = ConnectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bits = s
+            .parse::<u8>()
+            .map_err(|_| ConnectionError::Other(format!("Invalid stop bits '{s}'")))?;
+        StopBits::try_from(bits)
+    }
+}
+
+impl FromStr for Parity {
+    type Err = ConnectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" | "n" => Ok(Parity::None),
+            "odd" | "o" => Ok(Parity::Odd),
+            "even" | "e" => Ok(Parity::Even),
+            _ => Err(ConnectionError::Other(format!(
+                "Invalid parity '{s}': expected none, odd or even"
+            ))),
+        }
+    }
+}
+
+impl FromStr for FlowControl {
+    type Err = ConnectionError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "none" => Ok(FlowControl::None),
+            "software" | "xonxoff" => Ok(FlowControl::Software),
+            "hardware" | "rtscts" => Ok(FlowControl::Hardware),
+            _ => Err(ConnectionError::Other(format!(
+                "Invalid flow control '{s}': expected none, software or hardware"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for DataBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", u8::from(*self))
+    }
+}
+
+impl fmt::Display for StopBits {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", u8::from(*self))
+    }
+}
+
+impl fmt::Display for Parity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Parity::None => "none",
+            Parity::Odd => "odd",
+            Parity::Even => "even",
+        })
+    }
+}
+
+impl fmt::Display for FlowControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            FlowControl::None => "none",
+            FlowControl::Software => "software",
+            FlowControl::Hardware => "hardware",
+        })
+    }
+}
+
+/// The usual short form, e.g. `8N1` or `7E2`; flow control is not part of it.
+impl fmt::Display for Framing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parity = match self.parity {
+            Parity::None => 'N',
+            Parity::Odd => 'O',
+            Parity::Even => 'E',
+        };
+        write!(f, "{}{parity}{}", self.data_bits, self.stop_bits)
+    }
+}
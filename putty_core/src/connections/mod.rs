@@ -1,10 +1,20 @@
+pub mod baud;
 pub mod connection;
 pub mod errors;
+pub mod forward;
+pub mod framing;
 #[cfg(feature = "serial")]
 pub mod serial;
 #[cfg(feature = "ssh")]
 pub mod ssh;
+pub mod tcp;
+pub mod telnet;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 // Re-export the modules here for easy import elsewhere.
+pub use baud::*;
 pub use connection::*;
 pub use errors::*;
+pub use forward::*;
+pub use framing::*;
@@ -0,0 +1,93 @@
+use crate::connections::errors::ConnectionError;
+use crate::utils::escape::unescape;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Bytes sent to a serial device right after it is opened, e.g. `ATZ\r` to
+/// reset a modem, before any user input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitString {
+    pub bytes: Vec<u8>,
+    /// Pause between opening the port and sending `bytes`, for devices that
+    /// need time after DTR rises.
+    pub delay: Duration,
+    /// Reply that must arrive (e.g. `OK`) before the connection counts as
+    /// open. Output read while waiting is consumed, not shown to subscribers.
+    /// An empty reply counts as already received.
+    pub expect: Option<Vec<u8>>,
+    /// How long to wait for `expect`.
+    pub timeout: Duration,
+}
+
+impl InitString {
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        Self {
+            bytes: bytes.into(),
+            delay: Duration::ZERO,
+            expect: None,
+            timeout: Duration::from_secs(2),
+        }
+    }
+
+    /// Parse text with C-style escapes (`\r`, `\n`, `\t`, `\\`, `\xNN`),
+    /// as typed on a command line or stored in a profile.
+    pub fn from_escaped(text: &str) -> Result<Self, ConnectionError> {
+        Ok(Self::new(unescape(text)?))
+    }
+
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Require `reply` within `timeout` after sending.
+    pub fn expecting(mut self, reply: impl Into<Vec<u8>>, timeout: Duration) -> Self {
+        self.expect = Some(reply.into());
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send the init string on `port` and check the reply, if one is expected.
+    pub async fn run(
+        &self,
+        port: &mut (impl AsyncRead + AsyncWrite + Unpin),
+    ) -> Result<(), ConnectionError> {
+        if !self.delay.is_zero() {
+            tokio::time::sleep(self.delay).await;
+        }
+        port.write_all(&self.bytes).await?;
+        port.flush().await?;
+
+        let Some(expect) = self.expect.as_ref().filter(|e| !e.is_empty()) else {
+            return Ok(());
+        };
+        let mut received = Vec::new();
+        let mut buf = [0u8; 64];
+        let waited = tokio::time::timeout(self.timeout, async {
+            while !received
+                .windows(expect.len())
+                .any(|w| w == expect.as_slice())
+            {
+                let n = port.read(&mut buf).await?;
+                if n == 0 {
+                    return Err(ConnectionError::Other(format!(
+                        "Port closed while waiting for {:?} after the init string",
+                        String::from_utf8_lossy(expect)
+                    )));
+                }
+                received.extend_from_slice(&buf[..n]);
+            }
+            Ok::<_, ConnectionError>(())
+        })
+        .await;
+        match waited {
+            Ok(result) => result,
+            Err(_) => Err(ConnectionError::Other(format!(
+                "Init string got no {:?} reply within {:?} (received {:?})",
+                String::from_utf8_lossy(expect),
+                self.timeout,
+                String::from_utf8_lossy(&received)
+            ))),
+        }
+    }
+}
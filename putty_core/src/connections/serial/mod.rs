@@ -1,3 +1,9 @@
+pub mod init;
+pub mod ports;
+pub mod reset;
 pub mod serial_connection;
 
+pub use init::*;
+pub use ports::*;
+pub use reset::*;
 pub use serial_connection::*;
@@ -0,0 +1,64 @@
+use crate::connections::errors::ConnectionError;
+use tokio_serial::{SerialPortType, UsbPortInfo};
+
+/// A serial port found on this machine by [`list_ports`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SerialPortInfo {
+    /// What to pass as the port path, e.g. `/dev/ttyUSB0` or `COM3`.
+    pub name: String,
+    /// Set for USB adapters; built-in and virtual ports have none.
+    pub usb: Option<UsbInfo>,
+}
+
+/// What a USB serial adapter reports about itself.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UsbInfo {
+    pub vid: u16,
+    pub pid: u16,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+}
+
+impl From<tokio_serial::SerialPortInfo> for SerialPortInfo {
+    fn from(info: tokio_serial::SerialPortInfo) -> Self {
+        let usb = match info.port_type {
+            SerialPortType::UsbPort(usb) => Some(usb.into()),
+            _ => None,
+        };
+        SerialPortInfo {
+            name: info.port_name,
+            usb,
+        }
+    }
+}
+
+impl From<UsbPortInfo> for UsbInfo {
+    fn from(usb: UsbPortInfo) -> Self {
+        UsbInfo {
+            vid: usb.vid,
+            pid: usb.pid,
+            manufacturer: usb.manufacturer,
+            product: usb.product,
+            serial_number: usb.serial_number,
+        }
+    }
+}
+
+/// The serial ports of this machine, sorted by name. An empty list means
+/// there are none; being denied access to the device list is an
+/// `IoError` of kind `PermissionDenied`, so callers can tell the two apart.
+pub fn list_ports() -> Result<Vec<SerialPortInfo>, ConnectionError> {
+    let mut ports: Vec<SerialPortInfo> = tokio_serial::available_ports()
+        .map_err(|e| match e.kind() {
+            tokio_serial::ErrorKind::Io(kind) => {
+                ConnectionError::IoError(std::io::Error::new(kind, e.description))
+            }
+            _ => ConnectionError::from(e),
+        })?
+        .into_iter()
+        .map(SerialPortInfo::from)
+        .collect();
+    ports.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(ports)
+}
@@ -0,0 +1,115 @@
+use crate::connections::errors::ConnectionError;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio_serial::SerialPort;
+
+/// The DTR/RTS modem-control lines of a serial port.
+///
+/// Implemented for every `tokio_serial::SerialPort`; tests can implement it
+/// on a recorder to check what a [`ResetSequence`] does.
+pub trait ModemLines {
+    fn set_dtr(&mut self, level: bool) -> Result<(), ConnectionError>;
+    fn set_rts(&mut self, level: bool) -> Result<(), ConnectionError>;
+}
+
+impl<T: SerialPort + ?Sized> ModemLines for T {
+    fn set_dtr(&mut self, level: bool) -> Result<(), ConnectionError> {
+        Ok(self.write_data_terminal_ready(level)?)
+    }
+
+    fn set_rts(&mut self, level: bool) -> Result<(), ConnectionError> {
+        Ok(self.write_request_to_send(level)?)
+    }
+}
+
+/// One step of a [`ResetSequence`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResetStep {
+    Dtr(bool),
+    Rts(bool),
+    Wait(Duration),
+}
+
+const ESP32_STEPS: &[ResetStep] = &[
+    ResetStep::Dtr(false), // IO0 high
+    ResetStep::Rts(true),  // EN low, chip held in reset
+    ResetStep::Wait(Duration::from_millis(100)),
+    ResetStep::Dtr(true),  // IO0 low, selects the bootloader
+    ResetStep::Rts(false), // EN high, chip leaves reset
+    ResetStep::Wait(Duration::from_millis(50)),
+    ResetStep::Dtr(false), // release IO0
+];
+
+const ARDUINO_STEPS: &[ResetStep] = &[
+    ResetStep::Dtr(false),
+    ResetStep::Rts(false),
+    ResetStep::Wait(Duration::from_millis(250)),
+    ResetStep::Dtr(true),
+    ResetStep::Rts(true),
+    ResetStep::Wait(Duration::from_millis(50)),
+];
+
+/// Named DTR/RTS toggle sequences that reset a board, e.g. into its
+/// bootloader before flashing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ResetSequence {
+    /// Leave the lines alone.
+    #[default]
+    None,
+    /// ESP32/ESP8266 auto-program circuit: pulse EN (RTS) while holding
+    /// IO0 (DTR) low, so the chip boots into its serial bootloader. Same
+    /// timing as esptool's classic reset.
+    Esp32,
+    /// Arduino auto-reset: drop DTR/RTS, then raise them so the capacitor on
+    /// the reset line pulses the MCU into the bootloader, as avrdude does.
+    Arduino,
+}
+
+impl ResetSequence {
+    /// The line changes and pauses this sequence consists of, in order.
+    pub fn steps(self) -> &'static [ResetStep] {
+        match self {
+            ResetSequence::None => &[],
+            ResetSequence::Esp32 => ESP32_STEPS,
+            ResetSequence::Arduino => ARDUINO_STEPS,
+        }
+    }
+
+    /// Perform the sequence on `lines`.
+    pub async fn run(self, lines: &mut (impl ModemLines + ?Sized)) -> Result<(), ConnectionError> {
+        for step in self.steps() {
+            match *step {
+                ResetStep::Dtr(level) => lines.set_dtr(level)?,
+                ResetStep::Rts(level) => lines.set_rts(level)?,
+                ResetStep::Wait(pause) => tokio::time::sleep(pause).await,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ResetSequence {
+    type Err = ConnectionError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "none" => Ok(ResetSequence::None),
+            "esp32" => Ok(ResetSequence::Esp32),
+            "arduino" => Ok(ResetSequence::Arduino),
+            _ => Err(ConnectionError::Other(format!(
+                "Unknown reset sequence '{name}' (expected esp32, arduino or none)"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ResetSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ResetSequence::None => "none",
+            ResetSequence::Esp32 => "esp32",
+            ResetSequence::Arduino => "arduino",
+        })
+    }
+}
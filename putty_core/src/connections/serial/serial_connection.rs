@@ -1,14 +1,23 @@
-use crate::connections::connection::Connection;
+use crate::connections::baud::Baud;
+use crate::connections::connection::{Connection, NegotiatedParams};
 use crate::connections::errors::ConnectionError;
+use crate::connections::framing::{DataBits, FlowControl, Framing, ModemStatus, Parity, StopBits};
+use crate::connections::serial::init::InitString;
+use crate::connections::serial::reset::{ModemLines, ResetSequence};
 use async_trait::async_trait;
 use std::time::Duration;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio_serial::{SerialPortBuilderExt, SerialStream}; // Import SerialPortBuilderExt for open_native_async
+use tokio_serial::{SerialPort, SerialPortBuilderExt, SerialStream}; // Import SerialPortBuilderExt for open_native_async
 
 #[derive(Debug)]
 pub struct SerialConnection {
     port_path: String,
     baud_rate: u32,
+    framing: Framing,
+    /// Run on every successful `connect`.
+    reset: ResetSequence,
+    /// Sent on every successful `connect`, after the reset sequence.
+    init: Option<InitString>,
     inner: Option<SerialStream>,
 }
 
@@ -17,21 +26,100 @@ impl SerialConnection {
         Self {
             port_path,
             baud_rate,
+            framing: Framing::default(),
+            reset: ResetSequence::None,
+            init: None,
             inner: None,
         }
     }
+
+    /// Like [`new`](Self::new), but rejects a nonsensical `baud_rate` right away.
+    pub fn try_new(port_path: String, baud_rate: u32) -> Result<Self, ConnectionError> {
+        Baud::new(baud_rate)?;
+        Ok(Self::new(port_path, baud_rate))
+    }
+
+    /// Constructor for devices that are not 8N1, e.g. 7E1 on many
+    /// industrial controllers. Flow control stays off; see
+    /// [`with_framing`](Self::with_framing) to set it too.
+    pub fn with_settings(
+        port_path: String,
+        baud_rate: u32,
+        data_bits: DataBits,
+        parity: Parity,
+        stop_bits: StopBits,
+    ) -> Self {
+        Self::new(port_path, baud_rate).with_framing(Framing {
+            data_bits,
+            parity,
+            stop_bits,
+            flow_control: FlowControl::None,
+        })
+    }
+
+    /// Open the port with `framing` instead of 8N1 without flow control.
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    /// Throttle the line with `flow_control`, keeping the other framing
+    /// settings; see [`FlowControl`] for what each mode needs from the cable.
+    pub fn with_flow_control(mut self, flow_control: FlowControl) -> Self {
+        self.framing.flow_control = flow_control;
+        self
+    }
+
+    /// The framing the port is opened with.
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Run `sequence` right after the port is opened, e.g. to put a board
+    /// into its bootloader.
+    pub fn with_reset(mut self, sequence: ResetSequence) -> Self {
+        self.reset = sequence;
+        self
+    }
+
+    /// Send `init` right after the port is opened (and reset), before any
+    /// user input.
+    pub fn with_init(mut self, init: InitString) -> Self {
+        self.init = Some(init);
+        self
+    }
+
+    /// Toggle DTR/RTS according to `sequence` on the open port.
+    pub async fn reset_sequence(&mut self, sequence: ResetSequence) -> Result<(), ConnectionError> {
+        let port = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        log::info!("Running {sequence} reset sequence on {}", self.port_path);
+        sequence.run(port).await
+    }
 }
 
 #[async_trait]
 impl Connection for SerialConnection {
     async fn connect(&mut self) -> Result<(), ConnectionError> {
         log::info!("Attempting to open serial port: {}", self.port_path);
-        let builder =
-            tokio_serial::new(&self.port_path, self.baud_rate).timeout(Duration::from_millis(10));
+        Baud::new(self.baud_rate)?;
+        let builder = tokio_serial::new(&self.port_path, self.baud_rate)
+            .data_bits(self.framing.data_bits.into())
+            .parity(self.framing.parity.into())
+            .stop_bits(self.framing.stop_bits.into())
+            .flow_control(self.framing.flow_control.into())
+            .timeout(Duration::from_millis(10));
         match builder.open_native_async() {
             Ok(port) => {
                 log::info!("Successfully opened serial port: {}", self.port_path);
                 self.inner = Some(port);
+                self.reset_sequence(self.reset).await?;
+                if let (Some(init), Some(port)) = (&self.init, self.inner.as_mut()) {
+                    log::info!("Sending init string to {}", self.port_path);
+                    init.run(port).await?;
+                }
                 Ok(())
             }
             Err(e) => Err(ConnectionError::from(e)),
@@ -48,14 +136,12 @@ impl Connection for SerialConnection {
 
     async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError> {
         if let Some(port) = self.inner.as_mut() {
-            let bytes_written = port
-                .write(data)
-                .await
-                .map_err(|e| ConnectionError::Other(e.to_string()))?;
-            port.flush()
+            // The driver may accept fewer bytes than offered when its TX
+            // buffer is full; keep going until everything is queued.
+            port.write_all(data)
                 .await
                 .map_err(|e| ConnectionError::Other(e.to_string()))?;
-            Ok(bytes_written)
+            Ok(data.len())
         } else {
             log::error!("Cannot write: serial port not connected!");
             Err(ConnectionError::Other("Not connected".into()))
@@ -74,4 +160,151 @@ impl Connection for SerialConnection {
             Err(ConnectionError::Other("Not connected".into()))
         }
     }
+
+    /// Block until the driver has sent everything queued by `write`.
+    async fn flush(&mut self) -> Result<(), ConnectionError> {
+        let port = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        port.flush()
+            .await
+            .map_err(|e| ConnectionError::Other(e.to_string()))
+    }
+
+    fn is_connected(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    fn kind(&self) -> &'static str {
+        "serial"
+    }
+
+    async fn assert_modem_lines(&mut self) -> Result<(), ConnectionError> {
+        let port = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        port.set_dtr(true)?;
+        port.set_rts(true)
+    }
+
+    async fn set_dtr(&mut self, level: bool) -> Result<(), ConnectionError> {
+        let port = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        log::debug!(
+            "DTR {} on {}",
+            if level { "high" } else { "low" },
+            self.port_path
+        );
+        port.set_dtr(level)
+    }
+
+    async fn set_rts(&mut self, level: bool) -> Result<(), ConnectionError> {
+        let port = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        log::debug!(
+            "RTS {} on {}",
+            if level { "high" } else { "low" },
+            self.port_path
+        );
+        port.set_rts(level)
+    }
+
+    /// Waits for queued data to go out first, so the break does not cut
+    /// into the last write.
+    async fn send_break(&mut self, duration: Duration) -> Result<(), ConnectionError> {
+        self.flush().await?;
+        let port = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        log::debug!("BREAK for {duration:?} on {}", self.port_path);
+        port.set_break()?;
+        tokio::time::sleep(duration).await;
+        Ok(port.clear_break()?)
+    }
+
+    async fn modem_status(&mut self) -> Result<ModemStatus, ConnectionError> {
+        let port = self
+            .inner
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        Ok(ModemStatus {
+            cts: port.read_clear_to_send()?,
+            dsr: port.read_data_set_ready()?,
+            dcd: port.read_carrier_detect()?,
+            ri: port.read_ring_indicator()?,
+        })
+    }
+
+    /// Settings read back from the open port, which may differ from the
+    /// requested ones if the driver adjusted them.
+    fn negotiated(&self) -> NegotiatedParams {
+        let mut params = NegotiatedParams::new();
+        let Some(port) = self.inner.as_ref() else {
+            return params;
+        };
+        params.insert("port".into(), self.port_path.clone());
+        if let Ok(baud) = port.baud_rate() {
+            params.insert("baud".into(), baud.to_string());
+        }
+        if let Ok(data_bits) = port.data_bits() {
+            params.insert("data_bits".into(), data_bits.to_string());
+        }
+        if let Ok(parity) = port.parity() {
+            params.insert("parity".into(), parity.to_string());
+        }
+        if let Ok(stop_bits) = port.stop_bits() {
+            params.insert("stop_bits".into(), stop_bits.to_string());
+        }
+        if let Ok(flow_control) = port.flow_control() {
+            params.insert("flow_control".into(), flow_control.to_string());
+        }
+        params
+    }
+}
+
+impl From<DataBits> for tokio_serial::DataBits {
+    fn from(bits: DataBits) -> Self {
+        match bits {
+            DataBits::Five => tokio_serial::DataBits::Five,
+            DataBits::Six => tokio_serial::DataBits::Six,
+            DataBits::Seven => tokio_serial::DataBits::Seven,
+            DataBits::Eight => tokio_serial::DataBits::Eight,
+        }
+    }
+}
+
+impl From<Parity> for tokio_serial::Parity {
+    fn from(parity: Parity) -> Self {
+        match parity {
+            Parity::None => tokio_serial::Parity::None,
+            Parity::Odd => tokio_serial::Parity::Odd,
+            Parity::Even => tokio_serial::Parity::Even,
+        }
+    }
+}
+
+impl From<StopBits> for tokio_serial::StopBits {
+    fn from(bits: StopBits) -> Self {
+        match bits {
+            StopBits::One => tokio_serial::StopBits::One,
+            StopBits::Two => tokio_serial::StopBits::Two,
+        }
+    }
+}
+
+impl From<FlowControl> for tokio_serial::FlowControl {
+    fn from(flow: FlowControl) -> Self {
+        match flow {
+            FlowControl::None => tokio_serial::FlowControl::None,
+            FlowControl::Software => tokio_serial::FlowControl::Software,
+            FlowControl::Hardware => tokio_serial::FlowControl::Hardware,
+        }
+    }
 }
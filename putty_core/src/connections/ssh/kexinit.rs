@@ -0,0 +1,136 @@
+//! Recording the server's SSH_MSG_KEXINIT for [`SshConnection::probe`].
+//!
+//! russh only hands out the algorithms it settled on, so the probe reads the
+//! server's offer from the raw bytes instead: the identification line and the
+//! first binary packet are always sent in the clear.
+//!
+//! [`SshConnection::probe`]: super::SshConnection::probe
+
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+
+/// SSH_MSG_KEXINIT, RFC 4253 section 7.1.
+const MSG_KEXINIT: u8 = 20;
+/// Largest packet an implementation must accept (RFC 4253 section 6.1),
+/// plus room for the identification line and any lines before it.
+const MAX_RECORDED: usize = 35_000 + 8192;
+
+/// Algorithms a server offers in its SSH_MSG_KEXINIT, most preferred first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerAlgorithms {
+    pub kex: Vec<String>,
+    pub host_key: Vec<String>,
+    pub cipher_client_to_server: Vec<String>,
+    pub cipher_server_to_client: Vec<String>,
+    pub mac_client_to_server: Vec<String>,
+    pub mac_server_to_client: Vec<String>,
+    pub compression_client_to_server: Vec<String>,
+    pub compression_server_to_client: Vec<String>,
+}
+
+impl ServerAlgorithms {
+    /// Parse what a server sent up to and including its SSH_MSG_KEXINIT:
+    /// optional lines, the `SSH-` identification line, then the packet.
+    /// `None` if the bytes end early or are not laid out that way.
+    pub fn parse(received: &[u8]) -> Option<Self> {
+        let mut rest = received;
+        loop {
+            let end = rest.iter().position(|&b| b == b'\n')?;
+            let line = &rest[..end];
+            rest = &rest[end + 1..];
+            if line.starts_with(b"SSH-") {
+                break;
+            }
+        }
+
+        let packet_len = u32::from_be_bytes(rest.get(..4)?.try_into().ok()?) as usize;
+        let packet = rest.get(4..4 + packet_len)?;
+        let (&padding_len, packet) = packet.split_first()?;
+        let payload = packet.get(..packet.len().checked_sub(padding_len as usize)?)?;
+        let (&msg, payload) = payload.split_first()?;
+        if msg != MSG_KEXINIT {
+            return None;
+        }
+
+        // Skip the 16-byte cookie; the language lists that follow are unused.
+        let mut lists = payload.get(16..)?;
+        let mut next = || -> Option<Vec<String>> {
+            let len = u32::from_be_bytes(lists.get(..4)?.try_into().ok()?) as usize;
+            let names = std::str::from_utf8(lists.get(4..4 + len)?).ok()?;
+            lists = &lists[4 + len..];
+            Some(
+                names
+                    .split(',')
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_owned)
+                    .collect(),
+            )
+        };
+        Some(ServerAlgorithms {
+            kex: next()?,
+            host_key: next()?,
+            cipher_client_to_server: next()?,
+            cipher_server_to_client: next()?,
+            mac_client_to_server: next()?,
+            mac_server_to_client: next()?,
+            compression_client_to_server: next()?,
+            compression_server_to_client: next()?,
+        })
+    }
+}
+
+/// Passes a stream through, keeping a copy of the first bytes read from it.
+pub(crate) struct RecordingStream<S> {
+    inner: S,
+    received: Arc<Mutex<Vec<u8>>>,
+}
+
+impl<S> RecordingStream<S> {
+    /// Wrap `inner`; the bytes read so far can be looked at through the
+    /// returned buffer.
+    pub(crate) fn new(inner: S) -> (Self, Arc<Mutex<Vec<u8>>>) {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let stream = RecordingStream {
+            inner,
+            received: received.clone(),
+        };
+        (stream, received)
+    }
+}
+
+impl<S: AsyncRead + Unpin> AsyncRead for RecordingStream<S> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        let mut received = self.received.lock().unwrap();
+        let room = MAX_RECORDED.saturating_sub(received.len());
+        let fresh = &buf.filled()[before..];
+        received.extend_from_slice(&fresh[..fresh.len().min(room)]);
+        poll
+    }
+}
+
+impl<S: AsyncWrite + Unpin> AsyncWrite for RecordingStream<S> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.inner).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
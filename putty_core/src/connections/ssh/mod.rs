@@ -1,3 +1,9 @@
+mod kexinit;
+pub mod sftp;
 pub mod ssh_connection;
+pub mod x11;
 
+pub use kexinit::ServerAlgorithms;
+pub use sftp::{SftpClient, TransferProgress, TransferProgressSender};
 pub use ssh_connection::*;
+pub use x11::X11Display;
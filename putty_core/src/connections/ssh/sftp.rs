@@ -0,0 +1,113 @@
+use crate::connections::errors::ConnectionError;
+use russh_sftp::client::error::Error as SftpError;
+use russh_sftp::client::SftpSession;
+use std::path::Path;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::mpsc;
+
+/// Bytes read and written per SFTP request.
+const CHUNK_SIZE: usize = 32 * 1024;
+
+/// How far a [`SftpClient::put`] or [`SftpClient::get`] has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TransferProgress {
+    /// Bytes copied so far.
+    pub transferred: u64,
+    /// Size of the source file, if the other side reported one.
+    pub total: Option<u64>,
+}
+
+/// Where a [`SftpClient`] sends its [`TransferProgress`].
+pub type TransferProgressSender = mpsc::UnboundedSender<TransferProgress>;
+
+/// Uploads and downloads files over the SFTP subsystem of an SSH connection.
+///
+/// It runs on a channel of its own, so the interactive shell keeps working
+/// during transfers. Get one from
+/// [`SshConnection::sftp`](super::SshConnection::sftp) or
+/// [`ConnectionManager::sftp`](crate::ConnectionManager::sftp); it stays
+/// usable until [`close`](Self::close)d or the connection is stopped.
+pub struct SftpClient {
+    session: SftpSession,
+    progress: Option<TransferProgressSender>,
+}
+
+impl SftpClient {
+    pub(crate) fn new(session: SftpSession) -> Self {
+        Self {
+            session,
+            progress: None,
+        }
+    }
+
+    /// Report [`TransferProgress`] to `progress` after every chunk of a
+    /// transfer.
+    pub fn with_progress(mut self, progress: TransferProgressSender) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Upload `local` to `remote`, replacing the file if it exists. Returns
+    /// the number of bytes sent.
+    pub async fn put(&self, local: &Path, remote: &str) -> Result<u64, ConnectionError> {
+        let mut source = tokio::fs::File::open(local).await?;
+        let total = source.metadata().await?.len();
+        let mut target = self
+            .session
+            .create(remote)
+            .await
+            .map_err(|e| sftp_error("create", remote, e))?;
+        let sent = self.copy(&mut source, &mut target, Some(total)).await?;
+        target.shutdown().await?;
+        Ok(sent)
+    }
+
+    /// Download `remote` to `local`, replacing the file if it exists. Returns
+    /// the number of bytes received.
+    pub async fn get(&self, remote: &str, local: &Path) -> Result<u64, ConnectionError> {
+        let mut source = self
+            .session
+            .open(remote)
+            .await
+            .map_err(|e| sftp_error("open", remote, e))?;
+        let total = source.metadata().await.ok().and_then(|m| m.size);
+        let mut target = tokio::fs::File::create(local).await?;
+        let received = self.copy(&mut source, &mut target, total).await?;
+        target.flush().await?;
+        source.shutdown().await?;
+        Ok(received)
+    }
+
+    /// Close the SFTP channel. The SSH connection stays up.
+    pub async fn close(self) -> Result<(), ConnectionError> {
+        self.session
+            .close()
+            .await
+            .map_err(|e| ConnectionError::Other(format!("SFTP close failed: {e}")))
+    }
+
+    async fn copy(
+        &self,
+        source: &mut (impl AsyncRead + Unpin),
+        target: &mut (impl AsyncWrite + Unpin),
+        total: Option<u64>,
+    ) -> Result<u64, ConnectionError> {
+        let mut buf = vec![0; CHUNK_SIZE];
+        let mut transferred = 0;
+        loop {
+            let n = source.read(&mut buf).await?;
+            if n == 0 {
+                return Ok(transferred);
+            }
+            target.write_all(&buf[..n]).await?;
+            transferred += n as u64;
+            if let Some(progress) = &self.progress {
+                let _ = progress.send(TransferProgress { transferred, total });
+            }
+        }
+    }
+}
+
+fn sftp_error(action: &str, path: &str, e: SftpError) -> ConnectionError {
+    ConnectionError::Other(format!("SFTP cannot {action} {path}: {e}"))
+}
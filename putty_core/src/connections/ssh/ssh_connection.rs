@@ -1,13 +1,33 @@
-use crate::connections::{connection::Connection, errors::ConnectionError};
+use crate::connections::{
+    connection::{ConnectProgress, Connection, NegotiatedParams, ProgressSender},
+    errors::ConnectionError,
+    forward::{LocalForward, RemoteForward},
+    ssh::kexinit::{RecordingStream, ServerAlgorithms},
+    ssh::sftp::SftpClient,
+    ssh::x11::{X11Display, X11Forwarding},
+    tcp::open_tcp,
+};
 use async_trait::async_trait;
-use log::{debug, info};
-use russh::client::{self, AuthResult, Handle};
-use russh::keys::{load_secret_key, PrivateKeyWithHashAlg};
-use russh::{Channel, ChannelMsg, Disconnect};
-use std::collections::VecDeque;
-use std::path::PathBuf;
-use std::sync::Arc;
+use log::{debug, info, warn};
+use russh::client::{self, AuthResult, Handle, KeyboardInteractiveAuthResponse};
+#[cfg(unix)]
+use russh::keys::agent::{client::AgentClient, AgentIdentity};
+use russh::keys::{load_secret_key, HashAlg, PrivateKeyWithHashAlg, PublicKey};
+use russh::{Channel, ChannelMsg, ChannelStream, Disconnect, MethodKind};
+use russh_sftp::client::SftpSession;
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+use tokio::task::{JoinHandle, JoinSet};
+
+/// Default for [`SshConnection::with_keepalive`].
+const DEFAULT_KEEPALIVE: Duration = Duration::from_secs(30);
+/// Unanswered keepalives in a row after which the server is presumed dead.
+const KEEPALIVE_MAX: usize = 3;
 
 impl From<russh::Error> for ConnectionError {
     fn from(err: russh::Error) -> Self {
@@ -15,29 +35,358 @@ impl From<russh::Error> for ConnectionError {
     }
 }
 
-struct SshClient;
+struct SshClient {
+    /// Filled in during key exchange, read back once `connect` finishes.
+    negotiated: Arc<Mutex<NegotiatedParams>>,
+    /// Taken on the first key exchange, so re-keying does not report again.
+    handshake_progress: Option<ProgressSender>,
+    /// Pinned SHA-256 fingerprint; any other host key is rejected.
+    expected_host_key: Option<String>,
+    /// Fingerprint of a rejected host key, for the error message.
+    rejected_host_key: Arc<Mutex<Option<String>>>,
+    /// Set if X11 forwarding was requested.
+    x11: Option<Arc<X11Forwarding>>,
+    /// Local `host:port` to connect to for each port the server listens on
+    /// for a remote forward.
+    remote_targets: Arc<Mutex<HashMap<u32, (String, u16)>>>,
+    /// Why the session ended, if it did not end by our own request.
+    disconnect_reason: Arc<Mutex<Option<String>>>,
+    /// Interval and limit of unanswered keepalives, for the error message.
+    keepalive: (Duration, usize),
+}
 
 impl client::Handler for SshClient {
     type Error = russh::Error;
 
+    async fn disconnected(
+        &mut self,
+        reason: client::DisconnectReason<Self::Error>,
+    ) -> Result<(), Self::Error> {
+        let (description, result) = match reason {
+            // Ended by our own `disconnect`.
+            client::DisconnectReason::Error(russh::Error::Disconnect) => {
+                return Err(russh::Error::Disconnect);
+            }
+            client::DisconnectReason::ReceivedDisconnect(info) => {
+                (format!("SSH server disconnected: {}", info.message), Ok(()))
+            }
+            client::DisconnectReason::Error(russh::Error::KeepaliveTimeout) => {
+                let (interval, max) = self.keepalive;
+                (
+                    format!(
+                        "SSH server missed {max} keepalives sent every {interval:?}; \
+                         the connection is presumed dead"
+                    ),
+                    Err(russh::Error::KeepaliveTimeout),
+                )
+            }
+            client::DisconnectReason::Error(e) => (format!("SSH connection lost: {e}"), Err(e)),
+        };
+        warn!("{description}");
+        *self.disconnect_reason.lock().unwrap() = Some(description);
+        result
+    }
+
+    async fn kex_done(
+        &mut self,
+        _shared_secret: Option<&[u8]>,
+        names: &russh::Names,
+        session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        let mut params = self.negotiated.lock().unwrap();
+        params.insert(
+            "server_version".into(),
+            String::from_utf8_lossy(session.remote_sshid()).into_owned(),
+        );
+        params.insert("kex".into(), names.kex.as_ref().to_owned());
+        params.insert("host_key".into(), names.key.to_string());
+        params.insert("cipher".into(), names.cipher.as_ref().to_owned());
+        params.insert("mac".into(), names.client_mac.as_ref().to_owned());
+        params.insert(
+            "compression".into(),
+            format!("{:?}", names.client_compression),
+        );
+        if let Some(progress) = self.handshake_progress.take() {
+            let _ = progress.send(ConnectProgress::Handshaked);
+        }
+        Ok(())
+    }
+
     async fn check_server_key(
         &mut self,
-        _server_public_key: &russh::keys::PublicKey,
+        server_public_key: &russh::keys::PublicKey,
     ) -> Result<bool, Self::Error> {
-        // Host-key verification lives in the TODO list; accept everything for now.
+        // Without a pinned key, host-key verification lives in the TODO list;
+        // accept everything for now.
+        let Some(expected) = &self.expected_host_key else {
+            return Ok(true);
+        };
+        let actual = server_public_key.fingerprint(HashAlg::Sha256).to_string();
+        if same_fingerprint(expected, &actual) {
+            return Ok(true);
+        }
+        *self.rejected_host_key.lock().unwrap() = Some(actual);
+        Ok(false)
+    }
+
+    async fn server_channel_open_x11(
+        &mut self,
+        channel: Channel<client::Msg>,
+        originator_address: &str,
+        originator_port: u32,
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        // Dropping the channel refuses X11 connections nobody asked for.
+        let Some(x11) = self.x11.clone() else {
+            return Ok(());
+        };
+        debug!("X11 channel opened for {originator_address}:{originator_port}");
+        tokio::spawn(async move { x11.serve(channel).await });
+        Ok(())
+    }
+
+    async fn server_channel_open_forwarded_tcpip(
+        &mut self,
+        channel: Channel<client::Msg>,
+        connected_address: &str,
+        connected_port: u32,
+        originator_address: &str,
+        originator_port: u32,
+        _session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        // Dropping the channel refuses forwards nobody asked for.
+        let Some((host, port)) = self
+            .remote_targets
+            .lock()
+            .unwrap()
+            .get(&connected_port)
+            .cloned()
+        else {
+            debug!("Refusing unrequested forward from {connected_address}:{connected_port}");
+            return Ok(());
+        };
+        let peer = format!("{originator_address}:{originator_port}");
+        tokio::spawn(async move {
+            let mut socket = match TcpStream::connect((host.as_str(), port)).await {
+                Ok(socket) => socket,
+                Err(e) => {
+                    warn!("Remote forward from {peer}: cannot connect to {host}:{port}: {e}");
+                    return;
+                }
+            };
+            debug!("Forwarding {peer} to {host}:{port}");
+            let mut stream = channel.into_stream();
+            if let Err(e) = tokio::io::copy_bidirectional(&mut socket, &mut stream).await {
+                debug!("Remote forward from {peer} ended: {e}");
+            }
+        });
+        Ok(())
+    }
+}
+
+/// What an SSH server revealed during the handshake, see [`SshConnection::probe`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshProbe {
+    /// Identification string sent by the server, e.g. `SSH-2.0-OpenSSH_9.6`.
+    pub server_version: String,
+    /// Host-key algorithm, e.g. `ssh-ed25519`.
+    pub host_key_algorithm: String,
+    /// SHA-256 fingerprint of the host key in OpenSSH format (`SHA256:...`).
+    pub host_key_fingerprint: String,
+    /// Algorithms agreed on with the server for this handshake.
+    pub kex: String,
+    pub cipher: String,
+    pub mac: String,
+    pub compression: String,
+    /// Everything the server offered in its key exchange init.
+    pub server_algorithms: ServerAlgorithms,
+}
+
+/// Handler for [`SshConnection::probe`]. russh reports the finished key
+/// exchange before it asks about the host key, so the algorithms are kept
+/// from `kex_done` and the probe is handed back once the key is known.
+struct ProbeClient {
+    /// Bytes read from the server so far, holding its KEXINIT by `kex_done`.
+    received: Arc<Mutex<Vec<u8>>>,
+    /// Filled in by `kex_done`, host key fields still empty.
+    kex: Option<Result<SshProbe, ConnectionError>>,
+    done: Option<oneshot::Sender<Result<SshProbe, ConnectionError>>>,
+}
+
+impl client::Handler for ProbeClient {
+    type Error = russh::Error;
+
+    async fn kex_done(
+        &mut self,
+        _shared_secret: Option<&[u8]>,
+        names: &russh::Names,
+        session: &mut client::Session,
+    ) -> Result<(), Self::Error> {
+        if self.kex.is_some() {
+            return Ok(());
+        }
+        let Some(server_algorithms) = ServerAlgorithms::parse(&self.received.lock().unwrap())
+        else {
+            self.kex = Some(Err(ConnectionError::HandshakeError(
+                "SSH: could not read the server's key exchange init".into(),
+            )));
+            return Ok(());
+        };
+        self.kex = Some(Ok(SshProbe {
+            server_version: String::from_utf8_lossy(session.remote_sshid()).into_owned(),
+            host_key_algorithm: String::new(),
+            host_key_fingerprint: String::new(),
+            kex: names.kex.as_ref().to_owned(),
+            cipher: names.cipher.as_ref().to_owned(),
+            mac: names.client_mac.as_ref().to_owned(),
+            compression: format!("{:?}", names.client_compression),
+            server_algorithms,
+        }));
+        Ok(())
+    }
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &PublicKey,
+    ) -> Result<bool, Self::Error> {
+        let (Some(done), Some(kex)) = (self.done.take(), self.kex.take()) else {
+            return Ok(true);
+        };
+        let _ = done.send(kex.map(|probe| SshProbe {
+            host_key_algorithm: server_public_key.algorithm().to_string(),
+            host_key_fingerprint: server_public_key.fingerprint(HashAlg::Sha256).to_string(),
+            ..probe
+        }));
         Ok(true)
     }
 }
 
+/// One question in a keyboard-interactive round, e.g. `Password: ` or
+/// `Verification code: `.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthPrompt {
+    pub text: String,
+    /// Whether the answer may be shown while it is typed.
+    pub echo: bool,
+}
+
+/// One round of keyboard-interactive authentication as sent by the server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthChallenge {
+    pub name: String,
+    pub instructions: String,
+    /// Often one per round, but may be several or none.
+    pub prompts: Vec<AuthPrompt>,
+}
+
+impl AuthChallenge {
+    /// A round that only tells the user something, e.g. `Duo push sent to
+    /// your phone`, and expects no answers. The server replies once it has
+    /// made up its mind, which may take as long as the user needs to approve.
+    pub fn is_info_only(&self) -> bool {
+        self.prompts.is_empty()
+    }
+}
+
+/// Answers an [`AuthChallenge`] with one response per prompt, in order.
+/// See [`SshConnection::with_keyboard_interactive`].
+pub type PromptCallback = Arc<dyn Fn(&AuthChallenge) -> Vec<String> + Send + Sync>;
+
+/// The parts of a russh session handle the connection needs after connect,
+/// so sessions with any handler type can be adopted.
+#[async_trait]
+trait SessionHandle: Send + Sync {
+    async fn open_session(&self) -> Result<Channel<client::Msg>, russh::Error>;
+    async fn open_direct_tcpip(
+        &self,
+        host: &str,
+        port: u16,
+        originator: SocketAddr,
+    ) -> Result<Channel<client::Msg>, russh::Error>;
+    async fn cancel_remote_forward(&self, address: &str, port: u32) -> Result<(), russh::Error>;
+    async fn close(&self);
+    /// The session task has ended, e.g. because the server hung up.
+    fn is_closed(&self) -> bool;
+}
+
+#[async_trait]
+impl<H: client::Handler> SessionHandle for Handle<H> {
+    async fn open_session(&self) -> Result<Channel<client::Msg>, russh::Error> {
+        self.channel_open_session().await
+    }
+
+    async fn open_direct_tcpip(
+        &self,
+        host: &str,
+        port: u16,
+        originator: SocketAddr,
+    ) -> Result<Channel<client::Msg>, russh::Error> {
+        self.channel_open_direct_tcpip(
+            host,
+            port.into(),
+            originator.ip().to_string(),
+            originator.port().into(),
+        )
+        .await
+    }
+
+    async fn cancel_remote_forward(&self, address: &str, port: u32) -> Result<(), russh::Error> {
+        self.cancel_tcpip_forward(address, port).await
+    }
+
+    async fn close(&self) {
+        let _ = self
+            .disconnect(Disconnect::ByApplication, "bye", "en")
+            .await;
+    }
+
+    fn is_closed(&self) -> bool {
+        Handle::is_closed(self)
+    }
+}
+
 pub struct SshConnection {
     host: String,
     port: u16,
     username: String,
     password: Option<String>,
-    keyfile: Option<(PathBuf, Option<String>)>,
+    /// See [`with_keyboard_interactive`](Self::with_keyboard_interactive).
+    keyboard_interactive: Option<PromptCallback>,
+    /// See [`with_agent`](Self::with_agent).
+    agent: bool,
+    /// Private keys with optional passphrase, tried in order.
+    keyfiles: Vec<(PathBuf, Option<String>)>,
+    pty_size: (u16, u16),
+    /// See [`with_expected_host_key`](Self::with_expected_host_key).
+    expected_host_key: Option<String>,
+    /// See [`with_x11_forwarding`](Self::with_x11_forwarding).
+    x11_display: Option<X11Display>,
+    /// Serves the session's X11 channels; requested for the shell channel.
+    x11: Option<Arc<X11Forwarding>>,
+    /// See [`with_jump`](Self::with_jump).
+    jump: Option<Box<SshConnection>>,
+    /// See [`with_keepalive`](Self::with_keepalive).
+    keepalive: Duration,
+    /// Shared with the session handler, which fills it in when the session
+    /// ends unexpectedly.
+    disconnect_reason: Arc<Mutex<Option<String>>>,
+    /// Exit status the remote shell reported before closing its channel.
+    exit_status: Option<u32>,
+    /// See [`with_remote_forward`](Self::with_remote_forward).
+    remote_forwards: Vec<RemoteForward>,
+    /// `(bind_address, port)` the server listens on for the remote forwards
+    /// of the current session, cancelled on disconnect.
+    remote_listeners: Vec<(String, u32)>,
+    negotiated: Arc<Mutex<NegotiatedParams>>,
+    progress: Option<ProgressSender>,
 
-    session: Option<Handle<SshClient>>,
+    /// Set by [`from_session`](Self::from_session); such a connection cannot
+    /// be re-established once closed.
+    adopted: bool,
+    session: Option<Arc<dyn SessionHandle>>,
     channel: Option<Channel<client::Msg>>,
+    /// Listener tasks started by `local_forward`, each owning its relays.
+    forwards: Vec<JoinHandle<()>>,
     leftovers: VecDeque<u8>,
 }
 
@@ -48,9 +397,25 @@ impl SshConnection {
             port,
             username,
             password: Some(password),
-            keyfile: None,
+            keyboard_interactive: None,
+            agent: false,
+            keyfiles: Vec::new(),
+            pty_size: (80, 24),
+            expected_host_key: None,
+            x11_display: None,
+            x11: None,
+            jump: None,
+            keepalive: DEFAULT_KEEPALIVE,
+            disconnect_reason: Arc::default(),
+            exit_status: None,
+            remote_forwards: Vec::new(),
+            remote_listeners: Vec::new(),
+            negotiated: Arc::default(),
+            progress: None,
+            adopted: false,
             session: None,
             channel: None,
+            forwards: Vec::new(),
             leftovers: VecDeque::new(),
         }
     }
@@ -62,77 +427,836 @@ impl SshConnection {
         username: String,
         private_key: PathBuf,
         passphrase: Option<String>,
+    ) -> Self {
+        Self::with_keys(host, port, username, vec![(private_key, passphrase)])
+    }
+
+    /// Constructor for public-key authentication with several keys, tried in
+    /// order until the server accepts one (like multiple `IdentityFile`s).
+    pub fn with_keys(
+        host: String,
+        port: u16,
+        username: String,
+        keys: Vec<(PathBuf, Option<String>)>,
     ) -> Self {
         Self {
             host,
             port,
             username,
             password: None,
-            keyfile: Some((private_key, passphrase)),
+            keyboard_interactive: None,
+            agent: false,
+            keyfiles: keys,
+            pty_size: (80, 24),
+            expected_host_key: None,
+            x11_display: None,
+            x11: None,
+            jump: None,
+            keepalive: DEFAULT_KEEPALIVE,
+            disconnect_reason: Arc::default(),
+            exit_status: None,
+            remote_forwards: Vec::new(),
+            remote_listeners: Vec::new(),
+            negotiated: Arc::default(),
+            progress: None,
+            adopted: false,
             session: None,
             channel: None,
+            forwards: Vec::new(),
             leftovers: VecDeque::new(),
         }
     }
-}
 
-#[async_trait]
-impl Connection for SshConnection {
-    async fn connect(&mut self) -> Result<(), ConnectionError> {
-        let addr = format!("{}:{}", self.host, self.port);
-        info!("Connecting to SSH server at {addr}");
+    /// Constructor for keyboard-interactive authentication, where the server
+    /// asks questions (password, one-time code, ...) and `prompt` answers
+    /// them, returning one response per [`AuthPrompt`].
+    ///
+    /// Rounds without prompts (see [`AuthChallenge::is_info_only`]) are still
+    /// shown to `prompt` so the user sees their instructions, but its answers
+    /// are ignored; `connect` then waits for the server's next round.
+    ///
+    /// `prompt` runs on tokio's blocking thread pool, once per round, so it
+    /// may block, e.g. on reading the terminal; `connect` waits for it.
+    /// If the server does not offer keyboard-interactive but accepts
+    /// passwords, `prompt` is asked for the password with a single hidden
+    /// `Password: ` prompt instead.
+    pub fn with_keyboard_interactive(
+        host: String,
+        port: u16,
+        username: String,
+        prompt: impl Fn(&AuthChallenge) -> Vec<String> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            password: None,
+            keyboard_interactive: Some(Arc::new(prompt)),
+            ..Self::new(host, port, username, String::new())
+        }
+    }
 
-        let config = Arc::new(client::Config {
-            inactivity_timeout: Some(Duration::from_secs(60)),
-            ..Default::default()
-        });
+    /// Constructor for authentication through the running ssh-agent, found
+    /// via `SSH_AUTH_SOCK`. Each identity the agent holds is offered in turn;
+    /// the agent signs, so no key file or passphrase is needed here.
+    pub fn with_agent(host: String, port: u16, username: String) -> Self {
+        Self {
+            password: None,
+            agent: true,
+            ..Self::new(host, port, username, String::new())
+        }
+    }
+
+    /// Adopt an already authenticated `session` and an open `channel` on it
+    /// (typically with a shell or command running), skipping the connect
+    /// phase entirely. The connection takes ownership of both: it closes the
+    /// channel and disconnects the session on `disconnect`, after which it
+    /// cannot reconnect since it has no credentials.
+    ///
+    /// Negotiated parameters are only known for sessions this type opened
+    /// itself, so they stay empty here.
+    pub fn from_session<H: client::Handler + 'static>(
+        session: Handle<H>,
+        channel: Channel<client::Msg>,
+    ) -> Self {
+        Self {
+            host: String::new(),
+            port: 0,
+            username: String::new(),
+            password: None,
+            keyboard_interactive: None,
+            agent: false,
+            keyfiles: Vec::new(),
+            pty_size: (80, 24),
+            expected_host_key: None,
+            x11_display: None,
+            x11: None,
+            jump: None,
+            keepalive: DEFAULT_KEEPALIVE,
+            disconnect_reason: Arc::default(),
+            exit_status: None,
+            remote_forwards: Vec::new(),
+            remote_listeners: Vec::new(),
+            negotiated: Arc::default(),
+            progress: None,
+            adopted: true,
+            session: Some(Arc::new(session)),
+            channel: Some(channel),
+            forwards: Vec::new(),
+            leftovers: VecDeque::new(),
+        }
+    }
+
+    /// Send a keepalive whenever the server has been silent for `interval`,
+    /// so NAT gateways and firewalls do not drop an idle session; 30 seconds
+    /// unless set, zero disables them. After three unanswered keepalives in a
+    /// row the connection is closed, and reading from it fails with an error
+    /// saying so.
+    pub fn with_keepalive(mut self, interval: Duration) -> Self {
+        self.keepalive = interval;
+        self
+    }
+
+    /// Request a PTY of `cols` x `rows` instead of the default 80x24, e.g. the
+    /// size of the local terminal.
+    pub fn with_pty_size(mut self, cols: u16, rows: u16) -> Self {
+        self.pty_size = (cols, rows);
+        self
+    }
+
+    /// Only accept a server whose host key has this SHA-256 fingerprint, in
+    /// the `SHA256:...` form printed by `ssh-keygen -lf` and
+    /// [`probe`](Self::probe); the `SHA256:` prefix may be left out. Any
+    /// other key fails `connect` with a handshake error, without prompting.
+    pub fn with_expected_host_key(mut self, fingerprint: String) -> Self {
+        self.expected_host_key = Some(fingerprint);
+        self
+    }
 
-        let mut session = client::connect(config, addr, SshClient).await?;
+    /// Forward X11 connections from the remote side to the local `display`,
+    /// so remote GUI programs open their windows here. A server that refuses
+    /// X11 forwarding only gets a warning; the session still opens, and the
+    /// `x11` negotiated parameter reads `refused` instead of `forwarding`.
+    pub fn with_x11_forwarding(mut self, display: X11Display) -> Self {
+        self.x11_display = Some(display);
+        self
+    }
+
+    /// Have the server listen as described by `forward` once connected and
+    /// tunnel each connection it accepts back to `forward.host:port`, like
+    /// `ssh -R`. May be called several times. A server that refuses a forward
+    /// fails `connect`; the ports it listens on are reported in the
+    /// `remote_forwards` negotiated parameter.
+    pub fn with_remote_forward(mut self, forward: RemoteForward) -> Self {
+        self.remote_forwards.push(forward);
+        self
+    }
+
+    /// Reach this host through `jump`, like `ssh -J`: `connect` logs in to
+    /// the jump host first, opens a direct-tcpip channel from there to this
+    /// connection's host and port, and runs this session over that channel.
+    /// Each hop authenticates with its own credentials, so build `jump` with
+    /// whichever constructor fits the bastion. Jump hosts may have jump hosts
+    /// of their own. Stopping the connection logs out of both.
+    pub fn with_jump(mut self, jump: SshConnection) -> Self {
+        self.jump = Some(Box::new(jump));
+        self
+    }
+
+    /// Log in if needed and open a direct-tcpip channel to `host:port`, as
+    /// the transport of a session that uses this one as its jump host.
+    async fn tunnel(
+        &mut self,
+        host: &str,
+        port: u16,
+    ) -> Result<ChannelStream<client::Msg>, ConnectionError> {
+        if self.session.is_none() {
+            Box::pin(self.start_session())
+                .await
+                .map_err(|e| via_jump(&self.host, e))?;
+        }
+        let session = self
+            .session
+            .clone()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        let originator = SocketAddr::from(([127, 0, 0, 1], 0));
+        let channel = session
+            .open_direct_tcpip(host, port, originator)
+            .await
+            .map_err(|e| {
+                ConnectionError::TcpConnectError(format!(
+                    "jump host {} cannot reach {host}:{port}: {e}",
+                    self.host
+                ))
+            })?;
+        Ok(channel.into_stream())
+    }
+
+    /// Ask the server to listen for every configured remote forward and
+    /// record where connections to each port go.
+    async fn request_remote_forwards(
+        &mut self,
+        session: &Handle<SshClient>,
+        targets: &Mutex<HashMap<u32, (String, u16)>>,
+    ) -> Result<(), ConnectionError> {
+        // Listeners of an earlier, failed attempt went away with its session.
+        self.remote_listeners.clear();
+        let mut listening = Vec::new();
+        for forward in &self.remote_forwards {
+            let bound = session
+                .tcpip_forward(forward.bind_address.clone(), forward.bind_port.into())
+                .await
+                .map_err(|e| match e {
+                    russh::Error::RequestDenied => ConnectionError::Other(format!(
+                        "SSH server refused remote forward {forward}: it may not allow TCP \
+                         forwarding, port {} may be taken, or listening on {} may need \
+                         GatewayPorts",
+                        forward.bind_port, forward.bind_address
+                    )),
+                    e => e.into(),
+                })?;
+            // The server only names the port if it picked one.
+            let port = match bound {
+                0 => forward.bind_port.into(),
+                port => port,
+            };
+            info!(
+                "Server listens on {}:{port} for {}:{}",
+                forward.bind_address, forward.host, forward.port
+            );
+            targets
+                .lock()
+                .unwrap()
+                .insert(port, (forward.host.clone(), forward.port));
+            self.remote_listeners
+                .push((forward.bind_address.clone(), port));
+            listening.push(format!(
+                "{}:{port} -> {}:{}",
+                forward.bind_address, forward.host, forward.port
+            ));
+        }
+        if !listening.is_empty() {
+            self.negotiated
+                .lock()
+                .unwrap()
+                .insert("remote_forwards".into(), listening.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Authenticate `session` with the configured keys or password.
+    async fn authenticate(&self, session: &mut Handle<SshClient>) -> Result<(), ConnectionError> {
+        if !self.keyfiles.is_empty() {
+            self.authenticate_with_keys(session).await
+        } else if let Some(prompt) = &self.keyboard_interactive {
+            self.authenticate_keyboard_interactive(session, prompt)
+                .await
+        } else if self.agent {
+            self.authenticate_with_agent(session).await
+        } else if let Some(pw) = self.password.clone() {
+            let auth_result = session
+                .authenticate_password(self.username.clone(), pw)
+                .await
+                .map_err(|e| self.attribute(e))?;
+            if !auth_result.success() {
+                return Err(ConnectionError::AuthError(format!(
+                    "SSH password rejected for user {}",
+                    self.username
+                )));
+            }
+            Ok(())
+        } else {
+            Err(ConnectionError::AuthError(
+                "No SSH authentication method configured".into(),
+            ))
+        }
+    }
 
-        let auth_result: AuthResult = if let Some((key_path, passphrase)) = self.keyfile.clone() {
-            let key = load_secret_key(&key_path, passphrase.as_deref())
-                .map_err(|e| ConnectionError::Other(format!("SSH key load error: {e}")))?;
-            let rsa_hash = session.best_supported_rsa_hash().await?.flatten();
-            session
+    /// Offer each configured key until one is accepted. The winning key is
+    /// recorded as the `identity` negotiated parameter; if none works, the
+    /// error lists why each one failed.
+    async fn authenticate_with_keys(
+        &self,
+        session: &mut Handle<SshClient>,
+    ) -> Result<(), ConnectionError> {
+        let mut failures = Vec::new();
+        for (key_path, passphrase) in &self.keyfiles {
+            let key = match load_secret_key(key_path, passphrase.as_deref()) {
+                Ok(key) => key,
+                Err(e) => {
+                    failures.push(format!("{}: load error: {e}", key_path.display()));
+                    continue;
+                }
+            };
+            let rsa_hash = session
+                .best_supported_rsa_hash()
+                .await
+                .map_err(|e| self.attribute(e))?
+                .flatten();
+            let auth_result: AuthResult = session
                 .authenticate_publickey(
                     self.username.clone(),
                     PrivateKeyWithHashAlg::new(Arc::new(key), rsa_hash),
                 )
-                .await?
-        } else if let Some(pw) = self.password.clone() {
-            session
-                .authenticate_password(self.username.clone(), pw)
-                .await?
+                .await
+                .map_err(|e| self.attribute(e))?;
+            if auth_result.success() {
+                info!("Authenticated with key {}", key_path.display());
+                self.negotiated
+                    .lock()
+                    .unwrap()
+                    .insert("identity".into(), key_path.display().to_string());
+                return Ok(());
+            }
+            debug!("Key {} was rejected", key_path.display());
+            failures.push(format!("{}: rejected", key_path.display()));
+        }
+        Err(ConnectionError::AuthError(format!(
+            "SSH authentication failed; tried {}",
+            failures.join(", ")
+        )))
+    }
+
+    /// Offer each identity of the ssh-agent until one is accepted, recording
+    /// its comment as the `identity` negotiated parameter.
+    #[cfg(unix)]
+    async fn authenticate_with_agent(
+        &self,
+        session: &mut Handle<SshClient>,
+    ) -> Result<(), ConnectionError> {
+        let mut agent = AgentClient::connect_env().await.map_err(|e| match e {
+            russh::keys::Error::EnvVar(_) => {
+                ConnectionError::AuthError("SSH_AUTH_SOCK is not set; is ssh-agent running?".into())
+            }
+            e => ConnectionError::AuthError(format!(
+                "Cannot reach ssh-agent at {}: {e}",
+                std::env::var("SSH_AUTH_SOCK").unwrap_or_default()
+            )),
+        })?;
+        let identities = agent
+            .request_identities()
+            .await
+            .map_err(|e| ConnectionError::AuthError(format!("ssh-agent: {e}")))?;
+        if identities.is_empty() {
+            return Err(ConnectionError::AuthError(
+                "ssh-agent holds no identities; add one with ssh-add".into(),
+            ));
+        }
+
+        let rsa_hash = session
+            .best_supported_rsa_hash()
+            .await
+            .map_err(|e| self.attribute(e))?
+            .flatten();
+        for identity in &identities {
+            let auth_result = match identity {
+                AgentIdentity::PublicKey { key, .. } => {
+                    session
+                        .authenticate_publickey_with(
+                            self.username.clone(),
+                            key.clone(),
+                            rsa_hash,
+                            &mut agent,
+                        )
+                        .await
+                }
+                AgentIdentity::Certificate { certificate, .. } => {
+                    session
+                        .authenticate_certificate_with(
+                            self.username.clone(),
+                            certificate.clone(),
+                            rsa_hash,
+                            &mut agent,
+                        )
+                        .await
+                }
+            }
+            .map_err(|e| ConnectionError::AuthError(format!("ssh-agent: {e}")))?;
+            if auth_result.success() {
+                info!("Authenticated with agent identity {}", identity.comment());
+                self.negotiated
+                    .lock()
+                    .unwrap()
+                    .insert("identity".into(), format!("agent: {}", identity.comment()));
+                return Ok(());
+            }
+            debug!("Agent identity {} was rejected", identity.comment());
+        }
+        Err(ConnectionError::AuthError(format!(
+            "None of the {} ssh-agent identities was accepted for user {}",
+            identities.len(),
+            self.username
+        )))
+    }
+
+    #[cfg(not(unix))]
+    async fn authenticate_with_agent(
+        &self,
+        _session: &mut Handle<SshClient>,
+    ) -> Result<(), ConnectionError> {
+        Err(ConnectionError::AuthError(
+            "ssh-agent authentication is only supported on Unix".into(),
+        ))
+    }
+
+    /// Answer the server's keyboard-interactive rounds with `prompt` until it
+    /// decides. Falls back to asking `prompt` for a password if the server
+    /// only takes passwords.
+    async fn authenticate_keyboard_interactive(
+        &self,
+        session: &mut Handle<SshClient>,
+        prompt: &PromptCallback,
+    ) -> Result<(), ConnectionError> {
+        let mut reply = session
+            .authenticate_keyboard_interactive_start(self.username.clone(), None)
+            .await
+            .map_err(|e| self.attribute(e))?;
+        let mut rounds = 0;
+        loop {
+            match reply {
+                KeyboardInteractiveAuthResponse::Success => return Ok(()),
+                KeyboardInteractiveAuthResponse::InfoRequest {
+                    name,
+                    instructions,
+                    prompts,
+                } => {
+                    rounds += 1;
+                    let challenge = AuthChallenge {
+                        name,
+                        instructions,
+                        prompts: prompts
+                            .into_iter()
+                            .map(|p| AuthPrompt {
+                                text: p.prompt,
+                                echo: p.echo,
+                            })
+                            .collect(),
+                    };
+                    let responses = if challenge.is_info_only() {
+                        info!("SSH server: {}", challenge.instructions.trim());
+                        ask(prompt, challenge).await?;
+                        Vec::new()
+                    } else {
+                        ask(prompt, challenge).await?
+                    };
+                    reply = session
+                        .authenticate_keyboard_interactive_respond(responses)
+                        .await
+                        .map_err(|e| self.attribute(e))?;
+                }
+                KeyboardInteractiveAuthResponse::Failure {
+                    remaining_methods,
+                    partial_success,
+                } => {
+                    if partial_success {
+                        return Err(ConnectionError::AuthError(format!(
+                            "SSH server also requires one of: {}",
+                            method_names(&remaining_methods)
+                        )));
+                    }
+                    if rounds == 0 && remaining_methods.contains(&MethodKind::Password) {
+                        return self.authenticate_prompted_password(session, prompt).await;
+                    }
+                    return Err(ConnectionError::AuthError(format!(
+                        "SSH keyboard-interactive authentication failed for user {}",
+                        self.username
+                    )));
+                }
+            }
+        }
+    }
+
+    async fn authenticate_prompted_password(
+        &self,
+        session: &mut Handle<SshClient>,
+        prompt: &PromptCallback,
+    ) -> Result<(), ConnectionError> {
+        let challenge = AuthChallenge {
+            name: String::new(),
+            instructions: String::new(),
+            prompts: vec![AuthPrompt {
+                text: "Password: ".into(),
+                echo: false,
+            }],
+        };
+        let password = ask(prompt, challenge).await?.into_iter().next();
+        let auth_result = session
+            .authenticate_password(self.username.clone(), password.unwrap_or_default())
+            .await
+            .map_err(|e| self.attribute(e))?;
+        if !auth_result.success() {
+            return Err(ConnectionError::AuthError(format!(
+                "SSH password rejected for user {}",
+                self.username
+            )));
+        }
+        Ok(())
+    }
+
+    /// `connect_stream` returns before the key exchange has finished, so an
+    /// error while authenticating may still be a handshake failure (e.g. a
+    /// rejected host key). Key exchange is done once `kex_done` recorded it.
+    fn attribute(&self, err: russh::Error) -> ConnectionError {
+        if self.negotiated.lock().unwrap().contains_key("kex") {
+            err.into()
         } else {
+            ConnectionError::HandshakeError(format!("SSH: {err}"))
+        }
+    }
+
+    fn report(&self, milestone: ConnectProgress) {
+        if let Some(progress) = &self.progress {
+            let _ = progress.send(milestone);
+        }
+    }
+
+    /// Perform only the SSH handshake with `host:port` and report the server's
+    /// host key, the algorithms it offers and the ones negotiated, then
+    /// disconnect without authenticating. Meant for auditing servers.
+    pub async fn probe(host: &str, port: u16) -> Result<SshProbe, ConnectionError> {
+        let addr = format!("{host}:{port}");
+        info!("Probing SSH server at {addr}");
+
+        let config = Arc::new(client::Config::default());
+        let (done, done_rx) = oneshot::channel();
+        let (stream, received) = RecordingStream::new(open_tcp(host, port).await?);
+        let handler = ProbeClient {
+            received,
+            kex: None,
+            done: Some(done),
+        };
+        let session = client::connect_stream(config, stream, handler)
+            .await
+            .map_err(|e| ConnectionError::HandshakeError(format!("SSH: {e}")))?;
+
+        let probe = tokio::time::timeout(Duration::from_secs(10), done_rx)
+            .await
+            .map_err(|_| ConnectionError::HandshakeError("SSH: handshake timed out".into()))?
+            .map_err(|_| {
+                ConnectionError::HandshakeError("SSH: connection closed during handshake".into())
+            })??;
+
+        let _ = session
+            .disconnect(Disconnect::ByApplication, "probe done", "en")
+            .await;
+        Ok(probe)
+    }
+
+    /// Upload `local` to `remote` by running `cat > remote` on a separate exec
+    /// channel, for servers that have SFTP disabled.
+    ///
+    /// No PTY is requested, so the bytes reach the remote file untranslated.
+    /// Succeeds only if the remote command exits with status 0.
+    pub async fn upload_via_exec(&self, local: &Path, remote: &str) -> Result<(), ConnectionError> {
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        let file = tokio::fs::File::open(local).await?;
+
+        let mut channel = session.open_session().await?;
+        channel
+            .exec(true, format!("cat > {}", shell_quote(remote)))
+            .await?;
+        channel.data(file).await?;
+        channel.eof().await?;
+
+        let mut exit_status = None;
+        while let Some(msg) = channel.wait().await {
+            match msg {
+                ChannelMsg::Failure => {
+                    return Err(ConnectionError::Other(
+                        "SSH: server refused exec request".into(),
+                    ));
+                }
+                ChannelMsg::ExitStatus { exit_status: code } => exit_status = Some(code),
+                ChannelMsg::Close => break,
+                other => debug!("Ignoring SSH upload channel message: {other:?}"),
+            }
+        }
+
+        match exit_status {
+            Some(0) => {
+                info!("Uploaded {} to {remote}", local.display());
+                Ok(())
+            }
+            Some(code) => Err(ConnectionError::Other(format!(
+                "SSH: upload to {remote} failed with exit status {code}"
+            ))),
+            None => Err(ConnectionError::Other(format!(
+                "SSH: upload to {remote} ended without an exit status"
+            ))),
+        }
+    }
+
+    /// Run `command` on a channel of its own, without a PTY or shell, and
+    /// return its combined stdout and stderr plus its exit status. Logs in
+    /// first if needed, without opening the interactive shell, so scripts can
+    /// use a connection that never calls `connect`.
+    ///
+    /// Fails if the command ends without an exit status, e.g. when it was
+    /// killed by a signal.
+    pub async fn exec(&mut self, command: &str) -> Result<(Vec<u8>, i32), ConnectionError> {
+        if self.session.is_none() {
+            self.start_session().await?;
+        }
+        let session = self
+            .session
+            .clone()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+
+        let mut channel = session.open_session().await?;
+        channel.exec(true, command).await?;
+        let mut output = Vec::new();
+        let mut exit_status = None;
+        while let Some(msg) = channel.wait().await {
+            match msg {
+                ChannelMsg::Failure => {
+                    return Err(ConnectionError::Other(
+                        "SSH: server refused exec request".into(),
+                    ));
+                }
+                ChannelMsg::Data { data } | ChannelMsg::ExtendedData { data, .. } => {
+                    output.extend_from_slice(&data)
+                }
+                ChannelMsg::ExitStatus { exit_status: code } => exit_status = Some(code),
+                ChannelMsg::Close => break,
+                other => debug!("Ignoring SSH exec channel message: {other:?}"),
+            }
+        }
+
+        match exit_status {
+            Some(code) => Ok((output, code as i32)),
+            None => Err(ConnectionError::Other(format!(
+                "SSH: `{command}` ended without an exit status"
+            ))),
+        }
+    }
+
+    /// Connect, authenticate and set up the remote forwards, leaving the
+    /// session ready for shell or exec channels. On failure, also logs out
+    /// of the jump host again.
+    async fn start_session(&mut self) -> Result<(), ConnectionError> {
+        let started = self.log_in().await;
+        if started.is_err() {
+            if let Some(jump) = &mut self.jump {
+                let _ = jump.disconnect().await;
+            }
+        }
+        started
+    }
+
+    async fn log_in(&mut self) -> Result<(), ConnectionError> {
+        if self.adopted {
             return Err(ConnectionError::Other(
-                "No SSH authentication method configured".into(),
+                "Adopted SSH session was closed and cannot be reopened".into(),
             ));
+        }
+        let addr = format!("{}:{}", self.host, self.port);
+        info!("Connecting to SSH server at {addr}");
+        self.disconnect_reason.lock().unwrap().take();
+        // Left over from the previous session: `attribute` would take a
+        // failed handshake for failed authentication, and `negotiated` would
+        // describe a server this attempt may never have reached.
+        self.negotiated.lock().unwrap().clear();
+
+        // With keepalives, a dead server is noticed through the missed
+        // replies, so an idle session must not time out on its own.
+        let config = Arc::new(client::Config {
+            inactivity_timeout: self.keepalive.is_zero().then_some(Duration::from_secs(60)),
+            keepalive_interval: (!self.keepalive.is_zero()).then_some(self.keepalive),
+            keepalive_max: KEEPALIVE_MAX,
+            ..Default::default()
+        });
+
+        self.x11 = match &self.x11_display {
+            Some(display) => Some(Arc::new(X11Forwarding::new(display.clone()).await)),
+            None => None,
         };
+        let rejected_host_key = Arc::new(Mutex::new(None));
+        let remote_targets = Arc::new(Mutex::new(HashMap::new()));
+        let handler = SshClient {
+            negotiated: self.negotiated.clone(),
+            handshake_progress: self.progress.clone(),
+            expected_host_key: self.expected_host_key.clone(),
+            rejected_host_key: rejected_host_key.clone(),
+            x11: self.x11.clone(),
+            remote_targets: remote_targets.clone(),
+            disconnect_reason: self.disconnect_reason.clone(),
+            keepalive: (self.keepalive, KEEPALIVE_MAX),
+        };
+        // A russh channel stream is a plain `AsyncRead + AsyncWrite`, so the
+        // session runs over a jump host's tunnel just like over TCP.
+        let session = match &mut self.jump {
+            Some(jump) => {
+                let stream = jump.tunnel(&self.host, self.port).await?;
+                let jump_host = format!("{}@{}:{}", jump.username, jump.host, jump.port);
+                self.negotiated
+                    .lock()
+                    .unwrap()
+                    .insert("jump".into(), jump_host);
+                self.report(ConnectProgress::TcpConnected);
+                client::connect_stream(config, stream, handler).await
+            }
+            None => {
+                let stream = open_tcp(&self.host, self.port).await?;
+                self.report(ConnectProgress::TcpConnected);
+                client::connect_stream(config, stream, handler).await
+            }
+        };
+        let mut session =
+            session.map_err(|e| ConnectionError::HandshakeError(format!("SSH: {e}")))?;
 
-        if !auth_result.success() {
-            return Err(ConnectionError::Other("SSH authentication failed".into()));
+        // The key is checked during the key exchange, which `connect_stream`
+        // does not wait for; the first request afterwards fails if it was
+        // rejected.
+        let authenticated = self.authenticate(&mut session).await;
+        if let Some(actual) = rejected_host_key.lock().unwrap().take() {
+            return Err(ConnectionError::HandshakeError(format!(
+                "SSH host key mismatch: expected {}, server offered {actual}",
+                self.expected_host_key.as_deref().unwrap_or_default()
+            )));
         }
+        authenticated?;
+        self.report(ConnectProgress::Authenticated);
+        self.request_remote_forwards(&session, &remote_targets)
+            .await?;
+        self.session = Some(Arc::new(session));
+        Ok(())
+    }
 
-        let channel = session.channel_open_session().await?;
+    /// Open the interactive channel: PTY, X11 if configured, then the shell.
+    async fn open_shell(&mut self) -> Result<(), ConnectionError> {
+        let session = self
+            .session
+            .clone()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        let mut channel = session.open_session().await?;
+        let (cols, rows) = self.pty_size;
         channel
-            .request_pty(false, "xterm", 80, 24, 0, 0, &[])
+            .request_pty(false, "xterm", cols as u32, rows as u32, 0, 0, &[])
             .await?;
+        if let Some(x11) = &self.x11 {
+            let granted = x11.request(&mut channel).await?;
+            if !granted {
+                warn!("SSH server refused X11 forwarding; continuing without it");
+            }
+            self.negotiated.lock().unwrap().insert(
+                "x11".into(),
+                if granted { "forwarding" } else { "refused" }.into(),
+            );
+        }
         channel.request_shell(false).await?;
 
         info!("SSH connection established");
-        self.session = Some(session);
+        self.report(ConnectProgress::ShellReady);
         self.channel = Some(channel);
+        self.exit_status = None;
+        Ok(())
+    }
+
+    /// Start an SFTP client on a fresh channel of this session; the shell
+    /// channel is not touched, so both can be used at the same time.
+    pub async fn sftp(&self) -> Result<SftpClient, ConnectionError> {
+        let session = self
+            .session
+            .as_ref()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        let mut channel = session.open_session().await?;
+        channel.request_subsystem(true, "sftp").await?;
+        loop {
+            match channel.wait().await {
+                Some(ChannelMsg::Success) => break,
+                Some(ChannelMsg::Failure) | None => {
+                    return Err(ConnectionError::Other(
+                        "SSH: server refused the sftp subsystem; try upload_via_exec".into(),
+                    ));
+                }
+                Some(other) => debug!("Ignoring SSH sftp channel message: {other:?}"),
+            }
+        }
+        let session = SftpSession::new(channel.into_stream())
+            .await
+            .map_err(|e| ConnectionError::Other(format!("SFTP: {e}")))?;
+        Ok(SftpClient::new(session))
+    }
+}
+
+#[async_trait]
+impl Connection for SshConnection {
+    async fn connect(&mut self) -> Result<(), ConnectionError> {
+        if self.channel.is_some() {
+            return Ok(());
+        }
+        let fresh = self.session.is_none();
+        if fresh {
+            self.start_session().await?;
+        }
+        if let Err(e) = self.open_shell().await {
+            if fresh {
+                self.disconnect().await?;
+            }
+            return Err(e);
+        }
         Ok(())
     }
 
     async fn disconnect(&mut self) -> Result<(), ConnectionError> {
+        for forward in self.forwards.drain(..) {
+            forward.abort();
+        }
         if let Some(channel) = self.channel.take() {
             let _ = channel.close().await;
         }
+        let remote_listeners = std::mem::take(&mut self.remote_listeners);
         if let Some(session) = self.session.take() {
-            let _ = session
-                .disconnect(Disconnect::ByApplication, "bye", "en")
-                .await;
+            for (address, port) in remote_listeners {
+                if let Err(e) = session.cancel_remote_forward(&address, port).await {
+                    debug!("Cancelling remote forward {address}:{port} failed: {e}");
+                }
+            }
+            session.close().await;
+        }
+        self.x11 = None;
+        if let Some(jump) = &mut self.jump {
+            jump.disconnect().await?;
         }
         Ok(())
     }
@@ -168,8 +1292,21 @@ impl Connection for SshConnection {
                 Some(ChannelMsg::ExtendedData { data, .. }) => {
                     return Ok(copy_with_leftovers(&data, buffer, &mut self.leftovers));
                 }
-                Some(ChannelMsg::Eof) | Some(ChannelMsg::Close) | None => {
-                    return Err(ConnectionError::Other("SSH connection closed".into()));
+                // The remote side will not send more data; the I/O task's
+                // `EofPolicy` decides whether that ends the session.
+                Some(ChannelMsg::Eof) => return Ok(0),
+                // Sent when the shell exits, just before the channel closes.
+                Some(ChannelMsg::ExitStatus { exit_status }) => {
+                    self.exit_status = Some(exit_status);
+                }
+                Some(ChannelMsg::Close) | None => {
+                    let reason = self.disconnect_reason.lock().unwrap().take();
+                    return Err(match reason {
+                        Some(reason) => ConnectionError::Other(reason),
+                        None => ConnectionError::RemoteClosed {
+                            exit_status: self.exit_status,
+                        },
+                    });
                 }
                 Some(other) => {
                     debug!("Ignoring SSH channel message: {other:?}");
@@ -177,6 +1314,131 @@ impl Connection for SshConnection {
             }
         }
     }
+
+    fn set_progress(&mut self, progress: ProgressSender) {
+        self.progress = Some(progress);
+    }
+
+    /// The session is only kept once authenticated, and the shell channel
+    /// once it is open, so both being there and the session task still
+    /// running means the connection is up.
+    fn is_connected(&self) -> bool {
+        self.channel.is_some() && self.session.as_ref().is_some_and(|s| !s.is_closed())
+    }
+
+    fn kind(&self) -> &'static str {
+        "ssh"
+    }
+
+    fn negotiated(&self) -> NegotiatedParams {
+        let mut params = self.negotiated.lock().unwrap().clone();
+        if self.channel.is_some() {
+            let (cols, rows) = self.pty_size;
+            params.insert("term".into(), "xterm".into());
+            params.insert("pty_size".into(), format!("{cols}x{rows}"));
+        }
+        params
+    }
+
+    fn pty_size(&self) -> Option<(u16, u16)> {
+        Some(self.pty_size)
+    }
+
+    /// `write` only returns once the session has taken the data over, and
+    /// the session sends without buffering, so there is nothing left to
+    /// push out.
+    async fn flush(&mut self) -> Result<(), ConnectionError> {
+        match self.channel {
+            Some(_) => Ok(()),
+            None => Err(ConnectionError::Other("Not connected".into())),
+        }
+    }
+
+    async fn resize(&mut self, cols: u16, rows: u16) -> Result<(), ConnectionError> {
+        if let Some(channel) = self.channel.as_ref() {
+            channel
+                .window_change(cols as u32, rows as u32, 0, 0)
+                .await?;
+        }
+        self.pty_size = (cols, rows);
+        Ok(())
+    }
+
+    /// Listen on `forward`'s local address; each accepted connection gets
+    /// its own direct-tcpip channel over this session, so any number can run
+    /// at once. All of them end when the connection disconnects.
+    async fn local_forward(
+        &mut self,
+        forward: &LocalForward,
+    ) -> Result<SocketAddr, ConnectionError> {
+        let session = self
+            .session
+            .clone()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        let listener =
+            TcpListener::bind((forward.bind_address.as_str(), forward.bind_port)).await?;
+        let local = listener.local_addr()?;
+        info!(
+            "Forwarding {local} to {}:{} via {}",
+            forward.host, forward.port, self.host
+        );
+        self.forwards.push(tokio::spawn(serve_local_forward(
+            listener,
+            session,
+            forward.host.clone(),
+            forward.port,
+        )));
+        Ok(local)
+    }
+
+    async fn sftp(&mut self) -> Result<SftpClient, ConnectionError> {
+        SshConnection::sftp(self).await
+    }
+}
+
+/// Relay every connection accepted on `listener` through a direct-tcpip
+/// channel of `session` to `host:port`, until aborted, which also ends the
+/// relays.
+async fn serve_local_forward(
+    listener: TcpListener,
+    session: Arc<dyn SessionHandle>,
+    host: String,
+    port: u16,
+) {
+    let mut relays = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => {
+                let (mut socket, peer) = match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        warn!("Accepting a forwarded connection failed: {e}");
+                        continue;
+                    }
+                };
+                let session = session.clone();
+                let host = host.clone();
+                relays.spawn(async move {
+                    let channel = match session.open_direct_tcpip(&host, port, peer).await {
+                        Ok(channel) => channel,
+                        Err(e) => {
+                            warn!("SSH server refused forwarding {peer} to {host}:{port}: {e}");
+                            return;
+                        }
+                    };
+                    debug!("Forwarding {peer} to {host}:{port}");
+                    let mut stream = channel.into_stream();
+                    match tokio::io::copy_bidirectional(&mut socket, &mut stream).await {
+                        Ok((sent, received)) => {
+                            debug!("Forward from {peer} closed after {sent} bytes out, {received} in")
+                        }
+                        Err(e) => debug!("Forward from {peer} ended: {e}"),
+                    }
+                });
+            }
+            Some(_) = relays.join_next() => {}
+        }
+    }
 }
 
 fn copy_with_leftovers(src: &[u8], buf: &mut [u8], leftovers: &mut VecDeque<u8>) -> usize {
@@ -187,3 +1449,54 @@ fn copy_with_leftovers(src: &[u8], buf: &mut [u8], leftovers: &mut VecDeque<u8>)
     }
     n
 }
+
+/// Say which hop failed when logging in to a jump host goes wrong.
+fn via_jump(host: &str, err: ConnectionError) -> ConnectionError {
+    match err {
+        ConnectionError::DnsError(msg) => {
+            ConnectionError::DnsError(format!("jump host {host}: {msg}"))
+        }
+        ConnectionError::TcpConnectError(msg) => {
+            ConnectionError::TcpConnectError(format!("jump host {host}: {msg}"))
+        }
+        ConnectionError::HandshakeError(msg) => {
+            ConnectionError::HandshakeError(format!("jump host {host}: {msg}"))
+        }
+        ConnectionError::AuthError(msg) => {
+            ConnectionError::AuthError(format!("jump host {host}: {msg}"))
+        }
+        ConnectionError::Other(msg) => ConnectionError::Other(format!("jump host {host}: {msg}")),
+        other => other,
+    }
+}
+
+/// Run `prompt` on the blocking thread pool.
+async fn ask(
+    prompt: &PromptCallback,
+    challenge: AuthChallenge,
+) -> Result<Vec<String>, ConnectionError> {
+    let prompt = prompt.clone();
+    tokio::task::spawn_blocking(move || prompt(&challenge))
+        .await
+        .map_err(|e| ConnectionError::AuthError(format!("SSH prompt callback failed: {e}")))
+}
+
+/// `publickey, password` style list of auth methods.
+fn method_names(methods: &[MethodKind]) -> String {
+    methods
+        .iter()
+        .map(<&str>::from)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// Compare SHA-256 fingerprints, with or without the `SHA256:` prefix.
+fn same_fingerprint(expected: &str, actual: &str) -> bool {
+    let strip = |fp: &str| fp.trim().trim_start_matches("SHA256:").to_owned();
+    strip(expected) == strip(actual)
+}
+
+/// Quote `arg` for a POSIX shell by wrapping it in single quotes.
+fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', r"'\''"))
+}
@@ -0,0 +1,368 @@
+//! X11 forwarding for [`SshConnection`](super::SshConnection).
+//!
+//! Like OpenSSH, the server is given a random fake cookie. When a remote
+//! client opens an X11 channel, its connection setup must carry that cookie;
+//! it is then replaced with the local display's real cookie (from `xauth`)
+//! before the setup reaches the X server.
+
+use crate::connections::errors::ConnectionError;
+use log::{debug, warn};
+use russh::client;
+use russh::Channel;
+use std::fmt;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const AUTH_PROTOCOL: &str = "MIT-MAGIC-COOKIE-1";
+/// Displays over TCP listen on this port plus the display number.
+const X11_BASE_PORT: u32 = 6000;
+
+/// A local X display as named by `$DISPLAY`, e.g. `:0`, `:1.0` or
+/// `localhost:10.0`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct X11Display {
+    /// `None` for a display on this machine, reached over its Unix socket.
+    pub host: Option<String>,
+    pub number: u32,
+    pub screen: u32,
+}
+
+impl X11Display {
+    /// Parse a `$DISPLAY` value of the form `[host]:display[.screen]`. An
+    /// empty host or `unix` means the local socket.
+    pub fn parse(display: &str) -> Result<Self, ConnectionError> {
+        let invalid = || ConnectionError::Other(format!("Invalid X11 display {display:?}"));
+        let (host, rest) = display.rsplit_once(':').ok_or_else(invalid)?;
+        let (number, screen) = match rest.split_once('.') {
+            Some((number, screen)) => (number, screen.parse().map_err(|_| invalid())?),
+            None => (rest, 0),
+        };
+        Ok(Self {
+            host: match host {
+                "" | "unix" => None,
+                host => Some(host.to_owned()),
+            },
+            number: number.parse().map_err(|_| invalid())?,
+            screen,
+        })
+    }
+
+    /// TCP port of a display reached over the network.
+    fn tcp_port(&self) -> Result<u16, ConnectionError> {
+        X11_BASE_PORT
+            .checked_add(self.number)
+            .and_then(|port| u16::try_from(port).ok())
+            .ok_or_else(|| ConnectionError::Other(format!("X11 display {self} has no TCP port")))
+    }
+
+    /// The display named by the `DISPLAY` environment variable.
+    pub fn from_env() -> Result<Self, ConnectionError> {
+        let display = std::env::var("DISPLAY").map_err(|_| {
+            ConnectionError::Other("X11 forwarding needs $DISPLAY to be set".into())
+        })?;
+        Self::parse(&display)
+    }
+}
+
+impl fmt::Display for X11Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}.{}",
+            self.host.as_deref().unwrap_or_default(),
+            self.number,
+            self.screen
+        )
+    }
+}
+
+/// State shared by the X11 channels of one session.
+pub(crate) struct X11Forwarding {
+    display: X11Display,
+    fake_cookie: [u8; 16],
+    /// `None` if `xauth` knows no cookie, e.g. for a display with host-based
+    /// access control; the setup is then forwarded without authentication.
+    real_cookie: Option<Vec<u8>>,
+}
+
+impl X11Forwarding {
+    pub(crate) async fn new(display: X11Display) -> Self {
+        let real_cookie = xauth_cookie(&display).await;
+        if real_cookie.is_none() {
+            debug!("No xauth cookie for display {display}; forwarding without one");
+        }
+        Self {
+            display,
+            fake_cookie: rand::random(),
+            real_cookie,
+        }
+    }
+
+    /// Ask the server to forward X11 on `channel`, before the shell starts.
+    /// Returns whether the server agreed.
+    pub(crate) async fn request(
+        &self,
+        channel: &mut Channel<client::Msg>,
+    ) -> Result<bool, ConnectionError> {
+        channel
+            .request_x11(
+                true,
+                false,
+                AUTH_PROTOCOL,
+                to_hex(&self.fake_cookie),
+                self.display.screen,
+            )
+            .await?;
+        loop {
+            match channel.wait().await {
+                Some(russh::ChannelMsg::Success) => return Ok(true),
+                Some(russh::ChannelMsg::Failure) => return Ok(false),
+                Some(other) => debug!("Ignoring SSH channel message: {other:?}"),
+                None => return Err(ConnectionError::Other("SSH connection closed".into())),
+            }
+        }
+    }
+
+    /// Connect an X11 channel opened by the server to the local display and
+    /// relay between them until either side closes.
+    pub(crate) async fn serve(&self, channel: Channel<client::Msg>) {
+        let result = match &self.display.host {
+            #[cfg(unix)]
+            None => {
+                let path = format!("/tmp/.X11-unix/X{}", self.display.number);
+                match tokio::net::UnixStream::connect(path).await {
+                    Ok(local) => self.relay(channel, local).await,
+                    Err(e) => Err(e.into()),
+                }
+            }
+            host => {
+                let host = host.as_deref().unwrap_or("localhost");
+                match self.display.tcp_port() {
+                    Ok(port) => match TcpStream::connect((host, port)).await {
+                        Ok(local) => self.relay(channel, local).await,
+                        Err(e) => Err(e.into()),
+                    },
+                    Err(e) => Err(e),
+                }
+            }
+        };
+        if let Err(e) = result {
+            warn!("X11 forwarding to display {} failed: {e}", self.display);
+        }
+    }
+
+    async fn relay<S>(
+        &self,
+        channel: Channel<client::Msg>,
+        mut local: S,
+    ) -> Result<(), ConnectionError>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let mut remote = channel.into_stream();
+        let setup = read_setup(&mut remote).await?;
+        let setup = replace_cookie(&setup, &self.fake_cookie, self.real_cookie.as_deref())?;
+        local.write_all(&setup).await?;
+        tokio::io::copy_bidirectional(&mut remote, &mut local).await?;
+        Ok(())
+    }
+}
+
+/// Read an X11 connection setup up to the end of its auth data, however the
+/// bytes are split up on the way.
+async fn read_setup<R: AsyncRead + Unpin>(remote: &mut R) -> Result<Vec<u8>, ConnectionError> {
+    let mut setup = vec![0u8; 12];
+    remote.read_exact(&mut setup).await?;
+    let (name_len, data_len) = auth_lengths(&setup)?;
+    setup.resize(12 + padded(name_len) + padded(data_len), 0);
+    remote.read_exact(&mut setup[12..]).await?;
+    Ok(setup)
+}
+
+fn malformed() -> ConnectionError {
+    ConnectionError::Other("X11: malformed connection setup".into())
+}
+
+/// Lengths of the auth protocol name and data in an X11 connection setup
+/// header, in the byte order the header announces.
+fn auth_lengths(header: &[u8]) -> Result<(usize, usize), ConnectionError> {
+    if header.len() < 12 {
+        return Err(malformed());
+    }
+    let read_u16 = |at: usize| {
+        let bytes = [header[at], header[at + 1]];
+        match header[0] {
+            b'B' => Ok(u16::from_be_bytes(bytes) as usize),
+            b'l' => Ok(u16::from_le_bytes(bytes) as usize),
+            _ => Err(malformed()),
+        }
+    };
+    Ok((read_u16(6)?, read_u16(8)?))
+}
+
+/// Check that `setup` authenticates with `fake` and swap in `real`, or drop
+/// the authentication if there is no real cookie.
+fn replace_cookie(
+    setup: &[u8],
+    fake: &[u8],
+    real: Option<&[u8]>,
+) -> Result<Vec<u8>, ConnectionError> {
+    let (name_len, data_len) = auth_lengths(setup)?;
+    let data_start = 12 + padded(name_len);
+    let (Some(name), Some(data)) = (
+        setup.get(12..12 + name_len),
+        setup.get(data_start..data_start + data_len),
+    ) else {
+        return Err(malformed());
+    };
+    if name != AUTH_PROTOCOL.as_bytes() || data != fake {
+        return Err(ConnectionError::AuthError(
+            "X11: remote client sent the wrong cookie".into(),
+        ));
+    }
+
+    let (name, data): (&[u8], &[u8]) = match real {
+        Some(real) => (AUTH_PROTOCOL.as_bytes(), real),
+        None => (&[], &[]),
+    };
+    let encode = |n: usize| {
+        let n = u16::try_from(n).map_err(|_| {
+            ConnectionError::Other(format!("X11: local cookie of {n} bytes is too long"))
+        })?;
+        Ok::<_, ConnectionError>(match setup[0] {
+            b'B' => n.to_be_bytes(),
+            _ => n.to_le_bytes(),
+        })
+    };
+    let mut out = setup[..6].to_vec();
+    out.extend_from_slice(&encode(name.len())?);
+    out.extend_from_slice(&encode(data.len())?);
+    out.extend_from_slice(&setup[10..12]);
+    for field in [name, data] {
+        out.extend_from_slice(field);
+        out.resize(out.len() + padded(field.len()) - field.len(), 0);
+    }
+    Ok(out)
+}
+
+/// The MIT-MAGIC-COOKIE-1 `xauth` has for `display`, if any.
+async fn xauth_cookie(display: &X11Display) -> Option<Vec<u8>> {
+    let output = tokio::process::Command::new("xauth")
+        .args(["list", &display.to_string()])
+        .output()
+        .await
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(
+            |line| match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [_, AUTH_PROTOCOL, hex] => from_hex(hex),
+                _ => None,
+            },
+        )
+}
+
+/// X11 pads every variable-length field to four bytes.
+fn padded(len: usize) -> usize {
+    len.div_ceil(4) * 4
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAKE: [u8; 16] = [0xfa; 16];
+
+    /// A big-endian connection setup authenticating with `name` and `data`.
+    fn setup(name: &[u8], data: &[u8]) -> Vec<u8> {
+        let mut out = vec![b'B', 0, 0, 11, 0, 0];
+        out.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        out.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        out.extend_from_slice(&[0, 0]);
+        for field in [name, data] {
+            out.extend_from_slice(field);
+            out.resize(out.len() + padded(field.len()) - field.len(), 0);
+        }
+        out
+    }
+
+    #[test]
+    fn cookie_is_swapped_for_the_real_one() {
+        let out = replace_cookie(
+            &setup(AUTH_PROTOCOL.as_bytes(), &FAKE),
+            &FAKE,
+            Some(&[0x11; 16]),
+        )
+        .unwrap();
+        assert_eq!(out, setup(AUTH_PROTOCOL.as_bytes(), &[0x11; 16]));
+
+        let out = replace_cookie(&setup(AUTH_PROTOCOL.as_bytes(), &FAKE), &FAKE, None).unwrap();
+        assert_eq!(out, setup(b"", b""));
+    }
+
+    #[test]
+    fn short_setups_are_rejected() {
+        let full = setup(AUTH_PROTOCOL.as_bytes(), &FAKE);
+        for len in [0, 1, 11, 12, 30, full.len() - 1] {
+            assert!(
+                replace_cookie(&full[..len], &FAKE, None).is_err(),
+                "{len} bytes"
+            );
+        }
+        assert!(auth_lengths(&full[..11]).is_err());
+    }
+
+    #[test]
+    fn wrong_cookie_is_rejected() {
+        let err = replace_cookie(&setup(AUTH_PROTOCOL.as_bytes(), &[0; 16]), &FAKE, None);
+        assert!(matches!(err, Err(ConnectionError::AuthError(_))));
+    }
+
+    #[test]
+    fn oversized_real_cookie_is_rejected() {
+        let real = vec![0x11; usize::from(u16::MAX) + 1];
+        let setup = setup(AUTH_PROTOCOL.as_bytes(), &FAKE);
+        assert!(replace_cookie(&setup, &FAKE, Some(&real)).is_err());
+    }
+
+    #[tokio::test]
+    async fn fragmented_setup_is_read_whole() {
+        let full = setup(AUTH_PROTOCOL.as_bytes(), &FAKE);
+        let (mut client, mut server) = tokio::io::duplex(64);
+        let sent = full.clone();
+        tokio::spawn(async move {
+            for byte in sent {
+                client.write_all(&[byte]).await.unwrap();
+                tokio::task::yield_now().await;
+            }
+            client.write_all(b"rest of the stream").await.unwrap();
+        });
+        assert_eq!(read_setup(&mut server).await.unwrap(), full);
+    }
+
+    #[test]
+    fn display_numbers_past_the_port_range_have_no_tcp_port() {
+        let display = |number| X11Display {
+            host: Some("localhost".into()),
+            number,
+            screen: 0,
+        };
+        assert_eq!(display(10).tcp_port().unwrap(), 6010);
+        assert!(display(65_536 - 6000).tcp_port().is_err());
+        assert!(display(u32::MAX).tcp_port().is_err());
+    }
+}
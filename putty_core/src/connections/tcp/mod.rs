@@ -0,0 +1,23 @@
+pub mod raw_tcp_connection;
+
+pub use raw_tcp_connection::*;
+
+use crate::connections::errors::ConnectionError;
+use tokio::net::{lookup_host, TcpStream};
+
+/// Resolve `host` and open a TCP connection, telling DNS failures apart from
+/// connect failures.
+pub(crate) async fn open_tcp(host: &str, port: u16) -> Result<TcpStream, ConnectionError> {
+    let addrs: Vec<_> = lookup_host((host, port))
+        .await
+        .map_err(|e| ConnectionError::DnsError(format!("{host}: {e}")))?
+        .collect();
+    if addrs.is_empty() {
+        return Err(ConnectionError::DnsError(format!(
+            "{host}: no addresses found"
+        )));
+    }
+    TcpStream::connect(&addrs[..])
+        .await
+        .map_err(|e| ConnectionError::TcpConnectError(format!("{host}:{port}: {e}")))
+}
@@ -0,0 +1,121 @@
+use crate::connections::{
+    connection::{Connection, NegotiatedParams},
+    errors::ConnectionError,
+    tcp::open_tcp,
+};
+use async_trait::async_trait;
+use log::info;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// A plain TCP byte stream, e.g. a serial-to-network bridge or a telnet-less
+/// device console.
+pub struct RawTcpConnection {
+    host: String,
+    port: u16,
+    /// Set by [`from_stream`](Self::from_stream); such a connection cannot be
+    /// re-established once closed.
+    adopted: bool,
+    stream: Option<TcpStream>,
+}
+
+impl RawTcpConnection {
+    pub fn new(host: String, port: u16) -> Self {
+        Self {
+            host,
+            port,
+            adopted: false,
+            stream: None,
+        }
+    }
+
+    /// Adopt a stream that is already connected; `connect` then has nothing
+    /// to do. The connection takes ownership of `stream` and shuts it down on
+    /// `disconnect`, after which it cannot reconnect.
+    pub fn from_stream(stream: TcpStream) -> Self {
+        let (host, port) = match stream.peer_addr() {
+            Ok(addr) => (addr.ip().to_string(), addr.port()),
+            Err(_) => (String::new(), 0),
+        };
+        Self {
+            host,
+            port,
+            adopted: true,
+            stream: Some(stream),
+        }
+    }
+
+    fn stream(&mut self) -> Result<&mut TcpStream, ConnectionError> {
+        self.stream
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))
+    }
+}
+
+#[async_trait]
+impl Connection for RawTcpConnection {
+    async fn connect(&mut self) -> Result<(), ConnectionError> {
+        if self.stream.is_some() {
+            return Ok(());
+        }
+        if self.adopted {
+            return Err(ConnectionError::Other(
+                "Adopted TCP stream was closed and cannot be reopened".into(),
+            ));
+        }
+        info!("Connecting to {}:{}", self.host, self.port);
+        let stream = open_tcp(&self.host, self.port).await?;
+        stream.set_nodelay(true)?;
+        self.stream = Some(stream);
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ConnectionError> {
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.shutdown().await;
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError> {
+        self.stream()?.write_all(data).await?;
+        Ok(data.len())
+    }
+
+    async fn flush(&mut self) -> Result<(), ConnectionError> {
+        Ok(self.stream()?.flush().await?)
+    }
+
+    /// A peer that closes the socket ends the session with
+    /// [`RemoteClosed`](ConnectionError::RemoteClosed); the stream is dropped,
+    /// so [`is_connected`](Connection::is_connected) reports it too.
+    async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ConnectionError> {
+        let read = self.stream()?.read(buffer).await?;
+        if read == 0 && !buffer.is_empty() {
+            self.stream = None;
+            return Err(ConnectionError::RemoteClosed { exit_status: None });
+        }
+        Ok(read)
+    }
+
+    fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn kind(&self) -> &'static str {
+        "tcp"
+    }
+
+    fn negotiated(&self) -> NegotiatedParams {
+        let mut params = NegotiatedParams::new();
+        if let Some(stream) = &self.stream {
+            if let Ok(addr) = stream.peer_addr() {
+                params.insert("peer".into(), addr.to_string());
+            }
+            if let Ok(addr) = stream.local_addr() {
+                params.insert("local".into(), addr.to_string());
+            }
+        }
+        params
+    }
+}
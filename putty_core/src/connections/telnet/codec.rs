@@ -0,0 +1,290 @@
+//! The byte-level part of Telnet (RFC 854/855): data bytes are separated
+//! from IAC commands, and the option negotiation is answered.
+
+/// Interpret As Command; doubled to send a literal 0xFF.
+pub const IAC: u8 = 255;
+pub const DONT: u8 = 254;
+pub const DO: u8 = 253;
+pub const WONT: u8 = 252;
+pub const WILL: u8 = 251;
+/// Start of a subnegotiation, ended by IAC SE.
+pub const SB: u8 = 250;
+pub const SE: u8 = 240;
+
+/// The peer echoes what we send (RFC 857).
+pub const ECHO: u8 = 1;
+/// No Go Ahead after every transmission, i.e. character mode (RFC 858).
+pub const SUPPRESS_GO_AHEAD: u8 = 3;
+
+const CR: u8 = b'\r';
+const LF: u8 = b'\n';
+const NUL: u8 = 0;
+
+/// Longest subnegotiation kept; the rest of a longer one is dropped.
+const MAX_SUBNEGOTIATION: usize = 512;
+
+/// Where the decoder is in the byte stream; sequences may be split across
+/// reads at any byte.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    Data,
+    /// Right after a data CR, whose NUL padding is dropped.
+    Cr,
+    Iac,
+    /// After IAC WILL/WONT/DO/DONT, waiting for the option.
+    Negotiate(u8),
+    /// Inside IAC SB ... IAC SE.
+    Sub,
+    SubIac,
+}
+
+/// State of one option on one side of the connection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum Side {
+    #[default]
+    No,
+    Yes,
+    /// We asked for it and wait for the answer.
+    WantYes,
+}
+
+/// Telnet decoder and encoder for one connection.
+///
+/// The peer may turn on ECHO and SUPPRESS-GO-AHEAD, and we agree to suppress
+/// go-aheads ourselves; every other option is refused unless it was added
+/// with [`accept_remote`](Self::accept_remote) or
+/// [`request_local`](Self::request_local). Answers are only sent when an
+/// option actually changes state, so two peers cannot keep acknowledging
+/// each other.
+#[derive(Debug, Clone)]
+pub struct TelnetCodec {
+    state: State,
+    local: [Side; 256],
+    remote: [Side; 256],
+    accept_local: Vec<u8>,
+    accept_remote: Vec<u8>,
+    /// The subnegotiation being read, starting with its option.
+    sub: Vec<u8>,
+    subnegotiations: Vec<Vec<u8>>,
+    replies: Vec<u8>,
+    /// The last encoded data byte was a CR whose padding is not decided
+    /// yet: none if LF follows, NUL otherwise.
+    pending_cr: bool,
+}
+
+impl Default for TelnetCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TelnetCodec {
+    pub fn new() -> Self {
+        Self {
+            state: State::Data,
+            local: [Side::No; 256],
+            remote: [Side::No; 256],
+            accept_local: vec![SUPPRESS_GO_AHEAD],
+            accept_remote: vec![ECHO, SUPPRESS_GO_AHEAD],
+            sub: Vec::new(),
+            subnegotiations: Vec::new(),
+            replies: Vec::new(),
+            pending_cr: false,
+        }
+    }
+
+    /// Let the peer enable `option` on its side when it offers to.
+    pub fn accept_remote(&mut self, option: u8) {
+        if !self.accept_remote.contains(&option) {
+            self.accept_remote.push(option);
+        }
+    }
+
+    /// Offer to enable `option` on our side; IAC WILL is queued in the
+    /// replies and [`local_option`](Self::local_option) tells the outcome.
+    pub fn request_local(&mut self, option: u8) {
+        if !self.accept_local.contains(&option) {
+            self.accept_local.push(option);
+        }
+        if self.local[option as usize] == Side::No {
+            self.local[option as usize] = Side::WantYes;
+            self.reply(WILL, option);
+        }
+    }
+
+    /// Whether `option` is enabled on our side, or `None` while the peer has
+    /// not answered our request yet.
+    pub fn local_option(&self, option: u8) -> Option<bool> {
+        match self.local[option as usize] {
+            Side::No => Some(false),
+            Side::Yes => Some(true),
+            Side::WantYes => None,
+        }
+    }
+
+    /// Append the data bytes of `input` to `data` and queue answers to the
+    /// negotiation in it, see [`take_replies`](Self::take_replies). Commands
+    /// and subnegotiations never reach `data`; IAC IAC becomes one 0xFF and
+    /// the NUL after a CR is dropped. Subnegotiations of enabled options are
+    /// kept for [`take_subnegotiations`](Self::take_subnegotiations).
+    pub fn decode(&mut self, input: &[u8], data: &mut Vec<u8>) {
+        for &byte in input {
+            self.state = match (self.state, byte) {
+                (State::Data | State::Cr, IAC) => State::Iac,
+                (State::Cr, NUL) => State::Data,
+                (State::Data | State::Cr, CR) => {
+                    data.push(CR);
+                    State::Cr
+                }
+                (State::Data | State::Cr, byte) => {
+                    data.push(byte);
+                    State::Data
+                }
+                (State::Iac, IAC) => {
+                    data.push(IAC);
+                    State::Data
+                }
+                (State::Iac, WILL | WONT | DO | DONT) => State::Negotiate(byte),
+                (State::Iac, SB) => {
+                    self.sub.clear();
+                    State::Sub
+                }
+                // NOP, GA, AYT and friends carry nothing for us.
+                (State::Iac, _) => State::Data,
+                (State::Negotiate(command), option) => {
+                    self.negotiate(command, option);
+                    State::Data
+                }
+                (State::Sub, IAC) => State::SubIac,
+                (State::Sub, byte) | (State::SubIac, byte @ IAC) => {
+                    if self.sub.len() < MAX_SUBNEGOTIATION {
+                        self.sub.push(byte);
+                    }
+                    State::Sub
+                }
+                (State::SubIac, SE) => {
+                    self.end_subnegotiation();
+                    State::Data
+                }
+                (State::SubIac, _) => State::Sub,
+            };
+        }
+    }
+
+    /// Escape `data` for the wire: 0xFF is doubled and a CR that is not
+    /// followed by LF gets the NUL padding NVT asks for. The data may be
+    /// split across calls at any byte: the padding of a CR at the end is
+    /// decided by the next call, or sent by [`flush`](Self::flush).
+    pub fn encode(&mut self, data: &[u8]) -> Vec<u8> {
+        let mut wire = Vec::with_capacity(data.len() + 4);
+        for &byte in data {
+            if std::mem::take(&mut self.pending_cr) && byte != LF {
+                wire.push(NUL);
+            }
+            wire.push(byte);
+            match byte {
+                IAC => wire.push(IAC),
+                CR => self.pending_cr = true,
+                _ => {}
+            }
+        }
+        wire
+    }
+
+    /// The NUL padding of a CR that ended the encoded data, if it is still
+    /// owed; to be sent before anything but data, or when the writer
+    /// flushes.
+    pub fn flush(&mut self) -> Vec<u8> {
+        match std::mem::take(&mut self.pending_cr) {
+            true => vec![NUL],
+            false => Vec::new(),
+        }
+    }
+
+    /// IAC SB `option` `payload` IAC SE, with 0xFF in the payload doubled.
+    pub fn subnegotiation(option: u8, payload: &[u8]) -> Vec<u8> {
+        let mut wire = vec![IAC, SB, option];
+        for &byte in payload {
+            wire.push(byte);
+            if byte == IAC {
+                wire.push(IAC);
+            }
+        }
+        wire.extend_from_slice(&[IAC, SE]);
+        wire
+    }
+
+    /// Answers queued by [`decode`](Self::decode) that still have to be sent
+    /// to the peer.
+    pub fn take_replies(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.replies)
+    }
+
+    /// Subnegotiations received since the last call, unescaped, each
+    /// starting with its option.
+    pub fn take_subnegotiations(&mut self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.subnegotiations)
+    }
+
+    /// Whether the peer echoes what we send, so the user should not.
+    pub fn remote_echo(&self) -> bool {
+        self.remote[ECHO as usize] == Side::Yes
+    }
+
+    /// Whether both sides agreed to drop go-aheads (character mode).
+    pub fn suppress_go_ahead(&self) -> bool {
+        self.remote[SUPPRESS_GO_AHEAD as usize] == Side::Yes
+            && self.local[SUPPRESS_GO_AHEAD as usize] == Side::Yes
+    }
+
+    fn negotiate(&mut self, command: u8, option: u8) {
+        // WILL/WONT are about the peer's side, DO/DONT about ours.
+        let remote = matches!(command, WILL | WONT);
+        let enable = matches!(command, WILL | DO);
+        let (side, accepted) = match remote {
+            true => (
+                &mut self.remote[option as usize],
+                self.accept_remote.contains(&option),
+            ),
+            false => (
+                &mut self.local[option as usize],
+                self.accept_local.contains(&option),
+            ),
+        };
+        let (next, agree) = match (*side, enable) {
+            // The answer to our own request is not answered again.
+            (Side::WantYes, true) => (Side::Yes, None),
+            (Side::WantYes, false) => (Side::No, None),
+            (Side::Yes, true) | (Side::No, false) => return,
+            (Side::No, true) if accepted => (Side::Yes, Some(true)),
+            (Side::No, true) => (Side::No, Some(false)),
+            (Side::Yes, false) => (Side::No, Some(false)),
+        };
+        *side = next;
+        let Some(agree) = agree else {
+            return;
+        };
+        let answer = match (remote, agree) {
+            (true, true) => DO,
+            (true, false) => DONT,
+            (false, true) => WILL,
+            (false, false) => WONT,
+        };
+        self.reply(answer, option);
+    }
+
+    fn end_subnegotiation(&mut self) {
+        let Some(&option) = self.sub.first() else {
+            return;
+        };
+        let enabled =
+            self.local[option as usize] == Side::Yes || self.remote[option as usize] == Side::Yes;
+        if enabled {
+            self.subnegotiations.push(std::mem::take(&mut self.sub));
+        }
+    }
+
+    fn reply(&mut self, command: u8, option: u8) {
+        self.replies.extend_from_slice(&[IAC, command, option]);
+    }
+}
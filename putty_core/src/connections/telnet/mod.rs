@@ -0,0 +1,7 @@
+pub mod codec;
+pub mod rfc2217;
+pub mod telnet_connection;
+
+pub use codec::TelnetCodec;
+pub use rfc2217::Rfc2217Connection;
+pub use telnet_connection::*;
@@ -0,0 +1,341 @@
+//! Serial ports shared over Telnet with the COM-PORT-OPTION (RFC 2217), as
+//! offered by ser2net, most console servers and `pyserial`'s port server.
+//!
+//! Port settings and control lines travel as subnegotiations
+//! (`IAC SB 44 <command> <value> IAC SE`) in the same byte stream as the
+//! serial data. They are kept apart in both directions:
+//!
+//! - Incoming, the [`TelnetCodec`] state machine moves every byte between
+//!   IAC SB and IAC SE into a separate subnegotiation list, however the
+//!   stream is split across reads, so the data handed to the reader never
+//!   contains a piece of one. The list is drained after every socket read.
+//! - Outgoing, data is escaped before it is written and every command is
+//!   written as one complete frame. Both go through the manager's I/O task
+//!   one at a time, so a command never lands inside a data write.
+
+use crate::connections::{
+    connection::{Connection, NegotiatedParams},
+    errors::ConnectionError,
+    framing::{FlowControl, Framing, ModemStatus, Parity},
+    telnet::{codec::TelnetCodec, telnet_connection::TelnetConnection},
+};
+use async_trait::async_trait;
+use log::debug;
+use std::time::Duration;
+
+/// The Telnet option of RFC 2217.
+pub const COM_PORT_OPTION: u8 = 44;
+
+// Commands from the client. The server confirms each with the same command
+// plus 100 and the value it actually applied.
+const SET_BAUDRATE: u8 = 1;
+const SET_DATASIZE: u8 = 2;
+const SET_PARITY: u8 = 3;
+const SET_STOPSIZE: u8 = 4;
+const SET_CONTROL: u8 = 5;
+const SERVER_OFFSET: u8 = 100;
+/// Sent by the server whenever one of the modem lines changes.
+const NOTIFY_MODEMSTATE: u8 = 107;
+
+// Values of SET-CONTROL.
+const FLOW_NONE: u8 = 1;
+const FLOW_XON_XOFF: u8 = 2;
+const FLOW_HARDWARE: u8 = 3;
+const BREAK_ON: u8 = 5;
+const BREAK_OFF: u8 = 6;
+const DTR_ON: u8 = 8;
+const DTR_OFF: u8 = 9;
+const RTS_ON: u8 = 11;
+const RTS_OFF: u8 = 12;
+
+/// How long the server gets to accept the option and confirm the settings.
+const NEGOTIATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A remote serial port behind an RFC 2217 server. Baud rate and framing
+/// are sent on connect and only confirmed settings are reported by
+/// [`negotiated`](Connection::negotiated); DTR, RTS and BREAK are supported.
+pub struct Rfc2217Connection {
+    address: String,
+    baud_rate: u32,
+    framing: Framing,
+    telnet: TelnetConnection,
+    /// Data not handed to the reader yet, e.g. received during negotiation.
+    pending: Vec<u8>,
+    /// Settings as confirmed by the server.
+    confirmed: NegotiatedParams,
+    modem: ModemStatus,
+}
+
+impl Rfc2217Connection {
+    pub fn new(host: String, port: u16, baud_rate: u32) -> Self {
+        Self {
+            address: format!("{host}:{port}"),
+            baud_rate,
+            framing: Framing::default(),
+            telnet: TelnetConnection::new(host, port),
+            pending: Vec::new(),
+            confirmed: NegotiatedParams::new(),
+            modem: ModemStatus::default(),
+        }
+    }
+
+    /// Configure the port with `framing` instead of 8N1 without flow control.
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    fn command(command: u8, value: &[u8]) -> Vec<u8> {
+        TelnetCodec::subnegotiation(COM_PORT_OPTION, &[&[command], value].concat())
+    }
+
+    async fn control(&mut self, value: u8) -> Result<(), ConnectionError> {
+        if !self.telnet.is_connected() {
+            return Err(ConnectionError::Other("Not connected".into()));
+        }
+        self.telnet
+            .write_wire(&Self::command(SET_CONTROL, &[value]))
+            .await
+    }
+
+    /// Offer the option, send the settings and wait until the server has
+    /// confirmed all of them.
+    async fn negotiate(&mut self) -> Result<(), ConnectionError> {
+        self.telnet.codec_mut().request_local(COM_PORT_OPTION);
+        self.telnet.write_wire(&[]).await?;
+        let accepted = loop {
+            if let Some(accepted) = self.telnet.codec_mut().local_option(COM_PORT_OPTION) {
+                break accepted;
+            }
+            self.read_control().await?;
+        };
+        if !accepted {
+            return Err(ConnectionError::Other(format!(
+                "{} refused the COM-PORT-OPTION; is it an RFC 2217 server?",
+                self.address
+            )));
+        }
+
+        let settings = [
+            (SET_BAUDRATE, self.baud_rate.to_be_bytes().to_vec()),
+            (SET_DATASIZE, vec![u8::from(self.framing.data_bits)]),
+            (SET_PARITY, vec![parity_code(self.framing.parity)]),
+            (SET_STOPSIZE, vec![u8::from(self.framing.stop_bits)]),
+            (SET_CONTROL, vec![flow_code(self.framing.flow_control)]),
+        ];
+        let frames: Vec<u8> = settings
+            .iter()
+            .flat_map(|(command, value)| Self::command(*command, value))
+            .collect();
+        self.telnet.write_wire(&frames).await?;
+
+        let mut unconfirmed: Vec<u8> = settings
+            .iter()
+            .map(|(command, _)| command + SERVER_OFFSET)
+            .collect();
+        while !unconfirmed.is_empty() {
+            for answer in self.read_control().await? {
+                unconfirmed.retain(|&command| command != answer);
+            }
+        }
+        Ok(())
+    }
+
+    /// One socket read during negotiation; data is kept for the reader.
+    /// Returns the server commands received.
+    async fn read_control(&mut self) -> Result<Vec<u8>, ConnectionError> {
+        if !self.telnet.read_wire(&mut self.pending, 1024).await? {
+            return Err(ConnectionError::Other(format!(
+                "{} closed the connection during negotiation",
+                self.address
+            )));
+        }
+        Ok(self.handle_subnegotiations())
+    }
+
+    fn handle_subnegotiations(&mut self) -> Vec<u8> {
+        let mut commands = Vec::new();
+        for sub in self.telnet.codec_mut().take_subnegotiations() {
+            if let [COM_PORT_OPTION, command, value @ ..] = sub.as_slice() {
+                self.apply(*command, value);
+                commands.push(*command);
+            }
+        }
+        commands
+    }
+
+    /// Record what a server command tells about the port.
+    fn apply(&mut self, command: u8, value: &[u8]) {
+        let (key, setting) = match (command.wrapping_sub(SERVER_OFFSET), value) {
+            (SET_BAUDRATE, &[a, b, c, d]) => ("baud", u32::from_be_bytes([a, b, c, d]).to_string()),
+            (SET_DATASIZE, &[bits]) => ("data_bits", bits.to_string()),
+            (SET_PARITY, &[code]) => ("parity", parity_name(code).to_string()),
+            (SET_STOPSIZE, &[code]) => ("stop_bits", stop_bits_name(code).to_string()),
+            (SET_CONTROL, &[code @ FLOW_NONE..=FLOW_HARDWARE]) => {
+                ("flow_control", flow_name(code).to_string())
+            }
+            _ if command == NOTIFY_MODEMSTATE => {
+                if let &[state] = value {
+                    self.modem = ModemStatus {
+                        cts: state & 0x10 != 0,
+                        dsr: state & 0x20 != 0,
+                        ri: state & 0x40 != 0,
+                        dcd: state & 0x80 != 0,
+                    };
+                }
+                return;
+            }
+            _ => {
+                debug!(
+                    "{}: ignoring COM-PORT command {command} {value:?}",
+                    self.address
+                );
+                return;
+            }
+        };
+        self.confirmed.insert(key.into(), setting);
+    }
+}
+
+fn parity_code(parity: Parity) -> u8 {
+    match parity {
+        Parity::None => 1,
+        Parity::Odd => 2,
+        Parity::Even => 3,
+    }
+}
+
+fn parity_name(code: u8) -> &'static str {
+    match code {
+        1 => "none",
+        2 => "odd",
+        3 => "even",
+        4 => "mark",
+        5 => "space",
+        _ => "unknown",
+    }
+}
+
+fn stop_bits_name(code: u8) -> &'static str {
+    match code {
+        1 => "1",
+        2 => "2",
+        3 => "1.5",
+        _ => "unknown",
+    }
+}
+
+fn flow_code(flow_control: FlowControl) -> u8 {
+    match flow_control {
+        FlowControl::None => FLOW_NONE,
+        FlowControl::Software => FLOW_XON_XOFF,
+        FlowControl::Hardware => FLOW_HARDWARE,
+    }
+}
+
+fn flow_name(code: u8) -> &'static str {
+    match code {
+        FLOW_XON_XOFF => "software",
+        FLOW_HARDWARE => "hardware",
+        _ => "none",
+    }
+}
+
+#[async_trait]
+impl Connection for Rfc2217Connection {
+    async fn connect(&mut self) -> Result<(), ConnectionError> {
+        if self.telnet.is_connected() {
+            return Ok(());
+        }
+        self.telnet.connect().await?;
+        self.pending.clear();
+        self.confirmed.clear();
+        self.modem = ModemStatus::default();
+        let result = match tokio::time::timeout(NEGOTIATION_TIMEOUT, self.negotiate()).await {
+            Ok(result) => result,
+            Err(_) => Err(ConnectionError::Other(format!(
+                "{} did not confirm the port settings within {NEGOTIATION_TIMEOUT:?}",
+                self.address
+            ))),
+        };
+        if result.is_err() {
+            let _ = self.telnet.disconnect().await;
+        }
+        result
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ConnectionError> {
+        self.telnet.disconnect().await
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError> {
+        self.telnet.write(data).await
+    }
+
+    async fn flush(&mut self) -> Result<(), ConnectionError> {
+        self.telnet.flush().await
+    }
+
+    /// Server commands arriving between the data update the confirmed
+    /// settings and the modem status. Cancel-safe like the Telnet read, and
+    /// like it ends with [`RemoteClosed`](ConnectionError::RemoteClosed)
+    /// once the server closes the socket.
+    async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ConnectionError> {
+        loop {
+            if !self.pending.is_empty() {
+                let len = self.pending.len().min(buffer.len());
+                buffer[..len].copy_from_slice(&self.pending[..len]);
+                self.pending.drain(..len);
+                return Ok(len);
+            }
+            if !self
+                .telnet
+                .read_wire(&mut self.pending, buffer.len())
+                .await?
+            {
+                return Err(ConnectionError::RemoteClosed { exit_status: None });
+            }
+            self.handle_subnegotiations();
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        self.telnet.is_connected()
+    }
+
+    fn kind(&self) -> &'static str {
+        "rfc2217"
+    }
+
+    async fn set_dtr(&mut self, level: bool) -> Result<(), ConnectionError> {
+        self.control(if level { DTR_ON } else { DTR_OFF }).await
+    }
+
+    async fn set_rts(&mut self, level: bool) -> Result<(), ConnectionError> {
+        self.control(if level { RTS_ON } else { RTS_OFF }).await
+    }
+
+    async fn send_break(&mut self, duration: Duration) -> Result<(), ConnectionError> {
+        self.control(BREAK_ON).await?;
+        tokio::time::sleep(duration).await;
+        self.control(BREAK_OFF).await
+    }
+
+    /// The lines as last reported by the server, which notifies changes
+    /// only; all low until the first notification.
+    async fn modem_status(&mut self) -> Result<ModemStatus, ConnectionError> {
+        if !self.telnet.is_connected() {
+            return Err(ConnectionError::Other("Not connected".into()));
+        }
+        Ok(self.modem)
+    }
+
+    fn negotiated(&self) -> NegotiatedParams {
+        let mut params = self.telnet.negotiated();
+        if self.telnet.is_connected() {
+            params.insert("port".into(), format!("rfc2217://{}", self.address));
+            params.extend(self.confirmed.clone());
+        }
+        params
+    }
+}
@@ -0,0 +1,196 @@
+use crate::connections::{
+    connection::{Connection, NegotiatedParams},
+    errors::ConnectionError,
+    tcp::open_tcp,
+    telnet::codec::TelnetCodec,
+};
+use async_trait::async_trait;
+use log::info;
+use std::io;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+/// The well-known Telnet port.
+pub const TELNET_PORT: u16 = 23;
+
+/// A Telnet session over TCP, for lab gear and console servers that speak
+/// nothing else. Option negotiation is answered in the background (see
+/// [`TelnetCodec`]); reads and writes carry plain data.
+pub struct TelnetConnection {
+    host: String,
+    port: u16,
+    codec: TelnetCodec,
+    /// Negotiation answers not yet on the wire.
+    replies: Vec<u8>,
+    /// Raw bytes of the last socket read, before decoding.
+    wire: Vec<u8>,
+    stream: Option<TcpStream>,
+}
+
+impl TelnetConnection {
+    pub fn new(host: String, port: u16) -> Self {
+        Self {
+            host,
+            port,
+            codec: TelnetCodec::new(),
+            replies: Vec::new(),
+            wire: Vec::new(),
+            stream: None,
+        }
+    }
+
+    fn stream(&mut self) -> Result<&mut TcpStream, ConnectionError> {
+        self.stream
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))
+    }
+
+    /// Put as much of the queued answers on the wire as the socket takes
+    /// without waiting; the rest goes out with the next read or write.
+    fn send_replies(&mut self) -> Result<(), ConnectionError> {
+        let fresh = self.codec.take_replies();
+        if !fresh.is_empty() {
+            self.replies.extend(self.codec.flush());
+            self.replies.extend(fresh);
+        }
+        let Some(stream) = self.stream.as_ref() else {
+            return Ok(());
+        };
+        while !self.replies.is_empty() {
+            match stream.try_write(&self.replies) {
+                Ok(sent) => {
+                    self.replies.drain(..sent);
+                }
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(())
+    }
+
+    /// The codec of the current session, for layers that negotiate options
+    /// of their own.
+    pub(crate) fn codec_mut(&mut self) -> &mut TelnetCodec {
+        &mut self.codec
+    }
+
+    /// Send the command `frame` as is, after any queued answers. Data goes
+    /// through [`write`](Connection::write) instead, so that its CR padding
+    /// is kept track of.
+    pub(crate) async fn write_wire(&mut self, frame: &[u8]) -> Result<(), ConnectionError> {
+        let mut wire = std::mem::take(&mut self.replies);
+        wire.extend(self.codec.take_replies());
+        wire.extend_from_slice(frame);
+        if wire.is_empty() {
+            return Ok(());
+        }
+        // A CR the last data ended with is not followed by LF after all.
+        let wire = [self.codec.flush(), wire].concat();
+        self.stream()?.write_all(&wire).await?;
+        Ok(())
+    }
+
+    /// One socket read of at most `max` bytes: its data is appended to
+    /// `data` and its negotiation answered. Returns `false` at the end of
+    /// the stream. Cancel-safe: only the socket read is awaited.
+    pub(crate) async fn read_wire(
+        &mut self,
+        data: &mut Vec<u8>,
+        max: usize,
+    ) -> Result<bool, ConnectionError> {
+        self.send_replies()?;
+        self.wire.resize(max, 0);
+        let stream = self
+            .stream
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))?;
+        let read = stream.read(&mut self.wire).await?;
+        if read == 0 {
+            return Ok(false);
+        }
+        self.codec.decode(&self.wire[..read], data);
+        self.send_replies()?;
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Connection for TelnetConnection {
+    async fn connect(&mut self) -> Result<(), ConnectionError> {
+        if self.stream.is_some() {
+            return Ok(());
+        }
+        info!("Connecting to telnet://{}:{}", self.host, self.port);
+        let stream = open_tcp(&self.host, self.port).await?;
+        stream.set_nodelay(true)?;
+        self.stream = Some(stream);
+        // A new session negotiates from scratch.
+        self.codec = TelnetCodec::new();
+        self.replies.clear();
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ConnectionError> {
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.shutdown().await;
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError> {
+        self.write_wire(&[]).await?;
+        let wire = self.codec.encode(data);
+        self.stream()?.write_all(&wire).await?;
+        Ok(data.len())
+    }
+
+    /// Also sends the padding of a CR the data ended with, which otherwise
+    /// waits for the next byte to tell whether it is a line end.
+    async fn flush(&mut self) -> Result<(), ConnectionError> {
+        let padding = self.codec.flush();
+        let stream = self.stream()?;
+        stream.write_all(&padding).await?;
+        Ok(stream.flush().await?)
+    }
+
+    /// Only returns once there is data: reads that carried nothing but
+    /// commands are answered and followed by another read. Cancel-safe, as
+    /// the manager needs; nothing is lost between two awaits. A server that
+    /// closes the socket ends the session with
+    /// [`RemoteClosed`](ConnectionError::RemoteClosed), as Telnet has no
+    /// half-closed state to wait in.
+    async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ConnectionError> {
+        // Decoding never grows the data, so it always fits into `buffer`.
+        let mut data = Vec::with_capacity(buffer.len());
+        loop {
+            if !self.read_wire(&mut data, buffer.len()).await? {
+                return Err(ConnectionError::RemoteClosed { exit_status: None });
+            }
+            if !data.is_empty() {
+                buffer[..data.len()].copy_from_slice(&data);
+                return Ok(data.len());
+            }
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn kind(&self) -> &'static str {
+        "telnet"
+    }
+
+    fn negotiated(&self) -> NegotiatedParams {
+        let mut params = NegotiatedParams::new();
+        if let Some(stream) = &self.stream {
+            if let Ok(addr) = stream.peer_addr() {
+                params.insert("peer".into(), addr.to_string());
+            }
+            if let Ok(addr) = stream.local_addr() {
+                params.insert("local".into(), addr.to_string());
+            }
+        }
+        params
+    }
+}
@@ -0,0 +1,3 @@
+pub mod websocket_connection;
+
+pub use websocket_connection::*;
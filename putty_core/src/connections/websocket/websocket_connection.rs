@@ -0,0 +1,170 @@
+use crate::connections::{
+    connection::{Connection, NegotiatedParams},
+    errors::ConnectionError,
+    tcp::open_tcp,
+};
+use async_trait::async_trait;
+use futures_util::{SinkExt, StreamExt};
+use log::{debug, info};
+use std::net::SocketAddr;
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, Error, Message};
+use tokio_tungstenite::{client_async_tls, MaybeTlsStream, WebSocketStream};
+
+/// A device console behind a WebSocket, as served by many embedded boards.
+///
+/// Writes go out as binary messages; binary and text messages that arrive
+/// are read as bytes (text as its UTF-8 encoding). Pings are answered, and
+/// a close from the server, or the end of the stream, ends the session with
+/// [`RemoteClosed`](ConnectionError::RemoteClosed).
+pub struct WebSocketConnection {
+    url: String,
+    stream: Option<WebSocketStream<MaybeTlsStream<TcpStream>>>,
+    /// Rest of a message longer than the last read buffer.
+    pending: Vec<u8>,
+    peer: Option<SocketAddr>,
+    /// Subprotocol the server picked, if any.
+    subprotocol: Option<String>,
+}
+
+impl WebSocketConnection {
+    /// `url` is a `ws://` or `wss://` URL; it is checked on connect.
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            stream: None,
+            pending: Vec::new(),
+            peer: None,
+            subprotocol: None,
+        }
+    }
+
+    fn stream(
+        &mut self,
+    ) -> Result<&mut WebSocketStream<MaybeTlsStream<TcpStream>>, ConnectionError> {
+        self.stream
+            .as_mut()
+            .ok_or_else(|| ConnectionError::Other("Not connected".into()))
+    }
+}
+
+#[async_trait]
+impl Connection for WebSocketConnection {
+    async fn connect(&mut self) -> Result<(), ConnectionError> {
+        if self.stream.is_some() {
+            return Ok(());
+        }
+        let request = self
+            .url
+            .as_str()
+            .into_client_request()
+            .map_err(|e| ConnectionError::Other(format!("Invalid URL '{}': {e}", self.url)))?;
+        let default_port = match request.uri().scheme_str() {
+            Some("ws") => 80,
+            Some("wss") => 443,
+            _ => {
+                return Err(ConnectionError::Other(format!(
+                    "Invalid URL '{}': expected ws:// or wss://",
+                    self.url
+                )))
+            }
+        };
+        let host = request
+            .uri()
+            .host()
+            .unwrap_or_default()
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        let port = request.uri().port_u16().unwrap_or(default_port);
+
+        info!("Connecting to {}", self.url);
+        // Resolve and connect ourselves so that DNS and TCP failures are
+        // reported apart from a failed WebSocket or TLS handshake.
+        let tcp = open_tcp(&host, port).await?;
+        tcp.set_nodelay(true)?;
+        self.peer = tcp.peer_addr().ok();
+        let (stream, response) = client_async_tls(request, tcp)
+            .await
+            .map_err(|e| ConnectionError::HandshakeError(format!("{}: {e}", self.url)))?;
+        self.subprotocol = response
+            .headers()
+            .get("sec-websocket-protocol")
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+        self.stream = Some(stream);
+        self.pending.clear();
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ConnectionError> {
+        if let Some(mut stream) = self.stream.take() {
+            let _ = stream.close(None).await;
+        }
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError> {
+        self.stream()?.send(Message::binary(data.to_vec())).await?;
+        Ok(data.len())
+    }
+
+    async fn flush(&mut self) -> Result<(), ConnectionError> {
+        Ok(SinkExt::flush(self.stream()?).await?)
+    }
+
+    /// Only returns once there is data; control messages are handled in
+    /// between. Cancel-safe: a partly received message stays buffered in
+    /// the stream.
+    async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ConnectionError> {
+        loop {
+            if !self.pending.is_empty() {
+                let len = self.pending.len().min(buffer.len());
+                buffer[..len].copy_from_slice(&self.pending[..len]);
+                self.pending.drain(..len);
+                return Ok(len);
+            }
+            let message = match self.stream()?.next().await {
+                None | Some(Err(Error::ConnectionClosed | Error::AlreadyClosed)) => {
+                    return Err(ConnectionError::RemoteClosed { exit_status: None })
+                }
+                Some(Err(e)) => return Err(e.into()),
+                Some(Ok(message)) => message,
+            };
+            match message {
+                Message::Binary(data) => self.pending.extend_from_slice(&data),
+                Message::Text(text) => self.pending.extend_from_slice(text.as_bytes()),
+                // The stream queues the pong itself and sends it with the
+                // next read or write.
+                Message::Ping(_) | Message::Pong(_) | Message::Frame(_) => {}
+                Message::Close(frame) => {
+                    debug!("{} closed by the server: {frame:?}", self.url);
+                    return Err(ConnectionError::RemoteClosed { exit_status: None });
+                }
+            }
+        }
+    }
+
+    fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    fn kind(&self) -> &'static str {
+        "websocket"
+    }
+
+    fn negotiated(&self) -> NegotiatedParams {
+        let mut params = NegotiatedParams::new();
+        if self.stream.is_none() {
+            return params;
+        }
+        params.insert("url".into(), self.url.clone());
+        if let Some(peer) = self.peer {
+            params.insert("peer".into(), peer.to_string());
+        }
+        if let Some(subprotocol) = &self.subprotocol {
+            params.insert("subprotocol".into(), subprotocol.clone());
+        }
+        params
+    }
+}
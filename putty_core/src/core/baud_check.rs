@@ -0,0 +1,60 @@
+//! Heuristic for "the baud rate is probably wrong".
+//!
+//! At the wrong rate a UART still delivers bytes, but they are framing noise:
+//! mostly high-bit and control bytes instead of text. The serial drivers used
+//! here do not expose framing error counters, so the check only looks at the
+//! received bytes.
+
+/// Bytes collected before judging; fewer cannot tell noise from a short
+/// binary reply.
+const SAMPLE_LEN: usize = 32;
+
+/// A sample with a smaller share of printable bytes counts as noise.
+const MIN_PRINTABLE_PERCENT: u8 = 60;
+
+/// Share of `bytes` (0-100) that is readable text: printable ASCII, common
+/// whitespace, ANSI escapes and valid UTF-8 beyond ASCII.
+pub fn printable_percent(bytes: &[u8]) -> u8 {
+    if bytes.is_empty() {
+        return 100;
+    }
+    let printable: usize = bytes
+        .utf8_chunks()
+        .map(|chunk| {
+            chunk
+                .valid()
+                .chars()
+                .filter(|&c| !c.is_control() || matches!(c, '\r' | '\n' | '\t' | '\x1b'))
+                .map(char::len_utf8)
+                .sum::<usize>()
+        })
+        .sum();
+    (printable * 100 / bytes.len()) as u8
+}
+
+/// Judges the first [`SAMPLE_LEN`] bytes of a connection, once.
+#[derive(Debug, Default)]
+pub(crate) struct BaudCheck {
+    sample: Vec<u8>,
+    done: bool,
+}
+
+impl BaudCheck {
+    /// Feed received bytes. Returns the printable percentage the first time a
+    /// full sample looks like noise, `None` otherwise.
+    pub(crate) fn feed(&mut self, chunk: &[u8]) -> Option<u8> {
+        if self.done {
+            return None;
+        }
+        let wanted = SAMPLE_LEN - self.sample.len();
+        self.sample
+            .extend_from_slice(&chunk[..chunk.len().min(wanted)]);
+        if self.sample.len() < SAMPLE_LEN {
+            return None;
+        }
+        self.done = true;
+        let percent = printable_percent(&self.sample);
+        self.sample = Vec::new();
+        (percent < MIN_PRINTABLE_PERCENT).then_some(percent)
+    }
+}
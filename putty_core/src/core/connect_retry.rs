@@ -0,0 +1,160 @@
+use crate::connections::connection::Connection;
+use crate::connections::errors::ConnectionError;
+use log::warn;
+use std::time::Duration;
+use tokio::time::{timeout, Instant};
+
+/// Retry policy for the connect phase of a connection.
+///
+/// Failed `connect` attempts are retried with exponential backoff plus random
+/// jitter until `deadline` has elapsed since the first attempt. The last
+/// error is returned once the deadline does not leave room for another try.
+#[derive(Debug, Clone)]
+pub struct ConnectRetry {
+    /// Delay before the first retry; doubled after every failed attempt.
+    pub base_delay: Duration,
+    /// Upper bound for the delay between two attempts.
+    pub max_delay: Duration,
+    /// Total time budget for all attempts, measured from the first one.
+    pub deadline: Duration,
+}
+
+impl Default for ConnectRetry {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(2),
+            deadline: Duration::from_secs(10),
+        }
+    }
+}
+
+impl ConnectRetry {
+    /// Retry with the default backoff until `deadline` has elapsed.
+    pub fn new(deadline: Duration) -> Self {
+        Self {
+            deadline,
+            ..Self::default()
+        }
+    }
+
+    /// Use `base` as the first retry delay, growing up to `max`.
+    pub fn with_backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.base_delay = base;
+        self.max_delay = max;
+        self
+    }
+
+    /// Limit the whole connect phase to `deadline`.
+    pub fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    /// Call `conn.connect()` until it succeeds or the deadline runs out.
+    pub(crate) async fn connect(
+        &self,
+        conn: &mut (dyn Connection + Send + Unpin),
+    ) -> Result<(), ConnectionError> {
+        let deadline = Instant::now() + self.deadline;
+        let mut attempt = 0;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let err = match timeout(remaining, conn.connect()).await {
+                Ok(Ok(())) => return Ok(()),
+                Ok(Err(e)) => e,
+                Err(_) => {
+                    return Err(ConnectionError::Other(format!(
+                        "connect did not complete within {:?}",
+                        self.deadline
+                    )))
+                }
+            };
+
+            let delay = backoff(self.base_delay, self.max_delay, attempt);
+            if Instant::now() + delay >= deadline {
+                return Err(err);
+            }
+            attempt += 1;
+            warn!("Connect attempt {attempt} failed: {err}; retrying in {delay:?}");
+            tokio::time::sleep(delay).await;
+        }
+    }
+}
+
+/// Reconnect policy for an established connection whose transport is lost.
+///
+/// When a read fails or the other side closes the session, the I/O task
+/// disconnects the transport and calls `connect` on it again, waiting with
+/// exponential backoff plus jitter before every attempt. Subscriptions stay
+/// open meanwhile. It gives up after `max_attempts` failed attempts, if set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    /// Delay before the first attempt; doubled after every failed one.
+    pub base_delay: Duration,
+    /// Upper bound for the delay between two attempts.
+    pub max_delay: Duration,
+    /// Failed attempts after which the connection is given up; `None`
+    /// retries forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_attempts: None,
+        }
+    }
+}
+
+impl ReconnectPolicy {
+    /// Retry forever with the default backoff.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use `base` as the first delay, growing up to `max`.
+    pub fn with_backoff(mut self, base: Duration, max: Duration) -> Self {
+        self.base_delay = base;
+        self.max_delay = max;
+        self
+    }
+
+    /// Give up after `attempts` failed attempts.
+    pub fn with_max_attempts(mut self, attempts: u32) -> Self {
+        self.max_attempts = Some(attempts);
+        self
+    }
+
+    /// Call `conn.connect()` after a backoff delay until it succeeds or the
+    /// attempts run out, returning the last error then.
+    pub(crate) async fn reconnect(
+        &self,
+        conn: &mut (dyn Connection + Send + Unpin),
+    ) -> Result<(), ConnectionError> {
+        let mut attempt = 0;
+        loop {
+            tokio::time::sleep(backoff(self.base_delay, self.max_delay, attempt)).await;
+            let err = match conn.connect().await {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+            attempt += 1;
+            if self.max_attempts.is_some_and(|max| attempt >= max) {
+                return Err(err);
+            }
+            warn!("Reconnect attempt {attempt} failed: {err}");
+        }
+    }
+}
+
+/// Delay before retry number `attempt` (0-based): half of the capped
+/// exponential backoff plus a random share of the other half, so that
+/// clients failing together do not retry in lockstep.
+fn backoff(base: Duration, max: Duration, attempt: u32) -> Duration {
+    let backoff = base.saturating_mul(1u32 << attempt.min(16)).min(max);
+    let half = backoff / 2;
+    half + half.mul_f64(rand::random::<f64>())
+}
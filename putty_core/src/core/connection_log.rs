@@ -0,0 +1,58 @@
+//! Per-connection log verbosity.
+//!
+//! The I/O task of each connection logs under [`CONNECTION_LOG_TARGET`] and
+//! drops records above that connection's own level, so one connection can be
+//! made chatty while the others stay quiet. The installed logger still has
+//! the last word: to see a connection's debug records, let the target through
+//! it, e.g. `RUST_LOG=info,putty_core::connection=trace`.
+
+use log::{Level, LevelFilter};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Log target of everything a connection's I/O task reports.
+pub const CONNECTION_LOG_TARGET: &str = "putty_core::connection";
+
+/// The level a connection logs at, shared between the manager and the I/O
+/// task so it can be changed while the connection runs. Defaults to
+/// [`LevelFilter::Trace`], leaving the decision to the logger.
+#[derive(Debug, Clone)]
+pub(crate) struct ConnectionLog {
+    level: Arc<AtomicUsize>,
+}
+
+impl ConnectionLog {
+    pub(crate) fn new(level: Option<LevelFilter>) -> Self {
+        let log = Self {
+            level: Arc::new(AtomicUsize::new(LevelFilter::Trace as usize)),
+        };
+        if let Some(level) = level {
+            log.set_level(level);
+        }
+        log
+    }
+
+    pub(crate) fn set_level(&self, level: LevelFilter) {
+        self.level.store(level as usize, Ordering::Relaxed);
+    }
+
+    pub(crate) fn enabled(&self, level: Level) -> bool {
+        level as usize <= self.level.load(Ordering::Relaxed)
+    }
+}
+
+/// `log!` under [`CONNECTION_LOG_TARGET`], skipped when the connection's
+/// level is lower than `$level`.
+macro_rules! conn_log {
+    ($log:expr, $level:expr, $($arg:tt)+) => {
+        if $log.enabled($level) {
+            log::log!(
+                target: $crate::core::connection_log::CONNECTION_LOG_TARGET,
+                $level,
+                $($arg)+
+            );
+        }
+    };
+}
+
+pub(crate) use conn_log;
@@ -1,14 +1,178 @@
-use crate::connections::connection::Connection;
+use crate::connections::connection::{Connection, NegotiatedParams};
 use crate::connections::errors::ConnectionError;
-use log::{debug, error, info};
-use std::collections::HashMap;
-use std::sync::Arc;
-use tokio::sync::{broadcast, mpsc, Mutex};
+use crate::connections::forward::LocalForward;
+use crate::connections::framing::ModemStatus;
+#[cfg(feature = "ssh")]
+use crate::connections::ssh::SftpClient;
+use crate::core::baud_check::BaudCheck;
+use crate::core::connect_retry::ReconnectPolicy;
+use crate::core::connection_log::{conn_log, ConnectionLog};
+use crate::core::connection_options::{
+    ConnectionOptions, EofPolicy, KeepAliveAction, DEFAULT_PAUSE_BUFFER_BYTES,
+    DEFAULT_READ_BUFFER_SIZE, MAX_KEPT_SCROLLBACKS, MAX_KEPT_SCROLLBACK_BYTES,
+};
+use crate::core::connection_state::ConnectionState;
+use crate::core::events::{ConnectionEvent, ConnectionEventKind, DisconnectReason};
+use crate::core::scrollback::{KeptScrollbacks, Scrollback, ScrollbackStats};
+use crate::core::subscription::{LossySubscription, StableSubscription};
+use log::{debug, error, info, warn, Level, LevelFilter};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::net::SocketAddr;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex as StdMutex, RwLock, Weak};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, oneshot, watch, Mutex, OwnedSemaphorePermit, Semaphore};
+
+/// Chunks a subscriber may fall behind before it starts losing data.
+const BROADCAST_CAPACITY: usize = 256;
+
+/// Counts a write against [`ConnectionOptions::max_in_flight_writes`] until
+/// the I/O task is done with it and drops the event. `None` without a limit.
+type WriteSlot = Option<OwnedSemaphorePermit>;
 
 enum IoEvent {
-    Write(Vec<u8>),
+    Write(Vec<u8>, WriteSlot),
+    WriteAcked {
+        data: Vec<u8>,
+        reply: oneshot::Sender<Result<usize, ConnectionError>>,
+        slot: WriteSlot,
+    },
+    /// Written verbatim, bypassing every outgoing transform.
+    WriteRaw {
+        data: Vec<u8>,
+        reply: oneshot::Sender<Result<usize, ConnectionError>>,
+        slot: WriteSlot,
+    },
+    Flush {
+        reply: oneshot::Sender<Result<(), ConnectionError>>,
+    },
+    IsConnected {
+        reply: oneshot::Sender<bool>,
+    },
+    /// Hold received data back from subscribers until `Resume`.
+    Pause {
+        reply: oneshot::Sender<Result<(), ConnectionError>>,
+    },
+    /// Release the held data; replies with the number of bytes dropped.
+    Resume {
+        reply: oneshot::Sender<Result<u64, ConnectionError>>,
+    },
+    Resize {
+        cols: u16,
+        rows: u16,
+        reply: oneshot::Sender<Result<(), ConnectionError>>,
+    },
+    /// Modem-control lines and BREAK; queued behind earlier writes.
+    Line {
+        control: LineControl,
+        reply: oneshot::Sender<Result<(), ConnectionError>>,
+    },
+    ModemStatus {
+        reply: oneshot::Sender<Result<ModemStatus, ConnectionError>>,
+    },
+    LocalForward {
+        forward: LocalForward,
+        reply: oneshot::Sender<Result<SocketAddr, ConnectionError>>,
+    },
+    #[cfg(feature = "ssh")]
+    Sftp {
+        reply: oneshot::Sender<Result<SftpClient, ConnectionError>>,
+    },
     Stop,
 }
+
+/// What an [`IoEvent::Line`] does to the transport.
+#[derive(Debug, Clone, Copy)]
+enum LineControl {
+    Dtr(bool),
+    Rts(bool),
+    Break(Duration),
+}
+
+impl IoEvent {
+    /// Answer the event with an error instead of carrying it out, while
+    /// there is no transport to carry it out on.
+    ///
+    /// A plain `Write` has no reply to carry the error: `write_bytes` refuses
+    /// it up front, so only writes queued just before the transport was lost
+    /// end up here, and they are dropped with a warning.
+    fn refuse(self) {
+        let err = || ConnectionError::Other("Connection is reconnecting".into());
+        match self {
+            IoEvent::Stop => {}
+            IoEvent::Write(data, _) => {
+                warn!(
+                    "Dropped a write of {} bytes queued before the transport was lost",
+                    data.len()
+                );
+            }
+            IoEvent::WriteAcked { reply, .. } | IoEvent::WriteRaw { reply, .. } => {
+                let _ = reply.send(Err(err()));
+            }
+            IoEvent::Flush { reply }
+            | IoEvent::Pause { reply }
+            | IoEvent::Resize { reply, .. }
+            | IoEvent::Line { reply, .. } => {
+                let _ = reply.send(Err(err()));
+            }
+            IoEvent::Resume { reply } => {
+                let _ = reply.send(Err(err()));
+            }
+            IoEvent::ModemStatus { reply } => {
+                let _ = reply.send(Err(err()));
+            }
+            IoEvent::IsConnected { reply } => {
+                let _ = reply.send(false);
+            }
+            IoEvent::LocalForward { reply, .. } => {
+                let _ = reply.send(Err(err()));
+            }
+            #[cfg(feature = "ssh")]
+            IoEvent::Sftp { reply } => {
+                let _ = reply.send(Err(err()));
+            }
+        }
+    }
+}
+
+/// What became of a lost transport, see [`reconnect`].
+enum Reconnect {
+    Reconnected,
+    Stopped,
+    GaveUp(ConnectionError),
+}
+
+/// Disconnect `conn` and bring it back according to `policy`. Control
+/// events keep being answered meanwhile: `Stop` abandons the attempt, the
+/// rest are refused.
+async fn reconnect(
+    policy: &ReconnectPolicy,
+    conn: &mut (dyn Connection + Send + Unpin),
+    control: &mut mpsc::Receiver<IoEvent>,
+) -> Reconnect {
+    let _ = conn.disconnect().await;
+    let attempts = policy.reconnect(conn);
+    tokio::pin!(attempts);
+    loop {
+        tokio::select! {
+            result = &mut attempts => {
+                return match result {
+                    Ok(()) => Reconnect::Reconnected,
+                    Err(e) => Reconnect::GaveUp(e),
+                };
+            },
+            event = control.recv() => match event {
+                None | Some(IoEvent::Stop) => return Reconnect::Stopped,
+                Some(event) => event.refuse(),
+            },
+        }
+    }
+}
+
 /// Represents the I/O task handle for a connection.
 ///
 /// 1. ConnectionIOHandle holds the IO task that reads from the connection
@@ -18,7 +182,218 @@ enum IoEvent {
 struct ConnectionIOHandle {
     io_task_handle: tokio::task::JoinHandle<()>,
     write_stop_tx: mpsc::Sender<IoEvent>,
-    broadcast_tx: broadcast::Sender<Vec<u8>>,
+    /// Shared with the I/O task so [`ConnectionManager::drop_subscribers`]
+    /// can swap in a fresh channel while the task keeps running. The task
+    /// takes it when it ends, which closes every subscription.
+    broadcast_tx: Arc<RwLock<Option<broadcast::Sender<Vec<u8>>>>>,
+    /// Receiver created together with the broadcast channel, before the I/O
+    /// task could send anything; handed to the first
+    /// [`ConnectionManager::subscribe`] so it sees the session from its start.
+    first_subscriber: Option<broadcast::Receiver<Vec<u8>>>,
+    /// Last PTY size set on the connection, `None` for transports without a PTY.
+    pty_size: Option<(u16, u16)>,
+    kind: &'static str,
+    negotiated: NegotiatedParams,
+    connected_at: Instant,
+    /// The id the connection is registered under; shared with the I/O task
+    /// so its events follow a [`ConnectionManager::rename`].
+    current_id: Arc<RwLock<String>>,
+    /// Verbosity of the I/O task, see [`ConnectionManager::set_log_level`].
+    log: ConnectionLog,
+    /// Free write slots and their total, if writes are limited.
+    write_slots: Option<(Arc<Semaphore>, usize)>,
+    /// Filled by the I/O task with everything it reads.
+    scrollback: Arc<StdMutex<Scrollback>>,
+    /// Whether `scrollback` outlives the connection, see
+    /// [`ConnectionOptions::keep_scrollback`].
+    keep_scrollback: bool,
+    /// Kept up to date by the I/O task, see [`ConnectionManager::status`].
+    state: Arc<StdMutex<ConnectionState>>,
+    /// Counted by the I/O task, see [`ConnectionManager::metrics`].
+    traffic: Arc<Traffic>,
+}
+
+/// Byte totals of a connection, bumped by its I/O task.
+#[derive(Debug, Default)]
+struct Traffic {
+    read: AtomicU64,
+    written: AtomicU64,
+}
+
+impl Traffic {
+    /// Count the bytes of a successful write.
+    fn wrote(&self, result: &Result<usize, ConnectionError>) {
+        if let Ok(n) = result {
+            self.written.fetch_add(*n as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+impl ConnectionIOHandle {
+    /// Live subscribers, not counting the receiver kept for the first one.
+    fn subscriber_count(&self) -> usize {
+        self.broadcast_tx
+            .read()
+            .unwrap()
+            .as_ref()
+            .map_or(0, |tx| tx.receiver_count())
+            .saturating_sub(usize::from(self.first_subscriber.is_some()))
+    }
+
+    /// A receiver for what is read from now on; closed if the connection
+    /// has ended.
+    fn live_receiver(&self) -> broadcast::Receiver<Vec<u8>> {
+        match self.broadcast_tx.read().unwrap().as_ref() {
+            Some(tx) => tx.subscribe(),
+            None => broadcast::channel(1).1,
+        }
+    }
+
+    /// The receiver kept from before the first subscriber, or else a live one.
+    fn subscribe(&mut self) -> broadcast::Receiver<Vec<u8>> {
+        self.first_subscriber
+            .take()
+            .unwrap_or_else(|| self.live_receiver())
+    }
+
+    /// Fail while the transport is being reconnected, for writes that have no
+    /// reply to report it through.
+    fn check_not_reconnecting(&self) -> Result<(), ConnectionError> {
+        match *self.state.lock().unwrap() {
+            ConnectionState::Reconnecting => {
+                Err(ConnectionError::Other("Connection is reconnecting".into()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Take a write slot, or fail with `Busy` if none is free.
+    fn reserve_write(&self) -> Result<WriteSlot, ConnectionError> {
+        let Some((slots, limit)) = &self.write_slots else {
+            return Ok(None);
+        };
+        slots
+            .clone()
+            .try_acquire_owned()
+            .map(Some)
+            .map_err(|_| ConnectionError::Busy { limit: *limit })
+    }
+}
+
+/// Owns a transport while [`ConnectionManager::add_connection_with_options`]
+/// sets it up, and disconnects it unless it was handed on to the I/O task.
+struct ConnectGuard {
+    conn: Option<Box<dyn Connection + Send + Unpin>>,
+}
+
+impl ConnectGuard {
+    fn new(conn: Box<dyn Connection + Send + Unpin>) -> Self {
+        Self { conn: Some(conn) }
+    }
+
+    fn conn(&mut self) -> &mut (dyn Connection + Send + Unpin) {
+        self.conn
+            .as_deref_mut()
+            .expect("connection already released")
+    }
+
+    /// Disconnect now, on an error path.
+    async fn disconnect(mut self) {
+        if let Some(mut conn) = self.conn.take() {
+            let _ = conn.disconnect().await;
+        }
+    }
+
+    /// Setup succeeded; the caller owns the connection from here on.
+    fn release(mut self) -> Box<dyn Connection + Send + Unpin> {
+        self.conn.take().expect("connection already released")
+    }
+}
+
+impl Drop for ConnectGuard {
+    /// Reached when the setup future is cancelled; `disconnect` is async, so
+    /// it runs on a fresh task.
+    fn drop(&mut self) {
+        if let Some(mut conn) = self.conn.take() {
+            if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+                runtime.spawn(async move {
+                    let _ = conn.disconnect().await;
+                });
+            }
+        }
+    }
+}
+
+/// Marks an id as [`ConnectionState::Connecting`] while
+/// [`ConnectionManager::add_connection_with_options`] connects, on every
+/// path out of it including cancellation.
+struct ConnectingMark {
+    connecting: Arc<StdMutex<HashSet<String>>>,
+    id: String,
+}
+
+impl ConnectingMark {
+    fn new(connecting: &Arc<StdMutex<HashSet<String>>>, id: &str) -> Self {
+        connecting.lock().unwrap().insert(id.to_owned());
+        Self {
+            connecting: connecting.clone(),
+            id: id.to_owned(),
+        }
+    }
+}
+
+impl Drop for ConnectingMark {
+    fn drop(&mut self) {
+        self.connecting.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Snapshot of a connection's internal queues, see
+/// [`ConnectionManager::buffer_status`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BufferStatus {
+    /// Control events (writes, resizes, stop) waiting for the I/O task. Staying
+    /// near `control_capacity` means the transport cannot keep up with writes.
+    pub control_depth: usize,
+    pub control_capacity: usize,
+    /// Chunks the slowest subscriber has not received yet: the largest lag
+    /// over all subscribers, not any one of them. Reaching
+    /// `broadcast_capacity` means that subscriber is about to lag and lose
+    /// data; each subscriber's own lag is `len()` on its receiver. Until the
+    /// first subscriber attaches, this counts the chunks kept for it.
+    pub max_subscriber_lag: usize,
+    pub broadcast_capacity: usize,
+    /// Number of live subscribers.
+    pub subscribers: usize,
+}
+
+/// Traffic totals of a connection, see [`ConnectionManager::metrics`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionMetrics {
+    /// Bytes read from the transport.
+    pub bytes_in: u64,
+    /// Bytes written through `write_bytes` and friends, as passed in, i.e.
+    /// before CRLF translation. Keep-alive bytes are not counted.
+    pub bytes_out: u64,
+    /// When the connection was added; the totals count from here.
+    pub connected_at: Instant,
+    /// When this snapshot was taken.
+    pub at: Instant,
+}
+
+impl ConnectionMetrics {
+    /// Average bytes per second in and out between `earlier` and this
+    /// snapshot, for a throughput gauge. Zero if no time has passed.
+    pub fn rates_since(&self, earlier: &ConnectionMetrics) -> (f64, f64) {
+        let secs = self.at.saturating_duration_since(earlier.at).as_secs_f64();
+        if secs == 0.0 {
+            return (0.0, 0.0);
+        }
+        (
+            self.bytes_in.saturating_sub(earlier.bytes_in) as f64 / secs,
+            self.bytes_out.saturating_sub(earlier.bytes_out) as f64 / secs,
+        )
+    }
 }
 
 /// Manages multiple connections concurrently.
@@ -32,6 +407,36 @@ struct ConnectionIOHandle {
 #[derive(Clone)]
 pub struct ConnectionManager {
     inner: Arc<Mutex<HashMap<String, ConnectionIOHandle>>>,
+    events_tx: broadcast::Sender<ConnectionEvent>,
+    /// Scrollback of stopped connections that keep theirs, continued when
+    /// the id is added again.
+    stopped_scrollback: Arc<StdMutex<KeptScrollbacks>>,
+    /// Ids whose `add_connection` is still connecting.
+    connecting: Arc<StdMutex<HashSet<String>>>,
+    /// Never sent on; its receivers see it close once the last clone of the
+    /// manager is dropped.
+    alive: watch::Sender<()>,
+}
+
+/// A handle to a [`ConnectionManager`] that does not keep it alive, for
+/// subscriptions that outlive a single connection.
+pub(crate) struct WeakConnectionManager {
+    inner: Weak<Mutex<HashMap<String, ConnectionIOHandle>>>,
+    alive: watch::Receiver<()>,
+}
+
+impl WeakConnectionManager {
+    /// Like [`ConnectionManager::subscribe`]; `None` once the manager is gone.
+    pub(crate) async fn subscribe(&self, id: &str) -> Option<broadcast::Receiver<Vec<u8>>> {
+        let inner = self.inner.upgrade()?;
+        let mut map = inner.lock().await;
+        map.get_mut(id).map(ConnectionIOHandle::subscribe)
+    }
+
+    /// Resolves once the last clone of the manager has been dropped.
+    pub(crate) async fn dropped(&mut self) {
+        while self.alive.changed().await.is_ok() {}
+    }
 }
 
 impl Default for ConnectionManager {
@@ -44,6 +449,13 @@ impl ConnectionManager {
     pub fn new() -> Self {
         Self {
             inner: Arc::new(Mutex::new(HashMap::new())),
+            events_tx: broadcast::channel(64).0,
+            stopped_scrollback: Arc::new(StdMutex::new(KeptScrollbacks::new(
+                MAX_KEPT_SCROLLBACKS,
+                MAX_KEPT_SCROLLBACK_BYTES,
+            ))),
+            connecting: Arc::new(StdMutex::new(HashSet::new())),
+            alive: watch::channel(()).0,
         }
     }
 
@@ -56,91 +468,688 @@ impl ConnectionManager {
     ///   It then returns a `ConnectionHandle` that can be used to control
     ///   the connection.
     pub async fn add_connection(
+        &self,
+        id: String,
+        conn: Box<dyn Connection + Send + Unpin>,
+    ) -> Result<(), ConnectionError> {
+        self.add_connection_with_options(id, conn, ConnectionOptions::default())
+            .await
+    }
+
+    /// Like [`add_connection`](Self::add_connection), but the I/O task
+    /// applies the given per-connection `options`.
+    ///
+    /// Fails if `id` is already in use. On every error path, including this
+    /// future being dropped, the transport is disconnected again.
+    pub async fn add_connection_with_options(
         &self,
         id: String,
         mut conn: Box<dyn Connection + Send + Unpin>,
+        options: ConnectionOptions,
     ) -> Result<(), ConnectionError> {
-        conn.connect().await?;
+        let options = options.resolved();
+        if let Some(progress) = &options.progress {
+            conn.set_progress(progress.clone());
+        }
+        let connecting = ConnectingMark::new(&self.connecting, &id);
+        let mut guard = ConnectGuard::new(conn);
+        let connected = match &options.connect_retry {
+            Some(retry) => retry.connect(guard.conn()).await,
+            None => guard.conn().connect().await,
+        };
+        if let Err(e) = connected {
+            guard.disconnect().await;
+            return Err(e);
+        }
+
+        // Claim the id before the I/O task exists, so a failure here only
+        // has to undo the connect.
+        let mut map = self.inner.lock().await;
+        if map.contains_key(&id) {
+            drop(map);
+            guard.disconnect().await;
+            return Err(ConnectionError::Other(format!(
+                "Connection '{id}' already exists"
+            )));
+        }
+        let mut conn = guard.release();
+
+        let pty_size = conn.pty_size();
+        let kind = conn.kind();
+        let negotiated = conn.negotiated();
+        let connected_at = Instant::now();
 
         // Broadcast messages from the connection to all listeners(UIs)
         // Listeners(having subscribes via public API) <- I/O task
-        let (broadcast_tx, _) = broadcast::channel::<Vec<u8>>(256);
+        // Subscribe before the I/O task exists so its first chunk, the banner
+        // or device data, is kept for whoever subscribes first.
+        let (broadcast_tx, first_subscriber) = broadcast::channel::<Vec<u8>>(BROADCAST_CAPACITY);
+        let broadcast_tx = Arc::new(RwLock::new(Some(broadcast_tx)));
 
         // Channel public API -> I/O task.
         let (write_stop_tx, mut write_stop_rx) = mpsc::channel::<IoEvent>(32);
 
         // Per-connection I/O task
         let id_clone = id.clone();
+        let current_id = Arc::new(RwLock::new(id.clone()));
+        let task_id = current_id.clone();
         let broadcast_tx_clone = broadcast_tx.clone();
+        let events_tx = self.events_tx.clone();
+        let log = ConnectionLog::new(options.log_level);
+        let write_slots = options
+            .max_in_flight_writes
+            .map(|limit| (Arc::new(Semaphore::new(limit)), limit));
+        let connection_log = log.clone();
+        // A reconnect, i.e. an id added again after it was stopped, continues
+        // the scrollback of the previous session if that one kept it.
+        let previous_scrollback = self.stopped_scrollback.lock().unwrap().take(&id);
+        let scrollback = match previous_scrollback {
+            Some(scrollback) => {
+                let mut previous = scrollback.lock().unwrap();
+                if options.binary {
+                    previous.set_limit(options.scrollback_bytes);
+                } else {
+                    previous.reconnect(options.scrollback_bytes);
+                }
+                drop(previous);
+                scrollback
+            }
+            None => Arc::new(StdMutex::new(Scrollback::new(options.scrollback_bytes))),
+        };
+        let task_scrollback = scrollback.clone();
+        let keep_scrollback = options.keep_scrollback;
+        let state = Arc::new(StdMutex::new(ConnectionState::Connected));
+        let task_state = state.clone();
+        let traffic = Arc::new(Traffic::default());
+        let task_traffic = traffic.clone();
         let io_task_handle = tokio::spawn(async move {
-            info!("Async I/O task started for connection '{id_clone}'.");
-            let mut buf = [0u8; 256];
+            conn_log!(
+                log,
+                Level::Info,
+                "Async I/O task started for connection '{id_clone}'."
+            );
+            let mut buf = vec![
+                0u8;
+                options
+                    .read_buffer_size
+                    .map_or(DEFAULT_READ_BUFFER_SIZE, NonZeroUsize::get)
+            ];
+            let mut last_read = tokio::time::Instant::now();
+            let mut stall_reported = false;
+            // Reads, writes and keep-alives all restart the keep-alive interval.
+            let mut last_traffic = tokio::time::Instant::now();
+            // Only reads and writes count here, keep-alives do not.
+            let mut last_activity = tokio::time::Instant::now();
+            let session_deadline = options
+                .max_session
+                .map(|limit| tokio::time::Instant::now() + limit);
+            let mut baud_check = options.detect_baud_mismatch.then(BaudCheck::default);
+            let mut end_state = ConnectionState::Disconnected;
+            let pause_limit = options
+                .pause_buffer_bytes
+                .unwrap_or(DEFAULT_PAUSE_BUFFER_BYTES);
+            // While paused, reads are held here instead of being published,
+            // and what does not fit is counted.
+            let mut paused = false;
+            let mut held = Vec::new();
+            let mut dropped: u64 = 0;
+            if let Some(banner) = &options.banner {
+                publish(&broadcast_tx_clone, banner.clone());
+            }
             loop {
+                // Set by a read that lost the transport, to the state the
+                // connection ends in unless it is reconnected.
+                let mut lost = None;
                 // This implicitly awaits concurrently for
                 // the write_stop_rx.recv() and conn.read() futures
                 tokio::select! {
                     Some(event) = write_stop_rx.recv() => {
                         match event {
-                            IoEvent::Write(data) => {
-                                debug!("Write: {data:?} to connection");
-                                if let Err(e) = conn.write(&data).await {
-                                    error!("Write error on '{id_clone}': {e:?}");
+                            IoEvent::Write(data, _slot) => {
+                                last_traffic = tokio::time::Instant::now();
+                                last_activity = last_traffic;
+                                conn_log!(log, Level::Debug, "Write to '{id_clone}': {data:?}");
+                                let result = write_transformed(&mut conn, &data, &options).await;
+                                task_traffic.wrote(&result);
+                                if let Err(e) = result {
+                                    conn_log!(log, Level::Error, "Write error on '{id_clone}': {e:?}");
+                                }
+                            },
+                            IoEvent::WriteAcked { data, reply, slot: _slot } => {
+                                last_traffic = tokio::time::Instant::now();
+                                last_activity = last_traffic;
+                                conn_log!(log, Level::Debug, "Write (acked) to '{id_clone}': {data:?}");
+                                let result = write_transformed(&mut conn, &data, &options).await;
+                                task_traffic.wrote(&result);
+                                if let Err(e) = &result {
+                                    conn_log!(log, Level::Error, "Write error on '{id_clone}': {e:?}");
                                 }
+                                let _ = reply.send(result);
+                            },
+                            IoEvent::WriteRaw { data, reply, slot: _slot } => {
+                                last_traffic = tokio::time::Instant::now();
+                                last_activity = last_traffic;
+                                conn_log!(log, Level::Debug, "Write (raw) to '{id_clone}': {} bytes", data.len());
+                                let result = write_all(conn.as_mut(), &data).await;
+                                task_traffic.wrote(&result);
+                                if let Err(e) = &result {
+                                    conn_log!(log, Level::Error, "Write error on '{id_clone}': {e:?}");
+                                }
+                                let _ = reply.send(result);
+                            },
+                            IoEvent::Flush { reply } => {
+                                let _ = reply.send(conn.flush().await);
+                            },
+                            IoEvent::IsConnected { reply } => {
+                                let _ = reply.send(conn.is_connected());
+                            },
+                            IoEvent::Pause { reply } => {
+                                conn_log!(log, Level::Debug, "Pause '{id_clone}'");
+                                paused = true;
+                                let _ = reply.send(Ok(()));
+                            },
+                            IoEvent::Resume { reply } => {
+                                conn_log!(log, Level::Debug, "Resume '{id_clone}' with {} held bytes", held.len());
+                                paused = false;
+                                if !held.is_empty() {
+                                    publish(&broadcast_tx_clone, std::mem::take(&mut held));
+                                }
+                                if dropped > 0 {
+                                    conn_log!(log, Level::Warn, "Dropped {dropped} bytes from '{id_clone}' while paused");
+                                }
+                                let _ = reply.send(Ok(std::mem::take(&mut dropped)));
+                            },
+                            IoEvent::Resize { cols, rows, reply } => {
+                                conn_log!(log, Level::Debug, "Resize '{id_clone}' to {cols}x{rows}");
+                                let _ = reply.send(conn.resize(cols, rows).await);
+                            },
+                            IoEvent::Line { control, reply } => {
+                                conn_log!(log, Level::Debug, "{control:?} on '{id_clone}'");
+                                let result = match control {
+                                    LineControl::Dtr(level) => conn.set_dtr(level).await,
+                                    LineControl::Rts(level) => conn.set_rts(level).await,
+                                    LineControl::Break(duration) => conn.send_break(duration).await,
+                                };
+                                let _ = reply.send(result);
+                            },
+                            IoEvent::ModemStatus { reply } => {
+                                let _ = reply.send(conn.modem_status().await);
+                            },
+                            IoEvent::LocalForward { forward, reply } => {
+                                conn_log!(log, Level::Debug, "Local forward {forward} on '{id_clone}'");
+                                let _ = reply.send(conn.local_forward(&forward).await);
+                            },
+                            #[cfg(feature = "ssh")]
+                            IoEvent::Sftp { reply } => {
+                                conn_log!(log, Level::Debug, "SFTP client requested for '{id_clone}'");
+                                let _ = reply.send(conn.sftp().await);
                             },
                             IoEvent::Stop => {
-                                info!("Stop received for '{id_clone}'. Exiting task.");
+                                conn_log!(log, Level::Info, "Stop received for '{id_clone}'. Exiting task.");
+                                if let Err(e) = conn.flush().await {
+                                    conn_log!(log, Level::Warn, "Flush on stop failed for '{id_clone}': {e:?}");
+                                }
                                 break;
                             },
                         }
                     },
+                    _ = tokio::time::sleep_until(last_read + options.read_watchdog.unwrap_or_default()),
+                        if options.read_watchdog.is_some() && !stall_reported =>
+                    {
+                        let silent_for = last_read.elapsed();
+                        conn_log!(log, Level::Warn, "No data from '{id_clone}' for {silent_for:?}; transport may be stalled");
+                        stall_reported = true;
+                        let _ = events_tx.send(ConnectionEvent {
+                            id: task_id.read().unwrap().clone(),
+                            kind: ConnectionEventKind::Stalled { silent_for },
+                        });
+                    },
+                    _ = tokio::time::sleep_until(last_traffic + options.line_keepalive.map(|k| k.interval).unwrap_or_default()),
+                        if options.line_keepalive.is_some() =>
+                    {
+                        last_traffic = tokio::time::Instant::now();
+                        let result = match options.line_keepalive.map(|k| k.action).unwrap_or_default() {
+                            KeepAliveAction::AssertLines => conn.assert_modem_lines().await,
+                            KeepAliveAction::SendByte(byte) => write_all(conn.as_mut(), &[byte]).await.map(|_| ()),
+                        };
+                        match result {
+                            Ok(()) => conn_log!(log, Level::Trace, "Keep-alive sent on '{id_clone}'"),
+                            Err(e) => conn_log!(log, Level::Warn, "Keep-alive on '{id_clone}' failed: {e:?}"),
+                        }
+                    },
+                    _ = tokio::time::sleep_until(session_deadline.unwrap_or_else(tokio::time::Instant::now)),
+                        if session_deadline.is_some() =>
+                    {
+                        conn_log!(log, Level::Info, "'{id_clone}' reached its maximum session duration. Exiting task.");
+                        let _ = events_tx.send(ConnectionEvent {
+                            id: task_id.read().unwrap().clone(),
+                            kind: ConnectionEventKind::Disconnected { reason: DisconnectReason::MaxDuration },
+                        });
+                        break;
+                    },
+                    _ = tokio::time::sleep_until(last_activity + options.idle_timeout.unwrap_or_default()),
+                        if options.idle_timeout.is_some() =>
+                    {
+                        conn_log!(log, Level::Info, "'{id_clone}' was idle for {:?}. Exiting task.", last_activity.elapsed());
+                        let _ = events_tx.send(ConnectionEvent {
+                            id: task_id.read().unwrap().clone(),
+                            kind: ConnectionEventKind::Disconnected { reason: DisconnectReason::IdleTimeout },
+                        });
+                        break;
+                    },
                     result = conn.read(&mut buf) => {
                         match result {
                             Ok(0) => {
-                                debug!("Read 0 bytes from '{id_clone}'");
+                                if options.eof_policy == EofPolicy::CloseOnEof {
+                                    conn_log!(log, Level::Info, "EOF on '{id_clone}'.");
+                                    lost = Some(ConnectionState::Disconnected);
+                                } else {
+                                    conn_log!(log, Level::Debug, "Read 0 bytes from '{id_clone}'");
+                                }
                             },
                             Ok(n) => {
-                                debug!("Read {n} bytes from '{id_clone}'");
-                                let _ = broadcast_tx_clone.send(buf[..n].to_vec());
+                                conn_log!(log, Level::Debug, "Read {n} bytes from '{id_clone}'");
+                                task_traffic.read.fetch_add(n as u64, Ordering::Relaxed);
+                                last_read = tokio::time::Instant::now();
+                                last_traffic = last_read;
+                                last_activity = last_read;
+                                stall_reported = false;
+                                if let Some(printable_percent) =
+                                    baud_check.as_mut().and_then(|check| check.feed(&buf[..n]))
+                                {
+                                    conn_log!(log, Level::Warn, "Data from '{id_clone}' is only {printable_percent}% printable; is the baud rate right?");
+                                    let _ = events_tx.send(ConnectionEvent {
+                                        id: task_id.read().unwrap().clone(),
+                                        kind: ConnectionEventKind::PossibleBaudMismatch { printable_percent },
+                                    });
+                                }
+                                task_scrollback.lock().unwrap().push(&buf[..n]);
+                                if paused {
+                                    let room = pause_limit.saturating_sub(held.len()).min(n);
+                                    held.extend_from_slice(&buf[..room]);
+                                    dropped += (n - room) as u64;
+                                } else {
+                                    publish(&broadcast_tx_clone, buf[..n].to_vec());
+                                }
+                            },
+                            Err(ConnectionError::RemoteClosed { exit_status }) => {
+                                conn_log!(log, Level::Info, "'{id_clone}' was closed by the remote side (exit status {exit_status:?}).");
+                                let _ = events_tx.send(ConnectionEvent {
+                                    id: task_id.read().unwrap().clone(),
+                                    kind: ConnectionEventKind::RemoteClosed { exit_status },
+                                });
+                                lost = Some(ConnectionState::Disconnected);
                             },
                             Err(e) => {
-                                debug!("Read error on '{id_clone}': {e:?}");
-                                break;
+                                conn_log!(log, Level::Debug, "Read error on '{id_clone}': {e:?}");
+                                let _ = events_tx.send(ConnectionEvent {
+                                    id: task_id.read().unwrap().clone(),
+                                    kind: ConnectionEventKind::ReadError(e.to_string()),
+                                });
+                                lost = Some(ConnectionState::Failed(e.to_string()));
                             },
                         }
                     }
                 }
+
+                let Some(state) = lost else { continue };
+                end_state = state;
+                let Some(policy) = &options.reconnect else {
+                    conn_log!(
+                        log,
+                        Level::Info,
+                        "'{id_clone}' lost its transport. Exiting task."
+                    );
+                    break;
+                };
+                conn_log!(
+                    log,
+                    Level::Info,
+                    "'{id_clone}' lost its transport. Reconnecting."
+                );
+                *task_state.lock().unwrap() = ConnectionState::Reconnecting;
+                let _ = events_tx.send(ConnectionEvent {
+                    id: task_id.read().unwrap().clone(),
+                    kind: ConnectionEventKind::Reconnecting,
+                });
+                match reconnect(policy, conn.as_mut(), &mut write_stop_rx).await {
+                    Reconnect::Reconnected => {
+                        conn_log!(log, Level::Info, "'{id_clone}' reconnected.");
+                        *task_state.lock().unwrap() = ConnectionState::Connected;
+                        end_state = ConnectionState::Disconnected;
+                        last_read = tokio::time::Instant::now();
+                        last_traffic = last_read;
+                        last_activity = last_read;
+                        stall_reported = false;
+                        if !options.binary {
+                            task_scrollback.lock().unwrap().mark_reconnect();
+                        }
+                        let _ = events_tx.send(ConnectionEvent {
+                            id: task_id.read().unwrap().clone(),
+                            kind: ConnectionEventKind::Reconnected(conn.negotiated()),
+                        });
+                    }
+                    Reconnect::Stopped => {
+                        conn_log!(
+                            log,
+                            Level::Info,
+                            "Stop received for '{id_clone}' while reconnecting. Exiting task."
+                        );
+                        end_state = ConnectionState::Disconnected;
+                        break;
+                    }
+                    Reconnect::GaveUp(e) => {
+                        conn_log!(log, Level::Warn, "Giving up reconnecting '{id_clone}': {e}");
+                        end_state = ConnectionState::Failed(format!("reconnect failed: {e}"));
+                        break;
+                    }
+                }
+            }
+            if !held.is_empty() {
+                publish(&broadcast_tx_clone, held);
             }
             let _ = conn.disconnect().await;
-            info!("Async I/O task ended for '{id_clone}'.");
+            conn_log!(log, Level::Info, "Async I/O task ended for '{id_clone}'.");
+            *task_state.lock().unwrap() = end_state;
+            let _ = events_tx.send(ConnectionEvent {
+                id: task_id.read().unwrap().clone(),
+                kind: ConnectionEventKind::Closed,
+            });
+            // Subscribers see the end after the events that explain it.
+            broadcast_tx_clone.write().unwrap().take();
         });
 
         let handle = ConnectionIOHandle {
             io_task_handle,
             write_stop_tx,
             broadcast_tx,
+            first_subscriber: Some(first_subscriber),
+            pty_size,
+            kind,
+            negotiated: negotiated.clone(),
+            connected_at,
+            current_id,
+            log: connection_log,
+            write_slots,
+            scrollback,
+            keep_scrollback,
+            state,
+            traffic,
         };
-        {
-            let mut map = self.inner.lock().await;
-            map.insert(id.clone(), handle);
+        map.insert(id.clone(), handle);
+        drop(map);
+        drop(connecting);
+        let _ = self.events_tx.send(ConnectionEvent {
+            id,
+            kind: ConnectionEventKind::Opened(negotiated),
+        });
+
+        Ok(())
+    }
+
+    /// Ids of the connections this manager tracks, sorted.
+    pub async fn list_connections(&self) -> Vec<String> {
+        let map = self.inner.lock().await;
+        let mut ids: Vec<String> = map.keys().cloned().collect();
+        ids.sort();
+        ids
+    }
+
+    /// Whether a connection with `id` is tracked.
+    pub async fn contains(&self, id: &str) -> bool {
+        self.inner.lock().await.contains_key(id)
+    }
+
+    /// Register the live connection `old_id` under `new_id` instead.
+    ///
+    /// Data subscriptions keep working since the connection is not touched,
+    /// and later events carry `new_id`. Anything that stored `old_id` (a
+    /// [`StableSubscription`], a UI) is stale afterwards: calls with it fail
+    /// like for any unknown id. A [`ConnectionEventKind::Renamed`] event tells
+    /// listeners to switch. Fails if `old_id` does not exist or `new_id` does.
+    pub async fn rename(&self, old_id: &str, new_id: &str) -> Result<(), ConnectionError> {
+        let mut map = self.inner.lock().await;
+        if !map.contains_key(old_id) {
+            return Err(ConnectionError::Other(format!(
+                "No connection with id '{old_id}'"
+            )));
         }
+        if old_id == new_id {
+            return Ok(());
+        }
+        if map.contains_key(new_id) {
+            return Err(ConnectionError::Other(format!(
+                "Connection '{new_id}' already exists"
+            )));
+        }
+        let handle = map.remove(old_id).expect("checked above");
+        *handle.current_id.write().unwrap() = new_id.to_string();
+        map.insert(new_id.to_string(), handle);
+        drop(map);
 
+        info!("Renamed connection '{old_id}' to '{new_id}'");
+        let _ = self.events_tx.send(ConnectionEvent {
+            id: new_id.to_string(),
+            kind: ConnectionEventKind::Renamed {
+                old_id: old_id.to_string(),
+            },
+        });
+        Ok(())
+    }
+
+    /// Change how verbosely connection `id` logs while it runs, e.g. raise a
+    /// single flaky connection to `Debug` while the others stay at `Info`.
+    ///
+    /// This only filters the connection's own records (target
+    /// [`CONNECTION_LOG_TARGET`](crate::CONNECTION_LOG_TARGET));
+    /// the logger must let that target through at the chosen level.
+    /// `LevelFilter::Trace` hands the decision back to the logger.
+    pub async fn set_log_level(&self, id: &str, level: LevelFilter) -> Result<(), ConnectionError> {
+        let map = self.inner.lock().await;
+        let handle = map
+            .get(id)
+            .ok_or_else(|| ConnectionError::Other(format!("No connection with id '{id}'")))?;
+        handle.log.set_level(level);
         Ok(())
     }
 
+    /// Receive lifecycle events for all connections of this manager.
+    pub fn events(&self) -> broadcast::Receiver<ConnectionEvent> {
+        self.events_tx.subscribe()
+    }
+
     /// Subscribe to the byte stream of a connection.
+    ///
+    /// The first subscriber of a connection receives its data from the very
+    /// start, however soon after `add_connection` the I/O task begins
+    /// reading: no early bytes are lost, up to the channel capacity (if more
+    /// arrived before subscribing, `recv` reports the lag first). Later
+    /// subscribers receive what arrives after they subscribed.
+    /// [`subscribe_tee`](Self::subscribe_tee),
+    /// [`subscribe_lossy`](Self::subscribe_lossy) and
+    /// [`subscribe_stable`](Self::subscribe_stable) count as subscribers
+    /// here; one-shot readers like [`write_verified`](Self::write_verified)
+    /// do not.
     pub async fn subscribe(&self, id: &str) -> Option<broadcast::Receiver<Vec<u8>>> {
+        let mut map = self.inner.lock().await;
+        map.get_mut(id).map(ConnectionIOHandle::subscribe)
+    }
+
+    /// Like [`subscribe`](Self::subscribe), but always starting now: the
+    /// early data stays with the consumer the first subscription is meant
+    /// for. For internal readers that only wait for an answer to a write.
+    async fn subscribe_live(&self, id: &str) -> Option<broadcast::Receiver<Vec<u8>>> {
         let map = self.inner.lock().await;
-        map.get(id).map(|h| h.broadcast_tx.subscribe())
+        map.get(id).map(ConnectionIOHandle::live_receiver)
+    }
+
+    /// Number of live receivers of connection `id`'s byte stream, `None` if
+    /// there is no such connection.
+    pub async fn subscriber_count(&self, id: &str) -> Option<usize> {
+        let map = self.inner.lock().await;
+        map.get(id).map(ConnectionIOHandle::subscriber_count)
+    }
+
+    /// Disconnect every current subscriber of connection `id`, e.g. a viewer
+    /// that stopped reading or a stream a gRPC client leaked.
+    ///
+    /// The old receivers get the chunks already queued and then
+    /// `RecvError::Closed`. The connection itself stays up; later
+    /// [`subscribe`](Self::subscribe) calls receive its data as usual.
+    /// Returns how many subscribers were dropped.
+    pub async fn drop_subscribers(&self, id: &str) -> Result<usize, ConnectionError> {
+        let mut map = self.inner.lock().await;
+        let handle = map
+            .get_mut(id)
+            .ok_or_else(|| ConnectionError::Other(format!("No connection with id '{id}'")))?;
+        let dropped = handle.subscriber_count();
+        handle.first_subscriber = None;
+        let mut broadcast_tx = handle.broadcast_tx.write().unwrap();
+        if broadcast_tx.is_some() {
+            *broadcast_tx = Some(broadcast::channel::<Vec<u8>>(BROADCAST_CAPACITY).0);
+        }
+        drop(broadcast_tx);
+        info!("Dropped {dropped} subscriber(s) of '{id}'");
+        Ok(dropped)
+    }
+
+    /// Subscribe to a connection and copy every chunk to `writer` as well.
+    ///
+    /// Both sides are fed from one subscription, in order: a chunk is
+    /// written to `writer` first and then handed to the returned receiver.
+    /// The writer therefore always holds at least what the receiver has seen,
+    /// and if the subscription lags both miss the same chunks. A slow
+    /// receiver holds back the writer, too; dropping the receiver keeps the
+    /// writer going. The tee ends, flushing `writer`, when the connection
+    /// closes or a write to `writer` fails.
+    pub async fn subscribe_tee<W>(&self, id: &str, mut writer: W) -> Option<mpsc::Receiver<Vec<u8>>>
+    where
+        W: AsyncWrite + Unpin + Send + 'static,
+    {
+        let mut data_rx = self.subscribe(id).await?;
+        let (tee_tx, tee_rx) = mpsc::channel(BROADCAST_CAPACITY);
+        let id = id.to_string();
+        tokio::spawn(async move {
+            loop {
+                let chunk = match data_rx.recv().await {
+                    Ok(chunk) => chunk,
+                    Err(RecvError::Lagged(n)) => {
+                        warn!("Tee of '{id}' lagged; {n} chunks missing on both sides");
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                if let Err(e) = writer.write_all(&chunk).await {
+                    error!("Tee writer of '{id}' failed: {e}");
+                    break;
+                }
+                // A dropped receiver only ends the channel half of the tee.
+                let _ = tee_tx.send(chunk).await;
+            }
+            let _ = writer.flush().await;
+            let _ = writer.shutdown().await;
+        });
+        Some(tee_rx)
+    }
+
+    /// The most recent output of connection `id`, oldest byte first, up to
+    /// its scrollback limit. `None` for unknown ids.
+    ///
+    /// With [`ConnectionOptions::keep_scrollback`], stopping a connection and
+    /// adding the same id again keeps the scrollback, with
+    /// `--- reconnected ---` on a line of its own between the two sessions.
+    pub async fn scrollback(&self, id: &str) -> Option<Vec<u8>> {
+        let map = self.inner.lock().await;
+        map.get(id).map(|h| h.scrollback.lock().unwrap().contents())
+    }
+
+    /// Current scrollback size, limit and how much was evicted, e.g. to show
+    /// "scrollback truncated". `None` for unknown ids.
+    pub async fn scrollback_stats(&self, id: &str) -> Option<ScrollbackStats> {
+        let map = self.inner.lock().await;
+        map.get(id).map(|h| h.scrollback.lock().unwrap().stats())
+    }
+
+    /// Keep at most `bytes` of scrollback for connection `id` from now on.
+    /// Shrinking drops the oldest bytes immediately; zero stops recording.
+    pub async fn set_scrollback_limit(
+        &self,
+        id: &str,
+        bytes: usize,
+    ) -> Result<(), ConnectionError> {
+        let map = self.inner.lock().await;
+        let handle = map
+            .get(id)
+            .ok_or_else(|| ConnectionError::Other(format!("No connection with id '{id}'")))?;
+        handle.scrollback.lock().unwrap().set_limit(bytes);
+        Ok(())
+    }
+
+    /// Whether connection `id` is connecting, running, or has ended and how.
+    /// `None` for ids that are neither being added nor registered, including
+    /// stopped ones and ones whose `add_connection` failed.
+    pub async fn status(&self, id: &str) -> Option<ConnectionState> {
+        if let Some(handle) = self.inner.lock().await.get(id) {
+            return Some(handle.state.lock().unwrap().clone());
+        }
+        self.connecting
+            .lock()
+            .unwrap()
+            .contains(id)
+            .then_some(ConnectionState::Connecting)
+    }
+
+    /// Parameters the transport negotiated when the connection was opened.
+    pub async fn negotiated_params(&self, id: &str) -> Option<NegotiatedParams> {
+        let map = self.inner.lock().await;
+        map.get(id).map(|h| h.negotiated.clone())
+    }
+
+    /// Subscribe to the byte stream of a connection id, carrying on with a
+    /// [`SubscriptionItem::Lagged`](crate::SubscriptionItem::Lagged) marker
+    /// when the subscriber falls behind. See [`LossySubscription`].
+    pub async fn subscribe_lossy(&self, id: &str) -> Option<LossySubscription> {
+        let data_rx = self.subscribe(id).await?;
+        Some(LossySubscription::new(id.to_owned(), data_rx))
+    }
+
+    /// Subscribe to the byte stream of a connection id, following it across
+    /// reconnects. See [`StableSubscription`].
+    pub async fn subscribe_stable(&self, id: &str) -> Option<StableSubscription> {
+        // Subscribe to events first so a reconnect racing with this call is not missed.
+        let events_rx = self.events_tx.subscribe();
+        let data_rx = self.subscribe(id).await?;
+        Some(StableSubscription::new(
+            WeakConnectionManager {
+                inner: Arc::downgrade(&self.inner),
+                alive: self.alive.subscribe(),
+            },
+            id.to_string(),
+            data_rx,
+            events_rx,
+        ))
     }
 
     /// Write bytes to a specific connection by ID.
+    ///
+    /// Returns `data.len()` as soon as the bytes are queued. The I/O task
+    /// writes all of them, retrying after short writes; use
+    /// [`write_bytes_acked`](Self::write_bytes_acked) to learn whether the
+    /// transport actually took them.
+    ///
+    /// With [`ConnectionOptions::max_in_flight_writes`] set, this and the other
+    /// write methods fail with [`ConnectionError::Busy`] while the limit is
+    /// reached, rather than waiting. While the transport is being
+    /// reconnected, writes fail rather than being queued.
     pub async fn write_bytes(&self, id: &str, data: &[u8]) -> Result<usize, ConnectionError> {
         let map = self.inner.lock().await;
         if let Some(handle) = map.get(id) {
             debug!("write: {data:?}");
+            handle.check_not_reconnecting()?;
+            let slot = handle.reserve_write()?;
             handle
                 .write_stop_tx
-                .send(IoEvent::Write(data.to_vec()))
+                .send(IoEvent::Write(data.to_vec(), slot))
                 .await
                 .map_err(|_| ConnectionError::Other("Channel closed".into()))?;
             Ok(data.len())
@@ -151,11 +1160,489 @@ impl ConnectionManager {
         }
     }
 
-    /// Stop a connection.
+    /// Write bytes to a specific connection by ID and wait until the
+    /// transport has accepted them.
+    ///
+    /// Unlike [`write_bytes`](Self::write_bytes), which only reports that the
+    /// bytes were queued, this returns the transport's own write result.
+    pub async fn write_bytes_acked(&self, id: &str, data: &[u8]) -> Result<usize, ConnectionError> {
+        let (write_stop_tx, slot) = {
+            let map = self.inner.lock().await;
+            let handle = map
+                .get(id)
+                .ok_or_else(|| ConnectionError::Other(format!("No connection with id '{id}'")))?;
+            (handle.write_stop_tx.clone(), handle.reserve_write()?)
+        };
+        let (reply, reply_rx) = oneshot::channel();
+        write_stop_tx
+            .send(IoEvent::WriteAcked {
+                data: data.to_vec(),
+                reply,
+                slot,
+            })
+            .await
+            .map_err(|_| ConnectionError::Other("Channel closed".into()))?;
+        reply_rx
+            .await
+            .map_err(|_| ConnectionError::Other("Channel closed".into()))?
+    }
+
+    /// Stream everything `reader` yields into connection `id`, in writes of
+    /// at most `chunk_size` bytes, sleeping `pause` between them (zero for no
+    /// pause). Returns the number of bytes written once `reader` is at EOF.
+    ///
+    /// Each chunk is acknowledged by the transport before the next one is
+    /// read, so a pipe or socket is never buffered beyond one chunk. Chunks
+    /// go through the same outgoing transforms as
+    /// [`write_bytes`](Self::write_bytes), including
+    /// [`ConnectionOptions::char_delay`].
+    pub async fn write_from<R>(
+        &self,
+        id: &str,
+        mut reader: R,
+        chunk_size: usize,
+        pause: Duration,
+    ) -> Result<u64, ConnectionError>
+    where
+        R: AsyncRead + Unpin,
+    {
+        if chunk_size == 0 {
+            return Err(ConnectionError::Other(
+                "write_from needs a chunk size of at least 1".into(),
+            ));
+        }
+        let mut chunk = vec![0; chunk_size];
+        let mut written = 0;
+        loop {
+            let n = reader.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(written);
+            }
+            if written > 0 && !pause.is_zero() {
+                tokio::time::sleep(pause).await;
+            }
+            self.write_bytes_acked(id, &chunk[..n]).await?;
+            written += n as u64;
+        }
+    }
+
+    /// Write bytes verbatim and wait until the transport has accepted them.
+    ///
+    /// Skips every outgoing transform configured in [`ConnectionOptions`]
+    /// (CRLF translation, char delay), so the device receives exactly `data`.
+    /// Binary protocols and file transfers (XMODEM, SFTP, file send) must use
+    /// this instead of [`write_bytes`](Self::write_bytes).
+    pub async fn write_raw(&self, id: &str, data: &[u8]) -> Result<usize, ConnectionError> {
+        let (write_stop_tx, slot) = {
+            let map = self.inner.lock().await;
+            let handle = map
+                .get(id)
+                .ok_or_else(|| ConnectionError::Other(format!("No connection with id '{id}'")))?;
+            (handle.write_stop_tx.clone(), handle.reserve_write()?)
+        };
+        let (reply, reply_rx) = oneshot::channel();
+        write_stop_tx
+            .send(IoEvent::WriteRaw {
+                data: data.to_vec(),
+                reply,
+                slot,
+            })
+            .await
+            .map_err(|_| ConnectionError::Other("Channel closed".into()))?;
+        reply_rx
+            .await
+            .map_err(|_| ConnectionError::Other("Channel closed".into()))?
+    }
+
+    /// Write bytes and verify them against the device's echo.
+    ///
+    /// Meant for devices that echo what they receive, to catch line noise on
+    /// unreliable links. The echo is collected from a subscription taken before
+    /// the write; if it differs, [`ConnectionError::EchoMismatch`] names the
+    /// first differing byte. Fails if the full echo does not arrive within
+    /// `timeout`.
+    pub async fn write_verified(
+        &self,
+        id: &str,
+        data: &[u8],
+        timeout: Duration,
+    ) -> Result<usize, ConnectionError> {
+        let mut rx = self
+            .subscribe_live(id)
+            .await
+            .ok_or_else(|| ConnectionError::Other(format!("No connection with id '{id}'")))?;
+        let written = self.write_bytes_acked(id, data).await?;
+
+        let mut echoed = Vec::with_capacity(data.len());
+        let collected = tokio::time::timeout(timeout, async {
+            while echoed.len() < data.len() {
+                match rx.recv().await {
+                    Ok(chunk) => echoed.extend_from_slice(&chunk),
+                    Err(RecvError::Lagged(n)) => {
+                        return Err(ConnectionError::Other(format!(
+                            "Echo lost: subscriber lagged by {n} messages"
+                        )))
+                    }
+                    Err(RecvError::Closed) => {
+                        return Err(ConnectionError::Other(
+                            "Connection closed while waiting for echo".into(),
+                        ))
+                    }
+                }
+            }
+            Ok(())
+        })
+        .await;
+        match collected {
+            Ok(result) => result?,
+            Err(_) => {
+                return Err(ConnectionError::Other(format!(
+                    "Echo timed out: got {} of {} bytes",
+                    echoed.len(),
+                    data.len()
+                )))
+            }
+        }
+
+        if let Some(offset) = data.iter().zip(&echoed).position(|(a, b)| a != b) {
+            return Err(ConnectionError::EchoMismatch {
+                offset,
+                expected: data[offset],
+                actual: echoed[offset],
+            });
+        }
+        Ok(written)
+    }
+
+    /// Measure keystroke-to-echo latency: write the single byte `probe` and
+    /// time how long it takes to come back, e.g. to compare SSH with serial
+    /// or different buffer settings.
+    ///
+    /// Only works for devices and shells that echo what they receive; on
+    /// anything else it fails once `timeout` has passed. Output arriving
+    /// before the echo is skipped, so pick a `probe` the device is unlikely
+    /// to send on its own, and one that is harmless to type.
+    pub async fn measure_latency(
+        &self,
+        id: &str,
+        probe: u8,
+        timeout: Duration,
+    ) -> Result<Duration, ConnectionError> {
+        // Taken right before the write, so output from before cannot pass
+        // for the echo.
+        let mut rx = self
+            .subscribe_live(id)
+            .await
+            .ok_or_else(|| ConnectionError::Other(format!("No connection with id '{id}'")))?;
+        let started = Instant::now();
+        self.write_bytes_acked(id, &[probe]).await?;
+
+        let echoed = tokio::time::timeout(timeout, async {
+            loop {
+                match rx.recv().await {
+                    Ok(chunk) if chunk.contains(&probe) => return Ok(started.elapsed()),
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(n)) => {
+                        return Err(ConnectionError::Other(format!(
+                            "Echo lost: subscriber lagged by {n} messages"
+                        )))
+                    }
+                    Err(RecvError::Closed) => {
+                        return Err(ConnectionError::Other(
+                            "Connection closed while waiting for echo".into(),
+                        ))
+                    }
+                }
+            }
+        })
+        .await;
+        echoed.unwrap_or_else(|_| {
+            Err(ConnectionError::Other(format!(
+                "No echo of probe {probe:#04x} within {timeout:?}; does the device echo?"
+            )))
+        })
+    }
+
+    /// Wait until everything written to connection `id` so far has left the
+    /// transport's buffers. Stopping a connection flushes it as well.
+    pub async fn flush(&self, id: &str) -> Result<(), ConnectionError> {
+        self.request(id, |reply| IoEvent::Flush { reply }).await?
+    }
+
+    /// Whether the transport of connection `id` is up, as reported by
+    /// [`Connection::is_connected`]. `false` while reconnecting and once the
+    /// connection has ended, e.g. because the peer hung up; an error only for
+    /// ids that are not registered, so UIs can tell a dropped connection from
+    /// one that never existed.
+    pub async fn is_connected(&self, id: &str) -> Result<bool, ConnectionError> {
+        match self
+            .request(id, |reply| IoEvent::IsConnected { reply })
+            .await
+        {
+            // The task owning the transport is gone, but the id is still known.
+            Err(_) if self.contains(id).await => Ok(false),
+            result => result,
+        }
+    }
+
+    /// Stop passing data read from connection `id` on to subscribers, like
+    /// scroll lock, without disconnecting. Writes keep working, and reads
+    /// still go to the scrollback. Up to
+    /// [`ConnectionOptions::pause_buffer_bytes`] received bytes are held for
+    /// [`resume`](Self::resume); newer ones are dropped.
+    pub async fn pause(&self, id: &str) -> Result<(), ConnectionError> {
+        self.request(id, |reply| IoEvent::Pause { reply }).await?
+    }
+
+    /// Undo [`pause`](Self::pause): publish the held data as one chunk and
+    /// carry on. Returns how many bytes were dropped because the pause
+    /// buffer was full. Resuming a connection that is not paused does
+    /// nothing.
+    pub async fn resume(&self, id: &str) -> Result<u64, ConnectionError> {
+        self.request(id, |reply| IoEvent::Resume { reply }).await?
+    }
+
+    /// Resize the PTY of a connection to `cols` x `rows`.
+    ///
+    /// Connections without a PTY accept the request and ignore it.
+    pub async fn resize(&self, id: &str, cols: u16, rows: u16) -> Result<(), ConnectionError> {
+        self.request(id, |reply| IoEvent::Resize { cols, rows, reply })
+            .await??;
+
+        let mut map = self.inner.lock().await;
+        if let Some(size) = map.get_mut(id).and_then(|h| h.pty_size.as_mut()) {
+            *size = (cols, rows);
+        }
+        drop(map);
+        let _ = self.events_tx.send(ConnectionEvent {
+            id: id.to_string(),
+            kind: ConnectionEventKind::Resized { cols, rows },
+        });
+        Ok(())
+    }
+
+    /// Drive the DTR line of connection `id` high (`true`) or low, e.g. to
+    /// reset a board. Takes effect after the writes already queued, and
+    /// fails for transports without modem-control lines.
+    pub async fn set_dtr(&self, id: &str, level: bool) -> Result<(), ConnectionError> {
+        self.line_control(id, LineControl::Dtr(level)).await
+    }
+
+    /// Drive the RTS line of connection `id`; see [`set_dtr`](Self::set_dtr).
+    pub async fn set_rts(&self, id: &str, level: bool) -> Result<(), ConnectionError> {
+        self.line_control(id, LineControl::Rts(level)).await
+    }
+
+    /// Hold the line of connection `id` in the break condition for
+    /// `duration`, as some bootloaders and consoles expect. Queued writes go
+    /// out first; later ones wait until the break is over.
+    pub async fn send_break(&self, id: &str, duration: Duration) -> Result<(), ConnectionError> {
+        self.line_control(id, LineControl::Break(duration)).await
+    }
+
+    /// The CTS, DSR, DCD and RI lines of connection `id`, read by the task
+    /// that owns the transport. Cheap enough to poll for status LEDs; fails
+    /// for transports without modem-control lines.
+    pub async fn modem_status(&self, id: &str) -> Result<ModemStatus, ConnectionError> {
+        self.request(id, |reply| IoEvent::ModemStatus { reply })
+            .await?
+    }
+
+    async fn line_control(&self, id: &str, control: LineControl) -> Result<(), ConnectionError> {
+        self.request(id, move |reply| IoEvent::Line { control, reply })
+            .await?
+    }
+
+    /// Forward local TCP connections through connection `id`, like
+    /// `ssh -L`; see [`LocalForward`]. Returns the address listened on, which
+    /// tells the port picked for a `bind_port` of `0`. The forward lasts until
+    /// the connection is stopped. Fails for transports that cannot tunnel.
+    pub async fn local_forward(
+        &self,
+        id: &str,
+        forward: LocalForward,
+    ) -> Result<SocketAddr, ConnectionError> {
+        self.request(id, move |reply| IoEvent::LocalForward { forward, reply })
+            .await?
+    }
+
+    /// Start an [`SftpClient`] on connection `id`, on its own channel next to
+    /// the interactive shell. Fails for transports other than SSH and for
+    /// servers without the sftp subsystem.
+    #[cfg(feature = "ssh")]
+    pub async fn sftp(&self, id: &str) -> Result<SftpClient, ConnectionError> {
+        self.request(id, |reply| IoEvent::Sftp { reply }).await?
+    }
+
+    /// Send the event `make` builds around a reply channel to the task that
+    /// owns connection `id`, and wait for its answer.
+    async fn request<T>(
+        &self,
+        id: &str,
+        make: impl FnOnce(oneshot::Sender<T>) -> IoEvent,
+    ) -> Result<T, ConnectionError> {
+        let write_stop_tx = {
+            let map = self.inner.lock().await;
+            map.get(id)
+                .map(|h| h.write_stop_tx.clone())
+                .ok_or_else(|| ConnectionError::Other(format!("No connection with id '{id}'")))?
+        };
+        let (reply, reply_rx) = oneshot::channel();
+        write_stop_tx
+            .send(make(reply))
+            .await
+            .map_err(|_| ConnectionError::Other("Channel closed".into()))?;
+        reply_rx
+            .await
+            .map_err(|_| ConnectionError::Other("Channel closed".into()))
+    }
+
+    /// Last PTY size set on a connection as `(cols, rows)`.
+    ///
+    /// Returns `None` for unknown ids and for connections without a PTY.
+    pub async fn pty_size(&self, id: &str) -> Option<(u16, u16)> {
+        let map = self.inner.lock().await;
+        map.get(id).and_then(|h| h.pty_size)
+    }
+
+    /// Bytes read and written so far by connection `id`, for traffic
+    /// displays. Sample it twice and use
+    /// [`ConnectionMetrics::rates_since`] for a throughput gauge. Returns
+    /// `None` for unknown ids.
+    pub async fn metrics(&self, id: &str) -> Option<ConnectionMetrics> {
+        let map = self.inner.lock().await;
+        map.get(id).map(|h| ConnectionMetrics {
+            bytes_in: h.traffic.read.load(Ordering::Relaxed),
+            bytes_out: h.traffic.written.load(Ordering::Relaxed),
+            connected_at: h.connected_at,
+            at: Instant::now(),
+        })
+    }
+
+    /// Current fill level of a connection's control and broadcast channels,
+    /// for diagnosing backpressure. The broadcast side is summed up as the
+    /// slowest subscriber's lag. Returns `None` for unknown ids.
+    pub async fn buffer_status(&self, id: &str) -> Option<BufferStatus> {
+        let map = self.inner.lock().await;
+        map.get(id).map(|h| BufferStatus {
+            control_depth: h.write_stop_tx.max_capacity() - h.write_stop_tx.capacity(),
+            control_capacity: h.write_stop_tx.max_capacity(),
+            max_subscriber_lag: h
+                .broadcast_tx
+                .read()
+                .unwrap()
+                .as_ref()
+                .map_or(0, |tx| tx.len()),
+            broadcast_capacity: BROADCAST_CAPACITY,
+            subscribers: h.subscriber_count(),
+        })
+    }
+
+    /// One-shot, human-readable snapshot of every connection for bug reports.
+    ///
+    /// Lists id, transport kind, I/O task state, uptime, subscriber count and
+    /// the number of control events (writes, resizes, stop) still queued.
+    pub async fn debug_dump(&self) -> String {
+        let mut rows: Vec<_> = {
+            let map = self.inner.lock().await;
+            map.iter()
+                .map(|(id, h)| {
+                    (
+                        id.clone(),
+                        h.kind,
+                        if h.io_task_handle.is_finished() {
+                            "finished"
+                        } else {
+                            "running"
+                        },
+                        h.connected_at.elapsed(),
+                        h.subscriber_count(),
+                        h.write_stop_tx.max_capacity() - h.write_stop_tx.capacity(),
+                    )
+                })
+                .collect()
+        };
+        rows.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut out = format!("ConnectionManager: {} connection(s)\n", rows.len());
+        for (id, kind, state, uptime, subscribers, pending) in rows {
+            let _ = writeln!(
+                out,
+                "  {id}: kind={kind} state={state} uptime={:.1}s subscribers={subscribers} pending_control={pending}",
+                uptime.as_secs_f64()
+            );
+        }
+        out
+    }
+
+    /// Log [`debug_dump`](Self::debug_dump) every time the process receives
+    /// SIGUSR1, for as long as the runtime runs.
+    #[cfg(unix)]
+    pub fn dump_on_sigusr1(&self) {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let Ok(mut sigusr1) = signal(SignalKind::user_defined1()) else {
+                return;
+            };
+            while sigusr1.recv().await.is_some() {
+                info!("{}", manager.debug_dump().await);
+            }
+        });
+    }
+
+    /// Stop every connection, e.g. on application exit, and return how each
+    /// stop went, sorted by id.
+    ///
+    /// All I/O tasks are told to stop before any is waited for, so slow
+    /// transports wind down in parallel. When this returns the map is empty
+    /// and every transport is disconnected. An error means the connection's
+    /// I/O task panicked. Scrollback is kept as for
+    /// [`stop_connection`](Self::stop_connection).
+    pub async fn shutdown_all(&self) -> Vec<(String, Result<(), ConnectionError>)> {
+        let handles: Vec<_> = self.inner.lock().await.drain().collect();
+        for (_, handle) in &handles {
+            let _ = handle.write_stop_tx.send(IoEvent::Stop).await;
+        }
+        let mut results = Vec::with_capacity(handles.len());
+        for (id, handle) in handles {
+            self.keep_scrollback(&id, &handle);
+            let result = handle
+                .io_task_handle
+                .await
+                .map_err(|e| ConnectionError::Other(format!("I/O task of '{id}' failed: {e}")));
+            results.push((id, result));
+        }
+        results.sort_by(|a, b| a.0.cmp(&b.0));
+        results
+    }
+
+    /// Set the scrollback of the stopped connection `handle` aside for the
+    /// next connection under `id`, if it asked for that.
+    fn keep_scrollback(&self, id: &str, handle: &ConnectionIOHandle) {
+        if handle.keep_scrollback {
+            self.stopped_scrollback
+                .lock()
+                .unwrap()
+                .insert(id.to_owned(), handle.scrollback.clone());
+        }
+    }
+
+    /// Drop the scrollback kept for the stopped connection `id`, so the next
+    /// connection under that id starts afresh. Returns whether there was one.
+    pub fn forget_scrollback(&self, id: &str) -> bool {
+        self.stopped_scrollback.lock().unwrap().take(id).is_some()
+    }
+
+    /// Stop a connection. Its scrollback is kept in case the id is added
+    /// again if [`ConnectionOptions::keep_scrollback`] is set, see
+    /// [`scrollback`](Self::scrollback).
     pub async fn stop_connection(&self, id: &str) -> Result<(), ConnectionError> {
         let mut map = self.inner.lock().await;
         if let Some(handle) = map.remove(id) {
             let _ = handle.write_stop_tx.send(IoEvent::Stop).await;
+            self.keep_scrollback(id, &handle);
             let _ = handle.io_task_handle.await;
             Ok(())
         } else {
@@ -165,3 +1652,79 @@ impl ConnectionManager {
         }
     }
 }
+
+/// Send `chunk` to the subscribers of a connection whose I/O task is running.
+fn publish(broadcast_tx: &RwLock<Option<broadcast::Sender<Vec<u8>>>>, chunk: Vec<u8>) {
+    if let Some(tx) = broadcast_tx.read().unwrap().as_ref() {
+        let _ = tx.send(chunk);
+    }
+}
+
+/// Write `data` through the outgoing transforms configured in `options`.
+///
+/// Reports the length of the caller's `data`, not of the translated bytes.
+async fn write_transformed(
+    conn: &mut Box<dyn Connection + Send + Unpin>,
+    data: &[u8],
+    options: &ConnectionOptions,
+) -> Result<usize, ConnectionError> {
+    if !options.crlf {
+        return write_with_char_delay(conn, data, options.char_delay).await;
+    }
+    let mut translated = Vec::with_capacity(data.len());
+    for &byte in data {
+        if byte == b'\n' {
+            translated.push(b'\r');
+        }
+        translated.push(byte);
+    }
+    write_with_char_delay(conn, &translated, options.char_delay).await?;
+    Ok(data.len())
+}
+
+/// Write all of `data`, retrying after short writes.
+///
+/// `Connection::write` may accept only part of the buffer; the rest is
+/// offered again until everything went out. A write of zero bytes is
+/// reported as an error instead of looping forever.
+async fn write_all(
+    conn: &mut (dyn Connection + Send + Unpin),
+    mut data: &[u8],
+) -> Result<usize, ConnectionError> {
+    let total = data.len();
+    while !data.is_empty() {
+        let n = conn.write(data).await?;
+        if n == 0 {
+            return Err(ConnectionError::Other(format!(
+                "Write stalled after {} of {total} bytes",
+                total - data.len()
+            )));
+        }
+        if n < data.len() {
+            debug!(
+                "Short write: {n} of {} bytes, retrying the rest",
+                data.len()
+            );
+        }
+        data = &data[n..];
+    }
+    Ok(total)
+}
+
+/// Write `data`, sleeping `char_delay` between consecutive bytes.
+async fn write_with_char_delay(
+    conn: &mut Box<dyn Connection + Send + Unpin>,
+    data: &[u8],
+    char_delay: Duration,
+) -> Result<usize, ConnectionError> {
+    if char_delay.is_zero() {
+        return write_all(conn.as_mut(), data).await;
+    }
+    for (i, byte) in data.iter().enumerate() {
+        if i > 0 {
+            tokio::time::sleep(char_delay).await;
+        }
+        write_all(conn.as_mut(), std::slice::from_ref(byte)).await?;
+    }
+    Ok(data.len())
+}
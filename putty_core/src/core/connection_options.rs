@@ -0,0 +1,268 @@
+use crate::connections::connection::ProgressSender;
+use crate::core::connect_retry::{ConnectRetry, ReconnectPolicy};
+use log::LevelFilter;
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+/// Bytes the I/O task reads at once unless
+/// [`ConnectionOptions::read_buffer_size`] says otherwise.
+pub const DEFAULT_READ_BUFFER_SIZE: usize = 256;
+
+/// Bytes a paused connection holds back unless
+/// [`ConnectionOptions::pause_buffer_bytes`] says otherwise.
+pub const DEFAULT_PAUSE_BUFFER_BYTES: usize = 1024 * 1024;
+
+/// Most scrollbacks of stopped connections a manager keeps, see
+/// [`ConnectionOptions::keep_scrollback`]; the oldest go first.
+pub const MAX_KEPT_SCROLLBACKS: usize = 32;
+
+/// Most bytes the kept scrollbacks of stopped connections may hold together,
+/// see [`ConnectionOptions::keep_scrollback`]; the oldest go first.
+pub const MAX_KEPT_SCROLLBACK_BYTES: usize = 16 * 1024 * 1024;
+
+/// What the I/O task does when a read reports end of stream (`Ok(0)`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EofPolicy {
+    /// Treat EOF as transient and keep reading. Right for interactive shells
+    /// and serial ports, where a zero-length read does not end the session.
+    #[default]
+    KeepOpen,
+    /// End the session on EOF. Right for exec/command connections, whose
+    /// output is complete once the remote side closes it.
+    CloseOnEof,
+}
+
+/// What the I/O task does to keep an idle serial device happy, see
+/// [`LineKeepAlive`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeepAliveAction {
+    /// Raise DTR and RTS again, for devices that reset when DTR drops.
+    #[default]
+    AssertLines,
+    /// Send this byte, for devices that want to hear from the host. It is
+    /// written raw, without the outgoing transforms.
+    SendByte(u8),
+}
+
+/// Perform `action` whenever the connection has seen no traffic in either
+/// direction for `interval`, and every `interval` after that while it stays
+/// idle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineKeepAlive {
+    pub interval: Duration,
+    pub action: KeepAliveAction,
+}
+
+/// Per-connection settings consumed by the I/O task.
+///
+/// `ConnectionOptions::default()` gives the same behaviour as a plain
+/// `ConnectionManager::add_connection`. The outgoing transforms (`crlf`,
+/// `char_delay`) apply to `write_bytes` and friends but never to
+/// `ConnectionManager::write_raw`.
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionOptions {
+    /// Pause inserted between consecutive transmitted bytes, for slow terminals
+    /// that lose characters sent back-to-back. Zero disables it.
+    pub char_delay: Duration,
+    /// Translate every outgoing `\n` into `\r\n`, for devices that expect
+    /// CRLF line endings.
+    pub crlf: bool,
+    /// Retry a failing `connect` with backoff and jitter. `None` fails on the
+    /// first error.
+    pub connect_retry: Option<ConnectRetry>,
+    /// Bring the transport back when a read fails or the session is closed
+    /// from the other side. `None` ends the connection instead.
+    pub reconnect: Option<ReconnectPolicy>,
+    /// Whether a remote EOF ends the session.
+    pub eof_policy: EofPolicy,
+    /// Receives the transport's connect milestones.
+    pub progress: Option<ProgressSender>,
+    /// Traffic is expected at least this often. If nothing is read for this
+    /// long, a `Stalled` event is published. Leave `None` for connections
+    /// that may legitimately sit idle.
+    pub read_watchdog: Option<Duration>,
+    /// Hard limit on how long the session lives, counted from connect and
+    /// regardless of traffic. Once it is reached the connection is stopped
+    /// and a `Disconnected { reason: MaxDuration }` event is published.
+    pub max_session: Option<Duration>,
+    /// Stop the connection once nothing was read or written for this long,
+    /// and publish a `Disconnected { reason: IdleTimeout }` event. Keep-alives
+    /// do not count as traffic. `None` keeps idle connections open.
+    pub idle_timeout: Option<Duration>,
+    /// Keep the last this many received bytes for
+    /// [`ConnectionManager::scrollback`](crate::ConnectionManager::scrollback).
+    /// Zero keeps none; adjustable later with
+    /// [`ConnectionManager::set_scrollback_limit`](crate::ConnectionManager::set_scrollback_limit).
+    pub scrollback_bytes: usize,
+    /// Keep the scrollback once the connection is stopped, so that adding
+    /// the same id again continues it after a `--- reconnected ---` marker.
+    /// Kept scrollbacks are bounded by [`MAX_KEPT_SCROLLBACKS`] and
+    /// [`MAX_KEPT_SCROLLBACK_BYTES`] and can be dropped early with
+    /// [`ConnectionManager::forget_scrollback`](crate::ConnectionManager::forget_scrollback).
+    /// Off by default: a new connection under a reused id starts afresh.
+    pub keep_scrollback: bool,
+    /// Published to subscribers as the first data of the session, e.g.
+    /// `=== connected to rack1 ===\r\n`, so a log or terminal shows where
+    /// the connection starts. Never sent to the device.
+    pub banner: Option<Vec<u8>>,
+    /// Keep an idle serial device alive by asserting its control lines or
+    /// sending a no-op byte. `None` leaves an idle line alone.
+    pub line_keepalive: Option<LineKeepAlive>,
+    /// Publish a `PossibleBaudMismatch` event if the first bytes read look
+    /// like framing noise rather than text. Advisory, meant for serial ports
+    /// and off by default since binary protocols would trip it.
+    pub detect_baud_mismatch: bool,
+    /// Most verbose level the connection's I/O task logs at, see
+    /// [`ConnectionManager::set_log_level`](crate::ConnectionManager::set_log_level).
+    /// `None` leaves filtering to the logger.
+    pub log_level: Option<LevelFilter>,
+    /// Writes that may be queued or in progress at once. Further writes fail
+    /// immediately with [`ConnectionError::Busy`](crate::connections::errors::ConnectionError::Busy)
+    /// instead of waiting for room. `None` lets callers wait.
+    pub max_in_flight_writes: Option<usize>,
+    /// Treat the stream as data rather than text, for firmware images and
+    /// sensor streams: `crlf`, `banner` and `detect_baud_mismatch` are
+    /// ignored, and no reconnect marker is added to the scrollback. Bytes
+    /// pass through untouched in both directions.
+    pub binary: bool,
+    /// Most bytes a single read may return, and so the largest chunk
+    /// subscribers get. Larger buffers mean fewer reads and broadcasts on
+    /// busy links. `None` uses [`DEFAULT_READ_BUFFER_SIZE`].
+    pub read_buffer_size: Option<NonZeroUsize>,
+    /// Received bytes held back while the connection is paused, see
+    /// [`ConnectionManager::pause`](crate::ConnectionManager::pause). Bytes
+    /// beyond this are dropped. `None` uses [`DEFAULT_PAUSE_BUFFER_BYTES`].
+    pub pause_buffer_bytes: Option<usize>,
+}
+
+impl ConnectionOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert `delay` between each transmitted byte.
+    pub fn with_char_delay(mut self, delay: Duration) -> Self {
+        self.char_delay = delay;
+        self
+    }
+
+    /// Translate outgoing `\n` into `\r\n`.
+    pub fn with_crlf(mut self, crlf: bool) -> Self {
+        self.crlf = crlf;
+        self
+    }
+
+    /// Decide whether a remote EOF ends the session.
+    pub fn with_eof_policy(mut self, policy: EofPolicy) -> Self {
+        self.eof_policy = policy;
+        self
+    }
+
+    /// Send connect milestones (TCP up, handshake, auth, shell) to `progress`.
+    pub fn with_progress(mut self, progress: ProgressSender) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Publish a `Stalled` event when nothing is read for `window`.
+    pub fn with_read_watchdog(mut self, window: Duration) -> Self {
+        self.read_watchdog = Some(window);
+        self
+    }
+
+    /// Stop the connection once it has been up for `limit`.
+    pub fn with_max_session(mut self, limit: Duration) -> Self {
+        self.max_session = Some(limit);
+        self
+    }
+
+    /// Stop the connection after `timeout` without traffic in either
+    /// direction. Zero disables it.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = (!timeout.is_zero()).then_some(timeout);
+        self
+    }
+
+    /// Keep the last `bytes` received bytes as scrollback.
+    pub fn with_scrollback(mut self, bytes: usize) -> Self {
+        self.scrollback_bytes = bytes;
+        self
+    }
+
+    /// Keep the scrollback for the next connection under the same id.
+    pub fn with_keep_scrollback(mut self, keep: bool) -> Self {
+        self.keep_scrollback = keep;
+        self
+    }
+
+    /// Show `banner` to subscribers once the connection is up.
+    pub fn with_banner(mut self, banner: impl Into<Vec<u8>>) -> Self {
+        self.banner = Some(banner.into());
+        self
+    }
+
+    /// Perform `action` after every `interval` without traffic.
+    pub fn with_line_keepalive(mut self, interval: Duration, action: KeepAliveAction) -> Self {
+        self.line_keepalive = Some(LineKeepAlive { interval, action });
+        self
+    }
+
+    /// Warn via a `PossibleBaudMismatch` event when the first bytes look like
+    /// they were received at the wrong baud rate.
+    pub fn with_baud_mismatch_detection(mut self, detect: bool) -> Self {
+        self.detect_baud_mismatch = detect;
+        self
+    }
+
+    /// Start the connection at log `level` instead of the logger's default.
+    pub fn with_log_level(mut self, level: LevelFilter) -> Self {
+        self.log_level = Some(level);
+        self
+    }
+
+    /// Reject writes with `Busy` while `limit` writes are outstanding.
+    pub fn with_max_in_flight_writes(mut self, limit: usize) -> Self {
+        self.max_in_flight_writes = Some(limit);
+        self
+    }
+
+    /// Pass bytes through untouched, whatever the text options say.
+    pub fn with_binary(mut self, binary: bool) -> Self {
+        self.binary = binary;
+        self
+    }
+
+    /// Read up to `size` bytes at once.
+    pub fn with_read_buffer_size(mut self, size: NonZeroUsize) -> Self {
+        self.read_buffer_size = Some(size);
+        self
+    }
+
+    /// Hold back at most `bytes` received bytes while paused.
+    pub fn with_pause_buffer(mut self, bytes: usize) -> Self {
+        self.pause_buffer_bytes = Some(bytes);
+        self
+    }
+
+    /// `self` with the text options that `binary` overrides switched off.
+    pub(crate) fn resolved(mut self) -> Self {
+        if self.binary {
+            self.crlf = false;
+            self.banner = None;
+            self.detect_baud_mismatch = false;
+        }
+        self
+    }
+
+    /// Retry the connect phase according to `retry`.
+    pub fn with_connect_retry(mut self, retry: ConnectRetry) -> Self {
+        self.connect_retry = Some(retry);
+        self
+    }
+
+    /// Reconnect a lost transport according to `policy`.
+    pub fn with_reconnect(mut self, policy: ReconnectPolicy) -> Self {
+        self.reconnect = Some(policy);
+        self
+    }
+}
@@ -0,0 +1,33 @@
+use std::fmt;
+
+/// Where a connection is in its life, see [`ConnectionManager::status`].
+///
+/// [`ConnectionManager::status`]: crate::ConnectionManager::status
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// `add_connection` is still running the transport's `connect`.
+    Connecting,
+    /// The I/O task is running.
+    Connected,
+    /// The transport was lost and the reconnect policy is trying to bring
+    /// it back.
+    Reconnecting,
+    /// The session ended normally: EOF, the remote side closing it, or the
+    /// manager ending it. The id stays registered until it is stopped.
+    Disconnected,
+    /// The session ended on a read error, or the reconnect policy gave up,
+    /// with a description.
+    Failed(String),
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connecting => f.write_str("connecting"),
+            Self::Connected => f.write_str("connected"),
+            Self::Reconnecting => f.write_str("reconnecting"),
+            Self::Disconnected => f.write_str("disconnected"),
+            Self::Failed(reason) => write!(f, "failed: {reason}"),
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use crate::connections::connection::NegotiatedParams;
+use std::time::Duration;
+
+/// Lifecycle notification published by the `ConnectionManager`.
+///
+/// Obtain a receiver with `ConnectionManager::events`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConnectionEvent {
+    /// Id of the connection the event belongs to.
+    pub id: String,
+    pub kind: ConnectionEventKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionEventKind {
+    /// The connection was connected and registered under `id`, with the
+    /// parameters the transport actually negotiated.
+    Opened(NegotiatedParams),
+    /// The read watchdog saw no data for `silent_for`; the transport may be
+    /// wedged. Published once per silence, re-armed by the next read.
+    Stalled { silent_for: Duration },
+    /// The first bytes read were mostly unprintable (only `printable_percent`
+    /// percent text), as happens at the wrong baud rate. Published at most
+    /// once per connection, and only when `ConnectionOptions` asks for it.
+    PossibleBaudMismatch { printable_percent: u8 },
+    /// The connection formerly known as `old_id` is now registered under the
+    /// event's `id`.
+    Renamed { old_id: String },
+    /// The PTY of the connection was resized to `cols` x `rows` through
+    /// `ConnectionManager::resize`.
+    Resized { cols: u16, rows: u16 },
+    /// The connection manager ended the session on its own, for `reason`.
+    /// Followed by `Closed`.
+    Disconnected { reason: DisconnectReason },
+    /// The remote side ended the session, with the exit status its shell
+    /// reported, if any. Followed by `Closed`.
+    RemoteClosed { exit_status: Option<u32> },
+    /// Reading from the transport failed with this error, which lost it.
+    /// Followed by `Reconnecting` or `Closed`.
+    ReadError(String),
+    /// The transport was lost and `ConnectionOptions::reconnect` is bringing
+    /// it back. Writes fail until `Reconnected`; if the policy gives up,
+    /// `Closed` follows instead.
+    Reconnecting,
+    /// The transport is connected again, with the parameters negotiated
+    /// this time. Subscriptions carry on with the new session's data.
+    Reconnected(NegotiatedParams),
+    /// The I/O task of the connection ended. Subscriptions to its data close
+    /// once they have delivered what was read.
+    Closed,
+}
+
+/// Why the connection manager ended a session.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// `ConnectionOptions::max_session` elapsed.
+    MaxDuration,
+    /// Nothing was read or written for `ConnectionOptions::idle_timeout`.
+    IdleTimeout,
+}
@@ -1 +1,10 @@
+pub mod baud_check;
+pub mod connect_retry;
+pub mod connection_log;
 pub mod connection_manager;
+pub mod connection_options;
+pub mod connection_state;
+pub mod events;
+pub mod scrollback;
+pub mod session_logger;
+pub mod subscription;
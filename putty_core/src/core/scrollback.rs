@@ -0,0 +1,122 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Written between the output of two sessions that share a scrollback.
+const RECONNECT_MARKER: &[u8] = b"\r\n--- reconnected ---\r\n";
+
+/// Size and history of a connection's scrollback, see
+/// [`ConnectionManager::scrollback_stats`](crate::ConnectionManager::scrollback_stats).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScrollbackStats {
+    /// Bytes currently held, never more than `limit`.
+    pub size: usize,
+    pub limit: usize,
+    /// Bytes dropped from the front so far to stay within `limit`. Non-zero
+    /// means the scrollback no longer starts at the beginning of the session.
+    pub evicted: u64,
+}
+
+/// The most recent `limit` bytes a connection received, oldest first.
+#[derive(Debug, Default)]
+pub(crate) struct Scrollback {
+    bytes: VecDeque<u8>,
+    limit: usize,
+    evicted: u64,
+}
+
+impl Scrollback {
+    /// A scrollback of `limit` bytes. Zero keeps nothing.
+    pub(crate) fn new(limit: usize) -> Self {
+        Self {
+            bytes: VecDeque::new(),
+            limit,
+            evicted: 0,
+        }
+    }
+
+    pub(crate) fn push(&mut self, data: &[u8]) {
+        if self.limit == 0 {
+            return;
+        }
+        self.bytes.extend(data);
+        self.evict();
+    }
+
+    /// Continue this scrollback for a new session with limit `limit`,
+    /// marking where the new session starts.
+    pub(crate) fn reconnect(&mut self, limit: usize) {
+        self.set_limit(limit);
+        self.mark_reconnect();
+    }
+
+    /// Mark where the session continues after its transport was reconnected.
+    pub(crate) fn mark_reconnect(&mut self) {
+        self.push(RECONNECT_MARKER);
+    }
+
+    /// Change the limit; shrinking evicts the oldest bytes right away.
+    pub(crate) fn set_limit(&mut self, limit: usize) {
+        self.limit = limit;
+        self.evict();
+    }
+
+    pub(crate) fn contents(&self) -> Vec<u8> {
+        self.bytes.iter().copied().collect()
+    }
+
+    pub(crate) fn stats(&self) -> ScrollbackStats {
+        ScrollbackStats {
+            size: self.bytes.len(),
+            limit: self.limit,
+            evicted: self.evicted,
+        }
+    }
+
+    fn evict(&mut self) {
+        let excess = self.bytes.len().saturating_sub(self.limit);
+        self.bytes.drain(..excess);
+        self.evicted += excess as u64;
+    }
+}
+
+/// Scrollbacks of stopped connections that asked to keep theirs, by id,
+/// oldest first. Holds at most `max_count` of them and `max_bytes` in total,
+/// dropping the oldest to make room.
+#[derive(Debug)]
+pub(crate) struct KeptScrollbacks {
+    entries: VecDeque<(String, Arc<Mutex<Scrollback>>)>,
+    max_count: usize,
+    max_bytes: usize,
+}
+
+impl KeptScrollbacks {
+    pub(crate) fn new(max_count: usize, max_bytes: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            max_count,
+            max_bytes,
+        }
+    }
+
+    /// Keep `scrollback` for `id`, replacing what was kept for it before.
+    pub(crate) fn insert(&mut self, id: String, scrollback: Arc<Mutex<Scrollback>>) {
+        self.take(&id);
+        self.entries.push_back((id, scrollback));
+        while self.entries.len() > self.max_count || self.bytes() > self.max_bytes {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Remove and return what was kept for `id`.
+    pub(crate) fn take(&mut self, id: &str) -> Option<Arc<Mutex<Scrollback>>> {
+        let index = self.entries.iter().position(|(kept, _)| kept == id)?;
+        self.entries.remove(index).map(|(_, scrollback)| scrollback)
+    }
+
+    fn bytes(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|(_, scrollback)| scrollback.lock().unwrap().bytes.len())
+            .sum()
+    }
+}
@@ -0,0 +1,446 @@
+use crate::core::events::{ConnectionEvent, ConnectionEventKind};
+use crate::utils::ansi::AnsiStripper;
+use log::warn;
+use std::fmt::{self, Write as _};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::fs::{self, File, OpenOptions};
+use tokio::io::AsyncWriteExt;
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio::task::JoinHandle;
+
+/// When a [`SessionLogger`] starts a new file.
+///
+/// The current file is renamed to `<path>.1`, older ones shift up
+/// (`.1` → `.2`, ...), and only the newest `keep` rotated files are retained.
+#[derive(Debug, Clone, Default)]
+pub struct LogRotation {
+    /// Rotate before a write would push the file past this many bytes.
+    pub max_size: Option<u64>,
+    /// Rotate once the file has been written to for this long.
+    pub max_age: Option<Duration>,
+    /// Number of rotated files to keep next to the current one.
+    pub keep: usize,
+}
+
+impl LogRotation {
+    /// Rotate at `max_size` bytes, keeping `keep` old files.
+    pub fn by_size(max_size: u64, keep: usize) -> Self {
+        Self {
+            max_size: Some(max_size),
+            max_age: None,
+            keep,
+        }
+    }
+
+    /// Rotate every `max_age`, keeping `keep` old files.
+    pub fn by_age(max_age: Duration, keep: usize) -> Self {
+        Self {
+            max_size: None,
+            max_age: Some(max_age),
+            keep,
+        }
+    }
+}
+
+/// What a [`SessionLogger`] writes to its file.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// The bytes exactly as received, escape sequences included.
+    #[default]
+    Raw,
+    /// Text with ANSI escape sequences removed.
+    Plain,
+    /// Like `Plain`, with every line prefixed by the seconds since the log
+    /// was opened, e.g. `[   12.345] `.
+    Timestamped,
+    /// An [asciinema v2](https://docs.asciinema.org/manual/asciicast/v2/)
+    /// recording: a JSON header with the terminal size, then one
+    /// `[seconds, "o", text]` line per chunk and a `[seconds, "r", "COLSxROWS"]`
+    /// line per resize, timed from when the file was opened. Escape sequences
+    /// are kept so the recording replays faithfully. The header is only
+    /// written to an empty file, so point it at a new one.
+    Asciinema,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "raw" => Ok(Self::Raw),
+            "plain" => Ok(Self::Plain),
+            "timestamped" => Ok(Self::Timestamped),
+            "asciinema" => Ok(Self::Asciinema),
+            other => Err(format!(
+                "unknown log format {other:?} (expected raw, plain, timestamped or asciinema)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LogFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Raw => "raw",
+            Self::Plain => "plain",
+            Self::Timestamped => "timestamped",
+            Self::Asciinema => "asciinema",
+        })
+    }
+}
+
+/// Writes the output of a connection to a transcript file.
+///
+/// A logger is just another broadcast subscriber, so it never affects what
+/// the live terminal shows. Transforms such as ANSI stripping apply only to
+/// the file. Several loggers, each with its own format, can follow the same
+/// connection.
+pub struct SessionLogger {
+    path: PathBuf,
+    file: File,
+    format: LogFormat,
+    ansi_stripper: Option<AnsiStripper>,
+    /// The next byte starts a new line (and needs a timestamp).
+    at_line_start: bool,
+    created_at: Instant,
+    /// Columns and rows recorded in an asciinema header; follows resizes.
+    terminal_size: (u16, u16),
+    /// Events of the manager and the id of the connection whose resizes
+    /// [`spawn`](Self::spawn) records.
+    resizes: Option<(broadcast::Receiver<ConnectionEvent>, String)>,
+    /// Trailing bytes of an incomplete UTF-8 sequence, held back until the
+    /// next chunk because asciinema events must be valid text.
+    pending_utf8: Vec<u8>,
+    rotation: Option<LogRotation>,
+    /// Size of the current file.
+    written: u64,
+    /// When the current file was opened; asciinema events are timed from it.
+    opened_at: Instant,
+}
+
+impl SessionLogger {
+    /// Open (or create) `path` for appending.
+    pub async fn create(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = open_append(&path).await?;
+        let written = file.metadata().await?.len();
+        Ok(Self {
+            path,
+            file,
+            format: LogFormat::Raw,
+            ansi_stripper: None,
+            at_line_start: true,
+            created_at: Instant::now(),
+            terminal_size: (80, 24),
+            resizes: None,
+            pending_utf8: Vec::new(),
+            rotation: None,
+            written,
+            opened_at: Instant::now(),
+        })
+    }
+
+    /// Remove escape sequences (colors, cursor movement, ...) before writing,
+    /// so the transcript is plain text.
+    pub fn with_strip_ansi(self, strip: bool) -> Self {
+        self.with_format(if strip {
+            LogFormat::Plain
+        } else {
+            LogFormat::Raw
+        })
+    }
+
+    /// Choose what ends up in the file; see [`LogFormat`].
+    pub fn with_format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self.ansi_stripper =
+            matches!(format, LogFormat::Plain | LogFormat::Timestamped).then(AnsiStripper::new);
+        self
+    }
+
+    /// Terminal size written to the header of an asciinema recording,
+    /// typically the connection's current PTY size. Defaults to 80x24.
+    pub fn with_terminal_size(mut self, cols: u16, rows: u16) -> Self {
+        self.terminal_size = (cols, rows);
+        self
+    }
+
+    /// Let [`spawn`](Self::spawn) record the resizes of connection `id`
+    /// announced on `events`, from `ConnectionManager::events`.
+    pub fn with_resize_events(
+        mut self,
+        events: broadcast::Receiver<ConnectionEvent>,
+        id: impl Into<String>,
+    ) -> Self {
+        self.resizes = Some((events, id.into()));
+        self
+    }
+
+    /// Start a new file according to `rotation` instead of growing one file forever.
+    pub fn with_rotation(mut self, rotation: LogRotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    /// Append one chunk of connection output.
+    pub async fn write_chunk(&mut self, chunk: &[u8]) -> io::Result<()> {
+        let stripped;
+        let bytes = match self.ansi_stripper.as_mut() {
+            Some(stripper) => {
+                stripped = stripper.strip(chunk);
+                &stripped[..]
+            }
+            None => chunk,
+        };
+        let stamped;
+        let bytes = match self.format {
+            LogFormat::Timestamped => {
+                stamped = self.timestamp_lines(bytes);
+                &stamped[..]
+            }
+            LogFormat::Asciinema => {
+                let text = self.cast_text(bytes);
+                return self.write_cast_event("o", &text).await;
+            }
+            LogFormat::Raw | LogFormat::Plain => bytes,
+        };
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        if self.needs_rotation(bytes.len() as u64) {
+            self.rotate().await?;
+        }
+        self.file.write_all(bytes).await?;
+        self.written += bytes.len() as u64;
+        self.file.flush().await
+    }
+
+    /// Note that the terminal is now `cols` x `rows`: an `"r"` event in an
+    /// asciinema recording, and the size for the headers of later files.
+    /// Other formats have no place for it.
+    pub async fn write_resize(&mut self, cols: u16, rows: u16) -> io::Result<()> {
+        self.terminal_size = (cols, rows);
+        if self.format != LogFormat::Asciinema {
+            return Ok(());
+        }
+        self.write_cast_event("r", &format!("{cols}x{rows}")).await
+    }
+
+    /// Append one `[time, code, data]` line, starting the file with a header
+    /// if it is empty. Nothing is written for empty `data`.
+    async fn write_cast_event(&mut self, code: &str, data: &str) -> io::Result<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        let mut event = self.cast_event(code, data);
+        if self.needs_rotation(event.len() as u64) {
+            self.rotate().await?;
+            // The new file counts time from its own header.
+            event = self.cast_event(code, data);
+        }
+        if self.written == 0 {
+            // Every file, including each rotated one, starts with a header.
+            let header = self.cast_header();
+            self.file.write_all(header.as_bytes()).await?;
+            self.written += header.len() as u64;
+        }
+        self.file.write_all(event.as_bytes()).await?;
+        self.written += event.len() as u64;
+        self.file.flush().await
+    }
+
+    fn timestamp_lines(&mut self, bytes: &[u8]) -> Vec<u8> {
+        let elapsed = self.created_at.elapsed();
+        let stamp = format!("[{:>5}.{:03}] ", elapsed.as_secs(), elapsed.subsec_millis());
+        let mut out = Vec::with_capacity(bytes.len() + stamp.len());
+        for &byte in bytes {
+            if self.at_line_start {
+                out.extend_from_slice(stamp.as_bytes());
+                self.at_line_start = false;
+            }
+            out.push(byte);
+            self.at_line_start = byte == b'\n';
+        }
+        out
+    }
+
+    fn cast_header(&self) -> String {
+        let (width, height) = self.terminal_size;
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        format!(
+            "{{\"version\": 2, \"width\": {width}, \"height\": {height}, \"timestamp\": {timestamp}}}\n"
+        )
+    }
+
+    /// `bytes` as text for an `"o"` event, empty if they only started a
+    /// UTF-8 sequence. Invalid UTF-8 becomes U+FFFD.
+    fn cast_text(&mut self, bytes: &[u8]) -> String {
+        let mut data = std::mem::take(&mut self.pending_utf8);
+        data.extend_from_slice(bytes);
+        let mut text = String::with_capacity(data.len());
+        let mut rest = &data[..];
+        while !rest.is_empty() {
+            match std::str::from_utf8(rest) {
+                Ok(valid) => {
+                    text.push_str(valid);
+                    break;
+                }
+                Err(e) => {
+                    let (valid, after) = rest.split_at(e.valid_up_to());
+                    text.push_str(std::str::from_utf8(valid).expect("checked prefix"));
+                    match e.error_len() {
+                        Some(len) => {
+                            text.push(char::REPLACEMENT_CHARACTER);
+                            rest = &after[len..];
+                        }
+                        None => {
+                            self.pending_utf8 = after.to_vec();
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+        text
+    }
+
+    fn cast_event(&self, code: &str, data: &str) -> String {
+        let elapsed = self.opened_at.elapsed().as_secs_f64();
+        format!("[{elapsed:.6}, \"{code}\", {}]\n", json_string(data))
+    }
+
+    fn needs_rotation(&self, incoming: u64) -> bool {
+        let Some(rotation) = &self.rotation else {
+            return false;
+        };
+        // A chunk is never split, so an empty file takes it even if oversized.
+        let too_big = rotation
+            .max_size
+            .is_some_and(|max| self.written > 0 && self.written + incoming > max);
+        let too_old = rotation
+            .max_age
+            .is_some_and(|max| self.opened_at.elapsed() >= max);
+        too_big || too_old
+    }
+
+    /// Shift `<path>.N` files up by one, move the current file to `<path>.1`
+    /// and reopen an empty one.
+    async fn rotate(&mut self) -> io::Result<()> {
+        let keep = self.rotation.as_ref().map_or(0, |r| r.keep);
+        self.file.sync_all().await?;
+
+        if keep == 0 {
+            fs::remove_file(&self.path).await?;
+        } else {
+            remove_if_exists(&rotated_path(&self.path, keep)).await?;
+            for n in (1..keep).rev() {
+                let from = rotated_path(&self.path, n);
+                if fs::try_exists(&from).await? {
+                    fs::rename(&from, rotated_path(&self.path, n + 1)).await?;
+                }
+            }
+            fs::rename(&self.path, rotated_path(&self.path, 1)).await?;
+        }
+
+        self.file = open_append(&self.path).await?;
+        self.written = 0;
+        self.opened_at = Instant::now();
+        Ok(())
+    }
+
+    /// Log everything received on `rx` until the connection's broadcast channel
+    /// closes, and the resizes from [`with_resize_events`](Self::with_resize_events).
+    pub fn spawn(mut self, mut rx: broadcast::Receiver<Vec<u8>>) -> JoinHandle<io::Result<()>> {
+        let mut resizes = self.resizes.take();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    received = rx.recv() => match received {
+                        Ok(chunk) => self.write_chunk(&chunk).await?,
+                        Err(RecvError::Lagged(n)) => {
+                            warn!("Session log lagged; {n} chunks missing from the transcript");
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    event = next_event(&mut resizes) => match event {
+                        Ok(event) => {
+                            let Some((_, id)) = resizes.as_mut() else { continue };
+                            match event.kind {
+                                ConnectionEventKind::Resized { cols, rows } if event.id == *id => {
+                                    self.write_resize(cols, rows).await?;
+                                }
+                                ConnectionEventKind::Renamed { old_id } if old_id == *id => {
+                                    *id = event.id;
+                                }
+                                _ => {}
+                            }
+                        }
+                        Err(RecvError::Lagged(n)) => {
+                            warn!("Session log missed {n} connection events; resizes may be lost");
+                        }
+                        Err(RecvError::Closed) => resizes = None,
+                    },
+                }
+            }
+            self.file.sync_all().await
+        })
+    }
+}
+
+/// The next event on `resizes`; never resolves without one.
+async fn next_event(
+    resizes: &mut Option<(broadcast::Receiver<ConnectionEvent>, String)>,
+) -> Result<ConnectionEvent, RecvError> {
+    match resizes {
+        Some((events, _)) => events.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// `text` as a JSON string literal.
+fn json_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+async fn open_append(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await
+}
+
+/// `session.log` → `session.log.<n>`.
+fn rotated_path(path: &Path, n: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{n}"));
+    PathBuf::from(name)
+}
+
+async fn remove_if_exists(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path).await {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
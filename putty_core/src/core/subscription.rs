@@ -0,0 +1,129 @@
+use crate::core::connection_manager::WeakConnectionManager;
+use crate::core::events::{ConnectionEvent, ConnectionEventKind};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+/// Item yielded by a [`StableSubscription`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SubscriptionItem {
+    /// Bytes read from the connection.
+    Data(Vec<u8>),
+    /// The connection was re-registered under the same id; data after this
+    /// marker comes from the new transport.
+    Reconnected,
+    /// The subscriber fell so far behind that the `missed` oldest chunks were
+    /// dropped; data carries on with the oldest chunk still buffered.
+    Lagged { missed: u64 },
+}
+
+/// A subscription that carries on when the subscriber cannot keep up.
+///
+/// A plain `broadcast::Receiver` reports lag as `RecvError::Lagged`, which
+/// loops like `while let Ok(chunk) = rx.recv().await` take for the end of
+/// the stream. This yields [`SubscriptionItem::Lagged`] instead and keeps
+/// delivering, so a burst costs some output rather than all of it. Get one
+/// from `ConnectionManager::subscribe_lossy`.
+pub struct LossySubscription {
+    id: String,
+    data_rx: broadcast::Receiver<Vec<u8>>,
+}
+
+impl LossySubscription {
+    pub(crate) fn new(id: String, data_rx: broadcast::Receiver<Vec<u8>>) -> Self {
+        Self { id, data_rx }
+    }
+
+    /// The connection id this subscription follows.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Receive the next [`SubscriptionItem::Data`] or
+    /// [`SubscriptionItem::Lagged`]. Returns `None` once the connection's
+    /// I/O task has ended and everything it read was delivered.
+    pub async fn recv(&mut self) -> Option<SubscriptionItem> {
+        match self.data_rx.recv().await {
+            Ok(chunk) => Some(SubscriptionItem::Data(chunk)),
+            Err(RecvError::Lagged(missed)) => Some(SubscriptionItem::Lagged { missed }),
+            Err(RecvError::Closed) => None,
+        }
+    }
+}
+
+/// A subscription to a connection id that survives reconnects.
+///
+/// A plain `broadcast::Receiver` from `ConnectionManager::subscribe` is tied to
+/// one I/O task and closes once that task ends. When the same id is added
+/// again, a `StableSubscription` re-subscribes to the new broadcast channel,
+/// yields [`SubscriptionItem::Reconnected`] and keeps delivering data. Lag is
+/// reported as [`SubscriptionItem::Lagged`], like a [`LossySubscription`].
+///
+/// The subscription does not keep the `ConnectionManager` alive.
+pub struct StableSubscription {
+    manager: WeakConnectionManager,
+    id: String,
+    data_rx: broadcast::Receiver<Vec<u8>>,
+    events_rx: broadcast::Receiver<ConnectionEvent>,
+}
+
+impl StableSubscription {
+    pub(crate) fn new(
+        manager: WeakConnectionManager,
+        id: String,
+        data_rx: broadcast::Receiver<Vec<u8>>,
+        events_rx: broadcast::Receiver<ConnectionEvent>,
+    ) -> Self {
+        Self {
+            manager,
+            id,
+            data_rx,
+            events_rx,
+        }
+    }
+
+    /// The connection id this subscription follows.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Receive the next item.
+    ///
+    /// While the connection is gone this waits until the id is added again.
+    /// Returns `None` once the connection has ended and every clone of the
+    /// `ConnectionManager` has been dropped.
+    pub async fn recv(&mut self) -> Option<SubscriptionItem> {
+        loop {
+            match self.data_rx.recv().await {
+                Ok(chunk) => return Some(SubscriptionItem::Data(chunk)),
+                Err(RecvError::Lagged(missed)) => {
+                    return Some(SubscriptionItem::Lagged { missed });
+                }
+                Err(RecvError::Closed) => {
+                    self.wait_until_reopened().await?;
+                    if let Some(data_rx) = self.manager.subscribe(&self.id).await {
+                        self.data_rx = data_rx;
+                        return Some(SubscriptionItem::Reconnected);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn wait_until_reopened(&mut self) -> Option<()> {
+        loop {
+            let event = tokio::select! {
+                event = self.events_rx.recv() => event,
+                () = self.manager.dropped() => return None,
+            };
+            match event {
+                Ok(event)
+                    if event.id == self.id
+                        && matches!(event.kind, ConnectionEventKind::Opened(_)) =>
+                {
+                    return Some(());
+                }
+                Ok(_) | Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
@@ -1,5 +1,17 @@
 pub mod connections;
 pub mod core;
+pub mod utils;
 
 // re‑export ergonomic entry point
-pub use core::connection_manager::ConnectionManager;
+pub use core::connect_retry::{ConnectRetry, ReconnectPolicy};
+pub use core::connection_log::CONNECTION_LOG_TARGET;
+pub use core::connection_manager::{BufferStatus, ConnectionManager, ConnectionMetrics};
+pub use core::connection_options::{
+    ConnectionOptions, EofPolicy, KeepAliveAction, LineKeepAlive, DEFAULT_PAUSE_BUFFER_BYTES,
+    DEFAULT_READ_BUFFER_SIZE, MAX_KEPT_SCROLLBACKS, MAX_KEPT_SCROLLBACK_BYTES,
+};
+pub use core::connection_state::ConnectionState;
+pub use core::events::{ConnectionEvent, ConnectionEventKind, DisconnectReason};
+pub use core::scrollback::ScrollbackStats;
+pub use core::session_logger::{LogFormat, LogRotation, SessionLogger};
+pub use core::subscription::{LossySubscription, StableSubscription, SubscriptionItem};
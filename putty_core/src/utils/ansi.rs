@@ -0,0 +1,60 @@
+//! Removal of terminal escape sequences from a byte stream.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum State {
+    #[default]
+    Ground,
+    /// After ESC, possibly inside intermediate bytes (`ESC ( B`).
+    Escape,
+    /// Inside `ESC [ ... final`.
+    Csi,
+    /// Inside a string sequence (`ESC ] ...`, `ESC P ...`) terminated by BEL or ST.
+    String,
+    /// ESC seen inside a string sequence; a following `\` ends it.
+    StringEscape,
+}
+
+/// Stateful filter that strips CSI/SGR, OSC and other escape sequences,
+/// leaving plain text.
+///
+/// The state survives between calls to [`strip`](Self::strip), so a sequence
+/// split across two chunks is still removed completely.
+#[derive(Debug, Clone, Default)]
+pub struct AnsiStripper {
+    state: State,
+}
+
+impl AnsiStripper {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Return `chunk` without escape sequences.
+    pub fn strip(&mut self, chunk: &[u8]) -> Vec<u8> {
+        const ESC: u8 = 0x1b;
+        const BEL: u8 = 0x07;
+
+        let mut out = Vec::with_capacity(chunk.len());
+        for &byte in chunk {
+            self.state = match (self.state, byte) {
+                (State::Ground, ESC) => State::Escape,
+                (State::Ground, _) => {
+                    out.push(byte);
+                    State::Ground
+                }
+                (State::Escape, b'[') => State::Csi,
+                (State::Escape, b']' | b'P' | b'X' | b'^' | b'_') => State::String,
+                (State::Escape, 0x20..=0x2f) => State::Escape,
+                (State::Escape, _) => State::Ground,
+                (State::Csi, 0x40..=0x7e) => State::Ground,
+                (State::Csi, _) => State::Csi,
+                (State::String, BEL) => State::Ground,
+                (State::String, ESC) => State::StringEscape,
+                (State::String, _) => State::String,
+                (State::StringEscape, b'\\') => State::Ground,
+                (State::StringEscape, _) => State::String,
+            };
+        }
+        out
+    }
+}
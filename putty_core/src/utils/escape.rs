@@ -0,0 +1,35 @@
+use crate::connections::errors::ConnectionError;
+
+/// Resolve `\r`, `\n`, `\t`, `\0`, `\\` and `\xNN` escapes.
+pub fn unescape(text: &str) -> Result<Vec<u8>, ConnectionError> {
+    let invalid = |what: &str| ConnectionError::Other(format!("Invalid escape {what} in {text:?}"));
+    let mut out = Vec::with_capacity(text.len());
+    let mut bytes = text.bytes();
+    while let Some(byte) = bytes.next() {
+        if byte != b'\\' {
+            out.push(byte);
+            continue;
+        }
+        match bytes.next() {
+            Some(b'r') => out.push(b'\r'),
+            Some(b'n') => out.push(b'\n'),
+            Some(b't') => out.push(b'\t'),
+            Some(b'0') => out.push(0),
+            Some(b'\\') => out.push(b'\\'),
+            Some(b'x') => {
+                let hex = [bytes.next(), bytes.next()];
+                let [Some(hi), Some(lo)] = hex else {
+                    return Err(invalid("\\x"));
+                };
+                let digits = [hi, lo];
+                let digits = std::str::from_utf8(&digits).map_err(|_| invalid("\\x"))?;
+                out.push(
+                    u8::from_str_radix(digits, 16).map_err(|_| invalid(&format!("\\x{digits}")))?,
+                );
+            }
+            Some(other) => return Err(invalid(&format!("\\{}", other as char))),
+            None => return Err(invalid("at end")),
+        }
+    }
+    Ok(out)
+}
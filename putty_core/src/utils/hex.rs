@@ -0,0 +1,50 @@
+use crate::connections::errors::ConnectionError;
+use std::fmt::Write as _;
+
+/// Parse hex bytes such as `AA BB 01 FF`, `aabb01ff`, `AA,BB,01,FF` or
+/// `0xAA 0xBB`. Whitespace and commas only separate bytes; each byte needs
+/// exactly two digits, so `A 1` is rejected rather than guessed at.
+pub fn parse_hex(text: &str) -> Result<Vec<u8>, ConnectionError> {
+    let invalid = |why: String| ConnectionError::Other(format!("Invalid hex {text:?}: {why}"));
+    let mut out = Vec::new();
+    for token in text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+    {
+        let digits = token
+            .strip_prefix("0x")
+            .or_else(|| token.strip_prefix("0X"))
+            .unwrap_or(token);
+        if let Some(bad) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+            return Err(invalid(format!("{bad:?} is not a hex digit")));
+        }
+        match digits.len() {
+            0 => return Err(invalid(format!("{token:?} has no digits"))),
+            n if n % 2 == 1 => {
+                return Err(invalid(format!("{token:?} has an odd number of digits")))
+            }
+            _ => {
+                for pair in digits.as_bytes().chunks(2) {
+                    let pair = std::str::from_utf8(pair).expect("ASCII hex digits");
+                    out.push(u8::from_str_radix(pair, 16).expect("checked above"));
+                }
+            }
+        }
+    }
+    if out.is_empty() {
+        return Err(invalid("no bytes given".into()));
+    }
+    Ok(out)
+}
+
+/// Format bytes as upper-case hex separated by spaces: `AA BB 01`.
+pub fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() * 3);
+    for (i, byte) in bytes.iter().enumerate() {
+        if i > 0 {
+            out.push(' ');
+        }
+        let _ = write!(out, "{byte:02X}");
+    }
+    out
+}
@@ -0,0 +1,55 @@
+//! Reassembly of lines that are redrawn in place with carriage returns.
+
+/// Stateful decoder that applies `\r` overwrites to a current-line buffer
+/// and emits each line once it is terminated by `\n`.
+///
+/// Progress bars print `10%\r50%\r100%\n`; appended naively that is noise,
+/// decoded it is the single line `100%`. Like a terminal, `\r` only moves
+/// back to the start of the line, so a shorter redraw leaves the tail of
+/// the previous one in place. Other control bytes, including escape
+/// sequences, pass through untouched; combine with
+/// [`AnsiStripper`](crate::utils::ansi::AnsiStripper) when needed.
+#[derive(Debug, Clone, Default)]
+pub struct LineAssembler {
+    line: Vec<u8>,
+    cursor: usize,
+}
+
+impl LineAssembler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed `chunk` and return the lines it completed, without their `\n`.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<Vec<u8>> {
+        let mut lines = Vec::new();
+        for &byte in chunk {
+            match byte {
+                b'\n' => {
+                    lines.push(std::mem::take(&mut self.line));
+                    self.cursor = 0;
+                }
+                b'\r' => self.cursor = 0,
+                _ => {
+                    match self.line.get_mut(self.cursor) {
+                        Some(slot) => *slot = byte,
+                        None => self.line.push(byte),
+                    }
+                    self.cursor += 1;
+                }
+            }
+        }
+        lines
+    }
+
+    /// The line currently being drawn, as it would appear on screen.
+    pub fn current(&self) -> &[u8] {
+        &self.line
+    }
+
+    /// Return the unterminated last line, if any, and reset the decoder.
+    pub fn finish(&mut self) -> Option<Vec<u8>> {
+        self.cursor = 0;
+        (!self.line.is_empty()).then(|| std::mem::take(&mut self.line))
+    }
+}
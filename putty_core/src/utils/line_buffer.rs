@@ -0,0 +1,49 @@
+//! Holding back partial lines so a display only redraws whole lines.
+
+/// Unterminated output beyond this many bytes is released anyway, so a
+/// device that never sends `\n` cannot grow the buffer without bound.
+const MAX_PENDING: usize = 4096;
+
+/// Buffers output and releases it up to and including the last `\n`.
+///
+/// Unlike [`LineAssembler`](crate::utils::line_assembler::LineAssembler) the
+/// bytes are passed on unchanged, escape sequences and `\r` included; only
+/// the timing changes. The caller decides when a trailing partial line (a
+/// prompt, say) has waited long enough and releases it with
+/// [`take_pending`](Self::take_pending).
+#[derive(Debug, Clone, Default)]
+pub struct LineBuffer {
+    pending: Vec<u8>,
+}
+
+impl LineBuffer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed `chunk` and return the bytes that are ready to display: all
+    /// complete lines, or everything once the partial line gets too long.
+    pub fn push(&mut self, chunk: &[u8]) -> Vec<u8> {
+        self.pending.extend_from_slice(chunk);
+        if self.pending.len() > MAX_PENDING {
+            return std::mem::take(&mut self.pending);
+        }
+        match self.pending.iter().rposition(|&b| b == b'\n') {
+            Some(end) => {
+                let rest = self.pending.split_off(end + 1);
+                std::mem::replace(&mut self.pending, rest)
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Whether a partial line is waiting.
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
+
+    /// Release the partial line, e.g. after a short timeout.
+    pub fn take_pending(&mut self) -> Option<Vec<u8>> {
+        (!self.pending.is_empty()).then(|| std::mem::take(&mut self.pending))
+    }
+}
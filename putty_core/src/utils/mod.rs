@@ -0,0 +1,5 @@
+pub mod ansi;
+pub mod escape;
+pub mod hex;
+pub mod line_assembler;
+pub mod line_buffer;
@@ -0,0 +1,126 @@
+use putty_core::connections::tcp::RawTcpConnection;
+use putty_core::{ConnectionEventKind, ConnectionManager, ConnectionState};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::init_logging;
+
+/// A loopback socket pair: the client end to hand over, the server end to
+/// play the device.
+async fn loopback_pair() -> (TcpStream, TcpStream) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (server, _) = listener.accept().await.unwrap();
+    (client, server)
+}
+
+#[tokio::test]
+async fn adopted_tcp_stream_round_trips_through_the_manager() {
+    init_logging();
+
+    let (client, mut device) = loopback_pair().await;
+    let peer = client.peer_addr().unwrap().to_string();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    connection_manager
+        .add_connection(
+            "adopted".into(),
+            Box::new(RawTcpConnection::from_stream(client)),
+        )
+        .await
+        .expect("adopting a connected stream should succeed");
+
+    match events.recv().await.unwrap().kind {
+        ConnectionEventKind::Opened(params) => {
+            assert_eq!(params.get("peer"), Some(&peer));
+        }
+        other => panic!("expected Opened, got {other:?}"),
+    }
+
+    // ── Manager -> device ────────────────────────────────────────────────
+    let mut rx = connection_manager.subscribe("adopted").await.unwrap();
+    connection_manager
+        .write_bytes_acked("adopted", b"ping")
+        .await
+        .expect("write should succeed");
+    let mut buf = [0u8; 4];
+    timeout(Duration::from_millis(500), device.read_exact(&mut buf))
+        .await
+        .expect("timeout waiting for the write")
+        .unwrap();
+    assert_eq!(&buf, b"ping");
+
+    // ── Device -> manager ────────────────────────────────────────────────
+    device.write_all(b"pong").await.unwrap();
+    let chunk = timeout(Duration::from_millis(500), rx.recv())
+        .await
+        .expect("timeout waiting for data")
+        .unwrap();
+    assert_eq!(chunk, b"pong");
+
+    // ── Stopping closes the adopted socket ───────────────────────────────
+    connection_manager.stop_connection("adopted").await.unwrap();
+    let n = timeout(Duration::from_millis(500), device.read(&mut buf))
+        .await
+        .expect("timeout waiting for EOF")
+        .unwrap();
+    assert_eq!(n, 0, "the manager should have shut the stream down");
+}
+
+#[tokio::test]
+async fn closed_adopted_stream_cannot_reconnect() {
+    use putty_core::connections::connection::Connection;
+
+    init_logging();
+
+    let (client, _device) = loopback_pair().await;
+    let mut connection = RawTcpConnection::from_stream(client);
+    connection.connect().await.expect("connect is a no-op");
+    connection.disconnect().await.unwrap();
+
+    let err = connection.connect().await.unwrap_err();
+    assert!(err.to_string().contains("cannot be reopened"), "{err}");
+}
+
+#[tokio::test]
+async fn peer_hanging_up_ends_the_session() {
+    init_logging();
+
+    let (client, device) = loopback_pair().await;
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    connection_manager
+        .add_connection(
+            "adopted".into(),
+            Box::new(RawTcpConnection::from_stream(client)),
+        )
+        .await
+        .expect("adopting a connected stream should succeed");
+    let mut rx = connection_manager.subscribe("adopted").await.unwrap();
+    drop(device);
+
+    let closed = timeout(Duration::from_secs(2), rx.recv())
+        .await
+        .expect("the subscription should close once the peer hangs up");
+    assert_eq!(closed, Err(RecvError::Closed));
+    assert_eq!(
+        connection_manager.status("adopted").await,
+        Some(ConnectionState::Disconnected)
+    );
+    let mut kinds = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        kinds.push(event.kind);
+    }
+    assert!(
+        kinds.contains(&ConnectionEventKind::RemoteClosed { exit_status: None }),
+        "{kinds:?}"
+    );
+
+    connection_manager.stop_connection("adopted").await.unwrap();
+}
@@ -0,0 +1,63 @@
+use putty_core::{ConnectionManager, ConnectionOptions};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn banner_reaches_subscribers_before_device_data() {
+    init_logging();
+
+    let banner = b"=== connected to rack1 ===\r\n";
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "rack1".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_banner(banner.as_slice()),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager
+        .subscribe("rack1")
+        .await
+        .expect("connection should exist");
+
+    test_to_fake_tx.send(b"login: ".to_vec()).await.unwrap();
+
+    let first = timeout(Duration::from_secs(1), rx.recv()).await.unwrap();
+    assert_eq!(first.unwrap(), banner);
+    let second = timeout(Duration::from_secs(1), rx.recv()).await.unwrap();
+    assert_eq!(second.unwrap(), b"login: ");
+
+    // ── The banner is for the reader only ────────────────────────────────
+    connection_manager.stop_connection("rack1").await.unwrap();
+    assert!(
+        fake_to_test_rx.try_recv().is_err(),
+        "the device must not receive the banner"
+    );
+}
+
+#[tokio::test]
+async fn no_banner_by_default() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("rack1".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager
+        .subscribe("rack1")
+        .await
+        .expect("connection should exist");
+
+    test_to_fake_tx.send(b"login: ".to_vec()).await.unwrap();
+
+    let first = timeout(Duration::from_secs(1), rx.recv()).await.unwrap();
+    assert_eq!(first.unwrap(), b"login: ");
+    connection_manager.stop_connection("rack1").await.unwrap();
+}
@@ -0,0 +1,33 @@
+use putty_core::connections::Baud;
+
+#[test]
+fn standard_rates_are_accepted_and_named() {
+    for rate in [300, 9600, 115_200, 921_600] {
+        let baud = Baud::new(rate).expect("standard baud should be valid");
+        assert!(baud.is_standard());
+        assert_eq!(baud.as_u32(), rate);
+    }
+    assert_eq!(Baud::new(115_200).unwrap(), Baud::B115200);
+    assert_eq!("9600".parse::<Baud>().unwrap(), Baud::B9600);
+}
+
+#[test]
+fn unusual_but_plausible_rates_use_other() {
+    assert_eq!(Baud::new(250_000).unwrap(), Baud::Other(250_000));
+}
+
+#[test]
+fn absurd_rates_are_rejected() {
+    assert!(Baud::new(3).is_err());
+    assert!(Baud::new(1_000_000_000).is_err());
+    assert!("fast".parse::<Baud>().is_err());
+}
+
+#[cfg(feature = "serial")]
+#[test]
+fn serial_connection_rejects_invalid_baud() {
+    use putty_core::connections::serial::SerialConnection;
+
+    assert!(SerialConnection::try_new("/dev/ttyUSB0".into(), 3).is_err());
+    assert!(SerialConnection::try_new("/dev/ttyUSB0".into(), 115_200).is_ok());
+}
@@ -0,0 +1,89 @@
+use putty_core::core::baud_check::printable_percent;
+use putty_core::{ConnectionEvent, ConnectionEventKind, ConnectionManager, ConnectionOptions};
+use tokio::sync::broadcast;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+/// Feed `chunks` into a fresh connection and return the printable percentage
+/// of the first `PossibleBaudMismatch` event, if one arrives.
+async fn mismatch_warning(options: ConnectionOptions, chunks: &[&[u8]]) -> Option<u8> {
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options("fakePort".into(), Box::new(fake_connection), options)
+        .await
+        .expect("add_connection should succeed");
+    let mut data_rx = connection_manager.subscribe("fakePort").await.unwrap();
+
+    for chunk in chunks {
+        test_to_fake_tx.send(chunk.to_vec()).await.unwrap();
+        data_rx.recv().await.unwrap();
+    }
+    next_mismatch(&mut events).await
+}
+
+async fn next_mismatch(events: &mut broadcast::Receiver<ConnectionEvent>) -> Option<u8> {
+    timeout(Duration::from_millis(100), async {
+        loop {
+            if let ConnectionEventKind::PossibleBaudMismatch { printable_percent } =
+                events.recv().await.unwrap().kind
+            {
+                return printable_percent;
+            }
+        }
+    })
+    .await
+    .ok()
+}
+
+/// What 115200 baud output looks like when read at 9600: mostly high-bit bytes.
+const NOISE: &[u8] = &[
+    0xf8, 0x80, 0xfe, 0x00, 0xe0, 0x9c, 0xff, 0x80, 0xf0, 0x86, 0x78, 0xfe, 0xc0, 0x80, 0x1e, 0xf8,
+    0x00, 0xe6, 0x80, 0xfc, 0x98, 0xff, 0x00, 0x80, 0xe0, 0xf8, 0x06, 0x80, 0xfe, 0x60, 0x9e, 0xf0,
+];
+
+#[tokio::test]
+async fn high_bit_noise_triggers_the_warning() {
+    init_logging();
+
+    let options = ConnectionOptions::new().with_baud_mismatch_detection(true);
+    // Split across reads, the sample is judged once enough bytes arrived.
+    let percent = mismatch_warning(options, &[&NOISE[..10], &NOISE[10..]])
+        .await
+        .expect("noise should trigger a PossibleBaudMismatch event");
+    assert!(percent < 20, "noise was judged {percent}% printable");
+}
+
+#[tokio::test]
+async fn clean_text_does_not_trigger_the_warning() {
+    init_logging();
+
+    let options = ConnectionOptions::new().with_baud_mismatch_detection(true);
+    let boot_log: &[&[u8]] = &[
+        b"\x1b[0;32mI (312) boot: ESP-IDF v5.1\x1b[0m\r\n",
+        "Grüße vom Gerät: temperature 21.5 °C\r\n".as_bytes(),
+    ];
+    assert_eq!(mismatch_warning(options, boot_log).await, None);
+}
+
+#[tokio::test]
+async fn detection_is_opt_in() {
+    init_logging();
+
+    assert_eq!(
+        mismatch_warning(ConnectionOptions::default(), &[NOISE]).await,
+        None
+    );
+}
+
+#[test]
+fn printable_percent_counts_text_and_utf8() {
+    assert_eq!(printable_percent(b"hello\r\n"), 100);
+    assert_eq!(printable_percent("µs °C".as_bytes()), 100);
+    assert_eq!(printable_percent(&[0xff, 0xfe, b'o', b'k']), 50);
+    assert_eq!(printable_percent(&[]), 100);
+}
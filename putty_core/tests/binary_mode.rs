@@ -0,0 +1,58 @@
+use putty_core::{ConnectionEventKind, ConnectionManager, ConnectionOptions};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+/// Line endings, an ANSI escape, NUL and bytes that are not UTF-8.
+const PAYLOAD: &[u8] = b"\r\n\x1b[1;32m\n\x00\xff\xfe\r";
+
+#[tokio::test]
+async fn binary_mode_passes_bytes_through_untouched() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    // Every text option is on, and binary mode has to override all of them.
+    let options = ConnectionOptions::new()
+        .with_crlf(true)
+        .with_banner(b"=== firmware ===\r\n".as_slice())
+        .with_baud_mismatch_detection(true)
+        .with_binary(true);
+    connection_manager
+        .add_connection_with_options("pipe".into(), Box::new(fake_connection), options)
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("pipe").await.unwrap();
+
+    // ── Write: no CRLF translation ───────────────────────────────────────
+    connection_manager
+        .write_bytes_acked("pipe", PAYLOAD)
+        .await
+        .unwrap();
+    let written = timeout(Duration::from_secs(1), fake_to_test_rx.recv())
+        .await
+        .expect("timeout waiting for the write")
+        .unwrap();
+    assert_eq!(written, PAYLOAD);
+
+    // ── Read: no banner in front, bytes as received ──────────────────────
+    for _ in 0..4 {
+        test_to_fake_tx.send(PAYLOAD.to_vec()).await.unwrap();
+        let read = timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("timeout waiting for data")
+            .unwrap();
+        assert_eq!(read, PAYLOAD);
+    }
+
+    connection_manager.stop_connection("pipe").await.unwrap();
+    while let Ok(event) = events.try_recv() {
+        assert!(
+            !matches!(event.kind, ConnectionEventKind::PossibleBaudMismatch { .. }),
+            "binary data must not be taken for noise"
+        );
+    }
+}
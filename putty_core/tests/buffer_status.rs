@@ -0,0 +1,63 @@
+use putty_core::{ConnectionManager, ConnectionOptions};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn buffer_status_reports_control_depth_and_slowest_lag() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+
+    // A long char delay keeps the I/O task busy with the first write,
+    // so the following ones pile up in the control channel.
+    connection_manager
+        .add_connection_with_options(
+            "slow".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_char_delay(Duration::from_secs(5)),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    let idle = connection_manager.buffer_status("slow").await.unwrap();
+    assert_eq!(idle.control_depth, 0);
+    assert_eq!(idle.subscribers, 0);
+
+    // ── A subscriber that never reads falls behind one that does ─────────
+    let lazy_rx = connection_manager.subscribe("slow").await.unwrap();
+    let mut keen_rx = connection_manager.subscribe("slow").await.unwrap();
+    test_to_fake_tx.send(b"one".to_vec()).await.unwrap();
+    test_to_fake_tx.send(b"two".to_vec()).await.unwrap();
+    for _ in 0..2 {
+        timeout(Duration::from_secs(1), keen_rx.recv())
+            .await
+            .expect("timeout waiting for output")
+            .expect("broadcast closed unexpectedly");
+    }
+
+    // ── Writes queue up behind a slow one ────────────────────────────────
+    connection_manager.write_bytes("slow", b"ab").await.unwrap();
+    // Wait until the task is stuck between the two bytes of the first write.
+    timeout(Duration::from_secs(1), fake_to_test_rx.recv())
+        .await
+        .expect("timeout waiting for first byte")
+        .expect("fake_to_test channel closed unexpectedly");
+    for _ in 0..5 {
+        connection_manager.write_bytes("slow", b"x").await.unwrap();
+    }
+
+    let status = connection_manager.buffer_status("slow").await.unwrap();
+    assert_eq!(status.control_depth, 5);
+    assert_eq!(status.control_capacity, 32);
+    assert_eq!(status.subscribers, 2);
+    // The slowest subscriber's lag, not the keen one's.
+    assert_eq!(status.max_subscriber_lag, 2);
+    assert_eq!(lazy_rx.len(), 2);
+    assert_eq!(keen_rx.len(), 0);
+
+    assert!(connection_manager.buffer_status("missing").await.is_none());
+}
@@ -0,0 +1,48 @@
+use putty_core::{ConnectionManager, ConnectionOptions};
+use tokio::time::{timeout, Duration, Instant};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn char_delay_spaces_out_transmitted_bytes() {
+    init_logging();
+
+    let char_delay = Duration::from_millis(20);
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+
+    connection_manager
+        .add_connection_with_options(
+            "slowTerm".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_char_delay(char_delay),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    let started = Instant::now();
+    connection_manager
+        .write_bytes("slowTerm", b"ATZ\r")
+        .await
+        .expect("write_bytes should succeed");
+
+    // ── Every byte arrives as its own write ──────────────────────────────
+    let mut received = Vec::new();
+    for _ in 0..4 {
+        let chunk = timeout(Duration::from_secs(1), fake_to_test_rx.recv())
+            .await
+            .expect("timeout waiting for a delayed byte")
+            .expect("fake_to_test channel closed unexpectedly");
+        assert_eq!(chunk.len(), 1, "char delay should write one byte at a time");
+        received.extend(chunk);
+    }
+    let elapsed = started.elapsed();
+
+    assert_eq!(received, b"ATZ\r");
+    assert!(
+        elapsed >= char_delay * 3,
+        "four bytes need at least three delays, took {elapsed:?}"
+    );
+}
@@ -11,8 +11,24 @@
 //!    serial port.
 
 use async_trait::async_trait;
-use putty_core::connections::{connection::Connection, errors::ConnectionError};
+use putty_core::connections::{
+    connection::{Connection, NegotiatedParams},
+    errors::ConnectionError,
+    ModemStatus,
+};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::sync::mpsc;
+use tokio::time::Instant;
+
+/// A modem-line change or BREAK the manager asked the fake for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineOp {
+    Dtr(bool),
+    Rts(bool),
+    Break(Duration),
+}
 
 pub struct FakeConnection {
     /// Bytes *pushed by the test* → appear as data read from the device.
@@ -24,6 +40,36 @@ pub struct FakeConnection {
     pub write_history: Vec<Vec<u8>>,
     pub connected: bool,
     pub disconnected: bool,
+    /// Counts `disconnect` calls; clone it before handing the fake over to
+    /// observe cleanup from the test.
+    pub disconnects: Arc<AtomicUsize>,
+    /// Time `connect` takes, to exercise cancellation.
+    pub connect_delay: Option<Duration>,
+    /// PTY size reported to the manager; `None` behaves like a serial port.
+    pub pty: Option<(u16, u16)>,
+    /// When set, every `write` fails without reaching the test.
+    pub fail_writes: bool,
+    /// Number of upcoming `connect` calls that fail before one succeeds.
+    pub fail_connects: usize,
+    /// When set, every `connect` after the first successful one fails.
+    pub fail_reconnects: bool,
+    /// Accept at most this many bytes per `write`, simulating short writes.
+    pub max_write_chunk: Option<usize>,
+    /// Time every `write` takes, simulating a slow transport.
+    pub write_delay: Option<Duration>,
+    /// When `assert_modem_lines` was called; clone it before handing the
+    /// fake over to observe keep-alives from the test.
+    pub line_asserts: Arc<Mutex<Vec<Instant>>>,
+    /// Every [`LineOp`] with the number of bytes written before it; clone
+    /// it before handing the fake over to check the ordering against writes.
+    pub line_ops: Arc<Mutex<Vec<(LineOp, usize)>>>,
+    /// Input lines reported by `modem_status`; `None` refuses like a
+    /// transport without control lines.
+    pub modem_status: Option<ModemStatus>,
+    /// When set, written bytes only reach the test once `flush` is called,
+    /// like a transport with a TX buffer.
+    pub buffer_writes: bool,
+    unflushed: Vec<u8>,
 }
 
 impl FakeConnection {
@@ -45,34 +91,87 @@ impl FakeConnection {
                 write_history: Vec::new(),
                 connected: false,
                 disconnected: false,
+                disconnects: Arc::new(AtomicUsize::new(0)),
+                connect_delay: None,
+                pty: None,
+                fail_writes: false,
+                fail_connects: 0,
+                fail_reconnects: false,
+                max_write_chunk: None,
+                write_delay: None,
+                line_asserts: Arc::default(),
+                line_ops: Arc::default(),
+                modem_status: None,
+                buffer_writes: false,
+                unflushed: Vec::new(),
             },
             test_to_fake_tx,
             fake_to_test_rx,
         )
     }
+
+    fn record_line_op(&self, op: LineOp) {
+        let written = self.write_history.iter().map(Vec::len).sum();
+        self.line_ops.lock().unwrap().push((op, written));
+    }
 }
 
 #[async_trait]
 impl Connection for FakeConnection {
     async fn connect(&mut self) -> Result<(), ConnectionError> {
+        if let Some(delay) = self.connect_delay {
+            tokio::time::sleep(delay).await;
+        }
+        if self.fail_connects > 0 {
+            self.fail_connects -= 1;
+            return Err(ConnectionError::Other("fake connect failure".into()));
+        }
+        if self.fail_reconnects && self.connected {
+            return Err(ConnectionError::Other("fake reconnect failure".into()));
+        }
         self.connected = true;
         Ok(())
     }
 
     async fn disconnect(&mut self) -> Result<(), ConnectionError> {
         self.disconnected = true;
+        self.disconnects.fetch_add(1, Ordering::SeqCst);
         Ok(())
     }
 
     async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError> {
+        if self.fail_writes {
+            return Err(ConnectionError::Other("fake write failure".into()));
+        }
+        if let Some(delay) = self.write_delay {
+            tokio::time::sleep(delay).await;
+        }
+
+        let data = match self.max_write_chunk {
+            Some(max) => &data[..data.len().min(max)],
+            None => data,
+        };
+
         // Record for later assertions …
         self.write_history.push(data.to_vec());
 
         // and echo back through the helper channel (rarely used).
-        let _ = self.fake_to_test_tx.send(data.to_vec()).await;
+        if self.buffer_writes {
+            self.unflushed.extend_from_slice(data);
+        } else {
+            let _ = self.fake_to_test_tx.send(data.to_vec()).await;
+        }
         Ok(data.len())
     }
 
+    async fn flush(&mut self) -> Result<(), ConnectionError> {
+        if !self.unflushed.is_empty() {
+            let pending = std::mem::take(&mut self.unflushed);
+            let _ = self.fake_to_test_tx.send(pending).await;
+        }
+        Ok(())
+    }
+
     async fn read(&mut self, destination_buffer: &mut [u8]) -> Result<usize, ConnectionError> {
         match self.test_to_fake_rx.recv().await {
             Some(mut incoming_chunk) => {
@@ -96,4 +195,53 @@ impl Connection for FakeConnection {
             )),
         }
     }
+
+    fn is_connected(&self) -> bool {
+        self.connected && !self.disconnected
+    }
+
+    fn kind(&self) -> &'static str {
+        "fake"
+    }
+
+    fn negotiated(&self) -> NegotiatedParams {
+        NegotiatedParams::from([("device".to_string(), "fake".to_string())])
+    }
+
+    fn pty_size(&self) -> Option<(u16, u16)> {
+        self.pty
+    }
+
+    async fn resize(&mut self, cols: u16, rows: u16) -> Result<(), ConnectionError> {
+        if self.pty.is_some() {
+            self.pty = Some((cols, rows));
+        }
+        Ok(())
+    }
+
+    async fn assert_modem_lines(&mut self) -> Result<(), ConnectionError> {
+        self.line_asserts.lock().unwrap().push(Instant::now());
+        Ok(())
+    }
+
+    async fn set_dtr(&mut self, level: bool) -> Result<(), ConnectionError> {
+        self.record_line_op(LineOp::Dtr(level));
+        Ok(())
+    }
+
+    async fn set_rts(&mut self, level: bool) -> Result<(), ConnectionError> {
+        self.record_line_op(LineOp::Rts(level));
+        Ok(())
+    }
+
+    async fn send_break(&mut self, duration: Duration) -> Result<(), ConnectionError> {
+        tokio::time::sleep(duration).await;
+        self.record_line_op(LineOp::Break(duration));
+        Ok(())
+    }
+
+    async fn modem_status(&mut self) -> Result<ModemStatus, ConnectionError> {
+        self.modem_status
+            .ok_or_else(|| ConnectionError::Other("fake has no modem status".into()))
+    }
 }
@@ -1 +1,20 @@
+// Each test binary uses its own subset of these helpers.
+#![allow(dead_code)]
+
+use log::LevelFilter;
+
 pub mod fake_connection;
+
+/// Log at debug level from the test, see [`init_logging_at`].
+pub fn init_logging() {
+    init_logging_at(LevelFilter::Debug);
+}
+
+/// Send log output at `level` and above to the test harness. Logs will
+/// appear only when you run with `-- --nocapture` or when the test fails.
+pub fn init_logging_at(level: LevelFilter) {
+    let _ = env_logger::Builder::from_default_env()
+        .filter_level(level)
+        .is_test(true)
+        .try_init();
+}
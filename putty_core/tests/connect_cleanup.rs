@@ -0,0 +1,102 @@
+use putty_core::{ConnectRetry, ConnectionManager, ConnectionOptions};
+use std::sync::atomic::Ordering;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn duplicate_id_disconnects_the_new_transport() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (first, first_tx, _first_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("dup".into(), Box::new(first))
+        .await
+        .expect("first add_connection should succeed");
+
+    // ── The second connect succeeds, but registering it fails ────────────
+    let (second, _second_tx, _second_rx) = FakeConnection::new();
+    let second_disconnects = second.disconnects.clone();
+    let err = connection_manager
+        .add_connection("dup".into(), Box::new(second))
+        .await
+        .expect_err("a duplicate id must be rejected");
+    assert!(err.to_string().contains("already exists"), "{err}");
+    assert_eq!(second_disconnects.load(Ordering::SeqCst), 1);
+
+    // ── The original connection is untouched ─────────────────────────────
+    let mut rx = connection_manager.subscribe("dup").await.unwrap();
+    first_tx.send(b"still here".to_vec()).await.unwrap();
+    let chunk = timeout(Duration::from_millis(200), rx.recv())
+        .await
+        .expect("timeout waiting for data")
+        .expect("broadcast channel closed unexpectedly");
+    assert_eq!(chunk, b"still here");
+}
+
+#[tokio::test]
+async fn failed_connect_is_disconnected() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.fail_connects = 1;
+    let disconnects = fake_connection.disconnects.clone();
+
+    connection_manager
+        .add_connection("broken".into(), Box::new(fake_connection))
+        .await
+        .expect_err("connect should fail");
+
+    assert_eq!(disconnects.load(Ordering::SeqCst), 1);
+    assert!(connection_manager.subscribe("broken").await.is_none());
+}
+
+#[tokio::test]
+async fn connect_deadline_disconnects_the_half_open_transport() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.connect_delay = Some(Duration::from_millis(500));
+    let disconnects = fake_connection.disconnects.clone();
+
+    connection_manager
+        .add_connection_with_options(
+            "slow".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new()
+                .with_connect_retry(ConnectRetry::new(Duration::from_millis(50))),
+        )
+        .await
+        .expect_err("connect should time out");
+
+    assert_eq!(disconnects.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn cancelled_add_connection_still_disconnects() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.connect_delay = Some(Duration::from_millis(500));
+    let disconnects = fake_connection.disconnects.clone();
+
+    // The caller gives up while connect is still running.
+    let add = connection_manager.add_connection("abandoned".into(), Box::new(fake_connection));
+    assert!(timeout(Duration::from_millis(50), add).await.is_err());
+
+    // Cleanup happens on a spawned task; give it a moment.
+    timeout(Duration::from_millis(500), async {
+        while disconnects.load(Ordering::SeqCst) == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("disconnect was never called");
+    assert!(connection_manager.subscribe("abandoned").await.is_none());
+}
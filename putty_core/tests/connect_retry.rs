@@ -0,0 +1,68 @@
+use putty_core::{ConnectRetry, ConnectionManager, ConnectionOptions};
+use tokio::time::Duration;
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+fn fast_retry(deadline: Duration) -> ConnectRetry {
+    ConnectRetry::new(deadline).with_backoff(Duration::from_millis(10), Duration::from_millis(40))
+}
+
+#[tokio::test]
+async fn connect_retry_recovers_from_a_transient_failure() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.fail_connects = 1;
+
+    connection_manager
+        .add_connection_with_options(
+            "flaky".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_connect_retry(fast_retry(Duration::from_secs(1))),
+        )
+        .await
+        .expect("second connect attempt should succeed");
+
+    assert!(connection_manager.subscribe("flaky").await.is_some());
+}
+
+#[tokio::test]
+async fn connect_retry_gives_up_at_the_deadline() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.fail_connects = usize::MAX;
+
+    let result = connection_manager
+        .add_connection_with_options(
+            "down".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_connect_retry(fast_retry(Duration::from_millis(100))),
+        )
+        .await;
+
+    assert!(
+        result.is_err(),
+        "connect should fail once the deadline is spent"
+    );
+    assert!(connection_manager.subscribe("down").await.is_none());
+}
+
+#[tokio::test]
+async fn without_retry_the_first_failure_is_returned() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.fail_connects = 1;
+
+    let result = connection_manager
+        .add_connection("flaky".into(), Box::new(fake_connection))
+        .await;
+
+    assert!(result.is_err());
+}
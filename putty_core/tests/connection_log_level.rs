@@ -0,0 +1,110 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use putty_core::{ConnectionManager, ConnectionOptions, CONNECTION_LOG_TARGET};
+use std::sync::Mutex;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+
+/// Keeps every connection record so the test can see what was emitted,
+/// standing in for a logger configured with `putty_core::connection=trace`.
+struct CapturingLogger {
+    records: Mutex<Vec<(Level, String)>>,
+}
+
+impl Log for CapturingLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.target() == CONNECTION_LOG_TARGET
+    }
+
+    fn log(&self, record: &Record) {
+        if self.enabled(record.metadata()) {
+            self.records
+                .lock()
+                .unwrap()
+                .push((record.level(), record.args().to_string()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CapturingLogger = CapturingLogger {
+    records: Mutex::new(Vec::new()),
+};
+
+fn records_at(level: Level) -> Vec<String> {
+    LOGGER
+        .records
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(l, _)| *l == level)
+        .map(|(_, message)| message.clone())
+        .collect()
+}
+
+#[tokio::test]
+async fn only_the_raised_connection_logs_at_debug() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(LevelFilter::Trace);
+
+    // ── Both connections start at info ───────────────────────────────────
+    let connection_manager = ConnectionManager::new();
+    let mut device_txs = Vec::new();
+    for id in ["flaky", "steady"] {
+        let (fake_connection, test_to_fake_tx, fake_to_test_rx) = FakeConnection::new();
+        connection_manager
+            .add_connection_with_options(
+                id.into(),
+                Box::new(fake_connection),
+                ConnectionOptions::new().with_log_level(LevelFilter::Info),
+            )
+            .await
+            .expect("add_connection should succeed");
+        device_txs.push((test_to_fake_tx, fake_to_test_rx));
+    }
+
+    connection_manager
+        .set_log_level("flaky", LevelFilter::Debug)
+        .await
+        .expect("set_log_level should succeed");
+    assert!(connection_manager
+        .set_log_level("missing", LevelFilter::Debug)
+        .await
+        .is_err());
+
+    // ── Same traffic on both ─────────────────────────────────────────────
+    for (id, (test_to_fake_tx, fake_to_test_rx)) in
+        ["flaky", "steady"].into_iter().zip(&mut device_txs)
+    {
+        let mut rx = connection_manager.subscribe(id).await.unwrap();
+        connection_manager
+            .write_bytes_acked(id, b"ping")
+            .await
+            .unwrap();
+        fake_to_test_rx.recv().await.unwrap();
+        test_to_fake_tx.send(b"pong".to_vec()).await.unwrap();
+        timeout(Duration::from_millis(200), rx.recv())
+            .await
+            .expect("timeout waiting for data")
+            .unwrap();
+    }
+
+    let debug = records_at(Level::Debug);
+    assert!(
+        debug.iter().any(|message| message.contains("'flaky'")),
+        "{debug:?}"
+    );
+    assert!(
+        debug.iter().all(|message| !message.contains("'steady'")),
+        "{debug:?}"
+    );
+
+    // Info records still come from both.
+    let info = records_at(Level::Info);
+    assert!(
+        info.iter().any(|message| message.contains("'steady'")),
+        "{info:?}"
+    );
+}
@@ -0,0 +1,119 @@
+use putty_core::{
+    ConnectionEventKind, ConnectionManager, ConnectionOptions, ConnectionState, EofPolicy,
+};
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+/// Wait for the `Closed` event of `id`; its state is final by then.
+async fn wait_closed(events: &mut broadcast::Receiver<putty_core::ConnectionEvent>, id: &str) {
+    timeout(Duration::from_secs(1), async {
+        loop {
+            let event = events.recv().await.unwrap();
+            if event.id == id && event.kind == ConnectionEventKind::Closed {
+                return;
+            }
+        }
+    })
+    .await
+    .expect("timeout waiting for Closed");
+}
+
+#[tokio::test]
+async fn status_goes_from_connecting_to_connected_to_gone() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.connect_delay = Some(Duration::from_millis(200));
+
+    let adding = tokio::spawn({
+        let connection_manager = connection_manager.clone();
+        async move {
+            connection_manager
+                .add_connection("dut".into(), Box::new(fake_connection))
+                .await
+        }
+    });
+    sleep(Duration::from_millis(50)).await;
+    assert_eq!(
+        connection_manager.status("dut").await,
+        Some(ConnectionState::Connecting)
+    );
+
+    adding
+        .await
+        .unwrap()
+        .expect("add_connection should succeed");
+    assert_eq!(
+        connection_manager.status("dut").await,
+        Some(ConnectionState::Connected)
+    );
+
+    connection_manager.stop_connection("dut").await.unwrap();
+    assert_eq!(connection_manager.status("dut").await, None);
+}
+
+#[tokio::test]
+async fn eof_leaves_the_connection_disconnected() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "exec".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_eof_policy(EofPolicy::CloseOnEof),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    test_to_fake_tx.send(Vec::new()).await.unwrap();
+    wait_closed(&mut events, "exec").await;
+    assert_eq!(
+        connection_manager.status("exec").await,
+        Some(ConnectionState::Disconnected)
+    );
+}
+
+#[tokio::test]
+async fn read_error_leaves_the_connection_failed() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("flaky".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    // Closing the input channel makes the fake's read fail.
+    drop(test_to_fake_tx);
+    wait_closed(&mut events, "flaky").await;
+    match connection_manager.status("flaky").await {
+        Some(ConnectionState::Failed(reason)) => {
+            assert!(reason.contains("no more data"), "{reason}")
+        }
+        other => panic!("expected a failed connection, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn failed_connect_leaves_no_status() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.fail_connects = 1;
+    assert!(connection_manager
+        .add_connection("dut".into(), Box::new(fake_connection))
+        .await
+        .is_err());
+    assert_eq!(connection_manager.status("dut").await, None);
+}
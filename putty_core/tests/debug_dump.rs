@@ -0,0 +1,35 @@
+use putty_core::ConnectionManager;
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn debug_dump_lists_connection_details() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+
+    connection_manager
+        .add_connection("fakePort".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let _first = connection_manager.subscribe("fakePort").await.unwrap();
+    let _second = connection_manager.subscribe("fakePort").await.unwrap();
+
+    let dump = connection_manager.debug_dump().await;
+    log::info!("{dump}");
+
+    assert!(dump.contains("1 connection(s)"), "dump: {dump}");
+    let line = dump
+        .lines()
+        .find(|l| l.trim_start().starts_with("fakePort:"))
+        .unwrap_or_else(|| panic!("no line for fakePort in dump: {dump}"));
+    assert!(line.contains("kind=fake"), "line: {line}");
+    assert!(line.contains("state=running"), "line: {line}");
+    assert!(line.contains("subscribers=2"), "line: {line}");
+    assert!(line.contains("pending_control=0"), "line: {line}");
+    assert!(line.contains("uptime="), "line: {line}");
+}
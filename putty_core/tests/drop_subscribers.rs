@@ -0,0 +1,59 @@
+use putty_core::ConnectionManager;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn dropping_subscribers_keeps_the_connection() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("viewer".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    assert_eq!(connection_manager.subscriber_count("viewer").await, Some(0));
+    assert_eq!(connection_manager.subscriber_count("nope").await, None);
+
+    let mut first = connection_manager.subscribe("viewer").await.unwrap();
+    let mut second = connection_manager.subscribe("viewer").await.unwrap();
+    assert_eq!(connection_manager.subscriber_count("viewer").await, Some(2));
+
+    // ── Dropping closes both receivers ───────────────────────────────────
+    assert_eq!(
+        connection_manager.drop_subscribers("viewer").await.unwrap(),
+        2
+    );
+    assert_eq!(connection_manager.subscriber_count("viewer").await, Some(0));
+    for rx in [&mut first, &mut second] {
+        let closed = timeout(Duration::from_millis(200), rx.recv())
+            .await
+            .expect("receiver should be closed, not pending");
+        assert_eq!(closed, Err(RecvError::Closed));
+    }
+
+    // ── The connection still reads and writes ────────────────────────────
+    let mut fresh = connection_manager.subscribe("viewer").await.unwrap();
+    test_to_fake_tx.send(b"still up".to_vec()).await.unwrap();
+    let chunk = timeout(Duration::from_millis(200), fresh.recv())
+        .await
+        .expect("timeout waiting for data")
+        .expect("new subscription should receive data");
+    assert_eq!(chunk, b"still up");
+
+    connection_manager
+        .write_bytes("viewer", b"ping")
+        .await
+        .unwrap();
+    let written = timeout(Duration::from_millis(200), fake_to_test_rx.recv())
+        .await
+        .expect("timeout waiting for the write")
+        .unwrap();
+    assert_eq!(written, b"ping");
+
+    assert!(connection_manager.drop_subscribers("nope").await.is_err());
+}
@@ -0,0 +1,64 @@
+use putty_core::{ConnectionEventKind, ConnectionManager, ConnectionOptions, EofPolicy};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn keep_open_survives_eof() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "shell".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_eof_policy(EofPolicy::KeepOpen),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("shell").await.unwrap();
+
+    // An empty chunk makes the fake's read return Ok(0), i.e. EOF.
+    test_to_fake_tx.send(Vec::new()).await.unwrap();
+    test_to_fake_tx.send(b"still here".to_vec()).await.unwrap();
+
+    let chunk = timeout(Duration::from_millis(200), rx.recv())
+        .await
+        .expect("timeout waiting for data after EOF")
+        .expect("connection closed on EOF");
+    assert_eq!(chunk, b"still here");
+}
+
+#[tokio::test]
+async fn close_on_eof_ends_the_session() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "exec".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_eof_policy(EofPolicy::CloseOnEof),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    test_to_fake_tx.send(Vec::new()).await.unwrap();
+
+    let closed = timeout(Duration::from_millis(200), async {
+        loop {
+            let event = events.recv().await.expect("event channel closed");
+            if event.kind == ConnectionEventKind::Closed {
+                break event;
+            }
+        }
+    })
+    .await
+    .expect("connection should close on EOF");
+    assert_eq!(closed.id, "exec");
+}
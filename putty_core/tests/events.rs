@@ -0,0 +1,67 @@
+use putty_core::{ConnectionEvent, ConnectionEventKind, ConnectionManager};
+use tokio::sync::broadcast;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+async fn next_event(events: &mut broadcast::Receiver<ConnectionEvent>) -> ConnectionEvent {
+    timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("timeout waiting for an event")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn lifecycle_is_opened_then_closed() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("dut".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    connection_manager.stop_connection("dut").await.unwrap();
+
+    let opened = next_event(&mut events).await;
+    assert_eq!(opened.id, "dut");
+    assert!(
+        matches!(opened.kind, ConnectionEventKind::Opened(_)),
+        "{opened:?}"
+    );
+    assert_eq!(
+        next_event(&mut events).await,
+        ConnectionEvent {
+            id: "dut".into(),
+            kind: ConnectionEventKind::Closed,
+        }
+    );
+}
+
+#[tokio::test]
+async fn read_error_is_reported_before_closed() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("dut".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    next_event(&mut events).await;
+
+    // Closing the input channel makes the fake's read fail.
+    drop(test_to_fake_tx);
+    match next_event(&mut events).await.kind {
+        ConnectionEventKind::ReadError(e) => assert!(e.contains("no more data"), "{e}"),
+        other => panic!("expected ReadError, got {other:?}"),
+    }
+    assert_eq!(
+        next_event(&mut events).await.kind,
+        ConnectionEventKind::Closed
+    );
+}
@@ -0,0 +1,89 @@
+use putty_core::ConnectionManager;
+use tokio::time::{sleep, timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn first_subscriber_gets_the_very_first_bytes() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    // The device talks as soon as the port opens.
+    test_to_fake_tx
+        .send(b"U-Boot 2024.01\r\n".to_vec())
+        .await
+        .unwrap();
+    connection_manager
+        .add_connection("board".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    assert_eq!(connection_manager.subscriber_count("board").await, Some(0));
+
+    // Let the I/O task read and broadcast before anyone subscribes.
+    sleep(Duration::from_millis(50)).await;
+    let mut first = connection_manager
+        .subscribe("board")
+        .await
+        .expect("connection should exist");
+    let mut second = connection_manager
+        .subscribe("board")
+        .await
+        .expect("connection should exist");
+    assert_eq!(connection_manager.subscriber_count("board").await, Some(2));
+
+    test_to_fake_tx.send(b"=> ".to_vec()).await.unwrap();
+
+    let chunk = timeout(Duration::from_secs(1), first.recv()).await.unwrap();
+    assert_eq!(chunk.unwrap(), b"U-Boot 2024.01\r\n");
+    let chunk = timeout(Duration::from_secs(1), first.recv()).await.unwrap();
+    assert_eq!(chunk.unwrap(), b"=> ");
+
+    // Later subscribers only see what arrives after they subscribed.
+    let chunk = timeout(Duration::from_secs(1), second.recv())
+        .await
+        .unwrap();
+    assert_eq!(chunk.unwrap(), b"=> ");
+
+    connection_manager.stop_connection("board").await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn write_verified_leaves_the_early_data_alone() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    test_to_fake_tx
+        .send(b"U-Boot 2024.01\r\n".to_vec())
+        .await
+        .unwrap();
+    connection_manager
+        .add_connection("board".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    sleep(Duration::from_millis(50)).await;
+
+    // The device echoes from here on.
+    tokio::spawn(async move {
+        while let Some(chunk) = fake_to_test_rx.recv().await {
+            let _ = test_to_fake_tx.send(chunk).await;
+        }
+    });
+    let written = connection_manager
+        .write_verified("board", b"help\r", Duration::from_secs(1))
+        .await
+        .expect("the echo should verify, not the banner");
+    assert_eq!(written, 5);
+
+    // The banner is still there for the real consumer, before the echo.
+    let mut first = connection_manager.subscribe("board").await.unwrap();
+    let chunk = timeout(Duration::from_secs(1), first.recv()).await.unwrap();
+    assert_eq!(chunk.unwrap(), b"U-Boot 2024.01\r\n");
+    let chunk = timeout(Duration::from_secs(1), first.recv()).await.unwrap();
+    assert_eq!(chunk.unwrap(), b"help\r");
+
+    connection_manager.stop_connection("board").await.unwrap();
+}
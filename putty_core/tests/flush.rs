@@ -0,0 +1,85 @@
+use putty_core::ConnectionManager;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn flush_pushes_buffered_writes_out() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    fake_connection.buffer_writes = true;
+    connection_manager
+        .add_connection("buffered".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    connection_manager
+        .write_bytes("buffered", b"first ")
+        .await
+        .unwrap();
+    connection_manager
+        .write_bytes("buffered", b"second")
+        .await
+        .unwrap();
+    assert!(
+        timeout(Duration::from_millis(100), fake_to_test_rx.recv())
+            .await
+            .is_err(),
+        "nothing should reach the wire before a flush"
+    );
+
+    // Queued behind both writes, so it covers them.
+    connection_manager.flush("buffered").await.unwrap();
+    let sent = timeout(Duration::from_secs(1), fake_to_test_rx.recv())
+        .await
+        .expect("flushed bytes")
+        .unwrap();
+    assert_eq!(sent, b"first second");
+
+    connection_manager
+        .stop_connection("buffered")
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn stopping_flushes_what_is_left() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    fake_connection.buffer_writes = true;
+    connection_manager
+        .add_connection("buffered".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    connection_manager
+        .write_bytes("buffered", b"bye")
+        .await
+        .unwrap();
+    connection_manager
+        .stop_connection("buffered")
+        .await
+        .unwrap();
+
+    let sent = timeout(Duration::from_secs(1), fake_to_test_rx.recv())
+        .await
+        .expect("bytes flushed on stop")
+        .unwrap();
+    assert_eq!(sent, b"bye");
+}
+
+#[tokio::test]
+async fn flush_of_an_unknown_connection_fails() {
+    let connection_manager = ConnectionManager::new();
+    let err = connection_manager.flush("nope").await.unwrap_err();
+    assert!(
+        err.to_string().contains("No connection with id 'nope'"),
+        "{err}"
+    );
+}
@@ -0,0 +1,80 @@
+use putty_core::connections::{DataBits, FlowControl, Framing, Parity, StopBits};
+
+#[test]
+fn default_is_8n1_without_flow_control() {
+    let framing = Framing::default();
+    assert_eq!(framing.to_string(), "8N1");
+    assert_eq!(framing.flow_control, FlowControl::None);
+}
+
+#[test]
+fn settings_parse_and_print_back() {
+    assert_eq!("7".parse::<DataBits>().unwrap(), DataBits::Seven);
+    assert_eq!("2".parse::<StopBits>().unwrap(), StopBits::Two);
+    assert_eq!("Even".parse::<Parity>().unwrap(), Parity::Even);
+    assert_eq!("o".parse::<Parity>().unwrap(), Parity::Odd);
+    assert_eq!(
+        "rtscts".parse::<FlowControl>().unwrap(),
+        FlowControl::Hardware
+    );
+    for flow in [
+        FlowControl::None,
+        FlowControl::Software,
+        FlowControl::Hardware,
+    ] {
+        assert_eq!(flow.to_string().parse::<FlowControl>().unwrap(), flow);
+    }
+
+    let framing = Framing {
+        data_bits: DataBits::Seven,
+        parity: Parity::Even,
+        stop_bits: StopBits::Two,
+        flow_control: FlowControl::Software,
+    };
+    assert_eq!(framing.to_string(), "7E2");
+}
+
+#[test]
+fn impossible_settings_are_rejected() {
+    assert!(DataBits::try_from(9).is_err());
+    assert!(StopBits::try_from(0).is_err());
+    assert!("1.5".parse::<StopBits>().is_err());
+    assert!("mark".parse::<Parity>().is_err());
+    assert!("dtrdsr".parse::<FlowControl>().is_err());
+}
+
+#[cfg(feature = "serial")]
+#[test]
+fn serial_connection_opens_with_the_requested_settings() {
+    use putty_core::connections::serial::SerialConnection;
+
+    let default = SerialConnection::new("/dev/ttyUSB0".into(), 9600);
+    assert_eq!(default.framing(), Framing::default());
+
+    let conn = SerialConnection::with_settings(
+        "/dev/ttyUSB0".into(),
+        9600,
+        DataBits::Seven,
+        Parity::Even,
+        StopBits::One,
+    );
+    assert_eq!(conn.framing().to_string(), "7E1");
+    assert_eq!(conn.framing().flow_control, FlowControl::None);
+}
+
+#[cfg(feature = "serial")]
+#[test]
+fn flow_control_keeps_the_other_settings() {
+    use putty_core::connections::serial::SerialConnection;
+
+    let conn = SerialConnection::with_settings(
+        "/dev/ttyUSB0".into(),
+        9600,
+        DataBits::Seven,
+        Parity::Even,
+        StopBits::One,
+    )
+    .with_flow_control(FlowControl::Hardware);
+    assert_eq!(conn.framing().to_string(), "7E1");
+    assert_eq!(conn.framing().flow_control, FlowControl::Hardware);
+}
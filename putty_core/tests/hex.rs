@@ -0,0 +1,46 @@
+use putty_core::utils::hex::{parse_hex, to_hex};
+
+#[test]
+fn common_hex_spellings_parse_to_the_same_bytes() {
+    let expected = vec![0xAA, 0xBB, 0x01, 0xFF];
+    for text in [
+        "AA BB 01 FF",
+        "aabb01ff",
+        "AA,BB,01,FF",
+        "AA, BB, 01, FF",
+        "0xAA 0xbb 0X01 0xff",
+        "  AABB\t01FF\n",
+    ] {
+        assert_eq!(parse_hex(text).unwrap(), expected, "parsing {text:?}");
+    }
+}
+
+#[test]
+fn every_byte_needs_two_digits() {
+    for text in ["1 2 A", "AA B", "0x1", "AA,F,BB"] {
+        let err = parse_hex(text).expect_err(text).to_string();
+        assert!(err.contains("odd number of digits"), "{text:?}: {err}");
+    }
+}
+
+#[test]
+fn malformed_hex_is_rejected_with_the_reason() {
+    for (text, reason) in [
+        ("AAB", "odd number of digits"),
+        ("AA BB0", "odd number of digits"),
+        ("AA GG", "'G' is not a hex digit"),
+        ("0x", "has no digits"),
+        ("", "no bytes given"),
+        (" , ", "no bytes given"),
+    ] {
+        let err = parse_hex(text).expect_err(text).to_string();
+        assert!(err.contains(reason), "{text:?}: {err}");
+    }
+}
+
+#[test]
+fn to_hex_round_trips() {
+    let bytes = [0x00, 0x7F, 0xAA, 0xFF];
+    assert_eq!(to_hex(&bytes), "00 7F AA FF");
+    assert_eq!(parse_hex(&to_hex(&bytes)).unwrap(), bytes);
+}
@@ -3,7 +3,11 @@
 //! feature is enabled -> on MacOS socat virtual serial behaves differently and it fails.
 #![cfg(all(feature = "hw-tests", target_os = "linux"))]
 
-use putty_core::{connections::serial::serial_connection::SerialConnection, ConnectionManager};
+use putty_core::{
+    connections::serial::{serial_connection::SerialConnection, InitString},
+    connections::FlowControl,
+    ConnectionManager,
+};
 
 use tokio::{
     io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
@@ -11,6 +15,8 @@ use tokio::{
     time::{timeout, Duration},
 };
 
+use putty_core::connections::errors::ConnectionError;
+
 use log::LevelFilter;
 use std::path::PathBuf;
 use tokio_serial::SerialPortBuilderExt;
@@ -54,6 +60,118 @@ async fn spawn_socat_pair() -> anyhow::Result<(PathBuf, PathBuf, Child)> {
     Ok((pty_paths[0].clone(), pty_paths[1].clone(), socat_child))
 }
 
+/// Echo everything read on `path` back to the sender. With `corrupt_at`, the
+/// byte at that offset of each chunk is flipped, like a noisy middle-man.
+fn spawn_echo(path: PathBuf, corrupt_at: Option<usize>) {
+    tokio::spawn(async move {
+        let mut echo_port = tokio_serial::new(path.to_string_lossy(), 115_200)
+            .open_native_async()
+            .expect("failed to open echo PTY");
+
+        let mut buffer = [0u8; 64];
+        loop {
+            let bytes_read = echo_port.read(&mut buffer).await.unwrap();
+            if let Some(i) = corrupt_at.filter(|&i| i < bytes_read) {
+                buffer[i] ^= 0xFF;
+            }
+            if bytes_read > 0 {
+                echo_port.write_all(&buffer[..bytes_read]).await.unwrap();
+            }
+        }
+    });
+}
+
+/// Open `path` through the manager under the id `"dev"`.
+async fn open_dev(path: &std::path::Path) -> ConnectionManager {
+    let connection_manager = ConnectionManager::new();
+    connection_manager
+        .add_connection(
+            "dev".into(),
+            Box::new(SerialConnection::new(
+                path.to_string_lossy().into_owned(),
+                115_200,
+            )),
+        )
+        .await
+        .expect("add_connection failed");
+    connection_manager
+}
+
+#[tokio::test]
+async fn verified_write_accepts_clean_echo() {
+    let _ = env_logger::Builder::from_default_env()
+        .filter_level(LevelFilter::Debug)
+        .is_test(true)
+        .try_init();
+
+    let (left_pty_path, right_pty_path, mut socat_child) =
+        spawn_socat_pair().await.expect("failed to spawn socat");
+    spawn_echo(right_pty_path, None);
+    let connection_manager = open_dev(&left_pty_path).await;
+
+    let written = connection_manager
+        .write_verified("dev", b"ping", Duration::from_secs(1))
+        .await
+        .expect("clean echo should verify");
+    assert_eq!(written, 4);
+
+    socat_child.kill().await.expect("failed to kill socat");
+}
+
+#[tokio::test]
+async fn verified_write_reports_corrupted_echo() {
+    let _ = env_logger::Builder::from_default_env()
+        .filter_level(LevelFilter::Debug)
+        .is_test(true)
+        .try_init();
+
+    let (left_pty_path, right_pty_path, mut socat_child) =
+        spawn_socat_pair().await.expect("failed to spawn socat");
+    spawn_echo(right_pty_path, Some(1));
+    let connection_manager = open_dev(&left_pty_path).await;
+
+    let err = connection_manager
+        .write_verified("dev", b"ping", Duration::from_secs(1))
+        .await
+        .expect_err("corrupted echo must be reported");
+    assert!(
+        matches!(
+            err,
+            ConnectionError::EchoMismatch {
+                offset: 1,
+                expected: b'i',
+                actual,
+            } if actual == b'i' ^ 0xFF
+        ),
+        "unexpected error: {err}"
+    );
+
+    socat_child.kill().await.expect("failed to kill socat");
+}
+
+#[tokio::test]
+async fn latency_through_a_loopback_is_measured() {
+    let _ = env_logger::Builder::from_default_env()
+        .filter_level(LevelFilter::Debug)
+        .is_test(true)
+        .try_init();
+
+    let (left_pty_path, right_pty_path, mut socat_child) =
+        spawn_socat_pair().await.expect("failed to spawn socat");
+    spawn_echo(right_pty_path, None);
+    let connection_manager = open_dev(&left_pty_path).await;
+
+    let probe_timeout = Duration::from_secs(1);
+    let latency = connection_manager
+        .measure_latency("dev", b'~', probe_timeout)
+        .await
+        .expect("loopback should echo the probe");
+    assert!(latency > Duration::ZERO);
+    assert!(latency < probe_timeout, "{latency:?}");
+
+    socat_child.kill().await.expect("failed to kill socat");
+}
+
 #[tokio::test]
 async fn virtual_serial_roundtrip() {
     // ── Logger: DEBUG by default, but RUST_LOG can override ───────────────────
@@ -120,3 +238,79 @@ async fn virtual_serial_roundtrip() {
         .await
         .expect("failed to wait for socat child process");
 }
+
+#[tokio::test]
+async fn init_string_is_written_first_after_connect() {
+    let _ = env_logger::Builder::from_default_env()
+        .filter_level(LevelFilter::Debug)
+        .is_test(true)
+        .try_init();
+
+    let (left_pty_path, right_pty_path, mut socat_child) =
+        spawn_socat_pair().await.expect("failed to spawn socat");
+    let mut device = tokio_serial::new(right_pty_path.to_string_lossy(), 115_200)
+        .open_native_async()
+        .expect("failed to open device PTY");
+
+    let connection_manager = ConnectionManager::new();
+    connection_manager
+        .add_connection(
+            "modem".into(),
+            Box::new(
+                SerialConnection::new(left_pty_path.to_string_lossy().into_owned(), 115_200)
+                    .with_init(InitString::new(*b"ATZ\r")),
+            ),
+        )
+        .await
+        .expect("add_connection failed");
+    connection_manager
+        .write_bytes("modem", b"ATI\r")
+        .await
+        .unwrap();
+
+    let mut received = Vec::new();
+    let mut buffer = [0u8; 64];
+    while received.len() < 8 {
+        let n = timeout(Duration::from_secs(1), device.read(&mut buffer))
+            .await
+            .expect("timeout waiting for init string")
+            .unwrap();
+        received.extend_from_slice(&buffer[..n]);
+    }
+    assert_eq!(received, b"ATZ\rATI\r");
+
+    socat_child.kill().await.expect("failed to kill socat");
+}
+
+/// A PTY cannot exercise RTS/CTS, but it keeps the termios flags, so reading
+/// them back shows the flow control reached the driver.
+#[tokio::test]
+async fn flow_control_is_applied_to_the_port() {
+    let (left_pty_path, _right_pty_path, mut socat_child) =
+        spawn_socat_pair().await.expect("failed to spawn socat");
+
+    let connection_manager = ConnectionManager::new();
+    for (id, flow) in [
+        ("rtscts", FlowControl::Hardware),
+        ("xonxoff", FlowControl::Software),
+    ] {
+        connection_manager
+            .add_connection(
+                id.into(),
+                Box::new(
+                    SerialConnection::new(left_pty_path.to_string_lossy().into_owned(), 115_200)
+                        .with_flow_control(flow),
+                ),
+            )
+            .await
+            .expect("add_connection failed");
+        let params = connection_manager.negotiated_params(id).await.unwrap();
+        assert_eq!(
+            params.get("flow_control").map(String::as_str),
+            Some(format!("{flow:?}").as_str())
+        );
+        connection_manager.stop_connection(id).await.unwrap();
+    }
+
+    socat_child.kill().await.expect("failed to kill socat");
+}
@@ -7,17 +7,25 @@
 
 use anyhow::{Context, Result};
 // use openssh::{KnownHosts, SessionBuilder, Stdio};
-use putty_core::{connections::ssh::ssh_connection::SshConnection, ConnectionManager};
+use putty_core::{
+    connections::{
+        connection::{ConnectProgress, Connection},
+        errors::ConnectionError,
+        ssh::{ssh_connection::SshConnection, X11Display},
+    },
+    ConnectionEventKind, ConnectionManager, ConnectionOptions,
+};
 use std::{
     fs,
     io::Write,
     net::{TcpListener, TcpStream},
     os::unix::fs::PermissionsExt,
+    path::PathBuf,
     process::{Child, Command},
     thread::sleep,
     time::{Duration, Instant},
 };
-use tempfile::{tempdir, NamedTempFile};
+use tempfile::{tempdir, NamedTempFile, TempDir};
 use which::which;
 
 // ---------------------------------------------------------------------------
@@ -45,42 +53,50 @@ fn wait_until_listening(port: u16, timeout_ms: u64) {
     panic!("sshd did not start on port {port}");
 }
 
-// ---------------------------------------------------------------------------
-// The actual test
-// ---------------------------------------------------------------------------
+/// A throw-away `sshd` listening on 127.0.0.1, killed on drop.
+struct TestSshd {
+    port: u16,
+    client_key: PathBuf,
+    user: String,
+    /// Public half of the server's host key, as written by `ssh-keygen`.
+    host_key_pub: PathBuf,
+    child: Child,
+    // Keeps keys and config alive for the lifetime of the server.
+    _workdir: TempDir,
+}
 
-#[tokio::test]
-async fn sshd_echo_roundtrip() -> Result<()> {
-    // ── 1. workspace (auto‑deleted) + free port ────────────────────────────
-    let workdir = tempdir()?;
-    let port = free_tcp_port();
+impl TestSshd {
+    fn spawn() -> Result<Self> {
+        // ── 1. workspace (auto‑deleted) + free port ────────────────────────
+        let workdir = tempdir()?;
+        let port = free_tcp_port();
 
-    // ── 2. generate **server** host key  (ssh‑host‑key) ────────────────────
-    let host_key = workdir.path().join("host_ed25519");
-    Command::new(which("ssh-keygen")?)
-        .args(["-q", "-t", "ed25519", "-N", "", "-f"])
-        .arg(&host_key)
-        .status()
-        .context("failed to create host key")?;
+        // ── 2. generate **server** host key  (ssh‑host‑key) ────────────────
+        let host_key = workdir.path().join("host_ed25519");
+        Command::new(which("ssh-keygen")?)
+            .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+            .arg(&host_key)
+            .status()
+            .context("failed to create host key")?;
 
-    // ── 3. generate **client** key pair  (used by this test) ───────────────
-    let client_key = workdir.path().join("client_ed25519");
-    Command::new(which("ssh-keygen")?)
-        .args(["-q", "-t", "ed25519", "-N", "", "-f"])
-        .arg(&client_key)
-        .status()
-        .context("failed to create client key")?;
-
-    // authorise that client key for *any* local user running the test
-    let authorized_keys = workdir.path().join("authorized_keys");
-    fs::copy(client_key.with_extension("pub"), &authorized_keys)?;
-    fs::set_permissions(&authorized_keys, fs::Permissions::from_mode(0o600))?;
-
-    // ── 4. minimal sshd_config written to a temp file ──────────────────────
-    let mut cfg = NamedTempFile::new_in(workdir.path())?;
-    writeln!(
-        cfg,
-        r#"
+        // ── 3. generate **client** key pair  (used by this test) ───────────
+        let client_key = workdir.path().join("client_ed25519");
+        Command::new(which("ssh-keygen")?)
+            .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+            .arg(&client_key)
+            .status()
+            .context("failed to create client key")?;
+
+        // authorise that client key for *any* local user running the test
+        let authorized_keys = workdir.path().join("authorized_keys");
+        fs::copy(client_key.with_extension("pub"), &authorized_keys)?;
+        fs::set_permissions(&authorized_keys, fs::Permissions::from_mode(0o600))?;
+
+        // ── 4. minimal sshd_config written to a temp file ──────────────────
+        let mut cfg = NamedTempFile::new_in(workdir.path())?;
+        writeln!(
+            cfg,
+            r#"
 Port {port}
 ListenAddress 127.0.0.1
 HostKey {host_key}
@@ -95,34 +111,81 @@ StrictModes           no          # ← allow /tmp parent dir
 PidFile {pidfile}
 LogLevel QUIET                  # ← set to DEBUG3 for more info
 "#,
-        port = port,
-        host_key = host_key.display(),
-        authorized_keys = authorized_keys.display(),
-        pidfile = workdir.path().join("sshd.pid").display(),
-    )?;
-    cfg.flush()?;
-
-    // ── 5. start sshd in the foreground (-D) so we can kill it later ───────
-    let sshd_path = which("sshd")?;
-    let mut sshd: Child = Command::new(sshd_path)
-        .args(["-e", "-D", "-f"])
-        .arg(cfg.path())
-        .spawn()
-        .context("unable to launch sshd")?;
-
-    wait_until_listening(port, 2_000);
+            port = port,
+            host_key = host_key.display(),
+            authorized_keys = authorized_keys.display(),
+            pidfile = workdir.path().join("sshd.pid").display(),
+        )?;
+        cfg.flush()?;
+        let (_, cfg_path) = cfg.keep()?;
 
-    // ── 6. client side: connect with the key we just made ──────────────────
-    let user: String =
-        std::env::var("USER").expect("USER env var is needed for ssh test but not set");
+        // ── 5. start sshd in the foreground (-D) so we can kill it later ───
+        let sshd_path = which("sshd")?;
+        let child: Child = Command::new(sshd_path)
+            .args(["-e", "-D", "-f"])
+            .arg(&cfg_path)
+            .spawn()
+            .context("unable to launch sshd")?;
 
-    let conn = SshConnection::with_key(
-        "127.0.0.1".into(),
-        port,
-        user.clone(),
-        client_key.clone(), // ← same key we just authored
-        None,               // passphrase
-    );
+        wait_until_listening(port, 2_000);
+
+        let user: String =
+            std::env::var("USER").expect("USER env var is needed for ssh test but not set");
+
+        Ok(Self {
+            port,
+            client_key,
+            user,
+            host_key_pub: host_key.with_extension("pub"),
+            child,
+            _workdir: workdir,
+        })
+    }
+
+    /// A not-yet-connected `SshConnection` authenticating with the test key.
+    fn connection(&self) -> SshConnection {
+        SshConnection::with_key(
+            "127.0.0.1".into(),
+            self.port,
+            self.user.clone(),
+            self.client_key.clone(),
+            None, // passphrase
+        )
+    }
+
+    /// Ask ssh-keygen for the fingerprint of the key sshd was started with.
+    fn host_key_fingerprint(&self) -> Result<String> {
+        let output = Command::new(which("ssh-keygen")?)
+            .args(["-l", "-E", "sha256", "-f"])
+            .arg(&self.host_key_pub)
+            .output()?;
+        let listing = String::from_utf8(output.stdout)?;
+        listing
+            .split_whitespace()
+            .nth(1)
+            .map(str::to_owned)
+            .context("unexpected ssh-keygen -l output")
+    }
+}
+
+impl Drop for TestSshd {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+// ---------------------------------------------------------------------------
+// The actual tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn sshd_echo_roundtrip() -> Result<()> {
+    // ── 1.–5. throw-away sshd with a generated host and client key ─────────
+    let sshd = TestSshd::spawn()?;
+
+    // ── 6. client side: connect with the key we just made ──────────────────
+    let conn = sshd.connection();
 
     let manager = ConnectionManager::new();
     manager
@@ -154,10 +217,213 @@ LogLevel QUIET                  # ← set to DEBUG3 for more info
 
     log::info!("received: {:?}", String::from_utf8_lossy(&echoed));
 
-    // ── 8. tidy up (unchanged) ────────────────────────────────────────────────
+    // ── 8. tidy up (sshd is killed when `sshd` drops) ─────────────────────────
+    manager.stop_connection("ssh").await.ok();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn opened_event_reports_server_version() -> Result<()> {
+    let sshd = TestSshd::spawn()?;
+
+    let manager = ConnectionManager::new();
+    let mut events = manager.events();
+    manager
+        .add_connection("ssh".into(), Box::new(sshd.connection()))
+        .await
+        .expect("add_connection failed");
+
+    let event = tokio::time::timeout(Duration::from_secs(2), events.recv()).await??;
+    let ConnectionEventKind::Opened(params) = event.kind else {
+        panic!("expected Opened, got {:?}", event.kind);
+    };
+    log::info!("negotiated: {params:?}");
+
+    let server_version = params
+        .get("server_version")
+        .expect("server version missing");
+    assert!(
+        server_version.starts_with("SSH-2.0-OpenSSH"),
+        "unexpected server version {server_version:?}"
+    );
+    assert!(params.contains_key("cipher"), "cipher missing: {params:?}");
+
+    manager.stop_connection("ssh").await.ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn upload_via_exec_preserves_binary_content() -> Result<()> {
+    let sshd = TestSshd::spawn()?;
+    let files = tempdir()?;
+
+    // Every byte value, including CR/LF and NUL, so any translation shows up.
+    let payload: Vec<u8> = (0..=255u8).cycle().take(64 * 1024 + 7).collect();
+    let local = files.path().join("payload.bin");
+    fs::write(&local, &payload)?;
+    let remote = files.path().join("uploaded file.bin");
+
+    let mut conn = sshd.connection();
+    conn.connect().await?;
+    conn.upload_via_exec(&local, remote.to_str().unwrap())
+        .await?;
+    conn.disconnect().await?;
+
+    assert_eq!(fs::read(&remote)?, payload);
+    Ok(())
+}
+
+#[tokio::test]
+async fn exec_returns_output_and_exit_status() -> Result<()> {
+    let sshd = TestSshd::spawn()?;
+
+    let mut conn = sshd.connection();
+    let (output, status) = conn.exec("echo hi").await?;
+    assert_eq!(output, b"hi\n");
+    assert_eq!(status, 0);
+
+    let (_, status) = conn.exec("exit 3").await?;
+    assert_eq!(status, 3);
+    conn.disconnect().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn probe_reports_ed25519_host_key_without_auth() -> Result<()> {
+    let sshd = TestSshd::spawn()?;
+
+    let probe = SshConnection::probe("127.0.0.1", sshd.port).await?;
+    log::info!("probe: {probe:?}");
+
+    let expected = sshd.host_key_fingerprint()?;
+
+    assert_eq!(probe.host_key_algorithm, "ssh-ed25519");
+    assert_eq!(probe.host_key_fingerprint, expected);
+    assert!(probe.server_version.starts_with("SSH-2.0-"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn connect_reports_progress_in_order() -> Result<()> {
+    let sshd = TestSshd::spawn()?;
+
+    let manager = ConnectionManager::new();
+    let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+    manager
+        .add_connection_with_options(
+            "ssh".into(),
+            Box::new(sshd.connection()),
+            ConnectionOptions::new().with_progress(progress_tx),
+        )
+        .await
+        .expect("add_connection failed");
+
+    // Everything was reported by the time add_connection returned.
+    let mut seen = Vec::new();
+    while let Ok(milestone) = progress_rx.try_recv() {
+        seen.push(milestone);
+    }
+    assert_eq!(
+        seen,
+        [
+            ConnectProgress::TcpConnected,
+            ConnectProgress::Handshaked,
+            ConnectProgress::Authenticated,
+            ConnectProgress::ShellReady,
+        ]
+    );
+
     manager.stop_connection("ssh").await.ok();
-    sshd.kill().ok();
-    sshd.wait().ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn with_keys_falls_through_to_the_authorized_key() -> Result<()> {
+    let sshd = TestSshd::spawn()?;
+
+    // A fresh key that sshd does not know, offered first.
+    let keys = tempdir()?;
+    let stranger = keys.path().join("stranger_ed25519");
+    Command::new(which("ssh-keygen")?)
+        .args(["-q", "-t", "ed25519", "-N", "", "-f"])
+        .arg(&stranger)
+        .status()
+        .context("failed to create extra key")?;
 
+    let conn = SshConnection::with_keys(
+        "127.0.0.1".into(),
+        sshd.port,
+        sshd.user.clone(),
+        vec![(stranger, None), (sshd.client_key.clone(), None)],
+    );
+
+    let manager = ConnectionManager::new();
+    manager
+        .add_connection("ssh".into(), Box::new(conn))
+        .await
+        .expect("second key should authenticate");
+
+    let params = manager.negotiated_params("ssh").await.unwrap();
+    assert_eq!(
+        params.get("identity").map(PathBuf::from),
+        Some(sshd.client_key.clone())
+    );
+
+    manager.stop_connection("ssh").await.ok();
+    Ok(())
+}
+
+#[tokio::test]
+async fn wrong_credentials_are_an_auth_error() -> Result<()> {
+    let sshd = TestSshd::spawn()?;
+
+    // sshd only accepts the test key, so any password is rejected.
+    let mut conn = SshConnection::new(
+        "127.0.0.1".into(),
+        sshd.port,
+        sshd.user.clone(),
+        "wrong".into(),
+    );
+    let err = conn.connect().await.unwrap_err();
+    assert!(matches!(err, ConnectionError::AuthError(_)), "{err:?}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn pinned_host_key_must_match() -> Result<()> {
+    let sshd = TestSshd::spawn()?;
+    let fingerprint = sshd.host_key_fingerprint()?;
+
+    let mut conn = sshd.connection().with_expected_host_key(fingerprint);
+    conn.connect()
+        .await
+        .expect("pinned fingerprint should match");
+    conn.disconnect().await?;
+
+    // Same length and format, different key.
+    let wrong = "SHA256:AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA";
+    let mut conn = sshd.connection().with_expected_host_key(wrong.into());
+    let err = conn.connect().await.unwrap_err();
+    assert!(matches!(err, ConnectionError::HandshakeError(_)), "{err:?}");
+    assert!(err.to_string().contains("host key mismatch"), "{err}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn refused_x11_forwarding_still_opens_the_session() -> Result<()> {
+    // The test sshd keeps OpenSSH's default `X11Forwarding no`.
+    let sshd = TestSshd::spawn()?;
+    let display = X11Display::parse(":0").unwrap();
+
+    let mut conn = sshd.connection().with_x11_forwarding(display);
+    conn.connect()
+        .await
+        .expect("a refused X11 request must not fail the session");
+    assert_eq!(
+        conn.negotiated().get("x11").map(String::as_str),
+        Some("refused")
+    );
+    conn.disconnect().await?;
     Ok(())
 }
@@ -0,0 +1,100 @@
+use putty_core::{
+    ConnectionEvent, ConnectionEventKind, ConnectionManager, ConnectionOptions, ConnectionState,
+    DisconnectReason, KeepAliveAction,
+};
+use std::time::Instant;
+use tokio::sync::broadcast;
+use tokio::time::{sleep, timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+const IDLE: Duration = Duration::from_millis(150);
+
+async fn wait_idle_timeout(events: &mut broadcast::Receiver<ConnectionEvent>) {
+    timeout(Duration::from_secs(2), async {
+        loop {
+            if events.recv().await.unwrap().kind
+                == (ConnectionEventKind::Disconnected {
+                    reason: DisconnectReason::IdleTimeout,
+                })
+            {
+                return;
+            }
+        }
+    })
+    .await
+    .expect("timeout waiting for the idle timeout");
+}
+
+#[tokio::test]
+async fn traffic_postpones_the_idle_timeout() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "lab".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_idle_timeout(IDLE),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("lab").await.unwrap();
+
+    // A write, then a read, each well within the timeout of the last.
+    sleep(IDLE / 2).await;
+    connection_manager
+        .write_bytes_acked("lab", b"ping")
+        .await
+        .unwrap();
+    sleep(IDLE / 2).await;
+    test_to_fake_tx.send(b"pong".to_vec()).await.unwrap();
+    rx.recv().await.unwrap();
+    let last_traffic = Instant::now();
+
+    wait_idle_timeout(&mut events).await;
+    assert!(last_traffic.elapsed() >= IDLE);
+    assert_eq!(
+        timeout(Duration::from_secs(1), events.recv())
+            .await
+            .unwrap()
+            .unwrap()
+            .kind,
+        ConnectionEventKind::Closed
+    );
+    assert_eq!(
+        connection_manager.status("lab").await,
+        Some(ConnectionState::Disconnected)
+    );
+}
+
+#[tokio::test]
+async fn keepalives_do_not_count_as_traffic() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "lab".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new()
+                .with_idle_timeout(IDLE)
+                .with_line_keepalive(IDLE / 5, KeepAliveAction::SendByte(0)),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    wait_idle_timeout(&mut events).await;
+}
+
+#[test]
+fn zero_idle_timeout_disables_it() {
+    let options = ConnectionOptions::new().with_idle_timeout(Duration::ZERO);
+    assert_eq!(options.idle_timeout, None);
+}
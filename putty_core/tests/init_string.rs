@@ -0,0 +1,63 @@
+#![cfg(feature = "serial")]
+
+use putty_core::connections::serial::InitString;
+use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+use tokio::time::Duration;
+
+#[test]
+fn escapes_are_resolved() {
+    let init = InitString::from_escaped(r"ATZ\r\n\x1b\\").unwrap();
+    assert_eq!(init.bytes, b"ATZ\r\n\x1b\\");
+
+    assert!(InitString::from_escaped(r"AT\q").is_err());
+    assert!(InitString::from_escaped(r"\x4").is_err());
+}
+
+#[tokio::test]
+async fn init_waits_for_the_expected_reply() {
+    let (mut port, mut device) = duplex(64);
+    let init = InitString::new(*b"ATZ\r").expecting(*b"OK", Duration::from_secs(1));
+
+    let modem = tokio::spawn(async move {
+        let mut command = [0u8; 4];
+        device.read_exact(&mut command).await.unwrap();
+        device.write_all(b"ATZ\r\r\nO").await.unwrap();
+        device.write_all(b"K\r\n").await.unwrap();
+        command
+    });
+
+    init.run(&mut port).await.expect("reply should be accepted");
+    assert_eq!(&modem.await.unwrap(), b"ATZ\r");
+}
+
+#[tokio::test]
+async fn init_fails_without_the_expected_reply() {
+    let (mut port, _device) = duplex(64);
+    let init = InitString::new(*b"ATZ\r").expecting(*b"OK", Duration::from_millis(50));
+
+    assert!(init.run(&mut port).await.is_err());
+}
+
+#[tokio::test]
+async fn empty_expected_reply_is_met_at_once() {
+    let (mut port, _device) = duplex(64);
+    let init = InitString::new(*b"ATZ\r").expecting(*b"", Duration::from_secs(1));
+
+    init.run(&mut port).await.expect("nothing to wait for");
+}
+
+#[tokio::test]
+async fn init_fails_when_the_port_closes_while_waiting() {
+    let (mut port, mut device) = duplex(64);
+    let init = InitString::new(*b"ATZ\r").expecting(*b"OK", Duration::from_secs(5));
+
+    tokio::spawn(async move {
+        let mut command = [0u8; 4];
+        device.read_exact(&mut command).await.unwrap();
+    });
+
+    let started = std::time::Instant::now();
+    let err = init.run(&mut port).await.unwrap_err();
+    assert!(err.to_string().contains("closed"), "{err}");
+    assert!(started.elapsed() < Duration::from_secs(1));
+}
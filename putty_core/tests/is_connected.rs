@@ -0,0 +1,43 @@
+use putty_core::{ConnectionManager, ConnectionState};
+use tokio::time::{sleep, timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn dropped_connection_is_no_longer_connected() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("dut".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    assert!(connection_manager.is_connected("dut").await.unwrap());
+
+    // Read errors end the connection but keep it registered.
+    drop(test_to_fake_tx);
+    timeout(Duration::from_secs(1), async {
+        while !matches!(
+            connection_manager.status("dut").await,
+            Some(ConnectionState::Failed(_))
+        ) {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("timeout waiting for the connection to fail");
+    assert!(!connection_manager.is_connected("dut").await.unwrap());
+}
+
+#[tokio::test]
+async fn unknown_connection_is_an_error() {
+    let connection_manager = ConnectionManager::new();
+    let err = connection_manager.is_connected("nope").await.unwrap_err();
+    assert!(
+        err.to_string().contains("No connection with id 'nope'"),
+        "{err}"
+    );
+}
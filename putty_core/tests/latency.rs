@@ -0,0 +1,86 @@
+use putty_core::ConnectionManager;
+use tokio::time::{sleep, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+/// Register a fake under `"dev"` whose device side answers every write after
+/// `delay`, prefixed with some unrelated output; `None` never answers.
+async fn device(delay: Option<Duration>) -> ConnectionManager {
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+
+    tokio::spawn(async move {
+        while let Some(chunk) = fake_to_test_rx.recv().await {
+            let Some(delay) = delay else { continue };
+            let _ = test_to_fake_tx.send(b"[kernel] eth0 up\r\n".to_vec()).await;
+            sleep(delay).await;
+            let _ = test_to_fake_tx.send(chunk).await;
+        }
+    });
+
+    connection_manager
+        .add_connection("dev".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    connection_manager
+}
+
+#[tokio::test]
+async fn latency_is_the_time_until_the_probe_is_echoed() {
+    init_logging();
+    let connection_manager = device(Some(Duration::from_millis(30))).await;
+
+    let timeout = Duration::from_secs(2);
+    let latency = connection_manager
+        .measure_latency("dev", b'~', timeout)
+        .await
+        .expect("echoing device should be measured");
+    assert!(latency >= Duration::from_millis(30), "{latency:?}");
+    assert!(latency < timeout, "{latency:?}");
+}
+
+#[tokio::test]
+async fn silent_device_times_out() {
+    init_logging();
+    let connection_manager = device(None).await;
+
+    let err = connection_manager
+        .measure_latency("dev", b'~', Duration::from_millis(100))
+        .await
+        .expect_err("nothing is echoed");
+    assert!(err.to_string().contains("No echo of probe 0x7e"), "{err}");
+
+    assert!(connection_manager
+        .measure_latency("missing", b'~', Duration::from_millis(100))
+        .await
+        .is_err());
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn early_output_is_not_taken_for_the_echo() {
+    init_logging();
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    // Printed before the probe is sent, and it contains the probe byte.
+    test_to_fake_tx
+        .send(b"root@board:~# ".to_vec())
+        .await
+        .unwrap();
+    connection_manager
+        .add_connection("dev".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    sleep(Duration::from_millis(50)).await;
+
+    let err = connection_manager
+        .measure_latency("dev", b'~', Duration::from_millis(100))
+        .await
+        .expect_err("the device never echoed the probe");
+    assert!(err.to_string().contains("No echo of probe"), "{err}");
+
+    // The prompt is still there for the real consumer.
+    let mut first = connection_manager.subscribe("dev").await.unwrap();
+    assert_eq!(first.recv().await.unwrap(), b"root@board:~# ");
+}
@@ -0,0 +1,30 @@
+use putty_core::utils::line_assembler::LineAssembler;
+
+#[test]
+fn carriage_returns_overwrite_the_current_line() {
+    let mut assembler = LineAssembler::new();
+
+    let lines = assembler.push(b"10%\r50%\r100%\n");
+
+    assert_eq!(lines, vec![b"100%".to_vec()]);
+    assert!(assembler.current().is_empty());
+}
+
+#[test]
+fn redraws_split_across_chunks_and_shorter_overwrites() {
+    let mut assembler = LineAssembler::new();
+
+    assert!(assembler.push(b"downloading 1").is_empty());
+    assert!(assembler.push(b"00%\r").is_empty());
+    assert_eq!(assembler.current(), b"downloading 100%");
+
+    // A shorter redraw keeps the tail, just like on a real terminal.
+    assert!(assembler.push(b"done").is_empty());
+    assert_eq!(assembler.current(), b"doneloading 100%");
+
+    // CRLF line endings finalize the line unchanged.
+    let lines = assembler.push(b"\rok\r\nnext");
+    assert_eq!(lines, vec![b"okneloading 100%".to_vec()]);
+    assert_eq!(assembler.finish(), Some(b"next".to_vec()));
+    assert_eq!(assembler.finish(), None);
+}
@@ -0,0 +1,44 @@
+use putty_core::utils::line_buffer::LineBuffer;
+
+#[test]
+fn complete_lines_pass_through_unchanged() {
+    let mut buffer = LineBuffer::new();
+
+    let ready = buffer.push(b"\x1b[32mok\x1b[0m\r\nsecond\n");
+
+    assert_eq!(ready, b"\x1b[32mok\x1b[0m\r\nsecond\n");
+    assert!(!buffer.has_pending());
+    assert_eq!(buffer.take_pending(), None);
+}
+
+#[test]
+fn partial_lines_wait_for_their_newline() {
+    let mut buffer = LineBuffer::new();
+
+    assert!(buffer.push(b"boo").is_empty());
+    assert!(buffer.has_pending());
+
+    // Completing the line releases it; the new partial line stays behind.
+    assert_eq!(buffer.push(b"t\r\nlogin: "), b"boot\r\n");
+    assert!(buffer.has_pending());
+
+    // A prompt never gets a newline; the caller releases it after a timeout.
+    assert_eq!(buffer.take_pending(), Some(b"login: ".to_vec()));
+    assert!(!buffer.has_pending());
+}
+
+#[test]
+fn an_endless_line_is_released_eventually() {
+    let mut buffer = LineBuffer::new();
+
+    let mut released = Vec::new();
+    for _ in 0..100 {
+        released.extend(buffer.push(&[b'.'; 100]));
+    }
+
+    assert!(
+        !released.is_empty(),
+        "a line without \\n must not be held forever"
+    );
+    assert!(released.iter().all(|&b| b == b'.'));
+}
@@ -0,0 +1,115 @@
+use putty_core::connections::tcp::RawTcpConnection;
+use putty_core::connections::{Connection, ModemStatus};
+use putty_core::ConnectionManager;
+use tokio::time::{Duration, Instant};
+
+mod common;
+use common::fake_connection::{FakeConnection, LineOp};
+
+#[tokio::test]
+async fn line_controls_reach_the_transport_in_order() {
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    let line_ops = fake_connection.line_ops.clone();
+    connection_manager
+        .add_connection("board".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    connection_manager.set_dtr("board", false).await.unwrap();
+    connection_manager.set_rts("board", true).await.unwrap();
+    connection_manager.set_dtr("board", true).await.unwrap();
+
+    assert_eq!(
+        *line_ops.lock().unwrap(),
+        [
+            (LineOp::Dtr(false), 0),
+            (LineOp::Rts(true), 0),
+            (LineOp::Dtr(true), 0),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn break_is_serialized_with_writes() {
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    let line_ops = fake_connection.line_ops.clone();
+    connection_manager
+        .add_connection("board".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    // Queued without waiting for the transport; the break still goes after it.
+    connection_manager
+        .write_bytes("board", b"boot")
+        .await
+        .unwrap();
+    let started = Instant::now();
+    let duration = Duration::from_millis(50);
+    connection_manager
+        .send_break("board", duration)
+        .await
+        .unwrap();
+    assert!(started.elapsed() >= duration);
+    connection_manager
+        .write_bytes("board", b"\r")
+        .await
+        .unwrap();
+
+    assert_eq!(fake_to_test_rx.recv().await.unwrap(), b"boot");
+    assert_eq!(fake_to_test_rx.recv().await.unwrap(), b"\r");
+    assert_eq!(*line_ops.lock().unwrap(), [(LineOp::Break(duration), 4)]);
+}
+
+#[tokio::test]
+async fn unknown_ids_are_rejected() {
+    let connection_manager = ConnectionManager::new();
+
+    let err = connection_manager
+        .send_break("nope", Duration::from_millis(10))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("nope"), "{err}");
+    assert!(connection_manager.set_dtr("nope", true).await.is_err());
+}
+
+#[tokio::test]
+async fn modem_status_is_read_from_the_transport() {
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    let status = ModemStatus {
+        cts: true,
+        dsr: true,
+        dcd: false,
+        ri: false,
+    };
+    fake_connection.modem_status = Some(status);
+    connection_manager
+        .add_connection("board".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    assert_eq!(
+        connection_manager.modem_status("board").await.unwrap(),
+        status
+    );
+}
+
+#[tokio::test]
+async fn transports_without_control_lines_refuse() {
+    let mut conn = RawTcpConnection::new("127.0.0.1".into(), 23);
+
+    let err = conn.modem_status().await.unwrap_err().to_string();
+    assert!(
+        err.contains("tcp connections have no modem status lines"),
+        "{err}"
+    );
+    let err = conn
+        .send_break(Duration::from_millis(10))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("cannot send a break"), "{err}");
+    assert!(conn.set_dtr(true).await.is_err());
+    assert!(conn.set_rts(true).await.is_err());
+}
@@ -0,0 +1,86 @@
+use putty_core::{ConnectionManager, ConnectionOptions, KeepAliveAction};
+use tokio::time::{sleep, timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn idle_line_gets_its_control_lines_reasserted() {
+    init_logging();
+
+    let interval = Duration::from_millis(50);
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    let line_asserts = fake_connection.line_asserts.clone();
+    connection_manager
+        .add_connection_with_options(
+            "modem".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_line_keepalive(interval, KeepAliveAction::AssertLines),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    sleep(interval * 5 + interval / 2).await;
+    let asserts = line_asserts.lock().unwrap().clone();
+    assert!(
+        (4..=5).contains(&asserts.len()),
+        "expected about 5 re-assertions, got {}",
+        asserts.len()
+    );
+    for pair in asserts.windows(2) {
+        assert!(pair[1] - pair[0] >= interval, "{asserts:?}");
+    }
+}
+
+#[tokio::test]
+async fn traffic_postpones_the_keepalive() {
+    init_logging();
+
+    let interval = Duration::from_millis(100);
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    let line_asserts = fake_connection.line_asserts.clone();
+    connection_manager
+        .add_connection_with_options(
+            "modem".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_line_keepalive(interval, KeepAliveAction::AssertLines),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    for _ in 0..6 {
+        test_to_fake_tx.send(b"data".to_vec()).await.unwrap();
+        sleep(interval / 3).await;
+    }
+    assert!(line_asserts.lock().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn keepalive_can_send_a_byte_instead() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    let line_asserts = fake_connection.line_asserts.clone();
+    connection_manager
+        .add_connection_with_options(
+            "modem".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new()
+                .with_crlf(true)
+                .with_line_keepalive(Duration::from_millis(30), KeepAliveAction::SendByte(b'\n')),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    let sent = timeout(Duration::from_secs(1), fake_to_test_rx.recv())
+        .await
+        .expect("keep-alive byte should be written")
+        .unwrap();
+    // Written raw: the CRLF transform does not apply.
+    assert_eq!(sent, b"\n");
+    assert!(line_asserts.lock().unwrap().is_empty());
+}
@@ -0,0 +1,41 @@
+use putty_core::ConnectionManager;
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn list_follows_adds_renames_and_stops() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    assert!(connection_manager.list_connections().await.is_empty());
+
+    for id in ["ttyUSB1", "ttyUSB0"] {
+        let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+        connection_manager
+            .add_connection(id.into(), Box::new(fake_connection))
+            .await
+            .expect("add_connection should succeed");
+    }
+    assert_eq!(
+        connection_manager.list_connections().await,
+        ["ttyUSB0", "ttyUSB1"]
+    );
+    assert!(connection_manager.contains("ttyUSB0").await);
+    assert!(!connection_manager.contains("ttyUSB2").await);
+
+    connection_manager.rename("ttyUSB1", "esp32").await.unwrap();
+    assert_eq!(
+        connection_manager.list_connections().await,
+        ["esp32", "ttyUSB0"]
+    );
+    assert!(!connection_manager.contains("ttyUSB1").await);
+
+    connection_manager.stop_connection("ttyUSB0").await.unwrap();
+    assert_eq!(connection_manager.list_connections().await, ["esp32"]);
+    assert!(!connection_manager.contains("ttyUSB0").await);
+
+    connection_manager.stop_connection("esp32").await.unwrap();
+    assert!(connection_manager.list_connections().await.is_empty());
+}
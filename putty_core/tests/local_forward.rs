@@ -0,0 +1,209 @@
+#![cfg(feature = "ssh")]
+
+//! `ssh -L` style forwarding through an in-process russh server, which
+//! connects direct-tcpip channels to a local echo server.
+
+use putty_core::connections::forward::{LocalForward, RemoteForward};
+use putty_core::connections::ssh::SshConnection;
+use putty_core::ConnectionManager;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, MethodKind, MethodSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+mod common;
+use common::fake_connection::FakeConnection;
+
+const PASSWORD: &str = "hunter2";
+
+struct Gateway;
+
+impl server::Handler for Gateway {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, _user: &str, password: &str) -> Result<Auth, Self::Error> {
+        Ok(if password == PASSWORD {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn channel_open_direct_tcpip(
+        &mut self,
+        channel: Channel<Msg>,
+        host_to_connect: &str,
+        port_to_connect: u32,
+        _originator_address: &str,
+        _originator_port: u32,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        let target = format!("{host_to_connect}:{port_to_connect}");
+        tokio::spawn(async move {
+            let mut socket = TcpStream::connect(target).await.unwrap();
+            let mut stream = channel.into_stream();
+            let _ = tokio::io::copy_bidirectional(&mut socket, &mut stream).await;
+        });
+        Ok(true)
+    }
+}
+
+/// Serve one SSH connection; returns the port.
+async fn spawn_gateway() -> u16 {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        methods: MethodSet::from(&[MethodKind::Password][..]),
+        ..Default::default()
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, Gateway).await.unwrap();
+        let _ = session.await;
+    });
+    port
+}
+
+/// Echo every connection back to itself; returns the port.
+async fn spawn_echo() -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let (mut rd, mut wr) = socket.split();
+                let _ = tokio::io::copy(&mut rd, &mut wr).await;
+            });
+        }
+    });
+    port
+}
+
+async fn echo_round_trip(socket: &mut TcpStream, message: &[u8]) {
+    socket.write_all(message).await.unwrap();
+    let mut reply = vec![0; message.len()];
+    timeout(Duration::from_secs(5), socket.read_exact(&mut reply))
+        .await
+        .expect("echo through the tunnel")
+        .unwrap();
+    assert_eq!(reply, message);
+}
+
+#[test]
+fn forward_specs_parse_like_ssh() {
+    assert_eq!(
+        LocalForward::parse("8080:internal:80").unwrap(),
+        LocalForward {
+            bind_address: "127.0.0.1".into(),
+            bind_port: 8080,
+            host: "internal".into(),
+            port: 80,
+        }
+    );
+    let forward: LocalForward = "0.0.0.0:8443:10.1.2.3:443".parse().unwrap();
+    assert_eq!(forward.bind_address, "0.0.0.0");
+    assert_eq!(forward.to_string(), "0.0.0.0:8443:10.1.2.3:443");
+
+    for bad in [
+        "8080",
+        "8080:internal",
+        "http:internal:80",
+        "8080::80",
+        "1:2:3:4:5",
+    ] {
+        assert!(LocalForward::parse(bad).is_err(), "{bad} should not parse");
+    }
+}
+
+#[test]
+fn forward_specs_take_bracketed_ipv6_addresses() {
+    assert_eq!(
+        LocalForward::parse("[::1]:8080:internal:80").unwrap(),
+        LocalForward {
+            bind_address: "::1".into(),
+            bind_port: 8080,
+            host: "internal".into(),
+            port: 80,
+        }
+    );
+    let forward: LocalForward = "8443:[fd00::7]:443".parse().unwrap();
+    assert_eq!(forward.bind_address, "127.0.0.1");
+    assert_eq!(forward.host, "fd00::7");
+
+    let both = "[::]:8443:[2001:db8::2]:443";
+    let remote = RemoteForward::parse(both).unwrap();
+    assert_eq!(
+        (remote.bind_address.as_str(), remote.host.as_str()),
+        ("::", "2001:db8::2")
+    );
+    // Displayed the way it was given, so it parses back to the same forward.
+    assert_eq!(remote.to_string(), both);
+    assert_eq!(LocalForward::parse(both).unwrap().to_string(), both);
+
+    for bad in [
+        "::1:8080:internal:80",
+        "[::1:8080:internal:80",
+        "[::1]8080:internal:80",
+        "[]:8080:internal:80",
+        "8080:[]:80",
+    ] {
+        assert!(LocalForward::parse(bad).is_err(), "{bad} should not parse");
+    }
+}
+
+#[tokio::test]
+async fn concurrent_forwards_share_the_session_and_stop_with_it() {
+    let ssh_port = spawn_gateway().await;
+    let echo_port = spawn_echo().await;
+    let manager = ConnectionManager::new();
+    let conn = SshConnection::new("127.0.0.1".into(), ssh_port, "ops".into(), PASSWORD.into());
+    manager
+        .add_connection("gw".into(), Box::new(conn))
+        .await
+        .expect("ssh login");
+
+    let forward = LocalForward::parse(&format!("0:127.0.0.1:{echo_port}")).unwrap();
+    let local = manager.local_forward("gw", forward).await.unwrap();
+    assert_ne!(local.port(), 0);
+
+    let mut first = TcpStream::connect(local).await.unwrap();
+    let mut second = TcpStream::connect(local).await.unwrap();
+    echo_round_trip(&mut first, b"first").await;
+    echo_round_trip(&mut second, b"second").await;
+    echo_round_trip(&mut first, b"first again").await;
+
+    // ── Stopping the connection closes the tunnels and the listener ──────
+    manager.stop_connection("gw").await.unwrap();
+    let mut rest = Vec::new();
+    let closed = timeout(Duration::from_secs(5), first.read_to_end(&mut rest)).await;
+    assert!(closed.is_ok(), "forwarded socket should be closed");
+    assert!(TcpStream::connect(local).await.is_err());
+}
+
+#[tokio::test]
+async fn transports_without_tunnels_refuse_to_forward() {
+    let manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    manager
+        .add_connection("fake".into(), Box::new(fake_connection))
+        .await
+        .unwrap();
+
+    let forward = LocalForward::parse("0:internal:80").unwrap();
+    assert!(manager.local_forward("fake", forward).await.is_err());
+}
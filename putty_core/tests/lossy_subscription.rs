@@ -0,0 +1,58 @@
+use putty_core::{ConnectionManager, SubscriptionItem};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+/// Chunks a subscriber may fall behind before it loses data.
+const BROADCAST_CAPACITY: usize = 256;
+
+#[tokio::test]
+async fn lossy_subscription_reports_lag_and_keeps_flowing() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("burst".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    let mut slow = connection_manager.subscribe_lossy("burst").await.unwrap();
+    let mut fast = connection_manager.subscribe("burst").await.unwrap();
+
+    // A burst the slow subscriber does not keep up with, paced by the fast one.
+    let burst = BROADCAST_CAPACITY + 44;
+    for i in 0..burst {
+        test_to_fake_tx
+            .send(format!("{i:03}").into_bytes())
+            .await
+            .unwrap();
+        timeout(Duration::from_secs(1), fast.recv())
+            .await
+            .expect("timeout waiting for the burst")
+            .unwrap();
+    }
+
+    assert_eq!(
+        slow.recv().await,
+        Some(SubscriptionItem::Lagged { missed: 44 })
+    );
+    assert_eq!(
+        slow.recv().await,
+        Some(SubscriptionItem::Data(b"044".to_vec()))
+    );
+
+    // Once caught up, the stream carries on as usual.
+    for _ in 45..burst {
+        assert!(matches!(slow.recv().await, Some(SubscriptionItem::Data(_))));
+    }
+    test_to_fake_tx.send(b"after".to_vec()).await.unwrap();
+    let item = timeout(Duration::from_secs(1), slow.recv())
+        .await
+        .expect("timeout waiting for data after the lag");
+    assert_eq!(item, Some(SubscriptionItem::Data(b"after".to_vec())));
+
+    connection_manager.stop_connection("burst").await.unwrap();
+    assert_eq!(slow.recv().await, None);
+}
@@ -0,0 +1,65 @@
+use putty_core::{ConnectionEventKind, ConnectionManager, ConnectionOptions, DisconnectReason};
+use std::time::Instant;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn busy_session_is_stopped_after_max_duration() {
+    init_logging();
+
+    let max_session = Duration::from_millis(150);
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    let started = Instant::now();
+    connection_manager
+        .add_connection_with_options(
+            "lab".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_max_session(max_session),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    // ── Keep the session busy in both directions ─────────────────────────
+    let manager = connection_manager.clone();
+    let chatter = tokio::spawn(async move {
+        loop {
+            if test_to_fake_tx.send(b"tick".to_vec()).await.is_err() {
+                break;
+            }
+            let _ = manager.write_bytes("lab", b"tock").await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    });
+
+    let mut kinds = Vec::new();
+    timeout(Duration::from_secs(2), async {
+        loop {
+            let kind = events.recv().await.expect("event channel closed").kind;
+            let closed = kind == ConnectionEventKind::Closed;
+            kinds.push(kind);
+            if closed {
+                break;
+            }
+        }
+    })
+    .await
+    .expect("session should end despite the traffic");
+    chatter.abort();
+
+    assert!(started.elapsed() >= max_session);
+    let ending = &kinds[kinds.len() - 2..];
+    assert_eq!(
+        ending,
+        [
+            ConnectionEventKind::Disconnected {
+                reason: DisconnectReason::MaxDuration
+            },
+            ConnectionEventKind::Closed,
+        ]
+    );
+}
@@ -0,0 +1,58 @@
+use putty_core::{ConnectionManager, ConnectionOptions};
+use tokio::time::{sleep, timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn metrics_count_bytes_in_and_out() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "dut".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_crlf(true),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("dut").await.unwrap();
+
+    let start = connection_manager.metrics("dut").await.unwrap();
+    assert_eq!((start.bytes_in, start.bytes_out), (0, 0));
+
+    // Counted as passed in: "ls\n" is 3 bytes even though "ls\r\n" is sent.
+    connection_manager
+        .write_bytes_acked("dut", b"ls\n")
+        .await
+        .unwrap();
+    connection_manager
+        .write_raw("dut", b"\x03\x04")
+        .await
+        .unwrap();
+
+    for chunk in [b"hello ".as_slice(), b"world"] {
+        test_to_fake_tx.send(chunk.to_vec()).await.unwrap();
+        timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("timeout waiting for the echo")
+            .unwrap();
+    }
+
+    sleep(Duration::from_millis(10)).await;
+    let now = connection_manager.metrics("dut").await.unwrap();
+    assert_eq!(now.bytes_in, 11);
+    assert_eq!(now.bytes_out, 5);
+    assert_eq!(now.connected_at, start.connected_at);
+    assert!(now.at > start.at);
+
+    let (rate_in, rate_out) = now.rates_since(&start);
+    assert!(rate_in > 0.0 && rate_out > 0.0);
+    assert!(rate_in > rate_out);
+    assert_eq!(now.rates_since(&now), (0.0, 0.0));
+
+    assert!(connection_manager.metrics("missing").await.is_none());
+}
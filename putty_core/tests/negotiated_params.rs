@@ -0,0 +1,36 @@
+use putty_core::{ConnectionEventKind, ConnectionManager};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn opened_event_carries_negotiated_params() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+
+    connection_manager
+        .add_connection("fakePort".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let event = timeout(Duration::from_millis(100), events.recv())
+        .await
+        .expect("timeout waiting for Opened")
+        .expect("event channel closed unexpectedly");
+    assert_eq!(event.id, "fakePort");
+    let ConnectionEventKind::Opened(params) = event.kind else {
+        panic!("expected Opened, got {:?}", event.kind);
+    };
+    assert_eq!(params.get("device").map(String::as_str), Some("fake"));
+
+    // The same parameters stay queryable after the event.
+    assert_eq!(
+        connection_manager.negotiated_params("fakePort").await,
+        Some(params)
+    );
+}
@@ -0,0 +1,158 @@
+use putty_core::{ConnectionManager, ConnectionOptions};
+use tokio::time::{sleep, timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+/// Reads go to the scrollback even while paused, so it tells us when the
+/// I/O task has taken `len` bytes off the connection.
+async fn wait_for_scrollback(connection_manager: &ConnectionManager, id: &str, len: usize) {
+    timeout(Duration::from_secs(1), async {
+        while connection_manager.scrollback(id).await.unwrap().len() < len {
+            sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("timeout waiting for the scrollback");
+}
+
+#[tokio::test]
+async fn paused_data_is_held_until_resume() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "console".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_scrollback(1024),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("console").await.unwrap();
+
+    connection_manager.pause("console").await.unwrap();
+    test_to_fake_tx.send(b"held ".to_vec()).await.unwrap();
+    test_to_fake_tx.send(b"back".to_vec()).await.unwrap();
+    wait_for_scrollback(&connection_manager, "console", 9).await;
+    assert!(
+        timeout(Duration::from_millis(100), rx.recv())
+            .await
+            .is_err(),
+        "nothing should be published while paused"
+    );
+
+    // ── Writes still go out while paused ────────────────────────────────
+    connection_manager
+        .write_bytes("console", b"ping")
+        .await
+        .unwrap();
+    let sent = timeout(Duration::from_secs(1), fake_to_test_rx.recv())
+        .await
+        .expect("write while paused")
+        .unwrap();
+    assert_eq!(sent, b"ping");
+
+    assert_eq!(connection_manager.resume("console").await.unwrap(), 0);
+    let data = timeout(Duration::from_secs(1), rx.recv())
+        .await
+        .expect("held data on resume")
+        .unwrap();
+    assert_eq!(data, b"held back");
+
+    // ── Back to normal afterwards ───────────────────────────────────────
+    test_to_fake_tx.send(b"live".to_vec()).await.unwrap();
+    let data = timeout(Duration::from_secs(1), rx.recv())
+        .await
+        .expect("live data after resume")
+        .unwrap();
+    assert_eq!(data, b"live");
+
+    connection_manager.stop_connection("console").await.unwrap();
+}
+
+#[tokio::test]
+async fn data_beyond_the_pause_buffer_is_dropped_and_counted() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "console".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new()
+                .with_scrollback(1024)
+                .with_pause_buffer(4),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("console").await.unwrap();
+
+    connection_manager.pause("console").await.unwrap();
+    test_to_fake_tx.send(b"abc".to_vec()).await.unwrap();
+    test_to_fake_tx.send(b"def".to_vec()).await.unwrap();
+    wait_for_scrollback(&connection_manager, "console", 6).await;
+
+    assert_eq!(connection_manager.resume("console").await.unwrap(), 2);
+    let data = timeout(Duration::from_secs(1), rx.recv())
+        .await
+        .expect("held data on resume")
+        .unwrap();
+    assert_eq!(data, b"abcd");
+    assert_eq!(
+        connection_manager.scrollback("console").await.unwrap(),
+        b"abcdef",
+        "the scrollback keeps everything"
+    );
+
+    // The count starts over with the next pause.
+    assert_eq!(connection_manager.resume("console").await.unwrap(), 0);
+
+    connection_manager.stop_connection("console").await.unwrap();
+}
+
+#[tokio::test]
+async fn held_data_is_published_before_close() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "console".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_scrollback(1024),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("console").await.unwrap();
+
+    connection_manager.pause("console").await.unwrap();
+    test_to_fake_tx.send(b"last words".to_vec()).await.unwrap();
+    wait_for_scrollback(&connection_manager, "console", 10).await;
+    connection_manager.stop_connection("console").await.unwrap();
+
+    let data = timeout(Duration::from_secs(1), rx.recv())
+        .await
+        .expect("held data before close")
+        .unwrap();
+    assert_eq!(data, b"last words");
+}
+
+#[tokio::test]
+async fn pause_of_an_unknown_connection_fails() {
+    let connection_manager = ConnectionManager::new();
+    let err = connection_manager.pause("nope").await.unwrap_err();
+    assert!(
+        err.to_string().contains("No connection with id 'nope'"),
+        "{err}"
+    );
+    let err = connection_manager.resume("nope").await.unwrap_err();
+    assert!(
+        err.to_string().contains("No connection with id 'nope'"),
+        "{err}"
+    );
+}
@@ -0,0 +1,211 @@
+#![cfg(feature = "ssh")]
+
+//! `ssh -J` style connections: an in-process russh bastion tunnels
+//! direct-tcpip channels to an in-process target whose shell echoes.
+
+use putty_core::connections::connection::Connection;
+use putty_core::connections::errors::ConnectionError;
+use putty_core::connections::ssh::SshConnection;
+use putty_core::ConnectionManager;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+
+/// Accepts `user` with `password` only. With `tunnels`, connects
+/// direct-tcpip channels and records their targets; otherwise refuses them.
+struct Host {
+    user: &'static str,
+    password: &'static str,
+    tunnels: Option<Arc<Mutex<Vec<String>>>>,
+    /// Channels running a shell; only those echo.
+    shells: HashSet<ChannelId>,
+}
+
+impl server::Handler for Host {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, user: &str, password: &str) -> Result<Auth, Self::Error> {
+        Ok(if (user, password) == (self.user, self.password) {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.shells.insert(channel);
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if !self.shells.contains(&channel) {
+            return Ok(());
+        }
+        let reply = [format!("{}: ", self.user).as_bytes(), data].concat();
+        session.data(channel, reply)?;
+        Ok(())
+    }
+
+    async fn channel_open_direct_tcpip(
+        &mut self,
+        channel: Channel<Msg>,
+        host_to_connect: &str,
+        port_to_connect: u32,
+        _originator_address: &str,
+        _originator_port: u32,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        let Some(tunnels) = &self.tunnels else {
+            return Ok(false);
+        };
+        let target = format!("{host_to_connect}:{port_to_connect}");
+        tunnels.lock().unwrap().push(target.clone());
+        tokio::spawn(async move {
+            let mut socket = TcpStream::connect(target).await.unwrap();
+            let mut stream = channel.into_stream();
+            let _ = tokio::io::copy_bidirectional(&mut socket, &mut stream).await;
+        });
+        Ok(true)
+    }
+}
+
+/// Serve one SSH connection with `handler`; returns the port.
+async fn spawn_host(handler: Host) -> u16 {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        methods: MethodSet::from(&[MethodKind::Password][..]),
+        auth_rejection_time: Duration::ZERO,
+        auth_rejection_time_initial: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, handler).await.unwrap();
+        let _ = session.await;
+    });
+    port
+}
+
+async fn spawn_target() -> u16 {
+    spawn_host(Host {
+        user: "ops",
+        password: "target-pw",
+        tunnels: None,
+        shells: HashSet::new(),
+    })
+    .await
+}
+
+fn bastion(port: u16, password: &str) -> SshConnection {
+    SshConnection::new("127.0.0.1".into(), port, "jumper".into(), password.into())
+}
+
+fn target(port: u16) -> SshConnection {
+    SshConnection::new("127.0.0.1".into(), port, "ops".into(), "target-pw".into())
+}
+
+#[tokio::test]
+async fn session_runs_through_the_jump_host_with_its_own_login() {
+    let target_port = spawn_target().await;
+    let tunnels = Arc::new(Mutex::new(Vec::new()));
+    let bastion_port = spawn_host(Host {
+        user: "jumper",
+        password: "bastion-pw",
+        tunnels: Some(tunnels.clone()),
+        shells: HashSet::new(),
+    })
+    .await;
+
+    let conn = target(target_port).with_jump(bastion(bastion_port, "bastion-pw"));
+    let manager = ConnectionManager::new();
+    manager
+        .add_connection("prod".into(), Box::new(conn))
+        .await
+        .expect("login through the bastion");
+    let mut rx = manager.subscribe("prod").await.unwrap();
+
+    manager.write_bytes("prod", b"uptime\n").await.unwrap();
+    let reply = timeout(Duration::from_secs(5), rx.recv())
+        .await
+        .expect("reply from the target")
+        .unwrap();
+    assert_eq!(reply, b"ops: uptime\n");
+
+    assert_eq!(
+        *tunnels.lock().unwrap(),
+        [format!("127.0.0.1:{target_port}")]
+    );
+    let negotiated = manager.negotiated_params("prod").await.unwrap();
+    assert_eq!(
+        negotiated["jump"],
+        format!("jumper@127.0.0.1:{bastion_port}")
+    );
+    manager.stop_connection("prod").await.unwrap();
+}
+
+#[tokio::test]
+async fn wrong_bastion_password_names_the_jump_host() {
+    let target_port = spawn_target().await;
+    let bastion_port = spawn_host(Host {
+        user: "jumper",
+        password: "bastion-pw",
+        tunnels: Some(Arc::default()),
+        shells: HashSet::new(),
+    })
+    .await;
+
+    let mut conn = target(target_port).with_jump(bastion(bastion_port, "guess"));
+    let err = conn.connect().await.unwrap_err();
+    assert!(matches!(err, ConnectionError::AuthError(_)), "{err:?}");
+    assert!(err.to_string().contains("jump host 127.0.0.1"), "{err}");
+}
+
+#[tokio::test]
+async fn bastion_refusing_the_tunnel_is_a_connect_error() {
+    let target_port = spawn_target().await;
+    let bastion_port = spawn_host(Host {
+        user: "jumper",
+        password: "bastion-pw",
+        tunnels: None,
+        shells: HashSet::new(),
+    })
+    .await;
+
+    let mut conn = target(target_port).with_jump(bastion(bastion_port, "bastion-pw"));
+    let err = conn.connect().await.unwrap_err();
+    assert!(
+        matches!(err, ConnectionError::TcpConnectError(_)),
+        "{err:?}"
+    );
+    assert!(
+        err.to_string()
+            .contains(&format!("cannot reach 127.0.0.1:{target_port}")),
+        "{err}"
+    );
+}
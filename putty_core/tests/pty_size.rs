@@ -0,0 +1,49 @@
+use putty_core::ConnectionManager;
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn pty_size_follows_resize() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+
+    // Keep the device-side senders alive so the I/O tasks keep running.
+    let (mut pty_connection, _pty_tx, _pty_rx) = FakeConnection::new();
+    pty_connection.pty = Some((80, 24));
+    let (plain_connection, _plain_tx, _plain_rx) = FakeConnection::new();
+
+    connection_manager
+        .add_connection("pty".into(), Box::new(pty_connection))
+        .await
+        .expect("adding pty should succeed");
+    connection_manager
+        .add_connection("plain".into(), Box::new(plain_connection))
+        .await
+        .expect("adding plain should succeed");
+
+    // ── Initial size comes from the transport ────────────────────────────
+    assert_eq!(connection_manager.pty_size("pty").await, Some((80, 24)));
+    assert_eq!(connection_manager.pty_size("plain").await, None);
+
+    // ── After a resize the queried size matches ──────────────────────────
+    connection_manager
+        .resize("pty", 132, 43)
+        .await
+        .expect("resize should succeed");
+    assert_eq!(connection_manager.pty_size("pty").await, Some((132, 43)));
+
+    // ── Non-PTY connections accept the resize but stay without a size ───
+    connection_manager
+        .resize("plain", 132, 43)
+        .await
+        .expect("resize of a non-PTY connection is a no-op");
+    assert_eq!(connection_manager.pty_size("plain").await, None);
+
+    connection_manager
+        .resize("missing", 1, 1)
+        .await
+        .expect_err("resizing an unknown id should fail");
+}
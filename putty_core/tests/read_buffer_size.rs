@@ -0,0 +1,70 @@
+use putty_core::connections::tcp::RawTcpConnection;
+use putty_core::{ConnectionManager, ConnectionOptions, DEFAULT_READ_BUFFER_SIZE};
+use std::num::NonZeroUsize;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::init_logging;
+
+/// Size of the contiguous input, already queued on the socket before the
+/// manager starts reading.
+const INPUT: usize = 64 * 1024;
+
+/// Feed `INPUT` bytes through a connection with `options` and return the
+/// size of every chunk subscribers received.
+async fn chunk_sizes(options: ConnectionOptions) -> Vec<usize> {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap())
+        .await
+        .unwrap();
+    let (mut device, _) = listener.accept().await.unwrap();
+    device.write_all(&vec![b'x'; INPUT]).await.unwrap();
+
+    let connection_manager = ConnectionManager::new();
+    connection_manager
+        .add_connection_with_options(
+            "bulk".into(),
+            Box::new(RawTcpConnection::from_stream(client)),
+            options,
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("bulk").await.unwrap();
+
+    let mut sizes = Vec::new();
+    let mut received = 0;
+    while received < INPUT {
+        let chunk = timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .expect("timeout waiting for data")
+            .unwrap();
+        received += chunk.len();
+        sizes.push(chunk.len());
+    }
+    assert_eq!(received, INPUT);
+    connection_manager.stop_connection("bulk").await.unwrap();
+    sizes
+}
+
+#[tokio::test]
+async fn larger_read_buffer_means_fewer_broadcasts() {
+    init_logging();
+
+    let default = chunk_sizes(ConnectionOptions::new()).await;
+    assert!(default.iter().all(|&n| n <= DEFAULT_READ_BUFFER_SIZE));
+    assert!(default.len() >= INPUT / DEFAULT_READ_BUFFER_SIZE);
+
+    let large = chunk_sizes(
+        ConnectionOptions::new().with_read_buffer_size(NonZeroUsize::new(8192).unwrap()),
+    )
+    .await;
+    assert!(large.iter().all(|&n| n <= 8192));
+    assert!(
+        large.len() * 8 <= default.len(),
+        "{} broadcasts with 8 KiB reads vs {} with the default",
+        large.len(),
+        default.len()
+    );
+}
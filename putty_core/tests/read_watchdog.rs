@@ -0,0 +1,71 @@
+use putty_core::{ConnectionEvent, ConnectionEventKind, ConnectionManager, ConnectionOptions};
+use tokio::sync::broadcast;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+/// Wait for the next `Stalled` event, skipping `Opened`.
+async fn next_stall(
+    events: &mut broadcast::Receiver<ConnectionEvent>,
+    within: Duration,
+) -> Option<Duration> {
+    timeout(within, async {
+        loop {
+            if let ConnectionEventKind::Stalled { silent_for } = events.recv().await.unwrap().kind {
+                return silent_for;
+            }
+        }
+    })
+    .await
+    .ok()
+}
+
+#[tokio::test]
+async fn silent_transport_triggers_a_stall_warning() {
+    init_logging();
+
+    let window = Duration::from_millis(50);
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+
+    connection_manager
+        .add_connection_with_options(
+            "telemetry".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_read_watchdog(window),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    // ── The fake says nothing: one warning, not one per window ───────────
+    let silent_for = next_stall(&mut events, Duration::from_secs(1))
+        .await
+        .expect("stall warning should fire");
+    assert!(silent_for >= window);
+    assert!(next_stall(&mut events, window * 3).await.is_none());
+
+    // ── Data re-arms the watchdog ────────────────────────────────────────
+    test_to_fake_tx.send(b"tick".to_vec()).await.unwrap();
+    assert!(next_stall(&mut events, Duration::from_secs(1))
+        .await
+        .is_some());
+}
+
+#[tokio::test]
+async fn idle_connection_without_watchdog_stays_quiet() {
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+
+    connection_manager
+        .add_connection("console".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    assert!(next_stall(&mut events, Duration::from_millis(150))
+        .await
+        .is_none());
+}
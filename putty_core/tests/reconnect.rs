@@ -0,0 +1,166 @@
+use putty_core::{
+    ConnectionEvent, ConnectionEventKind, ConnectionManager, ConnectionOptions, ConnectionState,
+    EofPolicy, ReconnectPolicy,
+};
+use std::sync::atomic::Ordering;
+use tokio::sync::broadcast;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+/// EOF ends the session, so an empty chunk from the test loses the transport.
+fn options(policy: ReconnectPolicy) -> ConnectionOptions {
+    ConnectionOptions::new()
+        .with_eof_policy(EofPolicy::CloseOnEof)
+        .with_reconnect(policy.with_backoff(Duration::from_millis(10), Duration::from_millis(20)))
+        .with_scrollback(1024)
+}
+
+async fn next_event(events: &mut broadcast::Receiver<ConnectionEvent>) -> ConnectionEventKind {
+    timeout(Duration::from_secs(1), events.recv())
+        .await
+        .expect("timeout waiting for an event")
+        .unwrap()
+        .kind
+}
+
+#[tokio::test]
+async fn lost_transport_is_reconnected_and_subscribers_carry_on() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    let disconnects = fake_connection.disconnects.clone();
+    connection_manager
+        .add_connection_with_options(
+            "dut".into(),
+            Box::new(fake_connection),
+            options(ReconnectPolicy::new()),
+        )
+        .await
+        .expect("add_connection should succeed");
+    assert!(matches!(
+        next_event(&mut events).await,
+        ConnectionEventKind::Opened(_)
+    ));
+    let mut rx = connection_manager.subscribe("dut").await.unwrap();
+
+    test_to_fake_tx.send(b"before".to_vec()).await.unwrap();
+    test_to_fake_tx.send(Vec::new()).await.unwrap();
+    assert_eq!(
+        next_event(&mut events).await,
+        ConnectionEventKind::Reconnecting
+    );
+    assert!(matches!(
+        next_event(&mut events).await,
+        ConnectionEventKind::Reconnected(_)
+    ));
+    assert_eq!(disconnects.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        connection_manager.status("dut").await,
+        Some(ConnectionState::Connected)
+    );
+
+    // The subscription from before the drop carries on.
+    test_to_fake_tx.send(b"after".to_vec()).await.unwrap();
+    for expected in [b"before".as_slice(), b"after"] {
+        let chunk = timeout(Duration::from_secs(1), rx.recv())
+            .await
+            .expect("timeout waiting for data")
+            .unwrap();
+        assert_eq!(chunk, expected);
+    }
+    assert_eq!(
+        connection_manager.scrollback("dut").await.unwrap(),
+        b"before\r\n--- reconnected ---\r\nafter"
+    );
+
+    connection_manager.stop_connection("dut").await.unwrap();
+}
+
+#[tokio::test]
+async fn stop_interrupts_reconnecting() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (mut fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.fail_reconnects = true;
+    connection_manager
+        .add_connection_with_options(
+            "dut".into(),
+            Box::new(fake_connection),
+            options(ReconnectPolicy::new()),
+        )
+        .await
+        .expect("add_connection should succeed");
+    next_event(&mut events).await;
+
+    test_to_fake_tx.send(Vec::new()).await.unwrap();
+    assert_eq!(
+        next_event(&mut events).await,
+        ConnectionEventKind::Reconnecting
+    );
+    assert_eq!(
+        connection_manager.status("dut").await,
+        Some(ConnectionState::Reconnecting)
+    );
+    let err = connection_manager
+        .write_bytes_acked("dut", b"hello")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("reconnecting"), "{err}");
+    let err = connection_manager
+        .write_bytes("dut", b"hello")
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("reconnecting"), "{err}");
+
+    timeout(
+        Duration::from_secs(1),
+        connection_manager.stop_connection("dut"),
+    )
+    .await
+    .expect("stop should not wait for the reconnect policy")
+    .unwrap();
+}
+
+#[tokio::test]
+async fn reconnecting_gives_up_after_max_attempts() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    let (mut fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.fail_reconnects = true;
+    connection_manager
+        .add_connection_with_options(
+            "dut".into(),
+            Box::new(fake_connection),
+            options(ReconnectPolicy::new().with_max_attempts(3)),
+        )
+        .await
+        .expect("add_connection should succeed");
+    next_event(&mut events).await;
+    let mut rx = connection_manager.subscribe("dut").await.unwrap();
+
+    test_to_fake_tx.send(Vec::new()).await.unwrap();
+    assert_eq!(
+        next_event(&mut events).await,
+        ConnectionEventKind::Reconnecting
+    );
+    assert_eq!(next_event(&mut events).await, ConnectionEventKind::Closed);
+    match connection_manager.status("dut").await {
+        Some(ConnectionState::Failed(reason)) => {
+            assert!(reason.contains("fake reconnect failure"), "{reason}")
+        }
+        other => panic!("expected a failed connection, got {other:?}"),
+    }
+    assert!(
+        rx.recv().await.is_err(),
+        "subscription should close once the policy gives up"
+    );
+}
@@ -0,0 +1,205 @@
+#![cfg(feature = "ssh")]
+
+//! `ssh -R` style forwarding against an in-process russh server that listens
+//! on the requested ports and tunnels what it accepts back to the client.
+
+use putty_core::connections::connection::Connection;
+use putty_core::connections::forward::RemoteForward;
+use putty_core::connections::ssh::SshConnection;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, MethodKind, MethodSet};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+const PASSWORD: &str = "hunter2";
+
+/// What the server did with forward requests, as seen by the test.
+#[derive(Debug, PartialEq, Eq)]
+enum Forwarding {
+    Listening(u32),
+    Cancelled(u32),
+}
+
+struct Server {
+    allow_forwarding: bool,
+    listeners: HashMap<u32, JoinHandle<()>>,
+    report: mpsc::UnboundedSender<Forwarding>,
+}
+
+impl server::Handler for Server {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, _user: &str, password: &str) -> Result<Auth, Self::Error> {
+        Ok(if password == PASSWORD {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn tcpip_forward(
+        &mut self,
+        address: &str,
+        port: &mut u32,
+        session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        if !self.allow_forwarding {
+            return Ok(false);
+        }
+        let listener = TcpListener::bind(("127.0.0.1", *port as u16)).await?;
+        *port = listener.local_addr()?.port().into();
+        let (address, bound) = (address.to_owned(), *port);
+        let handle = session.handle();
+        self.listeners.insert(
+            bound,
+            tokio::spawn(async move {
+                loop {
+                    let (mut socket, peer) = listener.accept().await.unwrap();
+                    let channel = handle
+                        .channel_open_forwarded_tcpip(
+                            address.clone(),
+                            bound,
+                            peer.ip().to_string(),
+                            peer.port().into(),
+                        )
+                        .await
+                        .unwrap();
+                    tokio::spawn(async move {
+                        let mut stream = channel.into_stream();
+                        let _ = tokio::io::copy_bidirectional(&mut socket, &mut stream).await;
+                    });
+                }
+            }),
+        );
+        let _ = self.report.send(Forwarding::Listening(bound));
+        Ok(true)
+    }
+
+    async fn cancel_tcpip_forward(
+        &mut self,
+        _address: &str,
+        port: u32,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        let Some(listener) = self.listeners.remove(&port) else {
+            return Ok(false);
+        };
+        listener.abort();
+        let _ = self.report.send(Forwarding::Cancelled(port));
+        Ok(true)
+    }
+}
+
+/// Serve one SSH connection; returns its port and the forwarding reports.
+async fn spawn_server(allow_forwarding: bool) -> (u16, mpsc::UnboundedReceiver<Forwarding>) {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        methods: MethodSet::from(&[MethodKind::Password][..]),
+        ..Default::default()
+    });
+    let (report, reports) = mpsc::unbounded_channel();
+    let handler = Server {
+        allow_forwarding,
+        listeners: HashMap::new(),
+        report,
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, handler).await.unwrap();
+        let _ = session.await;
+    });
+    (port, reports)
+}
+
+/// A local service answering every line with `name: line`; returns its port.
+async fn spawn_service(name: &'static str) -> u16 {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        loop {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            tokio::spawn(async move {
+                let mut buf = [0; 64];
+                while let Ok(n @ 1..) = socket.read(&mut buf).await {
+                    let reply = [name.as_bytes(), b": ", &buf[..n]].concat();
+                    if socket.write_all(&reply).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+    port
+}
+
+async fn ask(port: u32, question: &[u8]) -> Vec<u8> {
+    let mut socket = TcpStream::connect(("127.0.0.1", port as u16))
+        .await
+        .unwrap();
+    socket.write_all(question).await.unwrap();
+    let mut reply = vec![0; 64];
+    let n = timeout(Duration::from_secs(5), socket.read(&mut reply))
+        .await
+        .expect("reply through the tunnel")
+        .unwrap();
+    reply.truncate(n);
+    reply
+}
+
+#[tokio::test]
+async fn server_side_connections_reach_the_local_services() {
+    let (ssh_port, mut reports) = spawn_server(true).await;
+    let web = spawn_service("web").await;
+    let api = spawn_service("api").await;
+
+    let mut conn = SshConnection::new("127.0.0.1".into(), ssh_port, "ops".into(), PASSWORD.into())
+        .with_remote_forward(RemoteForward::parse(&format!("0:127.0.0.1:{web}")).unwrap())
+        .with_remote_forward(RemoteForward::parse(&format!("0:127.0.0.1:{api}")).unwrap());
+    conn.connect()
+        .await
+        .expect("ssh login with remote forwards");
+
+    let Some(Forwarding::Listening(web_port)) = reports.recv().await else {
+        panic!("server should listen for the first forward");
+    };
+    let Some(Forwarding::Listening(api_port)) = reports.recv().await else {
+        panic!("server should listen for the second forward");
+    };
+    assert!(conn.negotiated()["remote_forwards"].contains(&format!("localhost:{web_port}")));
+
+    assert_eq!(ask(web_port, b"hi").await, b"web: hi");
+    assert_eq!(ask(api_port, b"hi").await, b"api: hi");
+
+    conn.disconnect().await.unwrap();
+    assert_eq!(reports.recv().await, Some(Forwarding::Cancelled(web_port)));
+    assert_eq!(reports.recv().await, Some(Forwarding::Cancelled(api_port)));
+}
+
+#[tokio::test]
+async fn refused_remote_forward_fails_connect_with_a_reason() {
+    let (ssh_port, _reports) = spawn_server(false).await;
+
+    let mut conn = SshConnection::new("127.0.0.1".into(), ssh_port, "ops".into(), PASSWORD.into())
+        .with_remote_forward(RemoteForward::parse("0.0.0.0:9000:localhost:3000").unwrap());
+    let err = conn.connect().await.unwrap_err().to_string();
+    assert!(err.contains("refused remote forward"), "{err}");
+    assert!(err.contains("GatewayPorts"), "{err}");
+}
@@ -0,0 +1,103 @@
+use putty_core::{ConnectionEvent, ConnectionEventKind, ConnectionManager};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn renamed_connection_answers_only_to_its_new_id() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("/dev/ttyUSB0".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let mut old_subscription = connection_manager.subscribe("/dev/ttyUSB0").await.unwrap();
+    let mut events = connection_manager.events();
+
+    connection_manager
+        .rename("/dev/ttyUSB0", "esp32-lab")
+        .await
+        .expect("rename should succeed");
+
+    assert_eq!(
+        events.recv().await.unwrap(),
+        ConnectionEvent {
+            id: "esp32-lab".into(),
+            kind: ConnectionEventKind::Renamed {
+                old_id: "/dev/ttyUSB0".into()
+            },
+        }
+    );
+
+    // ── Operations work under the new id ─────────────────────────────────
+    connection_manager
+        .write_bytes_acked("esp32-lab", b"ping")
+        .await
+        .expect("write under the new id should succeed");
+    let written = timeout(Duration::from_millis(200), fake_to_test_rx.recv())
+        .await
+        .expect("timeout waiting for the write")
+        .unwrap();
+    assert_eq!(written, b"ping");
+    assert!(connection_manager.subscribe("esp32-lab").await.is_some());
+
+    // ── ... and fail under the old one ───────────────────────────────────
+    assert!(connection_manager
+        .write_bytes("/dev/ttyUSB0", b"ping")
+        .await
+        .is_err());
+    assert!(connection_manager.subscribe("/dev/ttyUSB0").await.is_none());
+    assert!(connection_manager
+        .stop_connection("/dev/ttyUSB0")
+        .await
+        .is_err());
+
+    // ── Existing data subscriptions keep flowing ─────────────────────────
+    test_to_fake_tx.send(b"pong".to_vec()).await.unwrap();
+    let chunk = timeout(Duration::from_millis(200), old_subscription.recv())
+        .await
+        .expect("timeout waiting for data")
+        .unwrap();
+    assert_eq!(chunk, b"pong");
+
+    // ── Events from the I/O task carry the new id ────────────────────────
+    connection_manager
+        .stop_connection("esp32-lab")
+        .await
+        .unwrap();
+    let closed = timeout(Duration::from_millis(200), events.recv())
+        .await
+        .expect("timeout waiting for Closed")
+        .unwrap();
+    assert_eq!(closed.id, "esp32-lab");
+    assert_eq!(closed.kind, ConnectionEventKind::Closed);
+}
+
+#[tokio::test]
+async fn rename_refuses_unknown_and_taken_ids() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    for id in ["a", "b"] {
+        let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+        connection_manager
+            .add_connection(id.into(), Box::new(fake_connection))
+            .await
+            .expect("add_connection should succeed");
+    }
+
+    let err = connection_manager.rename("missing", "c").await.unwrap_err();
+    assert!(err.to_string().contains("No connection"), "{err}");
+
+    let err = connection_manager.rename("a", "b").await.unwrap_err();
+    assert!(err.to_string().contains("already exists"), "{err}");
+
+    // Both connections are still where they were.
+    assert!(connection_manager.subscribe("a").await.is_some());
+    assert!(connection_manager.subscribe("b").await.is_some());
+}
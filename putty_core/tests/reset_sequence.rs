@@ -0,0 +1,81 @@
+#![cfg(feature = "serial")]
+
+use putty_core::connections::errors::ConnectionError;
+use putty_core::connections::serial::{ModemLines, ResetSequence};
+use tokio::time::{Duration, Instant};
+
+/// Records every line change together with the time it happened.
+struct LineRecorder {
+    started: Instant,
+    ops: Vec<(&'static str, bool, Duration)>,
+}
+
+impl LineRecorder {
+    fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            ops: Vec::new(),
+        }
+    }
+}
+
+impl ModemLines for LineRecorder {
+    fn set_dtr(&mut self, level: bool) -> Result<(), ConnectionError> {
+        self.ops.push(("dtr", level, self.started.elapsed()));
+        Ok(())
+    }
+
+    fn set_rts(&mut self, level: bool) -> Result<(), ConnectionError> {
+        self.ops.push(("rts", level, self.started.elapsed()));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn esp32_sequence_toggles_lines_with_esptool_timing() {
+    let mut lines = LineRecorder::new();
+    ResetSequence::Esp32.run(&mut lines).await.unwrap();
+
+    let changes: Vec<_> = lines.ops.iter().map(|(l, v, _)| (*l, *v)).collect();
+    assert_eq!(
+        changes,
+        [
+            ("dtr", false),
+            ("rts", true),
+            ("dtr", true),
+            ("rts", false),
+            ("dtr", false),
+        ]
+    );
+
+    // EN is held low for 100 ms, IO0 stays low 50 ms after EN is released.
+    let at = |i: usize| lines.ops[i].2;
+    assert!(at(2) - at(1) >= Duration::from_millis(100));
+    assert!(at(4) - at(3) >= Duration::from_millis(50));
+}
+
+#[tokio::test]
+async fn arduino_sequence_drops_then_raises_both_lines() {
+    let mut lines = LineRecorder::new();
+    ResetSequence::Arduino.run(&mut lines).await.unwrap();
+
+    let changes: Vec<_> = lines.ops.iter().map(|(l, v, _)| (*l, *v)).collect();
+    assert_eq!(
+        changes,
+        [("dtr", false), ("rts", false), ("dtr", true), ("rts", true)]
+    );
+    assert!(lines.ops[2].2 - lines.ops[1].2 >= Duration::from_millis(250));
+}
+
+#[tokio::test]
+async fn none_sequence_leaves_lines_alone() {
+    let mut lines = LineRecorder::new();
+    ResetSequence::None.run(&mut lines).await.unwrap();
+    assert!(lines.ops.is_empty());
+
+    assert_eq!(
+        "ESP32".parse::<ResetSequence>().unwrap(),
+        ResetSequence::Esp32
+    );
+    assert!("stm32".parse::<ResetSequence>().is_err());
+}
@@ -0,0 +1,217 @@
+use putty_core::connections::telnet::codec::{DONT, IAC, WILL};
+use putty_core::connections::telnet::rfc2217::COM_PORT_OPTION;
+use putty_core::connections::telnet::{Rfc2217Connection, TelnetCodec};
+use putty_core::connections::{Connection, FlowControl, Framing, ModemStatus, Parity};
+use putty_core::{ConnectionManager, ConnectionState};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{timeout, Duration};
+
+/// A port server that has accepted the option and confirmed the settings.
+struct PortServer {
+    stream: TcpStream,
+    codec: TelnetCodec,
+    /// COM-PORT commands received, without the option byte.
+    commands: Vec<Vec<u8>>,
+    data: Vec<u8>,
+}
+
+impl PortServer {
+    /// Accept one client and confirm its five settings, answering the baud
+    /// rate with `baud_rate` as a server that cannot do the requested one.
+    async fn accept(listener: &TcpListener, baud_rate: u32) -> Self {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut codec = TelnetCodec::new();
+        codec.accept_remote(COM_PORT_OPTION);
+        let mut server = Self {
+            stream,
+            codec,
+            commands: Vec::new(),
+            data: Vec::new(),
+        };
+        while server.commands.len() < 5 {
+            server.read().await;
+        }
+        for command in server.commands.clone() {
+            let value = match command[0] {
+                1 => baud_rate.to_be_bytes().to_vec(),
+                _ => command[1..].to_vec(),
+            };
+            server.send_command(command[0] + 100, &value).await;
+        }
+        server
+    }
+
+    async fn read(&mut self) {
+        let mut buffer = [0u8; 256];
+        let read = timeout(Duration::from_secs(2), self.stream.read(&mut buffer))
+            .await
+            .expect("the client went quiet")
+            .unwrap();
+        assert!(read > 0, "the client hung up");
+        self.codec.decode(&buffer[..read], &mut self.data);
+        let replies = self.codec.take_replies();
+        self.stream.write_all(&replies).await.unwrap();
+        for sub in self.codec.take_subnegotiations() {
+            assert_eq!(sub[0], COM_PORT_OPTION);
+            self.commands.push(sub[1..].to_vec());
+        }
+    }
+
+    async fn send_command(&mut self, command: u8, value: &[u8]) {
+        let frame = TelnetCodec::subnegotiation(COM_PORT_OPTION, &[&[command], value].concat());
+        self.stream.write_all(&frame).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn settings_are_sent_and_confirmed_values_reported() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move { PortServer::accept(&listener, 57_600).await });
+
+    let framing = Framing {
+        parity: Parity::Even,
+        flow_control: FlowControl::Hardware,
+        ..Framing::default()
+    };
+    let mut conn = Rfc2217Connection::new("127.0.0.1".into(), port, 115_200).with_framing(framing);
+    conn.connect().await.expect("negotiation should succeed");
+    let server = server.await.unwrap();
+
+    assert_eq!(
+        server.commands,
+        [
+            [&[1u8][..], &115_200u32.to_be_bytes()].concat(),
+            vec![2, 8],
+            vec![3, 3],
+            vec![4, 1],
+            vec![5, 3],
+        ]
+    );
+    let params = conn.negotiated();
+    assert_eq!(params["baud"], "57600");
+    assert_eq!(params["data_bits"], "8");
+    assert_eq!(params["parity"], "even");
+    assert_eq!(params["stop_bits"], "1");
+    assert_eq!(params["flow_control"], "hardware");
+    assert_eq!(params["port"], format!("rfc2217://127.0.0.1:{port}"));
+}
+
+#[tokio::test]
+async fn data_and_commands_share_the_stream_without_mixing() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move { PortServer::accept(&listener, 9_600).await });
+
+    let connection_manager = ConnectionManager::new();
+    connection_manager
+        .add_connection(
+            "uart".into(),
+            Box::new(Rfc2217Connection::new("127.0.0.1".into(), port, 9_600)),
+        )
+        .await
+        .expect("negotiation should succeed");
+    let mut received = connection_manager.subscribe("uart").await.unwrap();
+    let mut server = server.await.unwrap();
+
+    // A modem state notification in the middle of the data, sent a byte at
+    // a time so that every split point is crossed.
+    let mut wire = b"ok".to_vec();
+    wire.extend(TelnetCodec::subnegotiation(
+        COM_PORT_OPTION,
+        &[107, 0x10 | 0x80],
+    ));
+    wire.extend_from_slice(&[IAC, IAC, b'\n']);
+    for byte in wire {
+        server.stream.write_all(&[byte]).await.unwrap();
+        server.stream.flush().await.unwrap();
+    }
+    let mut data = Vec::new();
+    while data.len() < 4 {
+        let chunk = timeout(Duration::from_secs(2), received.recv())
+            .await
+            .expect("no data reached the subscriber")
+            .unwrap();
+        data.extend(chunk);
+    }
+    assert_eq!(data, [b'o', b'k', IAC, b'\n']);
+    assert_eq!(
+        connection_manager.modem_status("uart").await.unwrap(),
+        ModemStatus {
+            cts: true,
+            dcd: true,
+            ..ModemStatus::default()
+        }
+    );
+
+    connection_manager
+        .write_bytes_acked("uart", &[b'x', IAC])
+        .await
+        .unwrap();
+    connection_manager.set_dtr("uart", false).await.unwrap();
+    connection_manager
+        .send_break("uart", Duration::from_millis(10))
+        .await
+        .unwrap();
+    while server.commands.len() < 8 {
+        server.read().await;
+    }
+    assert_eq!(server.data, [b'x', IAC]);
+    assert_eq!(server.commands[5..], [[5, 9], [5, 5], [5, 6]]);
+
+    connection_manager.stop_connection("uart").await.unwrap();
+}
+
+#[tokio::test]
+async fn port_server_hanging_up_ends_the_session() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move { PortServer::accept(&listener, 9_600).await });
+
+    let connection_manager = ConnectionManager::new();
+    connection_manager
+        .add_connection(
+            "uart".into(),
+            Box::new(Rfc2217Connection::new("127.0.0.1".into(), port, 9_600)),
+        )
+        .await
+        .expect("negotiation should succeed");
+    let mut received = connection_manager.subscribe("uart").await.unwrap();
+    drop(server.await.unwrap());
+
+    let closed = timeout(Duration::from_secs(2), received.recv())
+        .await
+        .expect("the subscription should close once the server hangs up");
+    assert_eq!(closed, Err(RecvError::Closed));
+    assert_eq!(
+        connection_manager.status("uart").await,
+        Some(ConnectionState::Disconnected)
+    );
+
+    connection_manager.stop_connection("uart").await.unwrap();
+}
+
+#[tokio::test]
+async fn plain_telnet_servers_are_rejected() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut offer = [0u8; 3];
+        stream.read_exact(&mut offer).await.unwrap();
+        assert_eq!(offer, [IAC, WILL, COM_PORT_OPTION]);
+        stream
+            .write_all(&[IAC, DONT, COM_PORT_OPTION])
+            .await
+            .unwrap();
+        stream
+    });
+
+    let mut conn = Rfc2217Connection::new("127.0.0.1".into(), port, 9_600);
+    let err = conn.connect().await.unwrap_err().to_string();
+    assert!(err.contains("refused the COM-PORT-OPTION"), "{err}");
+    assert!(!conn.is_connected());
+    let _ = server.await.unwrap();
+}
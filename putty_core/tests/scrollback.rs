@@ -0,0 +1,216 @@
+use putty_core::{ConnectionManager, ConnectionOptions, ScrollbackStats, MAX_KEPT_SCROLLBACKS};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn scrollback_evicts_the_oldest_bytes_and_counts_them() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "console".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_scrollback(16),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("console").await.unwrap();
+
+    // ── 30 bytes into a 16 byte scrollback ──────────────────────────────
+    for chunk in [&b"0123456789"[..], b"abcdefghij", b"ABCDEFGHIJ"] {
+        test_to_fake_tx.send(chunk.to_vec()).await.unwrap();
+        timeout(Duration::from_millis(200), rx.recv())
+            .await
+            .expect("timeout waiting for data")
+            .unwrap();
+    }
+    assert_eq!(
+        connection_manager.scrollback_stats("console").await,
+        Some(ScrollbackStats {
+            size: 16,
+            limit: 16,
+            evicted: 14,
+        })
+    );
+    assert_eq!(
+        connection_manager.scrollback("console").await.unwrap(),
+        b"efghijABCDEFGHIJ"
+    );
+
+    // ── Shrinking at runtime evicts right away ──────────────────────────
+    connection_manager
+        .set_scrollback_limit("console", 4)
+        .await
+        .unwrap();
+    assert_eq!(
+        connection_manager.scrollback_stats("console").await,
+        Some(ScrollbackStats {
+            size: 4,
+            limit: 4,
+            evicted: 26,
+        })
+    );
+    assert_eq!(
+        connection_manager.scrollback("console").await.unwrap(),
+        b"GHIJ"
+    );
+}
+
+#[tokio::test]
+async fn scrollback_is_off_by_default() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("console".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("console").await.unwrap();
+
+    test_to_fake_tx.send(b"hello".to_vec()).await.unwrap();
+    timeout(Duration::from_millis(200), rx.recv())
+        .await
+        .expect("timeout waiting for data")
+        .unwrap();
+    assert_eq!(
+        connection_manager.scrollback_stats("console").await,
+        Some(ScrollbackStats::default())
+    );
+    assert_eq!(connection_manager.scrollback_stats("nope").await, None);
+}
+
+#[tokio::test]
+async fn scrollback_survives_a_reconnect() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let options = ConnectionOptions::new()
+        .with_scrollback(1024)
+        .with_keep_scrollback(true);
+    let (first_connection, first_tx, _first_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(
+            "console".into(),
+            Box::new(first_connection),
+            options.clone(),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe("console").await.unwrap();
+    first_tx.send(b"login: ".to_vec()).await.unwrap();
+    timeout(Duration::from_millis(200), rx.recv())
+        .await
+        .expect("timeout waiting for pre-reconnect data")
+        .unwrap();
+
+    // ── Reconnect: stop the transport and register a new one ────────────
+    connection_manager.stop_connection("console").await.unwrap();
+    let (second_connection, second_tx, _second_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options("console".into(), Box::new(second_connection), options)
+        .await
+        .expect("re-adding console should succeed");
+    let mut rx = connection_manager.subscribe("console").await.unwrap();
+    second_tx.send(b"login: ".to_vec()).await.unwrap();
+    timeout(Duration::from_millis(200), rx.recv())
+        .await
+        .expect("timeout waiting for post-reconnect data")
+        .unwrap();
+
+    assert_eq!(
+        connection_manager.scrollback("console").await.unwrap(),
+        b"login: \r\n--- reconnected ---\r\nlogin: "
+    );
+}
+
+/// Add `id` with `options`, feed it `output` and stop it again.
+async fn run_session(
+    connection_manager: &ConnectionManager,
+    id: &str,
+    options: ConnectionOptions,
+    output: &[u8],
+) {
+    let (connection, tx, _rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options(id.into(), Box::new(connection), options)
+        .await
+        .expect("add_connection should succeed");
+    let mut rx = connection_manager.subscribe(id).await.unwrap();
+    tx.send(output.to_vec()).await.unwrap();
+    timeout(Duration::from_millis(200), rx.recv())
+        .await
+        .expect("timeout waiting for data")
+        .unwrap();
+    connection_manager.stop_connection(id).await.unwrap();
+}
+
+#[tokio::test]
+async fn scrollback_is_only_kept_on_request() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let options = ConnectionOptions::new().with_scrollback(1024);
+    run_session(&connection_manager, "console", options.clone(), b"old").await;
+
+    let (connection, _tx, _rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options("console".into(), Box::new(connection), options)
+        .await
+        .expect("re-adding console should succeed");
+    assert_eq!(
+        connection_manager.scrollback("console").await.unwrap(),
+        b"",
+        "an unrelated connection under a reused id starts afresh"
+    );
+}
+
+#[tokio::test]
+async fn kept_scrollback_can_be_forgotten() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let options = ConnectionOptions::new()
+        .with_scrollback(1024)
+        .with_keep_scrollback(true);
+    run_session(&connection_manager, "console", options.clone(), b"old").await;
+
+    assert!(connection_manager.forget_scrollback("console"));
+    assert!(!connection_manager.forget_scrollback("console"));
+
+    let (connection, _tx, _rx) = FakeConnection::new();
+    connection_manager
+        .add_connection_with_options("console".into(), Box::new(connection), options)
+        .await
+        .expect("re-adding console should succeed");
+    assert_eq!(connection_manager.scrollback("console").await.unwrap(), b"");
+}
+
+#[tokio::test]
+async fn kept_scrollbacks_are_bounded() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let options = ConnectionOptions::new()
+        .with_scrollback(1024)
+        .with_keep_scrollback(true);
+    for n in 0..=MAX_KEPT_SCROLLBACKS {
+        run_session(
+            &connection_manager,
+            &format!("dev{n}"),
+            options.clone(),
+            b"x",
+        )
+        .await;
+    }
+
+    // The oldest one made room for the newest.
+    assert!(!connection_manager.forget_scrollback("dev0"));
+    assert!(connection_manager.forget_scrollback("dev1"));
+    assert!(connection_manager.forget_scrollback(&format!("dev{MAX_KEPT_SCROLLBACKS}")));
+}
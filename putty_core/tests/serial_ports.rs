@@ -0,0 +1,49 @@
+#![cfg(feature = "serial")]
+
+use putty_core::connections::serial::{list_ports, SerialPortInfo, UsbInfo};
+use tokio_serial::{SerialPortType, UsbPortInfo};
+
+#[test]
+fn usb_adapters_keep_their_ids_and_names() {
+    let info = tokio_serial::SerialPortInfo {
+        port_name: "/dev/ttyUSB0".into(),
+        port_type: SerialPortType::UsbPort(UsbPortInfo {
+            vid: 0x0403,
+            pid: 0x6001,
+            serial_number: Some("A12345".into()),
+            manufacturer: Some("FTDI".into()),
+            product: Some("FT232R USB UART".into()),
+        }),
+    };
+
+    assert_eq!(
+        SerialPortInfo::from(info),
+        SerialPortInfo {
+            name: "/dev/ttyUSB0".into(),
+            usb: Some(UsbInfo {
+                vid: 0x0403,
+                pid: 0x6001,
+                manufacturer: Some("FTDI".into()),
+                product: Some("FT232R USB UART".into()),
+                serial_number: Some("A12345".into()),
+            }),
+        }
+    );
+}
+
+#[test]
+fn other_ports_have_no_usb_info() {
+    let info = tokio_serial::SerialPortInfo {
+        port_name: "/dev/ttyS0".into(),
+        port_type: SerialPortType::Unknown,
+    };
+    assert_eq!(SerialPortInfo::from(info).usb, None);
+}
+
+/// Whatever this machine has, the list comes back sorted; a machine without
+/// serial ports gets an empty list rather than an error.
+#[test]
+fn listed_ports_are_sorted_by_name() {
+    let ports = list_ports().expect("listing serial ports failed");
+    assert!(ports.windows(2).all(|pair| pair[0].name <= pair[1].name));
+}
@@ -0,0 +1,291 @@
+use putty_core::{ConnectionManager, LogFormat, LogRotation, SessionLogger};
+use regex::Regex;
+use tempfile::tempdir;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn strip_ansi_only_affects_the_log_file() {
+    init_logging();
+
+    let workdir = tempdir().unwrap();
+    let log_path = workdir.path().join("session.log");
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("fakePort".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let mut live_rx = connection_manager.subscribe("fakePort").await.unwrap();
+    let logger = SessionLogger::create(&log_path)
+        .await
+        .expect("log file should open")
+        .with_strip_ansi(true);
+    let logger_task = logger.spawn(connection_manager.subscribe("fakePort").await.unwrap());
+
+    // Colored output, with one SGR sequence split across two chunks.
+    let chunks: [&[u8]; 3] = [b"\x1b[1;3", b"1mred\x1b[0m ", b"\x1b]0;title\x07plain\r\n"];
+    for chunk in chunks {
+        test_to_fake_tx.send(chunk.to_vec()).await.unwrap();
+    }
+
+    // ── The live stream keeps the escape codes ───────────────────────────
+    let mut live = Vec::new();
+    for _ in chunks {
+        let chunk = timeout(Duration::from_millis(200), live_rx.recv())
+            .await
+            .expect("timeout waiting for live data")
+            .expect("broadcast channel closed unexpectedly");
+        live.extend(chunk);
+    }
+    assert_eq!(live, chunks.concat());
+
+    // ── The transcript is plain text ─────────────────────────────────────
+    connection_manager
+        .stop_connection("fakePort")
+        .await
+        .unwrap();
+    logger_task
+        .await
+        .expect("logger task panicked")
+        .expect("logger failed");
+
+    let logged = std::fs::read(&log_path).unwrap();
+    assert_eq!(String::from_utf8_lossy(&logged), "red plain\r\n");
+}
+
+#[tokio::test]
+async fn size_rotation_keeps_only_the_newest_files() {
+    let workdir = tempdir().unwrap();
+    let log_path = workdir.path().join("session.log");
+
+    let mut logger = SessionLogger::create(&log_path)
+        .await
+        .expect("log file should open")
+        .with_rotation(LogRotation::by_size(10, 2));
+
+    // Each chunk fills half a file, so every second chunk starts a new one.
+    for chunk in [
+        b"aaaaa", b"bbbbb", b"ccccc", b"ddddd", b"eeeee", b"fffff", b"ggggg",
+    ] {
+        logger.write_chunk(chunk).await.unwrap();
+    }
+
+    let read = |name: &str| std::fs::read_to_string(workdir.path().join(name)).unwrap();
+    assert_eq!(read("session.log"), "ggggg");
+    assert_eq!(read("session.log.1"), "eeeeefffff");
+    assert_eq!(read("session.log.2"), "cccccddddd");
+    assert!(
+        !workdir.path().join("session.log.3").exists(),
+        "only two rotated files should be kept"
+    );
+}
+
+#[tokio::test]
+async fn loggers_with_different_formats_follow_one_connection() {
+    let workdir = tempdir().unwrap();
+    let raw_path = workdir.path().join("capture.bin");
+    let stamped_path = workdir.path().join("session.txt");
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("fakePort".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let mut tasks = Vec::new();
+    for (path, format) in [
+        (&raw_path, LogFormat::Raw),
+        (&stamped_path, LogFormat::Timestamped),
+    ] {
+        let logger = SessionLogger::create(path)
+            .await
+            .expect("log file should open")
+            .with_format(format);
+        tasks.push(logger.spawn(connection_manager.subscribe("fakePort").await.unwrap()));
+    }
+
+    // The second line arrives split across chunks.
+    let chunks: [&[u8]; 3] = [b"\x1b[32mboot\x1b[0m\r\n", b"rea", b"dy\r\n"];
+    for chunk in chunks {
+        test_to_fake_tx.send(chunk.to_vec()).await.unwrap();
+    }
+    // Let the I/O task forward everything before the channel closes.
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    connection_manager
+        .stop_connection("fakePort")
+        .await
+        .unwrap();
+    for task in tasks {
+        task.await
+            .expect("logger task panicked")
+            .expect("logger failed");
+    }
+
+    assert_eq!(std::fs::read(&raw_path).unwrap(), chunks.concat());
+
+    let stamped = std::fs::read_to_string(&stamped_path).unwrap();
+    let line = Regex::new(r"^\[ *\d+\.\d{3}\] (boot|ready)\r$").unwrap();
+    let lines: Vec<&str> = stamped.split('\n').filter(|l| !l.is_empty()).collect();
+    assert_eq!(lines.len(), 2, "unexpected transcript: {stamped:?}");
+    for (l, word) in lines.iter().zip(["boot", "ready"]) {
+        let caps = line.captures(l).unwrap_or_else(|| panic!("bad line {l:?}"));
+        assert_eq!(&caps[1], word);
+    }
+}
+
+#[tokio::test]
+async fn asciinema_log_is_a_valid_v2_cast() {
+    let workdir = tempdir().unwrap();
+    let cast_path = workdir.path().join("session.cast");
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.pty = Some((80, 24));
+    connection_manager
+        .add_connection("fakePort".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    connection_manager
+        .resize("fakePort", 132, 40)
+        .await
+        .unwrap();
+
+    let (cols, rows) = connection_manager.pty_size("fakePort").await.unwrap();
+    let logger = SessionLogger::create(&cast_path)
+        .await
+        .expect("log file should open")
+        .with_format(LogFormat::Asciinema)
+        .with_terminal_size(cols, rows);
+    let task = logger.spawn(connection_manager.subscribe("fakePort").await.unwrap());
+
+    // "ü" is split across chunks and must not be torn apart.
+    let chunks: [&[u8]; 3] = [b"\x1b[32m\"ok\"\x1b[0m\r\n", b"gr\xc3", b"\xbcn\r\n"];
+    for chunk in chunks {
+        test_to_fake_tx.send(chunk.to_vec()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    connection_manager
+        .stop_connection("fakePort")
+        .await
+        .unwrap();
+    task.await
+        .expect("logger task panicked")
+        .expect("logger failed");
+
+    let cast = std::fs::read_to_string(&cast_path).unwrap();
+    let mut lines = cast.lines();
+
+    let header: serde_json::Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!(header["version"], 2);
+    assert_eq!(header["width"], 132);
+    assert_eq!(header["height"], 40);
+    assert!(header["timestamp"].as_u64().unwrap() > 0);
+
+    let events: Vec<(f64, String, String)> = lines
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{line:?}: {e}")))
+        .collect();
+    assert!(events.iter().all(|(_, kind, _)| kind == "o"));
+    assert!(
+        events.windows(2).all(|pair| pair[0].0 <= pair[1].0),
+        "times must not go backwards: {events:?}"
+    );
+    let output: String = events.iter().map(|(_, _, data)| data.as_str()).collect();
+    assert_eq!(output, "\x1b[32m\"ok\"\x1b[0m\r\ngrün\r\n");
+}
+
+#[tokio::test]
+async fn asciinema_log_records_resizes() {
+    let workdir = tempdir().unwrap();
+    let cast_path = workdir.path().join("session.cast");
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    fake_connection.pty = Some((80, 24));
+    connection_manager
+        .add_connection("fakePort".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let logger = SessionLogger::create(&cast_path)
+        .await
+        .expect("log file should open")
+        .with_format(LogFormat::Asciinema)
+        .with_resize_events(connection_manager.events(), "fakePort");
+    let task = logger.spawn(connection_manager.subscribe("fakePort").await.unwrap());
+
+    test_to_fake_tx.send(b"before".to_vec()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    connection_manager
+        .resize("fakePort", 132, 40)
+        .await
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    test_to_fake_tx.send(b"after".to_vec()).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    connection_manager
+        .stop_connection("fakePort")
+        .await
+        .unwrap();
+    task.await
+        .expect("logger task panicked")
+        .expect("logger failed");
+
+    let cast = std::fs::read_to_string(&cast_path).unwrap();
+    let events: Vec<(f64, String, String)> = cast
+        .lines()
+        .skip(1)
+        .map(|line| serde_json::from_str(line).unwrap_or_else(|e| panic!("{line:?}: {e}")))
+        .collect();
+    let events: Vec<(&str, &str)> = events
+        .iter()
+        .map(|(_, code, data)| (code.as_str(), data.as_str()))
+        .collect();
+    assert_eq!(events, [("o", "before"), ("r", "132x40"), ("o", "after")]);
+}
+
+#[tokio::test]
+async fn rotated_asciinema_files_count_time_from_their_own_header() {
+    let workdir = tempdir().unwrap();
+    let cast_path = workdir.path().join("session.cast");
+
+    let mut logger = SessionLogger::create(&cast_path)
+        .await
+        .expect("log file should open")
+        .with_format(LogFormat::Asciinema)
+        .with_rotation(LogRotation::by_size(200, 1));
+
+    logger.write_chunk(b"first").await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    logger.write_resize(100, 30).await.unwrap();
+    logger.write_chunk(&[b'x'; 150]).await.unwrap();
+
+    let read = |name: &str| std::fs::read_to_string(workdir.path().join(name)).unwrap();
+    let old = read("session.cast.1");
+    let new = read("session.cast");
+
+    let first_event = |cast: &str| -> (f64, String, String) {
+        serde_json::from_str(cast.lines().nth(1).unwrap()).unwrap()
+    };
+    assert_eq!(first_event(&old).2, "first");
+    let (time, code, data) = first_event(&new);
+    assert_eq!((code.as_str(), data.len()), ("o", 150));
+    assert!(time < 0.2, "new file starts at {time}s, not at 0");
+
+    // The new header carries the size the terminal had at the rotation.
+    let header: serde_json::Value = serde_json::from_str(new.lines().next().unwrap()).unwrap();
+    assert_eq!(
+        (header["width"].clone(), header["height"].clone()),
+        (100.into(), 30.into())
+    );
+}
@@ -0,0 +1,298 @@
+#![cfg(feature = "ssh")]
+
+//! File transfer against an in-process russh server whose sftp subsystem
+//! keeps files in memory, while its shell echoes whatever it receives.
+
+use putty_core::connections::ssh::{SshConnection, TransferProgress};
+use putty_core::ConnectionManager;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet};
+use russh_sftp::protocol::{
+    Attrs, Data, FileAttributes, Handle, OpenFlags, Status, StatusCode, Version,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+mod common;
+use common::fake_connection::FakeConnection;
+
+const PASSWORD: &str = "hunter2";
+
+type Files = Arc<Mutex<HashMap<String, Vec<u8>>>>;
+
+struct Server {
+    files: Files,
+    /// Session channels by id, until a subsystem request claims one.
+    channels: HashMap<ChannelId, Channel<Msg>>,
+}
+
+impl server::Handler for Server {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, _user: &str, password: &str) -> Result<Auth, Self::Error> {
+        Ok(if password == PASSWORD {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        self.channels.insert(channel.id(), channel);
+        Ok(true)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.data(channel, data.to_vec())?;
+        Ok(())
+    }
+
+    async fn subsystem_request(
+        &mut self,
+        channel: ChannelId,
+        name: &str,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        match self.channels.remove(&channel) {
+            Some(stream) if name == "sftp" => {
+                session.channel_success(channel)?;
+                let sftp = MemoryFs {
+                    files: self.files.clone(),
+                };
+                russh_sftp::server::run(stream.into_stream(), sftp).await;
+            }
+            _ => session.channel_failure(channel)?,
+        }
+        Ok(())
+    }
+}
+
+/// SFTP handler serving `files`; a handle is simply the file name.
+struct MemoryFs {
+    files: Files,
+}
+
+impl russh_sftp::server::Handler for MemoryFs {
+    type Error = StatusCode;
+
+    fn unimplemented(&self) -> Self::Error {
+        StatusCode::OpUnsupported
+    }
+
+    async fn init(
+        &mut self,
+        _version: u32,
+        _extensions: HashMap<String, String>,
+    ) -> Result<Version, Self::Error> {
+        Ok(Version::new())
+    }
+
+    async fn open(
+        &mut self,
+        id: u32,
+        filename: String,
+        pflags: OpenFlags,
+        _attrs: FileAttributes,
+    ) -> Result<Handle, Self::Error> {
+        let mut files = self.files.lock().unwrap();
+        if pflags.contains(OpenFlags::TRUNCATE) || pflags.contains(OpenFlags::CREATE) {
+            files.insert(filename.clone(), Vec::new());
+        } else if !files.contains_key(&filename) {
+            return Err(StatusCode::NoSuchFile);
+        }
+        Ok(Handle {
+            id,
+            handle: filename,
+        })
+    }
+
+    async fn close(&mut self, id: u32, _handle: String) -> Result<Status, Self::Error> {
+        Ok(ok(id))
+    }
+
+    async fn read(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        len: u32,
+    ) -> Result<Data, Self::Error> {
+        let files = self.files.lock().unwrap();
+        let file = files.get(&handle).ok_or(StatusCode::NoSuchFile)?;
+        let start = offset as usize;
+        if start >= file.len() {
+            return Err(StatusCode::Eof);
+        }
+        let end = file.len().min(start + len as usize);
+        Ok(Data {
+            id,
+            data: file[start..end].to_vec(),
+        })
+    }
+
+    async fn write(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        data: Vec<u8>,
+    ) -> Result<Status, Self::Error> {
+        let mut files = self.files.lock().unwrap();
+        let file = files.get_mut(&handle).ok_or(StatusCode::NoSuchFile)?;
+        let start = offset as usize;
+        if file.len() < start + data.len() {
+            file.resize(start + data.len(), 0);
+        }
+        file[start..start + data.len()].copy_from_slice(&data);
+        Ok(ok(id))
+    }
+
+    async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+        let files = self.files.lock().unwrap();
+        let file = files.get(&handle).ok_or(StatusCode::NoSuchFile)?;
+        let mut attrs = FileAttributes::empty();
+        attrs.size = Some(file.len() as u64);
+        Ok(Attrs { id, attrs })
+    }
+}
+
+fn ok(id: u32) -> Status {
+    Status {
+        id,
+        status_code: StatusCode::Ok,
+        error_message: "Ok".into(),
+        language_tag: "en-US".into(),
+    }
+}
+
+/// Serve one SSH connection backed by `files`; returns the port.
+async fn spawn_server(files: Files) -> u16 {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        methods: MethodSet::from(&[MethodKind::Password][..]),
+        ..Default::default()
+    });
+    let handler = Server {
+        files,
+        channels: HashMap::new(),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, handler).await.unwrap();
+        let _ = session.await;
+    });
+    port
+}
+
+#[tokio::test]
+async fn put_and_get_round_trip_next_to_the_shell() {
+    let files = Files::default();
+    let port = spawn_server(files.clone()).await;
+    let manager = ConnectionManager::new();
+    let conn = SshConnection::new("127.0.0.1".into(), port, "ops".into(), PASSWORD.into());
+    manager
+        .add_connection("box".into(), Box::new(conn))
+        .await
+        .expect("ssh login");
+    let mut shell = manager.subscribe("box").await.unwrap();
+
+    let dir = TempDir::new().unwrap();
+    let firmware: Vec<u8> = (0..100_000u32).map(|i| (i % 251) as u8).collect();
+    let local = dir.path().join("firmware.bin");
+    std::fs::write(&local, &firmware).unwrap();
+
+    let (progress, mut reports) = mpsc::unbounded_channel();
+    let sftp = manager
+        .sftp("box")
+        .await
+        .expect("sftp client")
+        .with_progress(progress);
+    let sent = sftp.put(&local, "/tmp/firmware.bin").await.unwrap();
+    assert_eq!(sent, firmware.len() as u64);
+    assert_eq!(files.lock().unwrap()["/tmp/firmware.bin"], firmware);
+
+    let mut last = None;
+    while let Ok(report) = reports.try_recv() {
+        last = Some(report);
+    }
+    assert_eq!(
+        last,
+        Some(TransferProgress {
+            transferred: firmware.len() as u64,
+            total: Some(firmware.len() as u64),
+        })
+    );
+
+    let copy = dir.path().join("copy.bin");
+    let received = sftp.get("/tmp/firmware.bin", &copy).await.unwrap();
+    assert_eq!(received, firmware.len() as u64);
+    assert_eq!(std::fs::read(&copy).unwrap(), firmware);
+
+    // The shell channel kept working alongside the transfer.
+    manager.write_bytes("box", b"still here").await.unwrap();
+    let echo = timeout(Duration::from_secs(5), shell.recv())
+        .await
+        .expect("shell echo")
+        .unwrap();
+    assert_eq!(echo, b"still here");
+
+    sftp.close().await.unwrap();
+    manager.stop_connection("box").await.unwrap();
+}
+
+#[tokio::test]
+async fn missing_remote_file_is_an_error() {
+    let port = spawn_server(Files::default()).await;
+    let mut conn = SshConnection::new("127.0.0.1".into(), port, "ops".into(), PASSWORD.into());
+    putty_core::connections::connection::Connection::connect(&mut conn)
+        .await
+        .expect("ssh login");
+
+    let dir = TempDir::new().unwrap();
+    let sftp = conn.sftp().await.expect("sftp client");
+    let err = sftp
+        .get("/nope", &dir.path().join("nope"))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("SFTP cannot open /nope"), "{err}");
+}
+
+#[tokio::test]
+async fn transports_without_sftp_refuse() {
+    let manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    manager
+        .add_connection("fake".into(), Box::new(fake_connection))
+        .await
+        .unwrap();
+
+    let err = manager.sftp("fake").await.err().expect("fake has no sftp");
+    assert!(err.to_string().contains("cannot transfer files"), "{err}");
+}
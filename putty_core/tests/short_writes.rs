@@ -0,0 +1,41 @@
+use putty_core::ConnectionManager;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn short_writes_are_retried_until_everything_is_written() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    fake_connection.max_write_chunk = Some(3);
+
+    connection_manager
+        .add_connection("tinyBuffer".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let written = connection_manager
+        .write_bytes_acked("tinyBuffer", b"0123456789")
+        .await
+        .expect("write should succeed");
+    assert_eq!(written, 10);
+
+    // ── The transport saw four writes that add up to the whole buffer ────
+    let mut chunks: Vec<Vec<u8>> = Vec::new();
+    while chunks.concat().len() < 10 {
+        let chunk = timeout(Duration::from_millis(100), fake_to_test_rx.recv())
+            .await
+            .expect("timeout waiting for the rest of the data")
+            .expect("fake_to_test channel closed unexpectedly");
+        chunks.push(chunk);
+    }
+    assert_eq!(
+        chunks.iter().map(Vec::len).collect::<Vec<_>>(),
+        [3, 3, 3, 1]
+    );
+    assert_eq!(chunks.concat(), b"0123456789");
+}
@@ -0,0 +1,40 @@
+use putty_core::ConnectionManager;
+use std::sync::atomic::Ordering;
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn shutdown_all_stops_and_disconnects_every_connection() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let mut disconnects = Vec::new();
+    let mut subscriptions = Vec::new();
+    for id in ["ttyUSB1", "ssh-box", "ttyUSB0"] {
+        let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+        disconnects.push(fake_connection.disconnects.clone());
+        connection_manager
+            .add_connection(id.into(), Box::new(fake_connection))
+            .await
+            .expect("add_connection should succeed");
+        subscriptions.push(connection_manager.subscribe(id).await.unwrap());
+    }
+
+    let results = connection_manager.shutdown_all().await;
+    let ids: Vec<_> = results.iter().map(|(id, _)| id.as_str()).collect();
+    assert_eq!(ids, ["ssh-box", "ttyUSB0", "ttyUSB1"]);
+    assert!(results.iter().all(|(_, result)| result.is_ok()));
+
+    assert!(connection_manager.list_connections().await.is_empty());
+    for count in &disconnects {
+        assert_eq!(count.load(Ordering::SeqCst), 1);
+    }
+    for rx in &mut subscriptions {
+        assert!(rx.recv().await.is_err(), "subscriptions should close");
+    }
+
+    // Nothing left to stop.
+    assert!(connection_manager.shutdown_all().await.is_empty());
+}
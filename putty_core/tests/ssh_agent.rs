@@ -0,0 +1,168 @@
+#![cfg(all(feature = "ssh", unix))]
+
+//! Public-key login through an ssh-agent: russh's agent server on a Unix
+//! socket in a temp dir, and an in-process SSH server that accepts one key.
+
+use putty_core::connections::connection::Connection;
+use putty_core::connections::errors::ConnectionError;
+use putty_core::connections::ssh::SshConnection;
+use russh::keys::agent::client::AgentClient;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::{PrivateKey, PublicKey};
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::{TcpListener, UnixListener};
+use tokio::sync::Mutex;
+
+/// `with_agent` reads `SSH_AUTH_SOCK`, so tests changing it take turns.
+static AUTH_SOCK: Mutex<()> = Mutex::const_new(());
+
+#[derive(Clone)]
+struct Agent;
+
+impl russh::keys::agent::server::Agent for Agent {}
+
+/// Accepts only `allowed` for public-key login.
+struct KeyServer {
+    allowed: PublicKey,
+}
+
+impl server::Handler for KeyServer {
+    type Error = russh::Error;
+
+    async fn auth_publickey(&mut self, _user: &str, key: &PublicKey) -> Result<Auth, Self::Error> {
+        Ok(if *key == self.allowed {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        Ok(())
+    }
+}
+
+fn key(seed: u8) -> PrivateKey {
+    PrivateKey::from(Ed25519Keypair::from_seed(&[seed; 32]))
+}
+
+/// Serve one SSH connection accepting only `allowed`; returns the port.
+async fn spawn_server(allowed: PublicKey) -> u16 {
+    let config = Arc::new(server::Config {
+        keys: vec![key(7)],
+        methods: MethodSet::from(&[MethodKind::PublicKey][..]),
+        auth_rejection_time: Duration::ZERO,
+        auth_rejection_time_initial: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, KeyServer { allowed })
+            .await
+            .unwrap();
+        let _ = session.await;
+    });
+    port
+}
+
+/// Run an agent on `socket` holding `keys`.
+async fn spawn_agent(socket: &Path, keys: &[PrivateKey]) {
+    let listener = UnixListener::bind(socket).unwrap();
+    let connections = futures::stream::unfold(listener, |listener| async move {
+        let stream = listener.accept().await.map(|(stream, _)| stream);
+        Some((stream, listener))
+    });
+    tokio::spawn(russh::keys::agent::server::serve(
+        Box::pin(connections),
+        Agent,
+    ));
+
+    let mut client = AgentClient::connect_uds(socket).await.unwrap();
+    for key in keys {
+        client.add_identity(key, &[]).await.unwrap();
+    }
+}
+
+#[tokio::test]
+async fn agent_identity_the_server_accepts_logs_in() {
+    let _env = AUTH_SOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("agent.sock");
+    // The first identity is unknown to the server, so the second must be tried.
+    spawn_agent(&socket, &[key(1), key(2)]).await;
+    std::env::set_var("SSH_AUTH_SOCK", &socket);
+    let port = spawn_server(key(2).public_key().clone()).await;
+
+    let mut conn = SshConnection::with_agent("127.0.0.1".into(), port, "ops".into());
+    conn.connect().await.expect("agent login");
+    assert!(conn.negotiated().contains_key("identity"));
+    conn.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn empty_agent_is_a_clear_auth_error() {
+    let _env = AUTH_SOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("agent.sock");
+    spawn_agent(&socket, &[]).await;
+    std::env::set_var("SSH_AUTH_SOCK", &socket);
+    let port = spawn_server(key(2).public_key().clone()).await;
+
+    let mut conn = SshConnection::with_agent("127.0.0.1".into(), port, "ops".into());
+    let err = conn.connect().await.unwrap_err();
+    assert!(
+        matches!(&err, ConnectionError::AuthError(msg) if msg.contains("no identities")),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn unrecognised_identities_are_a_clear_auth_error() {
+    let _env = AUTH_SOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("agent.sock");
+    spawn_agent(&socket, &[key(1)]).await;
+    std::env::set_var("SSH_AUTH_SOCK", &socket);
+    let port = spawn_server(key(2).public_key().clone()).await;
+
+    let mut conn = SshConnection::with_agent("127.0.0.1".into(), port, "ops".into());
+    let err = conn.connect().await.unwrap_err();
+    assert!(
+        matches!(&err, ConnectionError::AuthError(msg) if msg.contains("was accepted")),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn missing_agent_socket_is_a_clear_auth_error() {
+    let _env = AUTH_SOCK.lock().await;
+    let dir = tempfile::tempdir().unwrap();
+    std::env::set_var("SSH_AUTH_SOCK", dir.path().join("gone.sock"));
+    let port = spawn_server(key(2).public_key().clone()).await;
+
+    let mut conn = SshConnection::with_agent("127.0.0.1".into(), port, "ops".into());
+    let err = conn.connect().await.unwrap_err();
+    assert!(
+        matches!(&err, ConnectionError::AuthError(msg) if msg.contains("ssh-agent")),
+        "{err:?}"
+    );
+}
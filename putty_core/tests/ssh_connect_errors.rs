@@ -0,0 +1,128 @@
+#![cfg(feature = "ssh")]
+
+//! Each phase of an SSH connect fails with its own error variant.
+
+use putty_core::connections::connection::Connection;
+use putty_core::connections::errors::ConnectionError;
+use putty_core::connections::ssh::SshConnection;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet, Pty};
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+
+fn connection(host: &str, port: u16) -> SshConnection {
+    SshConnection::new(host.into(), port, "nobody".into(), "secret".into())
+}
+
+#[tokio::test]
+async fn unknown_host_is_a_dns_error() {
+    // `.invalid` is reserved and never resolves (RFC 6761).
+    let err = connection("no-such-host.invalid", 22)
+        .connect()
+        .await
+        .unwrap_err();
+    assert!(matches!(err, ConnectionError::DnsError(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn closed_port_is_a_tcp_connect_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    drop(listener);
+
+    let err = connection("127.0.0.1", port).connect().await.unwrap_err();
+    assert!(
+        matches!(err, ConnectionError::TcpConnectError(_)),
+        "{err:?}"
+    );
+}
+
+#[tokio::test]
+async fn non_ssh_server_is_a_handshake_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let _ = socket.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        // Dropping the socket closes the connection mid-handshake.
+    });
+
+    let err = connection("127.0.0.1", port).connect().await.unwrap_err();
+    assert!(matches!(err, ConnectionError::HandshakeError(_)), "{err:?}");
+}
+
+/// Accepts any password and opens a shell that never sends anything.
+struct Server;
+
+impl server::Handler for Server {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, _user: &str, _password: &str) -> Result<Auth, Self::Error> {
+        Ok(Auth::Accept)
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _term: &str,
+        _col_width: u32,
+        _row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _modes: &[(Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        Ok(())
+    }
+}
+
+/// Nothing of the first session's key exchange is carried into the second.
+#[tokio::test]
+async fn handshake_failure_after_a_reconnect_is_still_a_handshake_error() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        // A working SSH server first, then something else on the same port.
+        let config = Arc::new(server::Config {
+            keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+            methods: MethodSet::from(&[MethodKind::Password][..]),
+            ..Default::default()
+        });
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, Server).await.unwrap();
+        tokio::spawn(session);
+
+        let (mut socket, _) = listener.accept().await.unwrap();
+        let _ = socket.write_all(b"SSH-2.0-Gone\r\n").await;
+    });
+
+    let mut conn = connection("127.0.0.1", port);
+    conn.connect().await.expect("first session");
+    assert!(conn.negotiated().contains_key("kex"));
+    conn.disconnect().await.unwrap();
+
+    let err = conn.connect().await.unwrap_err();
+    assert!(matches!(err, ConnectionError::HandshakeError(_)), "{err:?}");
+    assert!(!conn.negotiated().contains_key("kex"));
+}
@@ -0,0 +1,158 @@
+#![cfg(feature = "ssh")]
+
+//! Single commands via `SshConnection::exec` against an in-process russh
+//! server that runs a tiny fake shell and records the channel requests.
+
+use putty_core::connections::connection::Connection;
+use putty_core::connections::ssh::SshConnection;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet, Pty};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpListener;
+
+const PASSWORD: &str = "hunter2";
+
+type Requests = Arc<Mutex<Vec<String>>>;
+
+struct Server {
+    requests: Requests,
+}
+
+impl server::Handler for Server {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, _user: &str, password: &str) -> Result<Auth, Self::Error> {
+        Ok(if password == PASSWORD {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _term: &str,
+        _col_width: u32,
+        _row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _modes: &[(Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.requests.lock().unwrap().push("pty".into());
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        self.requests.lock().unwrap().push("shell".into());
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn exec_request(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let command = String::from_utf8_lossy(data).into_owned();
+        self.requests
+            .lock()
+            .unwrap()
+            .push(format!("exec {command}"));
+        session.channel_success(channel)?;
+        let status = match command.as_str() {
+            "echo hi" => {
+                session.data(channel, b"hi\n".to_vec())?;
+                0
+            }
+            _ => {
+                session.extended_data(channel, 1, format!("{command}: not found\n"))?;
+                127
+            }
+        };
+        session.exit_status_request(channel, status)?;
+        session.eof(channel)?;
+        session.close(channel)?;
+        Ok(())
+    }
+}
+
+/// Serve one SSH connection; returns its port and the recorded requests.
+async fn spawn_server() -> (u16, Requests) {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        methods: MethodSet::from(&[MethodKind::Password][..]),
+        ..Default::default()
+    });
+    let requests = Requests::default();
+    let handler = Server {
+        requests: requests.clone(),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, handler).await.unwrap();
+        let _ = session.await;
+    });
+    (port, requests)
+}
+
+fn connection(port: u16) -> SshConnection {
+    SshConnection::new("127.0.0.1".into(), port, "ops".into(), PASSWORD.into())
+}
+
+#[tokio::test]
+async fn exec_needs_no_pty_or_shell() {
+    let (port, requests) = spawn_server().await;
+    let mut conn = connection(port);
+
+    let (output, status) = conn.exec("echo hi").await.expect("exec");
+    assert_eq!(output, b"hi\n");
+    assert_eq!(status, 0);
+
+    let (output, status) = conn.exec("frobnicate").await.expect("exec");
+    assert_eq!(output, b"frobnicate: not found\n");
+    assert_eq!(status, 127);
+
+    assert_eq!(
+        *requests.lock().unwrap(),
+        ["exec echo hi", "exec frobnicate"]
+    );
+    conn.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn connect_after_exec_opens_the_shell_on_the_same_session() {
+    let (port, requests) = spawn_server().await;
+    let mut conn = connection(port);
+
+    conn.exec("echo hi").await.expect("exec");
+    conn.connect().await.expect("shell after exec");
+    let (output, _) = conn.exec("echo hi").await.expect("exec next to the shell");
+    assert_eq!(output, b"hi\n");
+
+    // One login served all of it: the listener only accepts once.
+    assert_eq!(
+        *requests.lock().unwrap(),
+        ["exec echo hi", "pty", "shell", "exec echo hi"]
+    );
+    conn.disconnect().await.unwrap();
+}
@@ -0,0 +1,146 @@
+#![cfg(feature = "ssh")]
+
+//! Keepalives against an in-process russh server reached through a TCP relay
+//! that can be frozen, like a NAT gateway that silently dropped the session.
+
+use putty_core::connections::connection::Connection;
+use putty_core::connections::ssh::SshConnection;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::{sleep, timeout};
+
+const PASSWORD: &str = "hunter2";
+
+struct Echo;
+
+impl server::Handler for Echo {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, _user: &str, password: &str) -> Result<Auth, Self::Error> {
+        Ok(if password == PASSWORD {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.data(channel, data.to_vec())?;
+        Ok(())
+    }
+}
+
+/// Serve one SSH connection behind a relay; returns the relay's port and
+/// the switch that makes it drop everything.
+async fn spawn_server_behind_relay() -> (u16, Arc<AtomicBool>) {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        methods: MethodSet::from(&[MethodKind::Password][..]),
+        ..Default::default()
+    });
+    let server = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let server_addr = server.local_addr().unwrap();
+    tokio::spawn(async move {
+        let (socket, _) = server.accept().await.unwrap();
+        let session = server::run_stream(config, socket, Echo).await.unwrap();
+        let _ = session.await;
+    });
+
+    let frozen = Arc::new(AtomicBool::new(false));
+    let relay = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = relay.local_addr().unwrap().port();
+    tokio::spawn({
+        let frozen = frozen.clone();
+        async move {
+            let (client, _) = relay.accept().await.unwrap();
+            let upstream = TcpStream::connect(server_addr).await.unwrap();
+            let (client_rd, client_wr) = client.into_split();
+            let (upstream_rd, upstream_wr) = upstream.into_split();
+            tokio::spawn(relay_half(client_rd, upstream_wr, frozen.clone()));
+            relay_half(upstream_rd, client_wr, frozen).await;
+        }
+    });
+    (port, frozen)
+}
+
+/// Copy `from` to `to`, discarding everything once `frozen` is set.
+async fn relay_half(
+    mut from: impl AsyncRead + Unpin,
+    mut to: impl AsyncWrite + Unpin,
+    frozen: Arc<AtomicBool>,
+) {
+    let mut buf = [0; 4096];
+    while let Ok(n @ 1..) = from.read(&mut buf).await {
+        if !frozen.load(Ordering::SeqCst) && to.write_all(&buf[..n]).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn connection(port: u16) -> SshConnection {
+    SshConnection::new("127.0.0.1".into(), port, "ops".into(), PASSWORD.into())
+        .with_keepalive(Duration::from_millis(50))
+}
+
+#[tokio::test]
+async fn answered_keepalives_keep_an_idle_session_usable() {
+    let (port, _frozen) = spawn_server_behind_relay().await;
+    let mut conn = connection(port);
+    conn.connect().await.expect("ssh login");
+
+    // Idle for many keepalive intervals.
+    sleep(Duration::from_millis(500)).await;
+
+    conn.write(b"still there?").await.unwrap();
+    let mut buf = [0; 64];
+    let n = timeout(Duration::from_secs(5), conn.read(&mut buf))
+        .await
+        .expect("echo")
+        .unwrap();
+    assert_eq!(&buf[..n], b"still there?");
+    conn.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn missed_keepalives_close_the_session_with_a_reason() {
+    let (port, frozen) = spawn_server_behind_relay().await;
+    let mut conn = connection(port);
+    conn.connect().await.expect("ssh login");
+
+    frozen.store(true, Ordering::SeqCst);
+    let mut buf = [0; 64];
+    let err = timeout(Duration::from_secs(5), conn.read(&mut buf))
+        .await
+        .expect("the session should be given up")
+        .unwrap_err();
+    assert!(err.to_string().contains("missed 3 keepalives"), "{err}");
+}
@@ -0,0 +1,285 @@
+#![cfg(feature = "ssh")]
+
+//! Keyboard-interactive login against an in-process russh server that asks
+//! for a password and then a one-time code, like a corporate bastion, or
+//! announces a push notification before asking for a code.
+
+use putty_core::connections::connection::Connection;
+use putty_core::connections::errors::ConnectionError;
+use putty_core::connections::ssh::{AuthChallenge, AuthPrompt, SshConnection};
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Response, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet};
+use std::borrow::Cow;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+
+const PASSWORD: &str = "hunter2";
+const OTP: &str = "123456";
+
+/// Walks a client through two rounds: password, then one-time code.
+struct Bastion {
+    /// Index of the round the next response belongs to.
+    round: usize,
+    /// Whether keyboard-interactive is offered at all.
+    keyboard_interactive: bool,
+}
+
+impl server::Handler for Bastion {
+    type Error = russh::Error;
+
+    async fn auth_keyboard_interactive<'a>(
+        &'a mut self,
+        _user: &str,
+        _submethods: &str,
+        response: Option<Response<'a>>,
+    ) -> Result<Auth, Self::Error> {
+        if !self.keyboard_interactive {
+            return Ok(Auth::reject());
+        }
+        let answer = response
+            .and_then(|mut r| r.next())
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned());
+        let round = self.round;
+        self.round += 1;
+        Ok(match (round, answer.as_deref()) {
+            (0, None) => challenge("Password: ", false),
+            (1, Some(PASSWORD)) => challenge("Verification code: ", true),
+            (2, Some(OTP)) => Auth::Accept,
+            _ => Auth::reject(),
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        Ok(())
+    }
+}
+
+/// Announces a push notification first, takes a while to "approve" it, and
+/// only then asks for a code, like Duo's fallback flow.
+struct PushBastion {
+    round: usize,
+}
+
+impl server::Handler for PushBastion {
+    type Error = russh::Error;
+
+    async fn auth_keyboard_interactive<'a>(
+        &'a mut self,
+        _user: &str,
+        _submethods: &str,
+        response: Option<Response<'a>>,
+    ) -> Result<Auth, Self::Error> {
+        let answers: Option<Vec<String>> = response.map(|r| {
+            r.map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                .collect()
+        });
+        let round = self.round;
+        self.round += 1;
+        Ok(match (round, answers.as_deref()) {
+            (0, None) => Auth::Partial {
+                name: Cow::Borrowed("Duo two-factor"),
+                instructions: Cow::Borrowed("Push sent to your phone."),
+                prompts: Cow::Owned(Vec::new()),
+            },
+            // An info-only round must be acknowledged with no answers.
+            (1, Some([])) => {
+                tokio::time::sleep(Duration::from_millis(200)).await;
+                challenge("Verification code: ", true)
+            }
+            (2, Some([code])) if code == OTP => Auth::Accept,
+            _ => Auth::reject(),
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        Ok(())
+    }
+}
+
+fn challenge(prompt: &'static str, echo: bool) -> Auth {
+    Auth::Partial {
+        name: Cow::Borrowed("bastion"),
+        instructions: Cow::Borrowed(""),
+        prompts: Cow::Owned(vec![(Cow::Borrowed(prompt), echo)]),
+    }
+}
+
+/// Serve one SSH connection offering only `methods`; returns the port.
+async fn spawn_bastion(methods: &[MethodKind]) -> u16 {
+    let handler = Bastion {
+        round: 0,
+        keyboard_interactive: methods.contains(&MethodKind::KeyboardInteractive),
+    };
+    serve(methods, handler).await
+}
+
+async fn serve(
+    methods: &[MethodKind],
+    handler: impl server::Handler<Error = russh::Error> + Send + 'static,
+) -> u16 {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        methods: MethodSet::from(methods),
+        auth_rejection_time: Duration::ZERO,
+        auth_rejection_time_initial: Some(Duration::ZERO),
+        ..Default::default()
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, handler).await.unwrap();
+        let _ = session.await;
+    });
+    port
+}
+
+#[tokio::test]
+async fn password_and_otp_rounds_are_answered_by_the_callback() {
+    let port = spawn_bastion(&[MethodKind::KeyboardInteractive]).await;
+    let seen = Arc::new(Mutex::new(Vec::<AuthChallenge>::new()));
+    let record = seen.clone();
+
+    let mut conn = SshConnection::with_keyboard_interactive(
+        "127.0.0.1".into(),
+        port,
+        "ops".into(),
+        move |challenge| {
+            record.lock().unwrap().push(challenge.clone());
+            let answer = match challenge.prompts[0].text.as_str() {
+                "Password: " => PASSWORD,
+                _ => OTP,
+            };
+            vec![answer.to_string()]
+        },
+    );
+    conn.connect().await.expect("keyboard-interactive login");
+
+    let prompts: Vec<AuthPrompt> = seen
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|c| c.prompts.clone())
+        .collect();
+    assert_eq!(
+        prompts,
+        [
+            AuthPrompt {
+                text: "Password: ".into(),
+                echo: false
+            },
+            AuthPrompt {
+                text: "Verification code: ".into(),
+                echo: true
+            },
+        ]
+    );
+    conn.disconnect().await.unwrap();
+}
+
+#[tokio::test]
+async fn wrong_code_is_an_auth_error() {
+    let port = spawn_bastion(&[MethodKind::KeyboardInteractive]).await;
+
+    let mut conn = SshConnection::with_keyboard_interactive(
+        "127.0.0.1".into(),
+        port,
+        "ops".into(),
+        |challenge| match challenge.prompts[0].text.as_str() {
+            "Password: " => vec![PASSWORD.to_string()],
+            _ => vec!["000000".to_string()],
+        },
+    );
+    let err = conn.connect().await.unwrap_err();
+    assert!(matches!(err, ConnectionError::AuthError(_)), "{err:?}");
+}
+
+#[tokio::test]
+async fn password_only_server_gets_the_password_from_the_callback() {
+    // Offers only passwords, which this server's handler rejects; the point
+    // is that the callback is asked for one instead of failing outright.
+    let port = spawn_bastion(&[MethodKind::Password]).await;
+    let asked = Arc::new(Mutex::new(Vec::new()));
+    let record = asked.clone();
+
+    let mut conn = SshConnection::with_keyboard_interactive(
+        "127.0.0.1".into(),
+        port,
+        "ops".into(),
+        move |challenge| {
+            record.lock().unwrap().push(challenge.prompts.clone());
+            vec![PASSWORD.to_string()]
+        },
+    );
+    let err = conn.connect().await.unwrap_err();
+    assert!(err.to_string().contains("password rejected"), "{err}");
+    assert_eq!(
+        *asked.lock().unwrap(),
+        [vec![AuthPrompt {
+            text: "Password: ".into(),
+            echo: false
+        }]]
+    );
+}
+
+#[tokio::test]
+async fn info_only_round_is_shown_then_the_code_is_asked_for() {
+    let port = serve(&[MethodKind::KeyboardInteractive], PushBastion { round: 0 }).await;
+    let seen = Arc::new(Mutex::new(Vec::<AuthChallenge>::new()));
+    let record = seen.clone();
+
+    let mut conn = SshConnection::with_keyboard_interactive(
+        "127.0.0.1".into(),
+        port,
+        "ops".into(),
+        move |challenge| {
+            record.lock().unwrap().push(challenge.clone());
+            // A sloppy callback answering every round; the info-only round
+            // must not forward this to the server.
+            vec![OTP.to_string()]
+        },
+    );
+    conn.connect().await.expect("push then code login");
+    conn.disconnect().await.unwrap();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(seen.len(), 2, "{seen:?}");
+    assert!(seen[0].is_info_only());
+    assert_eq!(seen[0].instructions, "Push sent to your phone.");
+    assert_eq!(
+        seen[1].prompts,
+        [AuthPrompt {
+            text: "Verification code: ".into(),
+            echo: true
+        }]
+    );
+}
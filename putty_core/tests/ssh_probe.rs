@@ -0,0 +1,142 @@
+#![cfg(feature = "ssh")]
+
+//! `SshConnection::probe` against an in-process russh server with a narrowed
+//! algorithm offer, plus parsing of hand-built KEXINIT packets.
+
+use putty_core::connections::ssh::{ServerAlgorithms, SshConnection};
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server;
+use russh::{cipher, kex, Preferred};
+use std::borrow::Cow;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+
+struct Server;
+
+impl server::Handler for Server {
+    type Error = russh::Error;
+}
+
+/// Serve one SSH connection offering only the given kex and ciphers.
+async fn spawn_server(kex: &'static [kex::Name], ciphers: &'static [cipher::Name]) -> u16 {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        preferred: Preferred {
+            kex: Cow::Borrowed(kex),
+            cipher: Cow::Borrowed(ciphers),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        if let Ok(session) = server::run_stream(config, socket, Server).await {
+            let _ = session.await;
+        }
+    });
+    port
+}
+
+#[tokio::test]
+async fn probe_reports_what_the_server_offers() {
+    let port = spawn_server(
+        &[kex::CURVE25519],
+        &[cipher::AES_256_CTR, cipher::AES_128_CTR],
+    )
+    .await;
+
+    let probe = SshConnection::probe("127.0.0.1", port).await.unwrap();
+    let offered = &probe.server_algorithms;
+
+    assert_eq!(offered.kex[0], "curve25519-sha256");
+    assert_eq!(offered.host_key, ["ssh-ed25519"]);
+    assert_eq!(
+        offered.cipher_client_to_server,
+        ["aes256-ctr", "aes128-ctr"]
+    );
+    assert_eq!(
+        offered.cipher_server_to_client,
+        ["aes256-ctr", "aes128-ctr"]
+    );
+    assert!(!offered.mac_client_to_server.is_empty());
+    assert!(offered
+        .compression_server_to_client
+        .contains(&"none".to_owned()));
+
+    assert_eq!(probe.kex, "curve25519-sha256");
+    assert!(offered.cipher_client_to_server.contains(&probe.cipher));
+}
+
+/// Server output up to its KEXINIT, with one name-list per category.
+fn kexinit(lists: [&str; 10], msg: u8) -> Vec<u8> {
+    let mut payload = vec![msg];
+    payload.extend_from_slice(&[0xaa; 16]);
+    for list in lists {
+        payload.extend_from_slice(&(list.len() as u32).to_be_bytes());
+        payload.extend_from_slice(list.as_bytes());
+    }
+    payload.extend_from_slice(&[0, 0, 0, 0, 0]);
+    let padding = 8 - (payload.len() + 5) % 8 + 4;
+
+    let mut out = b"SSH-2.0-Test_1.0\r\n".to_vec();
+    out.extend_from_slice(&((payload.len() + padding + 1) as u32).to_be_bytes());
+    out.push(padding as u8);
+    out.extend_from_slice(&payload);
+    out.extend(std::iter::repeat_n(0, padding));
+    out
+}
+
+const LISTS: [&str; 10] = [
+    "curve25519-sha256,diffie-hellman-group14-sha256",
+    "ssh-ed25519,rsa-sha2-256",
+    "aes256-ctr",
+    "chacha20-poly1305@openssh.com",
+    "hmac-sha2-256",
+    "hmac-sha2-512",
+    "none,zlib@openssh.com",
+    "none",
+    "",
+    "",
+];
+
+#[test]
+fn parse_reads_every_name_list() {
+    let parsed = ServerAlgorithms::parse(&kexinit(LISTS, 20)).unwrap();
+    assert_eq!(
+        parsed,
+        ServerAlgorithms {
+            kex: vec![
+                "curve25519-sha256".into(),
+                "diffie-hellman-group14-sha256".into()
+            ],
+            host_key: vec!["ssh-ed25519".into(), "rsa-sha2-256".into()],
+            cipher_client_to_server: vec!["aes256-ctr".into()],
+            cipher_server_to_client: vec!["chacha20-poly1305@openssh.com".into()],
+            mac_client_to_server: vec!["hmac-sha2-256".into()],
+            mac_server_to_client: vec!["hmac-sha2-512".into()],
+            compression_client_to_server: vec!["none".into(), "zlib@openssh.com".into()],
+            compression_server_to_client: vec!["none".into()],
+        }
+    );
+}
+
+#[test]
+fn parse_skips_lines_before_the_identification() {
+    let mut received = b"Welcome\r\nauthorised use only\r\n".to_vec();
+    received.extend(kexinit(LISTS, 20));
+    let parsed = ServerAlgorithms::parse(&received).unwrap();
+    assert_eq!(parsed.host_key, ["ssh-ed25519", "rsa-sha2-256"]);
+}
+
+#[test]
+fn parse_refuses_truncated_or_foreign_packets() {
+    let full = kexinit(LISTS, 20);
+    for len in [0, 10, 20, 30, full.len() - 30] {
+        assert_eq!(ServerAlgorithms::parse(&full[..len]), None, "{len} bytes");
+    }
+    // SSH_MSG_IGNORE instead of SSH_MSG_KEXINIT.
+    assert_eq!(ServerAlgorithms::parse(&kexinit(LISTS, 2)), None);
+}
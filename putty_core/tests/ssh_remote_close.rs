@@ -0,0 +1,131 @@
+#![cfg(feature = "ssh")]
+
+//! The remote shell exiting ends the session, against an in-process russh
+//! server whose shell echoes input and exits on `exit`.
+
+use putty_core::connections::ssh::SshConnection;
+use putty_core::{ConnectionEventKind, ConnectionManager};
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::timeout;
+
+const PASSWORD: &str = "hunter2";
+
+struct Server;
+
+impl server::Handler for Server {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, _user: &str, password: &str) -> Result<Auth, Self::Error> {
+        Ok(if password == PASSWORD {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn data(
+        &mut self,
+        channel: ChannelId,
+        data: &[u8],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        if let Some(status) = data.strip_prefix(b"exit ") {
+            // Like a shell: exit status, EOF, then the channel closes.
+            let status = String::from_utf8_lossy(status).trim().parse().unwrap();
+            session.exit_status_request(channel, status)?;
+            session.eof(channel)?;
+            session.close(channel)?;
+        } else {
+            session.data(channel, data.to_vec())?;
+        }
+        Ok(())
+    }
+}
+
+/// Serve one SSH connection; returns its port.
+async fn spawn_server() -> u16 {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        methods: MethodSet::from(&[MethodKind::Password][..]),
+        ..Default::default()
+    });
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, Server).await.unwrap();
+        let _ = session.await;
+    });
+    port
+}
+
+#[tokio::test]
+async fn shell_exit_closes_subscriptions_and_reports_the_status() {
+    let port = spawn_server().await;
+    let manager = ConnectionManager::new();
+    let mut events = manager.events();
+    let conn = SshConnection::new("127.0.0.1".into(), port, "ops".into(), PASSWORD.into());
+    manager
+        .add_connection("box".into(), Box::new(conn))
+        .await
+        .expect("ssh login");
+    let mut shell = manager.subscribe("box").await.unwrap();
+
+    manager.write_bytes("box", b"echo hi").await.unwrap();
+    let echo = timeout(Duration::from_secs(5), shell.recv())
+        .await
+        .expect("shell echo")
+        .unwrap();
+    assert_eq!(echo, b"echo hi");
+
+    manager.write_bytes("box", b"exit 3\r").await.unwrap();
+    let closed = timeout(Duration::from_secs(5), shell.recv())
+        .await
+        .expect("the subscription should close once the shell exits");
+    assert_eq!(closed, Err(RecvError::Closed));
+
+    let mut kinds = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if !matches!(event.kind, ConnectionEventKind::Opened(_)) {
+            kinds.push(event.kind);
+        }
+    }
+    assert_eq!(
+        kinds,
+        [
+            ConnectionEventKind::RemoteClosed {
+                exit_status: Some(3)
+            },
+            ConnectionEventKind::Closed,
+        ]
+    );
+
+    // A late subscriber learns right away that the session is over.
+    let mut late = manager.subscribe("box").await.unwrap();
+    assert_eq!(late.recv().await, Err(RecvError::Closed));
+    manager.stop_connection("box").await.unwrap();
+}
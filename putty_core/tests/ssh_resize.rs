@@ -0,0 +1,138 @@
+#![cfg(feature = "ssh")]
+
+//! PTY size changes against an in-process russh server that reports the
+//! size of every pty and window-change request it receives.
+
+use putty_core::connections::ssh::SshConnection;
+use putty_core::ConnectionManager;
+use russh::keys::ssh_key::private::Ed25519Keypair;
+use russh::keys::PrivateKey;
+use russh::server::{self, Auth, Msg, Session};
+use russh::{Channel, ChannelId, MethodKind, MethodSet, Pty};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio::time::timeout;
+
+const PASSWORD: &str = "hunter2";
+
+/// A size request as seen by the server.
+#[derive(Debug, PartialEq, Eq)]
+enum SizeRequest {
+    Pty(u32, u32),
+    WindowChange(u32, u32),
+}
+
+struct Server {
+    report: mpsc::UnboundedSender<SizeRequest>,
+}
+
+impl server::Handler for Server {
+    type Error = russh::Error;
+
+    async fn auth_password(&mut self, _user: &str, password: &str) -> Result<Auth, Self::Error> {
+        Ok(if password == PASSWORD {
+            Auth::Accept
+        } else {
+            Auth::reject()
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        _channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        Ok(true)
+    }
+
+    async fn pty_request(
+        &mut self,
+        channel: ChannelId,
+        _term: &str,
+        col_width: u32,
+        row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _modes: &[(Pty, u32)],
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let _ = self.report.send(SizeRequest::Pty(col_width, row_height));
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn shell_request(
+        &mut self,
+        channel: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.channel_success(channel)?;
+        Ok(())
+    }
+
+    async fn window_change_request(
+        &mut self,
+        _channel: ChannelId,
+        col_width: u32,
+        row_height: u32,
+        _pix_width: u32,
+        _pix_height: u32,
+        _session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let _ = self
+            .report
+            .send(SizeRequest::WindowChange(col_width, row_height));
+        Ok(())
+    }
+}
+
+/// Serve one SSH connection; returns its port and the size reports.
+async fn spawn_server() -> (u16, mpsc::UnboundedReceiver<SizeRequest>) {
+    let config = Arc::new(server::Config {
+        keys: vec![PrivateKey::from(Ed25519Keypair::from_seed(&[7; 32]))],
+        methods: MethodSet::from(&[MethodKind::Password][..]),
+        ..Default::default()
+    });
+    let (report, reports) = mpsc::unbounded_channel();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    tokio::spawn(async move {
+        let (socket, _) = listener.accept().await.unwrap();
+        let session = server::run_stream(config, socket, Server { report })
+            .await
+            .unwrap();
+        let _ = session.await;
+    });
+    (port, reports)
+}
+
+async fn next(reports: &mut mpsc::UnboundedReceiver<SizeRequest>) -> SizeRequest {
+    timeout(Duration::from_secs(5), reports.recv())
+        .await
+        .expect("size request reached the server")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn resize_sends_a_window_change_to_the_server() {
+    let (port, mut reports) = spawn_server().await;
+    let manager = ConnectionManager::new();
+    let conn = SshConnection::new("127.0.0.1".into(), port, "ops".into(), PASSWORD.into())
+        .with_pty_size(100, 30);
+    manager
+        .add_connection("box".into(), Box::new(conn))
+        .await
+        .expect("ssh login");
+    assert_eq!(next(&mut reports).await, SizeRequest::Pty(100, 30));
+
+    manager.resize("box", 132, 43).await.unwrap();
+    assert_eq!(next(&mut reports).await, SizeRequest::WindowChange(132, 43));
+    assert_eq!(manager.pty_size("box").await, Some((132, 43)));
+
+    manager.resize("box", 80, 24).await.unwrap();
+    assert_eq!(next(&mut reports).await, SizeRequest::WindowChange(80, 24));
+
+    manager.stop_connection("box").await.unwrap();
+}
@@ -0,0 +1,91 @@
+use putty_core::{ConnectionManager, SubscriptionItem};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn stable_subscription_survives_reconnect() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (first_connection, first_tx, _first_rx) = FakeConnection::new();
+
+    connection_manager
+        .add_connection("dev".into(), Box::new(first_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let mut subscription = connection_manager
+        .subscribe_stable("dev")
+        .await
+        .expect("dev must exist");
+
+    // ── Data from the first transport ────────────────────────────────────
+    first_tx.send(b"before".to_vec()).await.unwrap();
+    let item = timeout(Duration::from_millis(200), subscription.recv())
+        .await
+        .expect("timeout waiting for pre-reconnect data");
+    assert_eq!(item, Some(SubscriptionItem::Data(b"before".to_vec())));
+
+    // ── Force a reconnect: drop the transport and register a new one ─────
+    connection_manager
+        .stop_connection("dev")
+        .await
+        .expect("stop should succeed");
+    let (second_connection, second_tx, _second_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("dev".into(), Box::new(second_connection))
+        .await
+        .expect("re-adding dev should succeed");
+
+    let item = timeout(Duration::from_millis(200), subscription.recv())
+        .await
+        .expect("timeout waiting for reconnect marker");
+    assert_eq!(item, Some(SubscriptionItem::Reconnected));
+
+    // ── The same subscription keeps delivering post-reconnect data ───────
+    second_tx.send(b"after".to_vec()).await.unwrap();
+    let item = timeout(Duration::from_millis(200), subscription.recv())
+        .await
+        .expect("timeout waiting for post-reconnect data");
+    assert_eq!(item, Some(SubscriptionItem::Data(b"after".to_vec())));
+}
+
+#[tokio::test]
+async fn stable_subscription_ends_once_the_manager_is_dropped() {
+    let connection_manager = ConnectionManager::new();
+    let (connection, _tx, _rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("dev".into(), Box::new(connection))
+        .await
+        .expect("add_connection should succeed");
+    // Another live connection keeps the events channel open, so only the
+    // manager being dropped can end the subscription.
+    let (other, _other_tx, _other_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("other".into(), Box::new(other))
+        .await
+        .expect("add_connection should succeed");
+
+    let mut subscription = connection_manager
+        .subscribe_stable("dev")
+        .await
+        .expect("dev must exist");
+    connection_manager
+        .stop_connection("dev")
+        .await
+        .expect("stop should succeed");
+
+    let waiting = tokio::spawn(async move { subscription.recv().await });
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(!waiting.is_finished(), "should wait for dev to come back");
+
+    drop(connection_manager);
+    let item = timeout(Duration::from_millis(500), waiting)
+        .await
+        .expect("recv should end once the manager is dropped")
+        .unwrap();
+    assert_eq!(item, None);
+}
@@ -0,0 +1,70 @@
+use putty_core::ConnectionManager;
+use tokio::io::AsyncReadExt;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn tee_writer_and_receiver_see_identical_bytes() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("fakePort".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    // The writer is one end of an in-memory pipe; the test reads the other.
+    let (writer, mut persisted) = tokio::io::duplex(4096);
+    let mut live_rx = connection_manager
+        .subscribe_tee("fakePort", writer)
+        .await
+        .expect("connection should exist");
+
+    let chunks: Vec<Vec<u8>> = vec![b"boot\r\n".to_vec(), vec![0x00, 0xff, 0x7f], b"ok".to_vec()];
+    for chunk in &chunks {
+        test_to_fake_tx.send(chunk.clone()).await.unwrap();
+    }
+
+    let mut live = Vec::new();
+    for _ in &chunks {
+        let chunk = timeout(Duration::from_millis(200), live_rx.recv())
+            .await
+            .expect("timeout waiting for live data")
+            .expect("tee closed unexpectedly");
+        live.extend(chunk);
+    }
+
+    // Closing the connection ends the tee, which shuts the writer down.
+    connection_manager
+        .stop_connection("fakePort")
+        .await
+        .unwrap();
+    let mut written = Vec::new();
+    timeout(
+        Duration::from_millis(500),
+        persisted.read_to_end(&mut written),
+    )
+    .await
+    .expect("tee never closed the writer")
+    .unwrap();
+
+    assert_eq!(live, chunks.concat());
+    assert_eq!(written, live);
+    assert!(
+        live_rx.recv().await.is_none(),
+        "receiver closes with the tee"
+    );
+}
+
+#[tokio::test]
+async fn tee_of_unknown_connection_is_none() {
+    let connection_manager = ConnectionManager::new();
+    assert!(connection_manager
+        .subscribe_tee("missing", tokio::io::sink())
+        .await
+        .is_none());
+}
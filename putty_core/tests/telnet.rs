@@ -0,0 +1,280 @@
+use putty_core::connections::telnet::codec::{
+    DO, DONT, ECHO, IAC, SB, SE, SUPPRESS_GO_AHEAD, WILL, WONT,
+};
+use putty_core::connections::telnet::{TelnetCodec, TelnetConnection};
+use putty_core::{ConnectionEventKind, ConnectionManager, ConnectionOptions, ConnectionState};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{timeout, Duration};
+
+/// Feed `input` to a fresh codec in chunks of `chunk` bytes.
+fn decode_in_chunks(input: &[u8], chunk: usize) -> (Vec<u8>, Vec<u8>, TelnetCodec) {
+    let mut codec = TelnetCodec::new();
+    let mut data = Vec::new();
+    let mut replies = Vec::new();
+    for piece in input.chunks(chunk) {
+        codec.decode(piece, &mut data);
+        replies.extend(codec.take_replies());
+    }
+    (data, replies, codec)
+}
+
+#[test]
+fn commands_are_stripped_however_the_stream_is_split() {
+    let input = [
+        b"login".as_slice(),
+        &[IAC, WILL, ECHO],
+        b": ",
+        &[IAC, SB, 24, 1, IAC, IAC, IAC, SE],
+        &[IAC, 241], // NOP
+        b"ok",
+    ]
+    .concat();
+
+    for chunk in 1..=input.len() {
+        let (data, replies, codec) = decode_in_chunks(&input, chunk);
+        assert_eq!(data, b"login: ok", "chunks of {chunk}");
+        assert_eq!(replies, [IAC, DO, ECHO], "chunks of {chunk}");
+        assert!(codec.remote_echo());
+    }
+}
+
+#[test]
+fn ff_bytes_are_escaped_both_ways() {
+    let (data, replies, _) = decode_in_chunks(&[0x01, IAC, IAC, 0x02], 2);
+    assert_eq!(data, [0x01, IAC, 0x02]);
+    assert!(replies.is_empty());
+
+    assert_eq!(
+        TelnetCodec::new().encode(&[0x01, IAC, 0x02]),
+        [0x01, IAC, IAC, 0x02]
+    );
+}
+
+#[test]
+fn bare_carriage_returns_carry_nul_padding() {
+    let mut codec = TelnetCodec::new();
+    assert_eq!(codec.encode(b"ls\r\n"), b"ls\r\n");
+    assert_eq!(codec.encode(b"a\rb\r\r"), b"a\r\0b\r\0\r");
+    assert_eq!(codec.flush(), b"\0");
+    assert!(codec.flush().is_empty());
+
+    let (data, _, _) = decode_in_chunks(b"a\r\0b\r\nc", 1);
+    assert_eq!(data, b"a\rb\r\nc");
+}
+
+#[test]
+fn line_ends_split_across_writes_are_not_padded() {
+    let mut codec = TelnetCodec::new();
+    assert_eq!(codec.encode(b"\r"), b"\r");
+    assert_eq!(codec.encode(b"\n"), b"\n");
+
+    // A bare CR gets its NUL once the next byte shows it is not a line end.
+    assert_eq!(codec.encode(b"ls\r"), b"ls\r");
+    assert_eq!(codec.encode(b"x"), b"\0x");
+}
+
+#[test]
+fn only_echo_and_suppress_go_ahead_are_accepted() {
+    let input = [
+        [IAC, WILL, SUPPRESS_GO_AHEAD],
+        [IAC, DO, SUPPRESS_GO_AHEAD],
+        [IAC, WILL, 31], // NAWS
+        [IAC, DO, 24],   // TERMINAL-TYPE
+        [IAC, DO, ECHO],
+    ]
+    .concat();
+    let (_, replies, codec) = decode_in_chunks(&input, input.len());
+
+    assert_eq!(
+        replies,
+        [
+            [IAC, DO, SUPPRESS_GO_AHEAD],
+            [IAC, WILL, SUPPRESS_GO_AHEAD],
+            [IAC, DONT, 31],
+            [IAC, WONT, 24],
+            [IAC, WONT, ECHO],
+        ]
+        .concat()
+    );
+    assert!(codec.suppress_go_ahead());
+    assert!(!codec.remote_echo());
+}
+
+#[test]
+fn repeated_requests_are_not_acknowledged_again() {
+    let input = [
+        [IAC, WILL, ECHO],
+        [IAC, WILL, ECHO],
+        [IAC, WONT, ECHO],
+        [IAC, WONT, ECHO],
+        [IAC, DONT, SUPPRESS_GO_AHEAD],
+    ]
+    .concat();
+    let (_, replies, codec) = decode_in_chunks(&input, 1);
+
+    assert_eq!(replies, [[IAC, DO, ECHO], [IAC, DONT, ECHO]].concat());
+    assert!(!codec.remote_echo());
+}
+
+#[test]
+fn requested_options_are_not_acknowledged_twice() {
+    let mut codec = TelnetCodec::new();
+    codec.request_local(44);
+    assert_eq!(codec.take_replies(), [IAC, WILL, 44]);
+    assert_eq!(codec.local_option(44), None);
+
+    codec.decode(&[IAC, DO, 44, IAC, DO, 44], &mut Vec::new());
+    assert!(codec.take_replies().is_empty());
+    assert_eq!(codec.local_option(44), Some(true));
+
+    let mut refused = TelnetCodec::new();
+    refused.request_local(44);
+    refused.take_replies();
+    refused.decode(&[IAC, DONT, 44], &mut Vec::new());
+    assert!(refused.take_replies().is_empty());
+    assert_eq!(refused.local_option(44), Some(false));
+}
+
+#[test]
+fn subnegotiations_of_enabled_options_are_kept_apart_from_data() {
+    let mut input = vec![IAC, DO, 44];
+    input.extend_from_slice(b"ab");
+    input.extend(TelnetCodec::subnegotiation(44, &[107, IAC]));
+    input.extend_from_slice(b"c");
+    input.extend(TelnetCodec::subnegotiation(24, &[0]));
+    assert_eq!(&input[input.len() - 6..], [IAC, SB, 24, 0, IAC, SE]);
+
+    for chunk in 1..=input.len() {
+        let mut codec = TelnetCodec::new();
+        codec.request_local(44);
+        let mut data = Vec::new();
+        for piece in input.chunks(chunk) {
+            codec.decode(piece, &mut data);
+        }
+        assert_eq!(data, b"abc", "chunks of {chunk}");
+        assert_eq!(
+            codec.take_subnegotiations(),
+            [vec![44, 107, IAC]],
+            "chunks of {chunk}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn session_answers_negotiation_and_passes_data() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let connection_manager = ConnectionManager::new();
+    connection_manager
+        .add_connection(
+            "router".into(),
+            Box::new(TelnetConnection::new("127.0.0.1".into(), port)),
+        )
+        .await
+        .expect("connecting to the listener should succeed");
+    let mut received = connection_manager.subscribe("router").await.unwrap();
+    let (mut device, _) = listener.accept().await.unwrap();
+
+    // Negotiation only: nothing may reach the subscribers.
+    device.write_all(&[IAC, WILL, ECHO]).await.unwrap();
+    let mut answer = [0u8; 3];
+    timeout(Duration::from_secs(2), device.read_exact(&mut answer))
+        .await
+        .expect("no answer to WILL ECHO")
+        .unwrap();
+    assert_eq!(answer, [IAC, DO, ECHO]);
+
+    device.write_all(&[b'>', IAC, IAC, b' ']).await.unwrap();
+    let chunk = timeout(Duration::from_secs(2), received.recv())
+        .await
+        .expect("no data reached the subscriber")
+        .unwrap();
+    assert_eq!(chunk, [b'>', IAC, b' ']);
+
+    connection_manager
+        .write_bytes_acked("router", &[b'x', IAC, b'\r'])
+        .await
+        .unwrap();
+    // The CR's padding waits for the next byte, or a flush.
+    connection_manager.flush("router").await.unwrap();
+    let mut sent = [0u8; 5];
+    timeout(Duration::from_secs(2), device.read_exact(&mut sent))
+        .await
+        .expect("the write did not arrive")
+        .unwrap();
+    assert_eq!(sent, [b'x', IAC, IAC, b'\r', 0]);
+
+    connection_manager.stop_connection("router").await.unwrap();
+}
+
+#[tokio::test]
+async fn line_ends_survive_a_char_delay() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let connection_manager = ConnectionManager::new();
+    connection_manager
+        .add_connection_with_options(
+            "router".into(),
+            Box::new(TelnetConnection::new("127.0.0.1".into(), port)),
+            ConnectionOptions::new().with_char_delay(Duration::from_millis(1)),
+        )
+        .await
+        .expect("connecting to the listener should succeed");
+    let (mut device, _) = listener.accept().await.unwrap();
+
+    // Every byte is a write of its own.
+    connection_manager
+        .write_bytes_acked("router", b"ls\r\nx")
+        .await
+        .unwrap();
+    let mut sent = [0u8; 5];
+    timeout(Duration::from_secs(2), device.read_exact(&mut sent))
+        .await
+        .expect("the write did not arrive")
+        .unwrap();
+    assert_eq!(&sent, b"ls\r\nx");
+
+    connection_manager.stop_connection("router").await.unwrap();
+}
+
+#[tokio::test]
+async fn server_hanging_up_ends_the_session() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    connection_manager
+        .add_connection(
+            "router".into(),
+            Box::new(TelnetConnection::new("127.0.0.1".into(), port)),
+        )
+        .await
+        .expect("connecting to the listener should succeed");
+    let mut received = connection_manager.subscribe("router").await.unwrap();
+    let (device, _) = listener.accept().await.unwrap();
+    drop(device);
+
+    let closed = timeout(Duration::from_secs(2), received.recv())
+        .await
+        .expect("the subscription should close once the server hangs up");
+    assert_eq!(closed, Err(RecvError::Closed));
+    assert_eq!(
+        connection_manager.status("router").await,
+        Some(ConnectionState::Disconnected)
+    );
+    let mut kinds = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        kinds.push(event.kind);
+    }
+    assert!(
+        kinds.contains(&ConnectionEventKind::RemoteClosed { exit_status: None }),
+        "{kinds:?}"
+    );
+
+    connection_manager.stop_connection("router").await.unwrap();
+}
@@ -0,0 +1,98 @@
+use log::LevelFilter;
+use putty_core::{ConnectionManager, ConnectionOptions};
+use tokio::time::{timeout, Duration, Instant};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging_at;
+
+/// 1 MiB in 256 byte pieces: 4096 passes through the I/O task each way.
+const TOTAL: usize = 1 << 20;
+const PIECE: usize = 256;
+/// Only there to turn a hang into a failure; a loaded runner may be slow.
+const HANG: Duration = Duration::from_secs(60);
+
+/// Send [`TOTAL`] bytes each way through one connection, check that every
+/// byte arrives in order, and return how long that took.
+async fn transfer_both_ways() -> Duration {
+    init_logging_at(LevelFilter::Info);
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    fake_connection.max_write_chunk = Some(PIECE);
+    connection_manager
+        .add_connection_with_options(
+            "pipe".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_scrollback(TOTAL),
+        )
+        .await
+        .expect("add_connection should succeed");
+    let started = Instant::now();
+
+    // ── Write: one large write, taken by the device in short writes ──────
+    let device = tokio::spawn(async move {
+        let mut received = Vec::with_capacity(TOTAL);
+        while received.len() < TOTAL {
+            received.extend(fake_to_test_rx.recv().await.expect("device input"));
+        }
+        received
+    });
+    let payload: Vec<u8> = (0..TOTAL).map(|i| (i % 251) as u8).collect();
+    connection_manager
+        .write_bytes_acked("pipe", &payload)
+        .await
+        .unwrap();
+    let written = timeout(HANG, device)
+        .await
+        .expect("the write side hung")
+        .unwrap();
+    assert!(written == payload, "the device got the write out of order");
+
+    // ── Read: the device sends as fast as the I/O task takes it ─────────
+    let sent = payload.clone();
+    let sender = tokio::spawn(async move {
+        for piece in sent.chunks(PIECE) {
+            test_to_fake_tx.send(piece.to_vec()).await.unwrap();
+        }
+        test_to_fake_tx
+    });
+    let _test_to_fake_tx = sender.await.unwrap();
+    timeout(HANG, async {
+        while connection_manager
+            .scrollback_stats("pipe")
+            .await
+            .unwrap()
+            .size
+            < TOTAL
+        {
+            tokio::task::yield_now().await;
+        }
+    })
+    .await
+    .expect("the read side hung");
+    let elapsed = started.elapsed();
+
+    assert!(
+        connection_manager.scrollback("pipe").await.unwrap() == payload,
+        "the read arrived out of order"
+    );
+    elapsed
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn a_large_transfer_arrives_whole_and_in_order() {
+    transfer_both_ways().await;
+}
+
+/// Benchmark: even a millisecond of delay per pass through the I/O task
+/// would take seconds. Run with `cargo test --release -- --ignored`.
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+#[ignore = "timing depends on the machine"]
+async fn a_large_transfer_is_drained_without_delay() {
+    let elapsed = transfer_both_ways().await;
+    assert!(
+        elapsed < Duration::from_secs(2),
+        "1 MiB each way took {elapsed:?}"
+    );
+}
@@ -0,0 +1,144 @@
+#![cfg(feature = "websocket")]
+
+use futures::{SinkExt, StreamExt};
+use putty_core::connections::errors::ConnectionError;
+use putty_core::connections::websocket::WebSocketConnection;
+use putty_core::connections::Connection;
+use putty_core::{ConnectionEventKind, ConnectionManager, ConnectionState};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{timeout, Duration};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{accept_async, WebSocketStream};
+
+async fn listen() -> (TcpListener, String) {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("ws://{}/console", listener.local_addr().unwrap());
+    (listener, url)
+}
+
+async fn accept(listener: &TcpListener) -> WebSocketStream<TcpStream> {
+    let (stream, _) = listener.accept().await.unwrap();
+    accept_async(stream).await.unwrap()
+}
+
+async fn next_message(board: &mut WebSocketStream<TcpStream>) -> Message {
+    timeout(Duration::from_secs(2), board.next())
+        .await
+        .expect("the client sent nothing")
+        .unwrap()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn frames_map_to_bytes_and_pings_are_answered() {
+    let (listener, url) = listen().await;
+    let server = tokio::spawn(async move { accept(&listener).await });
+    let mut conn = WebSocketConnection::new(url.clone());
+    conn.connect().await.expect("the handshake should succeed");
+    let mut board = server.await.unwrap();
+    assert_eq!(conn.negotiated()["url"], url);
+
+    board
+        .send(Message::Ping(b"tick".to_vec().into()))
+        .await
+        .unwrap();
+    board.send(Message::binary(vec![0u8, 0xFF])).await.unwrap();
+    board.send(Message::text("µC> ")).await.unwrap();
+    let mut buffer = [0u8; 64];
+    let read = conn.read(&mut buffer).await.unwrap();
+    assert_eq!(&buffer[..read], [0, 0xFF]);
+    let read = conn.read(&mut buffer).await.unwrap();
+    assert_eq!(&buffer[..read], "µC> ".as_bytes());
+    assert_eq!(
+        next_message(&mut board).await,
+        Message::Pong(b"tick".to_vec().into())
+    );
+
+    conn.write(b"reset\r").await.unwrap();
+    assert_eq!(
+        next_message(&mut board).await,
+        Message::binary(b"reset\r".to_vec())
+    );
+
+    board.close(None).await.unwrap();
+    let closed = timeout(Duration::from_secs(2), conn.read(&mut buffer))
+        .await
+        .expect("the close did not end the stream");
+    assert!(
+        matches!(
+            closed,
+            Err(ConnectionError::RemoteClosed { exit_status: None })
+        ),
+        "{closed:?}"
+    );
+}
+
+#[tokio::test]
+async fn long_messages_are_read_in_pieces() {
+    let (listener, url) = listen().await;
+    let server = tokio::spawn(async move { accept(&listener).await });
+    let connection_manager = ConnectionManager::new();
+    connection_manager
+        .add_connection("board".into(), Box::new(WebSocketConnection::new(url)))
+        .await
+        .expect("the handshake should succeed");
+    let mut received = connection_manager.subscribe("board").await.unwrap();
+    let mut board = server.await.unwrap();
+
+    let log: Vec<u8> = (0..20_000u32).map(|i| i as u8).collect();
+    board.send(Message::binary(log.clone())).await.unwrap();
+    let mut data = Vec::new();
+    while data.len() < log.len() {
+        let chunk = timeout(Duration::from_secs(2), received.recv())
+            .await
+            .expect("no data reached the subscriber")
+            .unwrap();
+        data.extend(chunk);
+    }
+    assert_eq!(data, log);
+
+    connection_manager.stop_connection("board").await.unwrap();
+}
+
+#[tokio::test]
+async fn close_from_the_board_ends_the_session() {
+    let (listener, url) = listen().await;
+    let server = tokio::spawn(async move { accept(&listener).await });
+    let connection_manager = ConnectionManager::new();
+    let mut events = connection_manager.events();
+    connection_manager
+        .add_connection("board".into(), Box::new(WebSocketConnection::new(url)))
+        .await
+        .expect("the handshake should succeed");
+    let mut received = connection_manager.subscribe("board").await.unwrap();
+    let mut board = server.await.unwrap();
+
+    board.close(None).await.unwrap();
+    let closed = timeout(Duration::from_secs(2), received.recv())
+        .await
+        .expect("the subscription should close once the board closes");
+    assert_eq!(closed, Err(RecvError::Closed));
+    assert_eq!(
+        connection_manager.status("board").await,
+        Some(ConnectionState::Disconnected)
+    );
+    let mut kinds = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        kinds.push(event.kind);
+    }
+    assert!(
+        kinds.contains(&ConnectionEventKind::RemoteClosed { exit_status: None }),
+        "{kinds:?}"
+    );
+
+    connection_manager.stop_connection("board").await.unwrap();
+}
+
+#[tokio::test]
+async fn other_schemes_are_rejected() {
+    let mut conn = WebSocketConnection::new("http://127.0.0.1:1/".into());
+    let err = conn.connect().await.unwrap_err().to_string();
+    assert!(err.contains("expected ws:// or wss://"), "{err}");
+    assert!(!conn.is_connected());
+}
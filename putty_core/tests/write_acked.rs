@@ -0,0 +1,46 @@
+use putty_core::ConnectionManager;
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn write_bytes_acked_reports_transport_result() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+
+    let (good_connection, _good_tx, mut good_rx) = FakeConnection::new();
+    let (mut bad_connection, _bad_tx, mut bad_rx) = FakeConnection::new();
+    bad_connection.fail_writes = true;
+
+    connection_manager
+        .add_connection("good".into(), Box::new(good_connection))
+        .await
+        .expect("adding good should succeed");
+    connection_manager
+        .add_connection("bad".into(), Box::new(bad_connection))
+        .await
+        .expect("adding bad should succeed");
+
+    // ── Successful write: acked only once the transport took the bytes ───
+    let written = connection_manager
+        .write_bytes_acked("good", b"ok")
+        .await
+        .expect("acked write should succeed");
+    assert_eq!(written, 2);
+    assert_eq!(good_rx.try_recv().expect("bytes reached the fake"), b"ok");
+
+    // ── Failing write: the transport error is returned to the caller ─────
+    connection_manager
+        .write_bytes_acked("bad", b"lost")
+        .await
+        .expect_err("acked write should surface the transport error");
+    assert!(
+        timeout(Duration::from_millis(50), bad_rx.recv())
+            .await
+            .is_err(),
+        "nothing should have reached the failing fake"
+    );
+}
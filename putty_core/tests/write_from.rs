@@ -0,0 +1,62 @@
+use putty_core::ConnectionManager;
+use tokio::time::{timeout, Duration, Instant};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn reader_is_streamed_in_order_and_in_chunks() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("dev".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let payload: Vec<u8> = (0..10_000u32).map(|i| (i % 251) as u8).collect();
+    let written = connection_manager
+        .write_from("dev", payload.as_slice(), 1024, Duration::ZERO)
+        .await
+        .expect("write_from should succeed");
+    assert_eq!(written, payload.len() as u64);
+
+    let mut received = Vec::new();
+    while received.len() < payload.len() {
+        let chunk = timeout(Duration::from_secs(1), fake_to_test_rx.recv())
+            .await
+            .expect("timeout waiting for a chunk")
+            .expect("fake connection closed");
+        assert!(chunk.len() <= 1024, "chunk of {} bytes", chunk.len());
+        received.extend_from_slice(&chunk);
+    }
+    assert_eq!(received, payload);
+}
+
+#[tokio::test]
+async fn pause_spaces_out_the_chunks() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, _fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("dev".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    let started = Instant::now();
+    let pause = Duration::from_millis(20);
+    connection_manager
+        .write_from("dev", &b"ATZ\rATI\rAT&V\r"[..], 4, pause)
+        .await
+        .expect("write_from should succeed");
+    // Four chunks, three pauses between them.
+    assert!(started.elapsed() >= pause * 3, "{:?}", started.elapsed());
+
+    assert!(connection_manager
+        .write_from("dev", &b"x"[..], 0, Duration::ZERO)
+        .await
+        .is_err());
+}
@@ -0,0 +1,92 @@
+use putty_core::connections::errors::ConnectionError;
+use putty_core::{ConnectionManager, ConnectionOptions};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn writes_beyond_the_limit_are_busy_until_the_backlog_drains() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (mut fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    fake_connection.write_delay = Some(Duration::from_millis(50));
+    connection_manager
+        .add_connection_with_options(
+            "slow".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_max_in_flight_writes(3),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    // ── Fill every slot ──────────────────────────────────────────────────
+    for i in 0..3u8 {
+        connection_manager
+            .write_bytes("slow", &[i])
+            .await
+            .expect("writes within the limit are queued");
+    }
+
+    // ── Every kind of write is turned away at once ───────────────────────
+    let busy = timeout(Duration::from_millis(20), async {
+        (
+            connection_manager.write_bytes("slow", b"x").await,
+            connection_manager.write_bytes_acked("slow", b"x").await,
+            connection_manager.write_raw("slow", b"x").await,
+        )
+    })
+    .await
+    .expect("a full connection must reject instead of waiting");
+    for result in [busy.0, busy.1, busy.2] {
+        assert!(
+            matches!(result, Err(ConnectionError::Busy { limit: 3 })),
+            "{result:?}"
+        );
+    }
+
+    // ── The queued writes still go through, in order ─────────────────────
+    for i in 0..3u8 {
+        let written = timeout(Duration::from_millis(500), fake_to_test_rx.recv())
+            .await
+            .expect("timeout waiting for a queued write")
+            .unwrap();
+        assert_eq!(written, [i]);
+    }
+
+    // ── Once drained, writes are accepted again ──────────────────────────
+    let written = timeout(Duration::from_millis(500), async {
+        loop {
+            match connection_manager.write_bytes_acked("slow", b"again").await {
+                Err(ConnectionError::Busy { .. }) => {
+                    tokio::time::sleep(Duration::from_millis(5)).await
+                }
+                other => break other,
+            }
+        }
+    })
+    .await
+    .expect("slots were never released");
+    assert_eq!(written.unwrap(), 5);
+}
+
+#[tokio::test]
+async fn without_a_limit_writes_are_never_busy() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+    connection_manager
+        .add_connection("plain".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+
+    for i in 0..20u8 {
+        connection_manager.write_bytes("plain", &[i]).await.unwrap();
+    }
+    for i in 0..20u8 {
+        assert_eq!(fake_to_test_rx.recv().await.unwrap(), [i]);
+    }
+}
@@ -0,0 +1,46 @@
+use putty_core::{ConnectionManager, ConnectionOptions};
+use tokio::time::{timeout, Duration};
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+#[tokio::test]
+async fn write_raw_bypasses_crlf_translation() {
+    init_logging();
+
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, _test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+
+    connection_manager
+        .add_connection_with_options(
+            "modem".into(),
+            Box::new(fake_connection),
+            ConnectionOptions::new().with_crlf(true),
+        )
+        .await
+        .expect("add_connection should succeed");
+
+    // ── write_bytes goes through the CRLF transform ──────────────────────
+    connection_manager
+        .write_bytes("modem", b"AT\n")
+        .await
+        .expect("write_bytes should succeed");
+    let translated = timeout(Duration::from_millis(100), fake_to_test_rx.recv())
+        .await
+        .expect("timeout waiting for translated write")
+        .expect("fake_to_test channel closed unexpectedly");
+    assert_eq!(translated, b"AT\r\n");
+
+    // ── write_raw delivers the bytes verbatim ────────────────────────────
+    let written = connection_manager
+        .write_raw("modem", b"\x00bin\n\xff")
+        .await
+        .expect("write_raw should succeed");
+    assert_eq!(written, 6);
+    let raw = timeout(Duration::from_millis(100), fake_to_test_rx.recv())
+        .await
+        .expect("timeout waiting for raw write")
+        .expect("fake_to_test channel closed unexpectedly");
+    assert_eq!(raw, b"\x00bin\n\xff");
+}
@@ -0,0 +1,59 @@
+use putty_core::{connections::errors::ConnectionError, ConnectionManager};
+use tokio::time::Duration;
+
+mod common;
+use common::fake_connection::FakeConnection;
+use common::init_logging;
+
+/// Register a fake under `"dev"` whose device side echoes every write,
+/// flipping the byte at `corrupt_at` when given.
+async fn echoing_manager(corrupt_at: Option<usize>) -> ConnectionManager {
+    let connection_manager = ConnectionManager::new();
+    let (fake_connection, test_to_fake_tx, mut fake_to_test_rx) = FakeConnection::new();
+
+    tokio::spawn(async move {
+        while let Some(mut chunk) = fake_to_test_rx.recv().await {
+            if let Some(byte) = corrupt_at.and_then(|i| chunk.get_mut(i)) {
+                *byte ^= 0xFF;
+            }
+            let _ = test_to_fake_tx.send(chunk).await;
+        }
+    });
+
+    connection_manager
+        .add_connection("dev".into(), Box::new(fake_connection))
+        .await
+        .expect("add_connection should succeed");
+    connection_manager
+}
+
+#[tokio::test]
+async fn write_verified_checks_the_echo() {
+    init_logging();
+
+    // ── Clean echo ───────────────────────────────────────────────────────
+    let clean = echoing_manager(None).await;
+    let written = clean
+        .write_verified("dev", b"AT\r", Duration::from_millis(200))
+        .await
+        .expect("clean echo should verify");
+    assert_eq!(written, 3);
+
+    // ── Corrupted echo names the first differing byte ────────────────────
+    let noisy = echoing_manager(Some(1)).await;
+    let err = noisy
+        .write_verified("dev", b"AT\r", Duration::from_millis(200))
+        .await
+        .expect_err("corrupted echo must be reported");
+    assert!(
+        matches!(
+            err,
+            ConnectionError::EchoMismatch {
+                offset: 1,
+                expected: b'T',
+                actual,
+            } if actual == b'T' ^ 0xFF
+        ),
+        "unexpected error: {err}"
+    );
+}
@@ -0,0 +1,33 @@
+#![cfg(feature = "ssh")]
+
+use putty_core::connections::ssh::X11Display;
+
+#[test]
+fn local_displays_use_the_unix_socket() {
+    for display in [":0", "unix:0", ":0.0"] {
+        assert_eq!(
+            X11Display::parse(display).unwrap(),
+            X11Display {
+                host: None,
+                number: 0,
+                screen: 0
+            },
+            "{display}"
+        );
+    }
+}
+
+#[test]
+fn remote_display_keeps_host_and_screen() {
+    let display = X11Display::parse("localhost:10.1").unwrap();
+    assert_eq!(display.host.as_deref(), Some("localhost"));
+    assert_eq!((display.number, display.screen), (10, 1));
+    assert_eq!(display.to_string(), "localhost:10.1");
+}
+
+#[test]
+fn malformed_displays_are_rejected() {
+    for display in ["", "localhost", ":x", ":0.y"] {
+        assert!(X11Display::parse(display).is_err(), "{display:?}");
+    }
+}
@@ -1,7 +1,7 @@
 fn main() {
     tonic_build::configure()
         .build_server(true)
-        .build_client(false) // we only need the server side
+        .build_client(true) // client stubs are only used by the integration tests
         // .out_dir("src/")              // generated code goes into src/  -> Does not work after rebuilds
         .compile_protos(&["proto/putty_interface.proto"], &["proto"])
         .unwrap();
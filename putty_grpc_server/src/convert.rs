@@ -1,33 +1,86 @@
 //! Bidirectional conversion helpers between the protobuf world
 //! and the domain structs that live in putty_storage.
 
+use std::path::PathBuf;
+
+use putty_core::connections::errors::ConnectionError;
+use putty_core::connections::telnet::TELNET_PORT;
+use putty_core::connections::Framing;
 use putty_storage::Profile;
 use tonic::Status;
 
-use crate::putty_interface::{profile_req, ProfileReq, Serial, Ssh};
+use crate::putty_interface::{profile_req, ProfileReq, Serial, SerialPortInfo, Ssh, Telnet};
 
 /// core ▸ protobuf
+///
+/// `ProfileReq.name` carries the qualified name (`group/name`), so grouped
+/// profiles need no field of their own. SSH secrets are left empty: they
+/// are only ever sent to the server, on save, never back out.
 impl From<Profile> for ProfileReq {
     fn from(p: Profile) -> Self {
+        let name = p.qualified_name();
         match p {
-            Profile::Serial { name, port, baud } => ProfileReq {
+            Profile::Serial {
+                port,
+                baud,
+                data_bits,
+                parity,
+                stop_bits,
+                flow_control,
+                ..
+            } => ProfileReq {
                 name,
-                kind: Some(profile_req::Kind::Serial(Serial { port, baud })),
+                kind: Some(profile_req::Kind::Serial(Serial {
+                    port,
+                    baud,
+                    data_bits: u8::from(data_bits).into(),
+                    parity: parity.to_string(),
+                    stop_bits: u8::from(stop_bits).into(),
+                    flow_control: flow_control.to_string(),
+                })),
             },
             Profile::Ssh {
-                name,
+                name: _,
+                group: _,
                 host,
                 port,
                 username,
-                password,
+                password: _,
                 keyring_id: _, // not needed here
+                key_path,
+                passphrase: _,
+                expected_host_key: _, // not exposed over gRPC yet
+                max_session_secs: _,  // not exposed over gRPC yet
+                banner: _,            // not exposed over gRPC yet
+                escape_char: _,       // only used by the CLI
+                escape_exit: _,       // only used by the CLI
             } => ProfileReq {
                 name,
                 kind: Some(profile_req::Kind::Ssh(Ssh {
                     host,
                     port: port as u32,
                     user: username,
-                    password,
+                    password: String::new(),
+                    key_path: key_path
+                        .map(|path| path.to_string_lossy().into_owned())
+                        .unwrap_or_default(),
+                    key_passphrase: String::new(),
+                })),
+            },
+            Profile::Telnet {
+                name: _,
+                group: _,
+                host,
+                port,
+                max_session_secs: _, // not exposed over gRPC yet
+                banner: _,           // not exposed over gRPC yet
+                escape_char: _,      // only used by the CLI
+                escape_exit: _,      // only used by the CLI
+            } => ProfileReq {
+                name,
+                kind: Some(profile_req::Kind::Telnet(Telnet {
+                    host,
+                    port: port.into(),
                 })),
             },
         }
@@ -42,20 +95,136 @@ impl TryFrom<ProfileReq> for Profile {
         let kind = m
             .kind
             .ok_or_else(|| Status::invalid_argument("Profile.kind missing"))?;
+        let (group, name) = Profile::split_qualified_name(&m.name);
+        let (group, name) = (group.map(str::to_owned), name.to_owned());
         match kind {
-            profile_req::Kind::Serial(s) => Ok(Profile::Serial {
-                name: m.name,
-                port: s.port,
-                baud: s.baud,
-            }),
+            profile_req::Kind::Serial(s) => {
+                let framing = Framing::try_from(&s)?;
+                Ok(Profile::Serial {
+                    name,
+                    group,
+                    port: s.port,
+                    baud: s.baud,
+                    data_bits: framing.data_bits,
+                    parity: framing.parity,
+                    stop_bits: framing.stop_bits,
+                    flow_control: framing.flow_control,
+                    init_string: None,      // kept by save_profile
+                    max_session_secs: None, // kept by save_profile
+                    banner: None,           // kept by save_profile
+                    escape_char: None,      // kept by save_profile
+                    escape_exit: None,      // kept by save_profile
+                })
+            }
             profile_req::Kind::Ssh(s) => Ok(Profile::Ssh {
-                name: m.name,
+                name,
+                group,
                 host: s.host,
                 port: s.port as u16,
                 username: s.user,
                 password: s.password,
                 keyring_id: None, // not needed in protobuf
+                key_path: non_empty(s.key_path).map(PathBuf::from),
+                passphrase: non_empty(s.key_passphrase),
+                expected_host_key: None, // kept by save_profile
+                max_session_secs: None,  // kept by save_profile
+                banner: None,            // kept by save_profile
+                escape_char: None,       // kept by save_profile
+                escape_exit: None,       // kept by save_profile
+            }),
+            profile_req::Kind::Telnet(t) => Ok(Profile::Telnet {
+                name,
+                group,
+                port: telnet_port(&t),
+                host: t.host,
+                max_session_secs: None, // kept by save_profile
+                banner: None,           // kept by save_profile
+                escape_char: None,      // kept by save_profile
+                escape_exit: None,      // kept by save_profile
             }),
         }
     }
 }
+
+/// protobuf ▸ core: the framing of a `Serial`; unset fields keep the 8N1
+/// default.
+impl TryFrom<&Serial> for Framing {
+    type Error = Status;
+
+    fn try_from(s: &Serial) -> Result<Self, Self::Error> {
+        let mut framing = Framing::default();
+        let invalid = |e: ConnectionError| Status::invalid_argument(e.to_string());
+        if s.data_bits != 0 {
+            let bits = u8::try_from(s.data_bits).unwrap_or(u8::MAX);
+            framing.data_bits = bits.try_into().map_err(invalid)?;
+        }
+        if !s.parity.is_empty() {
+            framing.parity = s.parity.parse().map_err(invalid)?;
+        }
+        if s.stop_bits != 0 {
+            let bits = u8::try_from(s.stop_bits).unwrap_or(u8::MAX);
+            framing.stop_bits = bits.try_into().map_err(invalid)?;
+        }
+        if !s.flow_control.is_empty() {
+            framing.flow_control = s.flow_control.parse().map_err(invalid)?;
+        }
+        Ok(framing)
+    }
+}
+
+/// core ▸ protobuf
+impl From<putty_core::connections::serial::SerialPortInfo> for SerialPortInfo {
+    fn from(port: putty_core::connections::serial::SerialPortInfo) -> Self {
+        let mut info = SerialPortInfo {
+            name: port.name,
+            ..Default::default()
+        };
+        if let Some(usb) = port.usb {
+            info.usb = true;
+            info.vid = usb.vid.into();
+            info.pid = usb.pid.into();
+            info.manufacturer = usb.manufacturer.unwrap_or_default();
+            info.product = usb.product.unwrap_or_default();
+            info.serial_number = usb.serial_number.unwrap_or_default();
+        }
+        info
+    }
+}
+
+/// Protobuf has no optional strings; empty means unset.
+pub fn non_empty(s: String) -> Option<String> {
+    (!s.is_empty()).then_some(s)
+}
+
+/// The port of a `Telnet`, where 0 stands for the well-known one.
+pub fn telnet_port(t: &Telnet) -> u16 {
+    match t.port {
+        0 => TELNET_PORT,
+        port => port as u16,
+    }
+}
+
+/// Map a failed connect to the closest gRPC status, so clients can tell
+/// "host not found" from "connection refused" or "wrong password". Also
+/// used for listing serial ports, where access can be denied.
+pub fn connect_status(err: ConnectionError) -> Status {
+    match err {
+        ConnectionError::DnsError(_) => Status::not_found(err.to_string()),
+        ConnectionError::TcpConnectError(_) | ConnectionError::HandshakeError(_) => {
+            Status::unavailable(err.to_string())
+        }
+        ConnectionError::AuthError(_) => Status::unauthenticated(err.to_string()),
+        ConnectionError::IoError(ref e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            Status::permission_denied(err.to_string())
+        }
+        _ => Status::internal(err.to_string()),
+    }
+}
+
+/// Map a failed write; a full connection asks the client to back off.
+pub fn write_status(err: ConnectionError) -> Status {
+    match err {
+        ConnectionError::Busy { .. } => Status::resource_exhausted(err.to_string()),
+        _ => Status::internal(err.to_string()),
+    }
+}
@@ -11,6 +11,9 @@ pub mod proto {
 pub use proto as putty_interface;
 
 mod convert;
+pub mod runtime;
 mod server;
 
-pub use server::run;
+pub use server::{
+    run, run_uds, run_uds_with_options, run_with_options, ConnectionService, ServerOptions,
+};
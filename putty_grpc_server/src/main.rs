@@ -1,5 +1,52 @@
+use clap::Parser;
+use putty_core::ConnectRetry;
+use putty_grpc_server::runtime::RuntimeArgs;
+use putty_grpc_server::ServerOptions;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Command-line arguments.
+#[derive(Parser, Debug)]
+#[command(name = "putty_grpc_server", about = "gRPC-Web server for putty-rs")]
+struct Args {
+    /// TCP address to listen on (gRPC + gRPC-Web)
+    #[arg(long, default_value = "0.0.0.0:50051", conflicts_with = "uds")]
+    addr: String,
+    /// Serve plain gRPC on this Unix domain socket instead of TCP
+    #[arg(long)]
+    uds: Option<PathBuf>,
+    /// Retry failing connects for up to this many seconds (capped by the
+    /// client's deadline) instead of failing on the first error
+    #[arg(long, value_name = "SECS")]
+    connect_retry_secs: Option<u64>,
+    /// Reject writes to a connection while this many are still outstanding,
+    /// instead of letting them queue up
+    #[arg(long, value_name = "N")]
+    max_in_flight_writes: Option<usize>,
+    /// Keep profiles in this directory instead of the user's config dir
+    #[arg(long, value_name = "DIR")]
+    profile_dir: Option<PathBuf>,
+    #[command(flatten)]
+    runtime: RuntimeArgs,
+}
+
 // ── main ──────────────────────────────────────────────────────────────────────
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    putty_grpc_server::run("0.0.0.0:50051").await
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+    let runtime = args.runtime.build()?;
+    runtime.block_on(serve(args))
+}
+
+async fn serve(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let options = ServerOptions {
+        connect_retry: args
+            .connect_retry_secs
+            .map(|secs| ConnectRetry::new(Duration::from_secs(secs))),
+        max_in_flight_writes: args.max_in_flight_writes,
+        profile_dir: args.profile_dir,
+    };
+    match args.uds {
+        Some(path) => putty_grpc_server::run_uds_with_options(path, options).await,
+        None => putty_grpc_server::run_with_options(&args.addr, options).await,
+    }
 }
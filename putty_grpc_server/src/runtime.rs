@@ -0,0 +1,74 @@
+//! Runtime flags of the `putty_grpc_server` binary, public so tests can
+//! start the server the way the binary does.
+
+use std::fmt;
+use std::io;
+use std::num::NonZeroUsize;
+use std::str::FromStr;
+use tokio::runtime::{Builder, Runtime};
+
+/// Which tokio scheduler the server runs on.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RuntimeFlavor {
+    /// Work-stealing pool of worker threads; tokio's default.
+    #[default]
+    MultiThread,
+    /// Everything on the thread that starts the runtime. Lowest overhead for
+    /// a handful of connections, but one busy task stalls the rest.
+    CurrentThread,
+}
+
+impl FromStr for RuntimeFlavor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "multi-thread" => Ok(Self::MultiThread),
+            "current-thread" => Ok(Self::CurrentThread),
+            other => Err(format!(
+                "unknown runtime flavor {other:?} (expected multi-thread or current-thread)"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for RuntimeFlavor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::MultiThread => "multi-thread",
+            Self::CurrentThread => "current-thread",
+        })
+    }
+}
+
+/// Tuning of the tokio runtime the server runs on.
+///
+/// The default matches `#[tokio::main]`: multi-threaded with one worker per
+/// CPU core (or `TOKIO_WORKER_THREADS`).
+#[derive(clap::Args, Debug, Clone)]
+pub struct RuntimeArgs {
+    /// Tokio scheduler: multi-thread, or current-thread to run everything on
+    /// one thread
+    #[arg(long, env = "PUTTY_RS_RUNTIME", default_value_t = RuntimeFlavor::MultiThread)]
+    pub runtime: RuntimeFlavor,
+    /// Worker threads of the multi-thread runtime [default: one per CPU core]
+    #[arg(long, env = "PUTTY_RS_WORKER_THREADS", value_name = "N")]
+    pub worker_threads: Option<NonZeroUsize>,
+}
+
+impl RuntimeArgs {
+    /// Build a runtime with all drivers (I/O, time) enabled.
+    pub fn build(&self) -> io::Result<Runtime> {
+        let mut builder = match self.runtime {
+            RuntimeFlavor::MultiThread => {
+                let mut builder = Builder::new_multi_thread();
+                if let Some(threads) = self.worker_threads {
+                    builder.worker_threads(threads.get());
+                }
+                builder
+            }
+            RuntimeFlavor::CurrentThread => Builder::new_current_thread(),
+        };
+        builder.enable_all().build()
+    }
+}
@@ -1,9 +1,16 @@
 use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
-use putty_core::{connections::connection::Connection, ConnectionManager};
+use putty_core::{
+    connections::connection::Connection, connections::telnet::TelnetConnection,
+    connections::websocket::WebSocketConnection, connections::Framing, utils::escape::unescape,
+    ConnectRetry, ConnectionManager, ConnectionOptions, SubscriptionItem,
+};
 use putty_storage::{Profile, ProfileStore};
 use tokio::sync::mpsc;
 use tonic::{
+    metadata::MetadataMap,
     transport::Server as TonicServer, // gRPC transport server
     Request,
     Response,
@@ -11,27 +18,104 @@ use tonic::{
 };
 use tonic_web::GrpcWebLayer;
 use tower_http::cors::{Any, CorsLayer};
-use tracing::info;
+use tracing::{info, warn};
 
+use crate::convert::{connect_status, non_empty, telnet_port, write_status};
 use crate::putty_interface::remote_connection_server::{RemoteConnection, RemoteConnectionServer};
 use crate::putty_interface::*;
 
+/// Server-wide settings shared by the TCP and UDS runners.
+#[derive(Debug, Clone, Default)]
+pub struct ServerOptions {
+    /// Retry failing connects in `create_remote_connection` with backoff and
+    /// jitter. `None` (the default) fails the RPC on the first error.
+    pub connect_retry: Option<ConnectRetry>,
+    /// Writes a connection may have outstanding before further `write` calls
+    /// fail with `RESOURCE_EXHAUSTED`, so a flooding client cannot pile up
+    /// waiting requests. `None` (the default) lets writes wait.
+    pub max_in_flight_writes: Option<usize>,
+    /// Directory of the profile store. `None` (the default) uses the user's
+    /// config directory, see `ProfileStore::from_env`.
+    pub profile_dir: Option<PathBuf>,
+}
+
 // ── gRPC service backed by putty_core ─────────────────────────────────────────
+/// The `RemoteConnection` service, backed by a [`ConnectionManager`]. Wrap it
+/// in a `RemoteConnectionServer` to serve it yourself, e.g. in tests.
 #[derive(Clone)]
-struct ConnectionService {
+pub struct ConnectionService {
     manager: ConnectionManager,
     profile_store: ProfileStore,
+    connect_retry: Option<ConnectRetry>,
+    max_in_flight_writes: Option<usize>,
 }
 
 impl ConnectionService {
-    fn new() -> Self {
+    /// A fresh manager and the profile store in `options.profile_dir`, or
+    /// the user's.
+    pub fn new(options: ServerOptions) -> Self {
+        let profile_store = match &options.profile_dir {
+            Some(dir) => ProfileStore::in_dir(dir.clone()),
+            None => ProfileStore::from_env(),
+        };
+        Self::with(ConnectionManager::new(), profile_store.expect("init store"))
+            .with_options(options)
+    }
+
+    /// Serve the connections of an existing `manager`, including ones added
+    /// to it directly rather than through `create_remote_connection`, and
+    /// the profiles of `profile_store`, e.g. one in a temporary directory.
+    pub fn with(manager: ConnectionManager, profile_store: ProfileStore) -> Self {
         Self {
-            manager: ConnectionManager::new(),
-            profile_store: ProfileStore::new().expect("init store"),
+            manager,
+            profile_store,
+            connect_retry: None,
+            max_in_flight_writes: None,
+        }
+    }
+
+    /// Apply server-wide `options` to connections created from now on.
+    pub fn with_options(mut self, options: ServerOptions) -> Self {
+        self.connect_retry = options.connect_retry;
+        self.max_in_flight_writes = options.max_in_flight_writes;
+        self
+    }
+
+    /// Connection options for one create request. The retry deadline is
+    /// shortened to the client's `grpc-timeout` so the server never keeps
+    /// retrying after the caller has given up.
+    fn connection_options(&self, metadata: &MetadataMap) -> ConnectionOptions {
+        let mut options = ConnectionOptions::new();
+        if let Some(retry) = &self.connect_retry {
+            let deadline = match grpc_timeout(metadata) {
+                Some(timeout) => retry.deadline.min(timeout),
+                None => retry.deadline,
+            };
+            options = options.with_connect_retry(retry.clone().with_deadline(deadline));
         }
+        if let Some(limit) = self.max_in_flight_writes {
+            options = options.with_max_in_flight_writes(limit);
+        }
+        options
     }
 }
 
+/// Parse the `grpc-timeout` header (e.g. `"1500m"`) into a duration.
+fn grpc_timeout(metadata: &MetadataMap) -> Option<Duration> {
+    let value = metadata.get("grpc-timeout")?.to_str().ok()?;
+    let (amount, unit) = value.split_at(value.len().checked_sub(1)?);
+    let amount: u64 = amount.parse().ok()?;
+    Some(match unit {
+        "H" => Duration::from_secs(amount.checked_mul(3600)?),
+        "M" => Duration::from_secs(amount.checked_mul(60)?),
+        "S" => Duration::from_secs(amount),
+        "m" => Duration::from_millis(amount),
+        "u" => Duration::from_micros(amount),
+        "n" => Duration::from_nanos(amount),
+        _ => return None,
+    })
+}
+
 #[tonic::async_trait]
 impl RemoteConnection for ConnectionService {
     type ReadStream = tokio_stream::wrappers::ReceiverStream<Result<ByteChunk, Status>>;
@@ -41,22 +125,36 @@ impl RemoteConnection for ConnectionService {
         req: Request<CreateRequest>,
     ) -> Result<Response<ConnectionId>, Status> {
         let id = uuid::Uuid::new_v4().to_string();
+        let mut options = self.connection_options(req.metadata());
         let conn: Box<dyn Connection + Send + Unpin + 'static> = match req
             .into_inner()
             .kind
             .ok_or(Status::invalid_argument("kind"))?
         {
-            create_request::Kind::Serial(s) => Box::new(
-                putty_core::connections::serial::SerialConnection::new(s.port, s.baud),
-            ),
+            create_request::Kind::Serial(s) => {
+                let framing = Framing::try_from(&s)?;
+                Box::new(
+                    putty_core::connections::serial::SerialConnection::new(s.port, s.baud)
+                        .with_framing(framing),
+                )
+            }
             create_request::Kind::Ssh(s) => {
-                Box::new(putty_core::connections::ssh::SshConnection::new(
-                    s.host,
-                    s.port as u16,
-                    s.user,
-                    s.password,
-                ))
+                use putty_core::connections::ssh::SshConnection;
+                Box::new(match non_empty(s.key_path) {
+                    Some(key_path) => SshConnection::with_key(
+                        s.host,
+                        s.port as u16,
+                        s.user,
+                        key_path.into(),
+                        non_empty(s.key_passphrase),
+                    ),
+                    None => SshConnection::new(s.host, s.port as u16, s.user, s.password),
+                })
             }
+            create_request::Kind::Telnet(t) => {
+                Box::new(TelnetConnection::new(t.host.clone(), telnet_port(&t)))
+            }
+            create_request::Kind::Websocket(w) => Box::new(WebSocketConnection::new(w.url)),
             create_request::Kind::Profile(profile_ref) => {
                 // 1. Look up the preset by name
                 let preset = self
@@ -64,31 +162,76 @@ impl RemoteConnection for ConnectionService {
                     .list()
                     .map_err(|e| Status::internal(e.to_string()))?
                     .into_iter()
-                    .find(|p| p.name() == profile_ref.name)
+                    .find(|p| p.qualified_name() == profile_ref.name)
                     .ok_or_else(|| Status::not_found("profile not found"))?;
+                if let Some(limit) = preset.max_session() {
+                    options = options.with_max_session(limit);
+                }
+                if let Some(banner) = preset.banner() {
+                    let banner =
+                        unescape(banner).map_err(|e| Status::invalid_argument(e.to_string()))?;
+                    options = options.with_banner(banner);
+                }
 
                 // 2. Turn that preset into the concrete connection
                 match preset {
-                    Profile::Serial { port, baud, .. } => Box::new(
-                        putty_core::connections::serial::SerialConnection::new(port, baud),
-                    ),
+                    Profile::Serial {
+                        port,
+                        baud,
+                        data_bits,
+                        parity,
+                        stop_bits,
+                        flow_control,
+                        init_string,
+                        ..
+                    } => {
+                        use putty_core::connections::serial::{InitString, SerialConnection};
+                        let mut conn = SerialConnection::new(port, baud).with_framing(Framing {
+                            data_bits,
+                            parity,
+                            stop_bits,
+                            flow_control,
+                        });
+                        if let Some(text) = init_string {
+                            let init = InitString::from_escaped(&text)
+                                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+                            conn = conn.with_init(init);
+                        }
+                        Box::new(conn)
+                    }
                     Profile::Ssh {
                         host,
                         port,
                         username,
                         password,
+                        key_path,
+                        passphrase,
+                        expected_host_key,
                         ..
-                    } => Box::new(putty_core::connections::ssh::SshConnection::new(
-                        host, port, username, password,
-                    )),
+                    } => {
+                        use putty_core::connections::ssh::SshConnection;
+                        let mut conn = match key_path {
+                            Some(key_path) => {
+                                SshConnection::with_key(host, port, username, key_path, passphrase)
+                            }
+                            None => SshConnection::new(host, port, username, password),
+                        };
+                        if let Some(fingerprint) = expected_host_key {
+                            conn = conn.with_expected_host_key(fingerprint);
+                        }
+                        Box::new(conn)
+                    }
+                    Profile::Telnet { host, port, .. } => {
+                        Box::new(TelnetConnection::new(host, port))
+                    }
                 }
             }
         };
 
         self.manager
-            .add_connection(id.clone(), conn)
+            .add_connection_with_options(id.clone(), conn, options)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(connect_status)?;
 
         Ok(Response::new(ConnectionId { id }))
     }
@@ -98,7 +241,7 @@ impl RemoteConnection for ConnectionService {
         self.manager
             .write_bytes(&m.id, &m.data)
             .await
-            .map_err(|e| Status::internal(e.to_string()))?;
+            .map_err(write_status)?;
         Ok(Response::new(Empty {}))
     }
 
@@ -114,14 +257,23 @@ impl RemoteConnection for ConnectionService {
         let id = req.into_inner().id;
         let mut rx = self
             .manager
-            .subscribe(&id)
+            .subscribe_lossy(&id)
             .await
             .ok_or(Status::not_found("no such connection"))?;
 
         let (tx, rx_stream) = mpsc::channel::<Result<ByteChunk, Status>>(64);
-        // forward every chunk from ConnectionManager → gRPC stream
+        // forward every chunk from ConnectionManager → gRPC stream; a client
+        // too slow to keep up loses the oldest chunks, not the stream
         tokio::spawn(async move {
-            while let Ok(chunk) = rx.recv().await {
+            while let Some(item) = rx.recv().await {
+                let chunk = match item {
+                    SubscriptionItem::Data(chunk) => chunk,
+                    SubscriptionItem::Lagged { missed } => {
+                        warn!("read stream of '{id}' fell behind; dropped {missed} chunks");
+                        continue;
+                    }
+                    SubscriptionItem::Reconnected => continue,
+                };
                 if tx.send(Ok(ByteChunk { data: chunk })).await.is_err() {
                     break; // client hung up
                 }
@@ -145,7 +297,8 @@ impl RemoteConnection for ConnectionService {
     }
 
     async fn save_profile(&self, req: Request<ProfileReq>) -> Result<Response<Empty>, Status> {
-        let profile: Profile = req.into_inner().try_into()?;
+        let mut profile: Profile = req.into_inner().try_into()?;
+        keep_cli_only_fields(&self.profile_store, &mut profile);
         self.profile_store
             .save(&profile)
             .map_err(|e| Status::internal(e.to_string()))?;
@@ -158,12 +311,145 @@ impl RemoteConnection for ConnectionService {
             .map_err(|e| Status::internal(e.to_string()))?;
         Ok(Response::new(Empty {}))
     }
+
+    /// An empty list if there are no ports; `PERMISSION_DENIED` if the
+    /// server may not look.
+    async fn list_serial_ports(
+        &self,
+        _: Request<Empty>,
+    ) -> Result<Response<SerialPortList>, Status> {
+        let ports = putty_core::connections::serial::list_ports()
+            .map_err(connect_status)?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+        Ok(Response::new(SerialPortList { ports }))
+    }
+}
+
+/// Fields only set from the CLI are not part of the proto, so saving a
+/// profile over gRPC keeps the stored ones instead of wiping them: the
+/// escape keys, and wherever the request leaves it unset, an SSH profile's
+/// pinned host key, a serial profile's init string, the session limit and
+/// the banner.
+fn keep_cli_only_fields(store: &ProfileStore, profile: &mut Profile) {
+    let Ok(stored) = store.resolve(&profile.qualified_name()) else {
+        return;
+    };
+    let (escape, exit) = stored.escape_keys();
+    profile.set_escape_keys(escape, exit);
+    match (profile, stored) {
+        (
+            Profile::Serial {
+                init_string,
+                max_session_secs,
+                banner,
+                ..
+            },
+            Profile::Serial {
+                init_string: stored_init_string,
+                max_session_secs: stored_max_session_secs,
+                banner: stored_banner,
+                ..
+            },
+        ) => {
+            *init_string = init_string.take().or(stored_init_string);
+            *max_session_secs = max_session_secs.or(stored_max_session_secs);
+            *banner = banner.take().or(stored_banner);
+        }
+        (
+            Profile::Ssh {
+                expected_host_key,
+                max_session_secs,
+                banner,
+                ..
+            },
+            Profile::Ssh {
+                expected_host_key: stored_host_key,
+                max_session_secs: stored_max_session_secs,
+                banner: stored_banner,
+                ..
+            },
+        ) => {
+            *expected_host_key = expected_host_key.take().or(stored_host_key);
+            *max_session_secs = max_session_secs.or(stored_max_session_secs);
+            *banner = banner.take().or(stored_banner);
+        }
+        (
+            Profile::Telnet {
+                max_session_secs,
+                banner,
+                ..
+            },
+            Profile::Telnet {
+                max_session_secs: stored_max_session_secs,
+                banner: stored_banner,
+                ..
+            },
+        ) => {
+            *max_session_secs = max_session_secs.or(stored_max_session_secs);
+            *banner = banner.take().or(stored_banner);
+        }
+        // Saved over a profile of another kind: nothing to keep.
+        _ => {}
+    }
 }
 
+/// Set up tracing and build the service shared by the TCP and UDS runners.
+fn init_service(options: ServerOptions) -> ConnectionService {
+    let _ = tracing_subscriber::fmt().try_init();
+
+    let service = ConnectionService::new(options);
+    #[cfg(unix)]
+    service.manager.dump_on_sigusr1();
+    service
+}
+
+/// Resolves once the process is asked to exit (Ctrl+C, or SIGTERM on Unix),
+/// after every connection has been stopped. Stopping them first also ends
+/// the open `read` streams, which the server waits for before it returns.
+async fn shutdown(manager: ConnectionManager) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {},
+                    _ = sigterm.recv() => {},
+                }
+            }
+            Err(_) => {
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    let _ = tokio::signal::ctrl_c().await;
+
+    info!("Shutting down");
+    for (id, result) in manager.shutdown_all().await {
+        match result {
+            Ok(()) => info!("Stopped connection '{id}'"),
+            Err(e) => warn!("Stopping connection '{id}' failed: {e}"),
+        }
+    }
+}
+
+/// Serve gRPC and gRPC-Web on `addr` until Ctrl+C or SIGTERM, then stop
+/// every connection and return.
 pub async fn run(addr: &str) -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt::init();
+    run_with_options(addr, ServerOptions::default()).await
+}
 
-    let server = RemoteConnectionServer::new(ConnectionService::new());
+/// Like [`run`], with non-default [`ServerOptions`].
+pub async fn run_with_options(
+    addr: &str,
+    options: ServerOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let service = init_service(options);
+    let manager = service.manager.clone();
 
     let addr: SocketAddr = addr.parse()?;
     info!("gRPC-Web listening on http://{addr}");
@@ -178,9 +464,283 @@ pub async fn run(addr: &str) -> Result<(), Box<dyn std::error::Error>> {
         .accept_http1(true) // gRPC-Web needs h1
         .layer(cors) // allow browser calls
         .layer(GrpcWebLayer::new()) // translate to gRPC-Web
-        .add_service(server)
-        .serve(addr)
+        .add_service(RemoteConnectionServer::new(service))
+        .serve_with_shutdown(addr, shutdown(manager))
+        .await?;
+
+    Ok(())
+}
+
+/// Serve plain gRPC on a Unix domain socket at `path`.
+///
+/// Filesystem permissions on the socket act as access control, which suits
+/// local-only deployments. gRPC-Web and CORS are not applied here: browsers
+/// cannot reach a Unix socket, so the web layer stays TCP-only (see [`run`]).
+/// A stale socket file left over from a previous run is removed first; any
+/// other file at `path` is left alone and fails the call.
+pub async fn run_uds(path: impl AsRef<Path>) -> Result<(), Box<dyn std::error::Error>> {
+    run_uds_with_options(path, ServerOptions::default()).await
+}
+
+/// Like [`run_uds`], with non-default [`ServerOptions`].
+#[cfg(unix)]
+pub async fn run_uds_with_options(
+    path: impl AsRef<Path>,
+    options: ServerOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use tokio::net::UnixListener;
+    use tokio_stream::wrappers::UnixListenerStream;
+
+    let service = init_service(options);
+    let manager = service.manager.clone();
+
+    let path = path.as_ref();
+    remove_stale_socket(path)?;
+    let listener = UnixListener::bind(path)?;
+    info!("gRPC listening on unix://{}", path.display());
+
+    TonicServer::builder()
+        .add_service(RemoteConnectionServer::new(service))
+        .serve_with_incoming_shutdown(UnixListenerStream::new(listener), shutdown(manager))
         .await?;
 
     Ok(())
 }
+
+/// Remove the socket a previous run left at `path`. Refuses to touch
+/// anything that is not a socket, so a mistyped `--uds` cannot delete a
+/// regular file.
+#[cfg(unix)]
+fn remove_stale_socket(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    match std::fs::symlink_metadata(path) {
+        Ok(metadata) if metadata.file_type().is_socket() => std::fs::remove_file(path),
+        Ok(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} exists and is not a socket", path.display()),
+        )),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn run_uds_with_options(
+    _path: impl AsRef<Path>,
+    _options: ServerOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    Err("Unix domain sockets are not supported on this platform".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn metadata_with_timeout(value: &str) -> MetadataMap {
+        let mut metadata = MetadataMap::new();
+        metadata.insert("grpc-timeout", value.parse().unwrap());
+        metadata
+    }
+
+    #[test]
+    fn grpc_timeout_parses_all_units() {
+        assert_eq!(
+            grpc_timeout(&metadata_with_timeout("2S")),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            grpc_timeout(&metadata_with_timeout("1500m")),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            grpc_timeout(&metadata_with_timeout("1H")),
+            Some(Duration::from_secs(3600))
+        );
+        assert_eq!(grpc_timeout(&metadata_with_timeout("10x")), None);
+        assert_eq!(grpc_timeout(&MetadataMap::new()), None);
+    }
+
+    #[test]
+    fn connect_errors_map_to_distinct_status_codes() {
+        use putty_core::connections::errors::ConnectionError;
+        use tonic::Code;
+
+        let code = |err| connect_status(err).code();
+        assert_eq!(code(ConnectionError::DnsError("x".into())), Code::NotFound);
+        assert_eq!(
+            code(ConnectionError::TcpConnectError("x".into())),
+            Code::Unavailable
+        );
+        assert_eq!(
+            code(ConnectionError::HandshakeError("x".into())),
+            Code::Unavailable
+        );
+        assert_eq!(
+            code(ConnectionError::AuthError("x".into())),
+            Code::Unauthenticated
+        );
+        assert_eq!(code(ConnectionError::Other("x".into())), Code::Internal);
+    }
+
+    #[test]
+    fn busy_writes_are_resource_exhausted() {
+        use putty_core::connections::errors::ConnectionError;
+        use tonic::Code;
+
+        assert_eq!(
+            write_status(ConnectionError::Busy { limit: 8 }).code(),
+            Code::ResourceExhausted
+        );
+        assert_eq!(
+            write_status(ConnectionError::Other("x".into())).code(),
+            Code::Internal
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn only_stale_sockets_are_removed() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let socket = dir.path().join("putty.sock");
+        drop(std::os::unix::net::UnixListener::bind(&socket).unwrap());
+        remove_stale_socket(&socket).unwrap();
+        assert!(!socket.exists());
+
+        remove_stale_socket(&socket).expect("a missing path is fine");
+
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "keep me").unwrap();
+        let err = remove_stale_socket(&file).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "keep me");
+    }
+
+    #[test]
+    fn saving_over_grpc_keeps_the_escape_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::in_dir(dir.path().join("profiles")).unwrap();
+        let telnet = |name: &str, escape_char, escape_exit| Profile::Telnet {
+            name: name.into(),
+            group: None,
+            host: "10.0.0.1".into(),
+            port: 23,
+            max_session_secs: None,
+            banner: None,
+            escape_char,
+            escape_exit,
+        };
+        store
+            .save(&telnet("router", Some('\x02'), Some('q')))
+            .unwrap();
+
+        let mut profile = telnet("router", None, None);
+        keep_cli_only_fields(&store, &mut profile);
+        assert_eq!(profile.escape_keys(), (Some('\x02'), Some('q')));
+
+        let mut new = telnet("switch", None, None);
+        keep_cli_only_fields(&store, &mut new);
+        assert_eq!(new.escape_keys(), (None, None));
+    }
+
+    #[test]
+    fn saving_over_grpc_keeps_the_pinned_host_key() {
+        const FINGERPRINT: &str = "SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8";
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::in_dir(dir.path().join("profiles")).unwrap();
+        let pinned = Profile::Ssh {
+            name: "prodbox".into(),
+            group: None,
+            host: "10.0.0.5".into(),
+            port: 22,
+            username: "ops".into(),
+            password: String::new(),
+            keyring_id: None,
+            key_path: None,
+            passphrase: None,
+            expected_host_key: Some(FINGERPRINT.into()),
+            max_session_secs: None,
+            banner: None,
+            escape_char: None,
+            escape_exit: None,
+        };
+        store.save(&pinned).unwrap();
+
+        // What the web UI sends back after listing the profile.
+        let mut resaved: Profile = ProfileReq::from(pinned).try_into().unwrap();
+        keep_cli_only_fields(&store, &mut resaved);
+        store.save(&resaved).unwrap();
+
+        let Profile::Ssh {
+            expected_host_key, ..
+        } = store.resolve("prodbox").unwrap()
+        else {
+            panic!("prodbox is an SSH profile");
+        };
+        assert_eq!(expected_host_key.as_deref(), Some(FINGERPRINT));
+    }
+
+    #[test]
+    fn saving_over_grpc_keeps_the_init_string_and_session_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::in_dir(dir.path().join("profiles")).unwrap();
+        let board = Profile::Serial {
+            name: "board".into(),
+            group: None,
+            port: "/dev/ttyUSB0".into(),
+            baud: 115200,
+            data_bits: Default::default(),
+            parity: Default::default(),
+            stop_bits: Default::default(),
+            flow_control: Default::default(),
+            init_string: Some("AT\\r".into()),
+            max_session_secs: Some(600),
+            banner: None,
+            escape_char: None,
+            escape_exit: None,
+        };
+        let router = Profile::Telnet {
+            name: "router".into(),
+            group: None,
+            host: "10.0.0.1".into(),
+            port: 23,
+            max_session_secs: Some(60),
+            banner: None,
+            escape_char: None,
+            escape_exit: None,
+        };
+        for profile in [board, router] {
+            store.save(&profile).unwrap();
+
+            let mut resaved: Profile = ProfileReq::from(profile.clone()).try_into().unwrap();
+            keep_cli_only_fields(&store, &mut resaved);
+            store.save(&resaved).unwrap();
+
+            assert_eq!(store.resolve(&profile.qualified_name()).unwrap(), profile);
+        }
+    }
+
+    #[test]
+    fn saving_over_grpc_keeps_the_banner() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = ProfileStore::in_dir(dir.path().join("profiles")).unwrap();
+        let router = Profile::Telnet {
+            name: "router".into(),
+            group: Some("lab".into()),
+            host: "10.0.0.1".into(),
+            port: 23,
+            max_session_secs: None,
+            banner: Some("=== rack1 ===\\r\\n".into()),
+            escape_char: None,
+            escape_exit: None,
+        };
+        store.save(&router).unwrap();
+
+        let mut resaved: Profile = ProfileReq::from(router.clone()).try_into().unwrap();
+        keep_cli_only_fields(&store, &mut resaved);
+        store.save(&resaved).unwrap();
+
+        assert_eq!(store.resolve("lab/router").unwrap(), router);
+    }
+}
@@ -0,0 +1,408 @@
+//! Run `ConnectionService` in-process on an ephemeral TCP port and drive a
+//! connection through the gRPC API.
+
+use std::time::Duration;
+
+use putty_core::connections::{connection::Connection, errors::ConnectionError};
+use putty_core::ConnectionManager;
+use putty_grpc_server::putty_interface::{
+    remote_connection_client::RemoteConnectionClient,
+    remote_connection_server::RemoteConnectionServer, ConnectionId, Empty, WriteRequest,
+};
+use putty_grpc_server::ConnectionService;
+use putty_storage::{Profile, ProfileStore};
+use tempfile::TempDir;
+use tokio::net::TcpListener;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::async_trait;
+use tonic::transport::Server;
+
+/// A device that echoes every write back as incoming data.
+struct EchoConnection {
+    echo_tx: mpsc::Sender<Vec<u8>>,
+    echo_rx: mpsc::Receiver<Vec<u8>>,
+}
+
+impl EchoConnection {
+    fn new() -> Self {
+        let (echo_tx, echo_rx) = mpsc::channel(32);
+        Self { echo_tx, echo_rx }
+    }
+}
+
+#[async_trait]
+impl Connection for EchoConnection {
+    async fn connect(&mut self) -> Result<(), ConnectionError> {
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<(), ConnectionError> {
+        Ok(())
+    }
+
+    async fn write(&mut self, data: &[u8]) -> Result<usize, ConnectionError> {
+        let _ = self.echo_tx.send(data.to_vec()).await;
+        Ok(data.len())
+    }
+
+    async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, ConnectionError> {
+        match self.echo_rx.recv().await {
+            Some(chunk) => {
+                buffer[..chunk.len()].copy_from_slice(&chunk);
+                Ok(chunk.len())
+            }
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Serve `service` on an ephemeral port and connect a client to it.
+async fn serve(
+    service: ConnectionService,
+) -> anyhow::Result<RemoteConnectionClient<tonic::transport::Channel>> {
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let addr = listener.local_addr()?;
+    tokio::spawn(async move {
+        Server::builder()
+            .add_service(RemoteConnectionServer::new(service))
+            .serve_with_incoming(TcpListenerStream::new(listener))
+            .await
+            .expect("grpc server failed");
+    });
+    Ok(RemoteConnectionClient::connect(format!("http://{addr}")).await?)
+}
+
+#[tokio::test]
+async fn write_and_read_round_trip_over_grpc() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+
+    // ── A manager seeded with a connection the RPCs can address ──────────
+    let manager = ConnectionManager::new();
+    manager
+        .add_connection("echo".into(), Box::new(EchoConnection::new()))
+        .await?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+    let mut client = serve(ConnectionService::with(manager, store)).await?;
+
+    let id = ConnectionId { id: "echo".into() };
+
+    // ── Subscribe first, then write ──────────────────────────────────────
+    let mut stream = client.read(id.clone()).await?.into_inner();
+    client
+        .write(WriteRequest {
+            id: "echo".into(),
+            data: b"ping".to_vec(),
+        })
+        .await?;
+
+    let chunk = tokio::time::timeout(Duration::from_secs(2), stream.message())
+        .await?
+        .expect("read stream failed")
+        .expect("read stream ended");
+    assert_eq!(chunk.data, b"ping");
+
+    // ── Unknown ids are reported, stop ends the stream ───────────────────
+    let err = client
+        .read(ConnectionId {
+            id: "missing".into(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), tonic::Code::NotFound);
+
+    client.stop(id).await?;
+    let end = tokio::time::timeout(Duration::from_secs(2), stream.message()).await?;
+    assert!(matches!(end, Ok(None)), "{end:?}");
+    Ok(())
+}
+
+#[tokio::test]
+async fn rpcs_use_the_injected_manager_and_store() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+    store.save(&Profile::Serial {
+        name: "bench".into(),
+        group: None,
+        port: "/dev/ttyUSB0".into(),
+        baud: 9600,
+        data_bits: Default::default(),
+        parity: Default::default(),
+        stop_bits: Default::default(),
+        flow_control: Default::default(),
+        init_string: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    })?;
+
+    let manager = ConnectionManager::new();
+    manager
+        .add_connection("echo".into(), Box::new(EchoConnection::new()))
+        .await?;
+    let mut client = serve(ConnectionService::with(manager.clone(), store)).await?;
+
+    // ── Profiles come from the injected store ────────────────────────────
+    let profiles = client.list_profiles(Empty {}).await?.into_inner().profiles;
+    let names: Vec<_> = profiles.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["bench"]);
+
+    // ── Stopping over gRPC removes the connection from our manager ───────
+    client.stop(ConnectionId { id: "echo".into() }).await?;
+    assert!(manager.subscribe("echo").await.is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn serial_ports_are_listed_like_the_core_sees_them() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+    let mut client = serve(ConnectionService::with(ConnectionManager::new(), store)).await?;
+
+    let ports = client.list_serial_ports(Empty {}).await?.into_inner().ports;
+    let names: Vec<_> = ports.into_iter().map(|p| p.name).collect();
+    let expected: Vec<_> = putty_core::connections::serial::list_ports()?
+        .into_iter()
+        .map(|p| p.name)
+        .collect();
+    assert_eq!(names, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn listed_profiles_carry_no_secrets() -> anyhow::Result<()> {
+    use putty_grpc_server::putty_interface::{profile_req, ProfileReq, Ssh};
+
+    let sandbox = TempDir::new()?;
+    let store =
+        ProfileStore::in_dir(sandbox.path().join("profiles"))?.with_passphrase("correct horse")?;
+    let mut client = serve(ConnectionService::with(
+        ConnectionManager::new(),
+        store.clone(),
+    ))
+    .await?;
+    let ssh = Ssh {
+        host: "10.0.0.5".into(),
+        port: 22,
+        user: "ops".into(),
+        password: "s3cr3t!".into(),
+        key_path: "/home/ops/.ssh/id_ed25519".into(),
+        key_passphrase: "open sesame".into(),
+    };
+    client
+        .save_profile(ProfileReq {
+            name: "prodbox".into(),
+            kind: Some(profile_req::Kind::Ssh(ssh.clone())),
+        })
+        .await?;
+
+    // ── Secrets go in on save but never come back out ────────────────────
+    let profiles = client.list_profiles(Empty {}).await?.into_inner().profiles;
+    assert_eq!(
+        profiles,
+        [ProfileReq {
+            name: "prodbox".into(),
+            kind: Some(profile_req::Kind::Ssh(Ssh {
+                password: String::new(),
+                key_passphrase: String::new(),
+                ..ssh
+            })),
+        }]
+    );
+
+    // ── The server still has them to connect with ────────────────────────
+    let Profile::Ssh {
+        password,
+        passphrase,
+        ..
+    } = store.resolve("prodbox")?
+    else {
+        panic!("prodbox is not an SSH profile");
+    };
+    assert_eq!(password, "s3cr3t!");
+    assert_eq!(passphrase.as_deref(), Some("open sesame"));
+    Ok(())
+}
+
+#[tokio::test]
+async fn telnet_sessions_open_over_grpc() -> anyhow::Result<()> {
+    use putty_grpc_server::putty_interface::{
+        create_request, profile_req, CreateRequest, ProfileReq, Telnet,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    const IAC: u8 = 255;
+    const WILL: u8 = 251;
+    const DO: u8 = 253;
+    const ECHO: u8 = 1;
+
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+    let mut client = serve(ConnectionService::with(ConnectionManager::new(), store)).await?;
+    let device = TcpListener::bind("127.0.0.1:0").await?;
+    let telnet = Telnet {
+        host: "127.0.0.1".into(),
+        port: device.local_addr()?.port().into(),
+    };
+
+    // ── Negotiation is answered, only the data reaches the stream ────────
+    let id = client
+        .create_remote_connection(CreateRequest {
+            kind: Some(create_request::Kind::Telnet(telnet.clone())),
+        })
+        .await?
+        .into_inner();
+    let (mut socket, _) = device.accept().await?;
+    socket.write_all(&[IAC, WILL, ECHO, b'o', b'k']).await?;
+    let mut answer = [0u8; 3];
+    tokio::time::timeout(Duration::from_secs(2), socket.read_exact(&mut answer)).await??;
+    assert_eq!(answer, [IAC, DO, ECHO]);
+
+    let mut stream = client.read(id.clone()).await?.into_inner();
+    let chunk = tokio::time::timeout(Duration::from_secs(2), stream.message())
+        .await?
+        .expect("read stream failed")
+        .expect("read stream ended");
+    assert_eq!(chunk.data, b"ok");
+    client.stop(id).await?;
+
+    // ── Telnet profiles round-trip through the profile RPCs ──────────────
+    let profile = ProfileReq {
+        name: "lab/switch".into(),
+        kind: Some(profile_req::Kind::Telnet(telnet)),
+    };
+    client.save_profile(profile.clone()).await?;
+    let profiles = client.list_profiles(Empty {}).await?.into_inner().profiles;
+    assert_eq!(profiles, [profile]);
+    Ok(())
+}
+
+#[tokio::test]
+async fn websocket_sessions_open_over_grpc() -> anyhow::Result<()> {
+    use futures_util::{SinkExt, StreamExt};
+    use putty_grpc_server::putty_interface::{create_request, CreateRequest, WebSocket};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+    let mut client = serve(ConnectionService::with(ConnectionManager::new(), store)).await?;
+    let device = TcpListener::bind("127.0.0.1:0").await?;
+    let url = format!("ws://{}/", device.local_addr()?);
+    // The handshake has to be answered while the create call is running.
+    let board = tokio::spawn(async move {
+        let (socket, _) = device.accept().await?;
+        tokio_tungstenite::accept_async(socket).await
+    });
+
+    let id = client
+        .create_remote_connection(CreateRequest {
+            kind: Some(create_request::Kind::Websocket(WebSocket { url })),
+        })
+        .await?
+        .into_inner();
+    let mut board = board.await??;
+
+    let mut stream = client.read(id.clone()).await?.into_inner();
+    board.send(Message::text("login: ")).await?;
+    let chunk = tokio::time::timeout(Duration::from_secs(2), stream.message())
+        .await?
+        .expect("read stream failed")
+        .expect("read stream ended");
+    assert_eq!(chunk.data, b"login: ");
+
+    client
+        .write(WriteRequest {
+            id: id.id.clone(),
+            data: b"root\n".to_vec(),
+        })
+        .await?;
+    let message = tokio::time::timeout(Duration::from_secs(2), board.next())
+        .await?
+        .expect("the board saw no write")?;
+    assert_eq!(message, Message::binary(b"root\n".to_vec()));
+    client.stop(id).await?;
+    Ok(())
+}
+
+/// A free local port with nothing listening on it yet.
+async fn unused_port() -> anyhow::Result<u16> {
+    let probe = TcpListener::bind("127.0.0.1:0").await?;
+    Ok(probe.local_addr()?.port())
+}
+
+fn retrying_service() -> anyhow::Result<(TempDir, ConnectionService)> {
+    use putty_core::ConnectRetry;
+    use putty_grpc_server::ServerOptions;
+
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+    let service =
+        ConnectionService::with(ConnectionManager::new(), store).with_options(ServerOptions {
+            connect_retry: Some(
+                ConnectRetry::new(Duration::from_secs(10))
+                    .with_backoff(Duration::from_millis(50), Duration::from_millis(100)),
+            ),
+            ..ServerOptions::default()
+        });
+    Ok((sandbox, service))
+}
+
+#[tokio::test]
+async fn create_retries_until_the_device_listens() -> anyhow::Result<()> {
+    use putty_grpc_server::putty_interface::{create_request, CreateRequest, Telnet};
+
+    let (_sandbox, service) = retrying_service()?;
+    let mut client = serve(service).await?;
+    let port = unused_port().await?;
+    // The first attempts are refused; the device comes up a moment later.
+    let device = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        let listener = TcpListener::bind(("127.0.0.1", port)).await?;
+        let (socket, _) = listener.accept().await?;
+        anyhow::Ok(socket)
+    });
+
+    let id = client
+        .create_remote_connection(CreateRequest {
+            kind: Some(create_request::Kind::Telnet(Telnet {
+                host: "127.0.0.1".into(),
+                port: port.into(),
+            })),
+        })
+        .await?
+        .into_inner();
+    assert!(!id.id.is_empty());
+    let _socket = device.await??;
+    client.stop(id).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn create_retries_are_capped_by_grpc_timeout() -> anyhow::Result<()> {
+    use putty_grpc_server::putty_interface::remote_connection_server::RemoteConnection;
+    use putty_grpc_server::putty_interface::{create_request, CreateRequest, Telnet};
+
+    // Called directly, so no client deadline cancels the handler: only the
+    // capped retry deadline can end it before the server's own 10 s.
+    let (_sandbox, service) = retrying_service()?;
+    let port = unused_port().await?;
+    let mut request = tonic::Request::new(CreateRequest {
+        kind: Some(create_request::Kind::Telnet(Telnet {
+            host: "127.0.0.1".into(),
+            port: port.into(),
+        })),
+    });
+    request
+        .metadata_mut()
+        .insert("grpc-timeout", "300m".parse()?);
+
+    let result = tokio::time::timeout(
+        Duration::from_secs(3),
+        service.create_remote_connection(request),
+    )
+    .await
+    .expect("the retries ran past the client's grpc-timeout");
+    assert!(result.is_err());
+    Ok(())
+}
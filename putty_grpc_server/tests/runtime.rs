@@ -0,0 +1,67 @@
+//! Serve the gRPC API on a runtime built from `RuntimeArgs`, the way the
+//! binary does, instead of `#[tokio::test]`.
+#![cfg(unix)]
+
+use std::num::NonZeroUsize;
+use std::time::Duration;
+
+use hyper_util::rt::TokioIo;
+use putty_grpc_server::putty_interface::{remote_connection_client::RemoteConnectionClient, Empty};
+use putty_grpc_server::runtime::{RuntimeArgs, RuntimeFlavor};
+use putty_grpc_server::ServerOptions;
+use tempfile::TempDir;
+use tokio::net::UnixStream;
+use tonic::transport::{Endpoint, Uri};
+use tower::service_fn;
+
+#[test]
+fn server_starts_with_a_configured_worker_count() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    // Keep the profile store away from the real user config.
+    let options = ServerOptions {
+        profile_dir: Some(sandbox.path().join("profiles")),
+        ..ServerOptions::default()
+    };
+
+    let runtime = RuntimeArgs {
+        runtime: RuntimeFlavor::MultiThread,
+        worker_threads: NonZeroUsize::new(2),
+    }
+    .build()?;
+    assert_eq!(runtime.metrics().num_workers(), 2);
+
+    runtime.block_on(async {
+        let socket_path = sandbox.path().join("putty_rs.sock");
+        tokio::spawn({
+            let socket_path = socket_path.clone();
+            async move {
+                putty_grpc_server::run_uds_with_options(socket_path, options)
+                    .await
+                    .expect("uds server failed");
+            }
+        });
+
+        // Wait for the server to bind the socket.
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !socket_path.exists() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await?;
+
+        // The URI is ignored; every connection goes to the socket.
+        let channel = Endpoint::try_from("http://[::]:50051")?
+            .connect_with_connector(service_fn(move |_: Uri| {
+                let socket_path = socket_path.clone();
+                async move {
+                    Ok::<_, std::io::Error>(TokioIo::new(UnixStream::connect(socket_path).await?))
+                }
+            }))
+            .await?;
+
+        let mut client = RemoteConnectionClient::new(channel);
+        let profiles = client.list_profiles(Empty {}).await?.into_inner().profiles;
+        assert!(profiles.is_empty(), "fresh store should have no profiles");
+        Ok(())
+    })
+}
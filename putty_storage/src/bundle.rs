@@ -0,0 +1,23 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+/// Every profile of a store in one file, for moving them between machines.
+///
+/// `{ "profiles": { "lab": { "kind":"Serial", ... },
+///                  "prod/web1": { "inherits":"fleet", ... } } }`
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct Bundle {
+    /// Profile files exactly as stored, keyed by qualified name.
+    pub profiles: BTreeMap<String, Map<String, Value>>,
+}
+
+/// What [`ProfileStore::import`](crate::ProfileStore::import) did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ImportSummary {
+    /// Profiles written, including replaced ones.
+    pub imported: usize,
+    /// Profiles left out because one of that name already existed.
+    pub skipped: usize,
+}
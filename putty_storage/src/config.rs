@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+
+/// Store-wide settings kept in `config.json` next to the `profiles` directory.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct StoreConfig {
+    /// Profile opened by `putty-rs connect`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub default_profile: Option<String>,
+}
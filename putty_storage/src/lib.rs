@@ -1,5 +1,9 @@
+mod bundle;
+mod config;
 mod profile;
+mod secret;
 mod store;
 
+pub use bundle::ImportSummary;
 pub use profile::Profile;
-pub use store::ProfileStore;
+pub use store::{ProfileStore, PASSPHRASE_ENV};
@@ -1,28 +1,118 @@
+use putty_core::connections::{
+    Baud, ConnectionError, DataBits, FlowControl, Framing, Parity, StopBits,
+};
+use putty_core::utils::escape::unescape;
 use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
 
 /// A user-named connection preset.
 ///
-/// For SSH, the password is **never** serialized; instead we keep a reference
-/// (`keyring_id`) to the OS keyring entry that holds the secret.
+/// For SSH, the password and the passphrase of a private key are **never**
+/// serialized; instead we keep a reference (`keyring_id`) to the OS keyring
+/// entries that hold the secrets.
 ///
 /// `{ "kind":"Ssh", "name":"prodbox", "host":"10.0.0.5", ...,
 ///    "keyring_id":"putty_rs:prodbox" }`
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// A profile may be filed under a `group` such as `prod`; names only need to
+/// be unique within their group, see [`qualified_name`](Self::qualified_name).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "kind")]
 pub enum Profile {
     Serial {
         name: String,
+        /// Folder the profile is filed under, e.g. `prod`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group: Option<String>,
         port: String,
         baud: u32,
+        /// Character format, 8N1 for files written before it was stored.
+        #[serde(default, with = "as_number")]
+        data_bits: DataBits,
+        #[serde(default, with = "as_text")]
+        parity: Parity,
+        #[serde(default, with = "as_number")]
+        stop_bits: StopBits,
+        #[serde(default, with = "as_text")]
+        flow_control: FlowControl,
+        /// Sent after opening the port, with C-style escapes (`ATZ\r`).
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        init_string: Option<String>,
+        /// Disconnect after this many seconds, however busy the session is.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_session_secs: Option<u64>,
+        /// Shown to the reader when the session starts, with C-style escapes
+        /// (`=== rack1 ===\r\n`); never sent to the device.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        banner: Option<String>,
+        /// Key that starts the exit sequence of an interactive session;
+        /// Ctrl+A (`"\u0001"`) when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        escape_char: Option<char>,
+        /// Key that ends the session after `escape_char`; `x` when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        escape_exit: Option<char>,
     },
     Ssh {
         name: String,
+        /// Folder the profile is filed under, e.g. `prod`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group: Option<String>,
         host: String,
         port: u16,
         username: String,
         #[serde(default, skip_serializing)]
         password: String,
         keyring_id: Option<String>,
+        /// Private key to log in with instead of the password.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        key_path: Option<PathBuf>,
+        /// Passphrase of `key_path`. Like the password it is **never**
+        /// serialized but kept in the key-ring.
+        #[serde(default, skip_serializing)]
+        passphrase: Option<String>,
+        /// Pinned SHA-256 host-key fingerprint (`SHA256:...`); connecting
+        /// fails if the server presents any other key.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        expected_host_key: Option<String>,
+        /// Disconnect after this many seconds, however busy the session is.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_session_secs: Option<u64>,
+        /// Shown to the reader when the session starts, with C-style escapes
+        /// (`=== rack1 ===\r\n`); never sent to the device.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        banner: Option<String>,
+        /// Key that starts the exit sequence of an interactive session;
+        /// Ctrl+A (`"\u0001"`) when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        escape_char: Option<char>,
+        /// Key that ends the session after `escape_char`; `x` when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        escape_exit: Option<char>,
+    },
+    Telnet {
+        name: String,
+        /// Folder the profile is filed under, e.g. `prod`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        group: Option<String>,
+        host: String,
+        port: u16,
+        /// Disconnect after this many seconds, however busy the session is.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        max_session_secs: Option<u64>,
+        /// Shown to the reader when the session starts, with C-style escapes
+        /// (`=== rack1 ===\r\n`); never sent to the device.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        banner: Option<String>,
+        /// Key that starts the exit sequence of an interactive session;
+        /// Ctrl+A (`"\u0001"`) when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        escape_char: Option<char>,
+        /// Key that ends the session after `escape_char`; `x` when unset.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        escape_exit: Option<char>,
     },
 }
 
@@ -32,6 +122,225 @@ impl Profile {
         match self {
             Profile::Serial { name, .. } => name,
             Profile::Ssh { name, .. } => name,
+            Profile::Telnet { name, .. } => name,
+        }
+    }
+
+    /// The group the profile is filed under, if any.
+    pub fn group(&self) -> Option<&str> {
+        match self {
+            Profile::Serial { group, .. }
+            | Profile::Ssh { group, .. }
+            | Profile::Telnet { group, .. } => group.as_deref(),
+        }
+    }
+
+    /// `group/name`, or just `name` for a profile without a group: the name
+    /// [`ProfileStore`](crate::ProfileStore) methods know the profile by.
+    pub fn qualified_name(&self) -> String {
+        match self.group() {
+            Some(group) => format!("{group}/{}", self.name()),
+            None => self.name().to_owned(),
+        }
+    }
+
+    /// Split a [`qualified_name`](Self::qualified_name) into group and name.
+    pub fn split_qualified_name(qualified: &str) -> (Option<&str>, &str) {
+        match qualified.split_once('/') {
+            Some((group, name)) => (Some(group), name),
+            None => (None, qualified),
+        }
+    }
+
+    /// The configured session limit, see
+    /// `putty_core::ConnectionOptions::max_session`.
+    pub fn max_session(&self) -> Option<Duration> {
+        match self {
+            Profile::Serial {
+                max_session_secs, ..
+            }
+            | Profile::Ssh {
+                max_session_secs, ..
+            }
+            | Profile::Telnet {
+                max_session_secs, ..
+            } => max_session_secs.map(Duration::from_secs),
+        }
+    }
+
+    /// The configured connect banner, still escaped, see
+    /// `putty_core::ConnectionOptions::banner`.
+    pub fn banner(&self) -> Option<&str> {
+        match self {
+            Profile::Serial { banner, .. }
+            | Profile::Ssh { banner, .. }
+            | Profile::Telnet { banner, .. } => banner.as_deref(),
+        }
+    }
+
+    /// How a serial profile frames characters; `None` for SSH and Telnet.
+    pub fn framing(&self) -> Option<Framing> {
+        match self {
+            Profile::Serial {
+                data_bits,
+                parity,
+                stop_bits,
+                flow_control,
+                ..
+            } => Some(Framing {
+                data_bits: *data_bits,
+                parity: *parity,
+                stop_bits: *stop_bits,
+                flow_control: *flow_control,
+            }),
+            Profile::Ssh { .. } | Profile::Telnet { .. } => None,
+        }
+    }
+
+    /// The configured escape and exit keys of interactive sessions; `None`
+    /// stands for the default (Ctrl+A, then `x`).
+    pub fn escape_keys(&self) -> (Option<char>, Option<char>) {
+        match self {
+            Profile::Serial {
+                escape_char,
+                escape_exit,
+                ..
+            }
+            | Profile::Ssh {
+                escape_char,
+                escape_exit,
+                ..
+            }
+            | Profile::Telnet {
+                escape_char,
+                escape_exit,
+                ..
+            } => (*escape_char, *escape_exit),
+        }
+    }
+
+    /// Replace the escape and exit keys, see [`escape_keys`](Self::escape_keys).
+    pub fn set_escape_keys(&mut self, escape: Option<char>, exit: Option<char>) {
+        match self {
+            Profile::Serial {
+                escape_char,
+                escape_exit,
+                ..
+            }
+            | Profile::Ssh {
+                escape_char,
+                escape_exit,
+                ..
+            }
+            | Profile::Telnet {
+                escape_char,
+                escape_exit,
+                ..
+            } => (*escape_char, *escape_exit) = (escape, exit),
+        }
+    }
+
+    /// Reject settings that can never work, such as an absurd baud rate, a
+    /// malformed escape in the banner, or a name or group that cannot be a
+    /// file name.
+    pub fn validate(&self) -> io::Result<()> {
+        check_path_component("name", self.name())?;
+        if let Some(group) = self.group() {
+            check_path_component("group", group)?;
         }
+        let (escape_char, escape_exit) = self.escape_keys();
+        if let Some(key) = [escape_char, escape_exit]
+            .into_iter()
+            .flatten()
+            .find(|key| !key.is_ascii())
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("Escape keys must be ASCII, got {key:?}"),
+            ));
+        }
+        if escape_char.unwrap_or('\u{1}') == escape_exit.unwrap_or('x') {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Escape and exit key must differ",
+            ));
+        }
+        if let Some(banner) = self.banner() {
+            unescape(banner).map_err(invalid_input)?;
+        }
+        match self {
+            Profile::Serial {
+                baud, init_string, ..
+            } => {
+                Baud::new(*baud).map_err(invalid_input)?;
+                if let Some(init) = init_string {
+                    unescape(init).map_err(invalid_input)?;
+                }
+                Ok(())
+            }
+            Profile::Ssh { .. } | Profile::Telnet { .. } => Ok(()),
+        }
+    }
+}
+
+/// Names and groups become file and directory names in the store.
+pub(crate) fn check_path_component(what: &str, value: &str) -> io::Result<()> {
+    if value.is_empty() || value == "." || value == ".." || value.contains(['/', '\\']) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Profile {what} must be a plain file name, got {value:?}"),
+        ));
+    }
+    Ok(())
+}
+
+fn invalid_input(err: ConnectionError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, err.to_string())
+}
+
+/// Data and stop bits as plain numbers: `"data_bits": 7`.
+mod as_number {
+    use putty_core::connections::ConnectionError;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: Copy + Into<u8>,
+        S: Serializer,
+    {
+        serializer.serialize_u8((*value).into())
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: TryFrom<u8, Error = ConnectionError>,
+        D: Deserializer<'de>,
+    {
+        T::try_from(u8::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// Parity and flow control by name: `"parity": "even"`.
+mod as_text {
+    use std::{fmt::Display, str::FromStr};
+
+    use putty_core::connections::ConnectionError;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<T: Display, S: Serializer>(
+        value: &T,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(value)
+    }
+
+    pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+    where
+        T: FromStr<Err = ConnectionError>,
+        D: Deserializer<'de>,
+    {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
     }
 }
@@ -0,0 +1,137 @@
+//! Passwords sealed under a user passphrase, for stores opened with
+//! [`ProfileStore::with_passphrase`](crate::ProfileStore::with_passphrase).
+//!
+//! The key is derived from the passphrase with Argon2id and a random salt,
+//! and the password is encrypted with AES-256-GCM. Salt and nonce are stored
+//! next to the ciphertext, so each profile file can be decrypted on its own.
+//!
+//! Argon2 is slow on purpose, so a store derives the key for each salt once
+//! and keeps it: saves reuse the first salt the store saw, with a fresh
+//! nonce every time, and listing profiles sealed under that salt derives
+//! nothing more.
+
+use std::{
+    fmt, io,
+    sync::{Arc, Mutex},
+};
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+
+const SALT_LEN: usize = argon2::RECOMMENDED_SALT_LEN;
+
+/// Keys derived so far, with the salt each was derived from.
+type DerivedKeys = Vec<(Vec<u8>, Key<Aes256Gcm>)>;
+
+/// The store passphrase and the keys derived from it so far, by salt. Never
+/// printed, not even by `Debug`.
+#[derive(Clone)]
+pub(crate) struct Passphrase {
+    secret: Arc<str>,
+    keys: Arc<Mutex<DerivedKeys>>,
+}
+
+impl fmt::Debug for Passphrase {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Passphrase(..)")
+    }
+}
+
+impl Passphrase {
+    pub(crate) fn new(passphrase: &str) -> io::Result<Self> {
+        if passphrase.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "passphrase must not be empty",
+            ));
+        }
+        Ok(Self {
+            secret: passphrase.into(),
+            keys: Arc::default(),
+        })
+    }
+
+    /// The key for `salt`, derived on first use.
+    fn key(&self, salt: &[u8]) -> io::Result<Key<Aes256Gcm>> {
+        // Held across the derivation so concurrent callers wait for it
+        // rather than repeat it.
+        let mut keys = self.keys.lock().unwrap();
+        if let Some((_, key)) = keys.iter().find(|(known, _)| known == salt) {
+            return Ok(*key);
+        }
+        let mut key = Key::<Aes256Gcm>::default();
+        Argon2::default()
+            .hash_password_into(self.secret.as_bytes(), salt, &mut key)
+            .map_err(|e| io::Error::other(format!("key derivation failed: {e}")))?;
+        keys.push((salt.to_vec(), key));
+        Ok(key)
+    }
+
+    /// The salt to seal under: the first one this store derived a key for,
+    /// or a fresh one.
+    fn salt(&self) -> Vec<u8> {
+        match self.keys.lock().unwrap().first() {
+            Some((salt, _)) => salt.clone(),
+            None => {
+                let mut salt = vec![0u8; SALT_LEN];
+                OsRng.fill_bytes(&mut salt);
+                salt
+            }
+        }
+    }
+
+    /// Encrypt `secret` under the store's salt and a fresh nonce.
+    pub(crate) fn seal(&self, secret: &str) -> io::Result<SealedSecret> {
+        let salt = self.salt();
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = Aes256Gcm::new(&self.key(&salt)?)
+            .encrypt(&nonce, secret.as_bytes())
+            .map_err(|_| io::Error::other("encryption failed"))?;
+        Ok(SealedSecret {
+            salt: STANDARD.encode(salt),
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(ciphertext),
+        })
+    }
+
+    /// Decrypt `sealed`; fails with `InvalidData` if the passphrase is wrong
+    /// or the file was tampered with.
+    pub(crate) fn open(&self, sealed: &SealedSecret) -> io::Result<String> {
+        let salt = decode(&sealed.salt)?;
+        let nonce = decode(&sealed.nonce)?;
+        if nonce.len() != 12 {
+            return Err(invalid_data("sealed secret has a malformed nonce"));
+        }
+        let plaintext = Aes256Gcm::new(&self.key(&salt)?)
+            .decrypt(
+                Nonce::from_slice(&nonce),
+                decode(&sealed.ciphertext)?.as_slice(),
+            )
+            .map_err(|_| invalid_data("wrong passphrase or corrupted secret"))?;
+        String::from_utf8(plaintext).map_err(|_| invalid_data("sealed secret is not UTF-8"))
+    }
+}
+
+/// An encrypted password as written to the profile file, all base64.
+///
+/// `{ "salt":"...", "nonce":"...", "ciphertext":"..." }`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SealedSecret {
+    salt: String,
+    nonce: String,
+    ciphertext: String,
+}
+
+fn decode(field: &str) -> io::Result<Vec<u8>> {
+    STANDARD
+        .decode(field)
+        .map_err(|e| invalid_data(&format!("sealed secret is not base64: {e}")))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.to_owned())
+}
@@ -1,38 +1,167 @@
 //! A very small profile store
 //!
 //! * Each SSH profile keeps its secret in the system key-ring under the single
-//!   **service** “`putty_rs`” and user **`putty_rs:<profile-name>`**.
-//! * Serial profiles contain no secret.
+//!   **service** “`putty_rs`” and user **`putty_rs:<qualified-name>`**. The
+//!   passphrase of a private key goes under the same user in the service
+//!   “`putty_rs:key-passphrase`”. Without a usable key-ring, the secrets are
+//!   kept in plain text in the profile file as `"password"` and
+//!   `"passphrase"`, with a warning, and move to the key-ring the next time
+//!   the profile is saved with one available.
+//! * Serial and Telnet profiles contain no secret.
+//! * Profiles with a group live in a subdirectory named after it,
+//!   `profiles/<group>/<name>.json`, and are known by their qualified name
+//!   `<group>/<name>` (see [`Profile::qualified_name`]); profiles without one
+//!   sit in `profiles/` directly.
+//! * A store opened [`with_passphrase`](ProfileStore::with_passphrase) skips
+//!   the key-ring and keeps the secrets in the profile file instead, sealed
+//!   under the passphrase as `"sealed_password"` and `"sealed_passphrase"`.
+//!   Files written without one
+//!   still load, with their secret from the key-ring. The CLI and the gRPC
+//!   server open the store so when `PUTTY_RS_PROFILE_PASSPHRASE` is set
+//!   (see [`ProfileStore::from_env`]).
+//! * Store-wide settings (e.g. the default profile) live in `config.json`
+//!   next to the profiles directory.
+//! * A profile file may name another profile in `"inherits"` (by qualified
+//!   name) and leave out every field it shares with it; see
+//!   [`ProfileStore::resolve`].
 
 use std::{fs, io, path::Path, path::PathBuf};
 
 use directories::ProjectDirs;
 use keyring::{Entry, Error as KrError};
 use log::{debug, warn};
-use serde_json::Error as SerdeError;
+use serde::{Deserialize, Serialize};
+use serde_json::{Error as SerdeError, Map, Value};
 
+use crate::bundle::{Bundle, ImportSummary};
+use crate::config::StoreConfig;
+use crate::profile::check_path_component;
+use crate::secret::{Passphrase, SealedSecret};
 use crate::Profile;
 
 /// Small wrapper that stores JSON files on disk **and** secrets in the key-ring.
 #[derive(Debug, Clone)]
 pub struct ProfileStore {
     dir: PathBuf,
+    config_path: PathBuf,
+    passphrase: Option<Passphrase>,
 }
 
-/// canonical key-ring user name: `putty_rs:<profile-name>`
+/// Environment variable holding the passphrase of
+/// [`ProfileStore::from_env`].
+pub const PASSPHRASE_ENV: &str = "PUTTY_RS_PROFILE_PASSPHRASE";
+
+/// Key-ring service of SSH passwords.
+const PASSWORD_SERVICE: &str = "putty_rs";
+/// Key-ring service of private-key passphrases.
+const KEY_PASSPHRASE_SERVICE: &str = "putty_rs:key-passphrase";
+
+/// A profile file: the profile plus, for passphrase stores, its sealed
+/// secrets.
+#[derive(Serialize)]
+struct StoredProfile<'a> {
+    #[serde(flatten)]
+    profile: &'a Profile,
+    #[serde(flatten)]
+    sealed: SealedSecrets,
+    #[serde(flatten)]
+    inline: InlineSecrets,
+}
+
+/// The secrets of an SSH profile sealed into its file.
+#[derive(Default, Serialize, Deserialize)]
+struct SealedSecrets {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed_password: Option<SealedSecret>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    sealed_passphrase: Option<SealedSecret>,
+}
+
+/// The secrets of an SSH profile in plain text in its file, for when the
+/// key-ring was unavailable as it was saved.
+#[derive(Default, Serialize, Deserialize)]
+struct InlineSecrets {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    passphrase: Option<String>,
+}
+
+/// canonical key-ring user name: `putty_rs:<qualified-name>`
 fn key_id(name: &str) -> String {
     format!("putty_rs:{name}")
 }
 
 /// open key-ring entry (logs every access so we see what happens)
-fn open_entry(id: &str) -> io::Result<Entry> {
-    debug!("key-ring open service='putty_rs' user='{id}'");
-    Entry::new("putty_rs", id).map_err(io::Error::other)
+fn open_entry(service: &str, id: &str) -> io::Result<Entry> {
+    debug!("key-ring open service='{service}' user='{id}'");
+    Entry::new(service, id).map_err(io::Error::other)
+}
+
+/// Store `secret` in the key-ring entry `id` of `service`.
+fn write_secret(service: &str, id: &str, secret: &str) -> io::Result<()> {
+    open_entry(service, id).and_then(|entry| entry.set_password(secret).map_err(io::Error::other))
 }
 
-/// Build `<dir>/<name>.json`.
+/// The secret in the key-ring entry `id` of `service`, if there is one. A
+/// key-ring that cannot be used counts as empty, with a warning about the
+/// `what` of the profile `name`.
+fn read_secret(service: &str, id: &str, name: &str, what: &str) -> Option<String> {
+    let entry = match open_entry(service, id) {
+        Ok(entry) => entry,
+        Err(e) => {
+            warn!("key-ring unavailable, {name} has no {what}: {e}");
+            return None;
+        }
+    };
+    match entry.get_password() {
+        Ok(sec) => Some(sec.trim_end_matches(['\r', '\n']).to_owned()),
+        Err(KrError::NoEntry) => {
+            debug!("no secret stored under service='{service}' id='{id}'");
+            None
+        }
+        Err(e) => {
+            warn!("key-ring read error: {e}");
+            None
+        }
+    }
+}
+
+/// Build `<dir>/<name>.json`, or `<dir>/<group>/<name>.json` for a qualified
+/// name with a group.
 fn json_path(dir: &Path, name: &str) -> PathBuf {
-    dir.join(format!("{name}.json"))
+    match Profile::split_qualified_name(name) {
+        (Some(group), name) => dir.join(group).join(format!("{name}.json")),
+        (None, name) => dir.join(format!("{name}.json")),
+    }
+}
+
+/// Reject qualified names that cannot be paths inside the store.
+fn check_name(name: &str) -> io::Result<()> {
+    let (group, base_name) = Profile::split_qualified_name(name);
+    check_path_component("name", base_name)?;
+    group.map_or(Ok(()), |group| check_path_component("group", group))
+}
+
+/// Copy the key-ring secret of `service` stored under `from` to `to` and
+/// drop the old entry; nothing to do if there is none.
+fn move_secret(service: &str, from: &str, to: &str) -> io::Result<()> {
+    let old = open_entry(service, from)?;
+    let secret = match old.get_password() {
+        Ok(secret) => secret,
+        Err(KrError::NoEntry) => return Ok(()),
+        Err(e) => return Err(io::Error::other(e)),
+    };
+    open_entry(service, to)?
+        .set_password(&secret)
+        .map_err(io::Error::other)?;
+    let _ = old.delete_credential();
+    Ok(())
+}
+
+/// `config.json` lives next to the profiles directory, not inside it.
+fn config_path(dir: &Path) -> PathBuf {
+    dir.parent().unwrap_or(dir).join("config.json")
 }
 
 impl ProfileStore {
@@ -44,110 +173,639 @@ impl ProfileStore {
             .join("profiles");
         fs::create_dir_all(&dir)?;
         debug!("profile store dir = {dir:?}");
-        Ok(Self { dir })
+        Ok(Self {
+            config_path: config_path(&dir),
+            dir,
+            passphrase: None,
+        })
     }
 
-    /// * Serial → copied 1:1 to JSON
-    /// * SSH → secret put in key-ring, redacted JSON on disk
+    /// [`new`](Self::new), opened [`with_passphrase`](Self::with_passphrase)
+    /// if the environment variable [`PASSPHRASE_ENV`] is set; how the CLI
+    /// and the gRPC server open the store.
+    pub fn from_env() -> io::Result<Self> {
+        let store = Self::new()?;
+        match std::env::var(PASSPHRASE_ENV) {
+            Ok(passphrase) => store.with_passphrase(&passphrase),
+            Err(_) => Ok(store),
+        }
+    }
+
+    /// Keep SSH passwords in the profile files, encrypted under
+    /// `passphrase`, instead of in the key-ring; for systems without one.
+    /// Profiles saved earlier keep loading their password from the key-ring
+    /// until they are saved again. Fails on an empty passphrase.
+    pub fn with_passphrase(mut self, passphrase: &str) -> io::Result<Self> {
+        self.passphrase = Some(Passphrase::new(passphrase)?);
+        Ok(self)
+    }
+
+    /// * Serial, Telnet → copied 1:1 to JSON
+    /// * SSH → password and key passphrase put in key-ring (or sealed into
+    ///   the JSON with a passphrase), redacted JSON on disk; in plain text in
+    ///   the JSON, with a warning, if the key-ring cannot take them. An empty
+    ///   password or missing key passphrase keeps the one stored before, as
+    ///   [`list`](Self::list) callers such as the gRPC server never see it
     pub fn save(&self, profile: &Profile) -> io::Result<()> {
-        debug!("save {}", profile.name());
+        let qualified = profile.qualified_name();
+        debug!("save {qualified}");
+        profile.validate()?;
 
+        let mut sealed = SealedSecrets::default();
+        let mut inline = InlineSecrets::default();
         let sanitized = match profile {
-            Profile::Serial { .. } => profile.clone(),
+            Profile::Serial { .. } | Profile::Telnet { .. } => profile.clone(),
             Profile::Ssh {
                 name,
+                group,
                 host,
                 port,
                 username,
                 password,
+                key_path,
+                passphrase,
+                expected_host_key,
+                max_session_secs,
+                banner,
+                escape_char,
+                escape_exit,
                 ..
             } => {
-                let id = key_id(name);
-                debug!("write secret len={} to id='{id}'", password.len());
+                let previous = self.read_document(&qualified).ok();
+                // Secrets left in the file by an earlier key-ring failure count
+                // as given, so they are sealed or moved to the key-ring now.
+                let left_inline: InlineSecrets = previous
+                    .clone()
+                    .and_then(|doc| serde_json::from_value(Value::Object(doc)).ok())
+                    .unwrap_or_default();
+                let password = match left_inline.password {
+                    Some(left) if password.is_empty() => left,
+                    _ => password.clone(),
+                };
+                let passphrase = passphrase
+                    .clone()
+                    .or_else(|| key_path.as_ref().and(left_inline.passphrase));
+                let keyring_id = match &self.passphrase {
+                    Some(store_passphrase) => {
+                        debug!("seal secret len={} into {name}.json", password.len());
+                        let kept: SealedSecrets = previous
+                            .and_then(|doc| serde_json::from_value(Value::Object(doc)).ok())
+                            .unwrap_or_default();
+                        sealed.sealed_password = if password.is_empty() {
+                            kept.sealed_password
+                        } else {
+                            Some(store_passphrase.seal(&password)?)
+                        };
+                        sealed.sealed_passphrase = match &passphrase {
+                            Some(passphrase) => Some(store_passphrase.seal(passphrase)?),
+                            None if key_path.is_some() => kept.sealed_passphrase,
+                            None => None,
+                        };
+                        None
+                    }
+                    None if password.is_empty() && passphrase.is_none() => {
+                        // Nothing new to store: keep pointing at the old secrets.
+                        previous
+                            .and_then(|mut doc| doc.remove("keyring_id"))
+                            .and_then(|id| id.as_str().map(str::to_owned))
+                            .or_else(|| Some(key_id(&qualified)))
+                    }
+                    None => {
+                        let id = key_id(&qualified);
+                        debug!("write secret len={} to id='{id}'", password.len());
 
-                if !password.is_empty() {
-                    open_entry(&id)?
-                        .set_password(password)
-                        .map_err(io::Error::other)?;
-                }
+                        let stored = if password.is_empty() {
+                            Ok(())
+                        } else {
+                            write_secret(PASSWORD_SERVICE, &id, &password)
+                        }
+                        .and_then(|()| match &passphrase {
+                            Some(passphrase) => {
+                                write_secret(KEY_PASSPHRASE_SERVICE, &id, passphrase)
+                            }
+                            None => Ok(()),
+                        });
+                        match stored {
+                            Ok(()) => Some(id),
+                            Err(e) => {
+                                warn!(
+                                    "key-ring unavailable, keeping the secrets of {name} in plain \
+                                     text in its profile file (set {PASSPHRASE_ENV} to seal them \
+                                     instead): {e}"
+                                );
+                                inline = InlineSecrets {
+                                    password: (!password.is_empty()).then_some(password),
+                                    passphrase,
+                                };
+                                None
+                            }
+                        }
+                    }
+                };
 
                 Profile::Ssh {
                     name: name.clone(),
+                    group: group.clone(),
                     host: host.clone(),
                     port: *port,
                     username: username.clone(),
                     password: String::new(),
-                    keyring_id: Some(id),
+                    keyring_id,
+                    key_path: key_path.clone(),
+                    passphrase: None,
+                    expected_host_key: expected_host_key.clone(),
+                    max_session_secs: *max_session_secs,
+                    banner: banner.clone(),
+                    escape_char: *escape_char,
+                    escape_exit: *escape_exit,
                 }
             }
         };
 
+        let path = json_path(&self.dir, &qualified);
+        if let Some(group_dir) = path.parent() {
+            fs::create_dir_all(group_dir)?;
+        }
         serde_json::to_writer_pretty(
-            fs::File::create(json_path(&self.dir, profile.name()))?,
-            &sanitized,
+            fs::File::create(path)?,
+            &StoredProfile {
+                profile: &sanitized,
+                sealed,
+                inline,
+            },
         )
         .map_err(SerdeError::into)
     }
 
-    /// Loads every JSON file; SSH profiles get their secret filled in from the
-    /// key-ring when available.
+    /// Loads every JSON file, in every group; SSH profiles get their secret
+    /// filled in from the key-ring when available. Inheritance is resolved,
+    /// and profiles that cannot be resolved, or whose sealed secret does not
+    /// open, are skipped with a warning.
     pub fn list(&self) -> io::Result<Vec<Profile>> {
+        let mut out = self.list_group(None)?;
+        for group in self.groups()? {
+            out.extend(self.list_group(Some(&group))?);
+        }
+        Ok(out)
+    }
+
+    /// Names of the groups that have profiles, sorted.
+    pub fn groups(&self) -> io::Result<Vec<String>> {
+        let mut groups = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            if let Some(group) = entry.file_name().to_str() {
+                groups.push(group.to_owned());
+            }
+        }
+        groups.sort_unstable();
+        Ok(groups)
+    }
+
+    /// Like [`list`](Self::list), but only the profiles of `group`, or only
+    /// those without a group for `None`. An unknown group has no profiles.
+    pub fn list_group(&self, group: Option<&str>) -> io::Result<Vec<Profile>> {
         debug!("KEYRING_BACKEND = {:?}", std::env::var("KEYRING_BACKEND"));
         let mut out = Vec::new();
+        for name in self.names_in(group)? {
+            match self.resolve_unlocked(&name) {
+                Ok((mut profile, sealed)) => match self.fill_secret(&mut profile, sealed) {
+                    Ok(()) => out.push(profile),
+                    Err(e) => warn!("bad profile {name}: {e}"),
+                },
+                Err(e) => warn!("bad profile {name}: {e}"),
+            }
+        }
+        Ok(out)
+    }
 
-        for entry in fs::read_dir(&self.dir)? {
+    /// Qualified names of the profile files of `group`, or of those without
+    /// a group for `None`, whether or not they resolve.
+    fn names_in(&self, group: Option<&str>) -> io::Result<Vec<String>> {
+        let dir = match group {
+            Some(group) => self.dir.join(group),
+            None => self.dir.clone(),
+        };
+        let entries = match fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if group.is_some() && e.kind() == io::ErrorKind::NotFound => {
+                return Ok(Vec::new())
+            }
+            Err(e) => return Err(e),
+        };
+        let mut names = Vec::new();
+        for entry in entries {
             let path = entry?.path();
             if path.extension().is_none_or(|e| e != "json") {
                 continue;
             }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            names.push(match group {
+                Some(group) => format!("{group}/{name}"),
+                None => name.to_owned(),
+            });
+        }
+        Ok(names)
+    }
+
+    /// Write every profile file into the bundle at `path`, keyed by qualified
+    /// name, and return how many there were. Files are copied as stored, so
+    /// `"inherits"` survives and secrets sealed under a passphrase stay
+    /// sealed. Secrets in the key-ring are not exported, and neither are
+    /// plain-text ones or `keyring_id`, which only means something on this
+    /// machine.
+    pub fn export(&self, path: &Path) -> io::Result<usize> {
+        let mut bundle = Bundle::default();
+        let mut names = self.names_in(None)?;
+        for group in self.groups()? {
+            names.extend(self.names_in(Some(&group))?);
+        }
+        for name in names {
+            let mut doc = self.read_document(&name)?;
+            for field in ["keyring_id", "password", "passphrase"] {
+                doc.remove(field);
+            }
+            bundle.profiles.insert(name, doc);
+        }
+        debug!("export {} profiles to {path:?}", bundle.profiles.len());
+        serde_json::to_writer_pretty(fs::File::create(path)?, &bundle)?;
+        Ok(bundle.profiles.len())
+    }
+
+    /// Add the profiles of a bundle written by [`export`](Self::export).
+    /// Profiles whose qualified name is already taken are replaced if
+    /// `overwrite` is set and skipped otherwise. Fails with `InvalidData`
+    /// before writing anything if the bundle is malformed, i.e. if a name is
+    /// not a valid path or a profile, with its `"inherits"` chain merged in
+    /// from the bundle and the store, is not a valid [`Profile`].
+    pub fn import(&self, path: &Path, overwrite: bool) -> io::Result<ImportSummary> {
+        let bundle: Bundle = serde_json::from_reader(fs::File::open(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let invalid = |name: &str, e: &dyn std::fmt::Display| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("profile {name}: {e}"))
+        };
+        // A base comes from the bundle if the import is going to write it.
+        let read = |name: &str| match bundle.profiles.get(name) {
+            Some(doc) if overwrite || !json_path(&self.dir, name).exists() => Ok(doc.clone()),
+            _ => self.read_document(name),
+        };
+        for (name, doc) in &bundle.profiles {
+            check_name(name).map_err(|e| invalid(name, &e))?;
+            let merged = self
+                .merge_inherited(name, doc.clone(), read)
+                .map_err(|e| invalid(name, &e))?;
+            let profile: Profile =
+                serde_json::from_value(Value::Object(merged)).map_err(|e| invalid(name, &e))?;
+            profile.validate().map_err(|e| invalid(name, &e))?;
+        }
+
+        let mut summary = ImportSummary::default();
+        for (name, doc) in bundle.profiles {
+            let target = json_path(&self.dir, &name);
+            if target.exists() && !overwrite {
+                debug!("import: keep existing {name}");
+                summary.skipped += 1;
+                continue;
+            }
+            self.write_document(&name, &doc)?;
+            summary.imported += 1;
+        }
+        Ok(summary)
+    }
+
+    /// Load the profile `name`, qualified with its group if it has one (e.g.
+    /// `prod/web1`), with its `"inherits"` chain merged in: fields of the base
+    /// profile apply unless the inheriting profile sets them, and bases may
+    /// inherit in turn. Secrets are merged like any other field, sealed ones
+    /// as well as `keyring_id`, so an SSH profile without a secret of its own
+    /// uses its base's password and key passphrase. Fails with `NotFound` if
+    /// `name` or a base is missing and with `InvalidData` on an inheritance
+    /// cycle or when a sealed secret does not open with the store's
+    /// passphrase.
+    pub fn resolve(&self, name: &str) -> io::Result<Profile> {
+        let (mut profile, sealed) = self.resolve_unlocked(name)?;
+        self.fill_secret(&mut profile, sealed)?;
+        Ok(profile)
+    }
+
+    /// [`resolve`](Self::resolve) without touching the key-ring; sealed
+    /// secrets are returned as is.
+    fn resolve_unlocked(&self, name: &str) -> io::Result<(Profile, SealedSecrets)> {
+        let mut merged = self.merge_inherited(name, self.read_document(name)?, |base| {
+            self.read_document(base)
+        })?;
+        let sealed = SealedSecrets {
+            sealed_password: merged
+                .remove("sealed_password")
+                .map(serde_json::from_value)
+                .transpose()?,
+            sealed_passphrase: merged
+                .remove("sealed_passphrase")
+                .map(serde_json::from_value)
+                .transpose()?,
+        };
+        let profile = serde_json::from_value(Value::Object(merged))?;
+        Ok((profile, sealed))
+    }
+
+    /// The document `doc` of the profile `name` with its `"inherits"` chain
+    /// merged in, bases loaded with `read`, and the name and group set from
+    /// `name`.
+    fn merge_inherited(
+        &self,
+        name: &str,
+        doc: Map<String, Value>,
+        read: impl Fn(&str) -> io::Result<Map<String, Value>>,
+    ) -> io::Result<Map<String, Value>> {
+        let mut chain = vec![name.to_owned()];
+        let mut layers = vec![doc];
+        while let Some(base) = layers.last_mut().and_then(|doc| doc.remove("inherits")) {
+            let Value::String(base) = base else {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("\"inherits\" of {:?} must be a profile name", chain.last()),
+                ));
+            };
+            if chain.contains(&base) {
+                chain.push(base);
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("inheritance cycle: {}", chain.join(" -> ")),
+                ));
+            }
+            let doc = read(&base).map_err(|e| {
+                io::Error::new(
+                    e.kind(),
+                    format!("{} inherits from {base}: {e}", chain.join(" -> ")),
+                )
+            })?;
+            chain.push(base);
+            layers.push(doc);
+        }
 
-            match fs::File::open(&path)
-                .and_then(|f| serde_json::from_reader::<_, Profile>(f).map_err(SerdeError::into))
+        // Apply the most basic profile first so each level overrides it.
+        let mut merged = Map::new();
+        for layer in layers.into_iter().rev() {
+            merged.extend(layer);
+        }
+        // Where the file lives decides the name and group, whatever it says.
+        let (group, base_name) = Profile::split_qualified_name(name);
+        merged.insert("name".into(), Value::String(base_name.to_owned()));
+        match group {
+            Some(group) => merged.insert("group".into(), Value::String(group.to_owned())),
+            None => merged.remove("group"),
+        };
+        Ok(merged)
+    }
+
+    /// Fill in an SSH profile's password unless it already has one, and its
+    /// key passphrase if it logs in with a key: from `sealed` if there is
+    /// one, else from the key-ring.
+    fn fill_secret(&self, profile: &mut Profile, sealed: SealedSecrets) -> io::Result<()> {
+        let qualified = profile.qualified_name();
+        let Profile::Ssh {
+            password,
+            keyring_id,
+            key_path,
+            passphrase,
+            name,
+            ..
+        } = profile
+        else {
+            return Ok(());
+        };
+        let id = keyring_id.clone().unwrap_or_else(|| key_id(&qualified));
+        if password.is_empty() {
+            let secret = self.open_secret(name, "password", sealed.sealed_password)?;
+            if let Some(secret) =
+                secret.or_else(|| read_secret(PASSWORD_SERVICE, &id, name, "password"))
             {
-                Ok(mut profile) => {
-                    if let Profile::Ssh {
-                        password,
-                        keyring_id,
-                        name,
-                        ..
-                    } = &mut profile
-                    {
-                        if password.is_empty() {
-                            let id = keyring_id.clone().unwrap_or_else(|| key_id(name));
-                            match open_entry(&id)?.get_password() {
-                                Ok(sec) => {
-                                    *password = sec.trim_end_matches(['\r', '\n']).to_owned();
-                                }
-                                Err(KrError::NoEntry) => {
-                                    debug!("no secret stored under id='{id}'");
-                                }
-                                Err(e) => warn!("key-ring read error: {e}"),
-                            }
-                        }
-                    }
-                    out.push(profile);
+                *password = secret;
+            }
+        }
+        if key_path.is_some() && passphrase.is_none() {
+            let secret = self.open_secret(name, "key passphrase", sealed.sealed_passphrase)?;
+            *passphrase =
+                secret.or_else(|| read_secret(KEY_PASSPHRASE_SERVICE, &id, name, "key passphrase"));
+        }
+        Ok(())
+    }
+
+    /// Open the `what` of the profile `name` if it was sealed into the file.
+    /// `None` if it was not, so it is looked up in the key-ring instead.
+    fn open_secret(
+        &self,
+        name: &str,
+        what: &str,
+        sealed: Option<SealedSecret>,
+    ) -> io::Result<Option<String>> {
+        match (sealed, &self.passphrase) {
+            (Some(sealed), Some(passphrase)) => passphrase
+                .open(&sealed)
+                .map(Some)
+                .map_err(|e| io::Error::new(e.kind(), format!("profile {name}: {e}"))),
+            (Some(_), None) => {
+                warn!("profile {name} has an encrypted {what}; open the store with a passphrase to use it");
+                Ok(None)
+            }
+            (None, _) => Ok(None),
+        }
+    }
+
+    /// The JSON object stored for `name`, exactly as written on disk.
+    fn read_document(&self, name: &str) -> io::Result<Map<String, Value>> {
+        let path = json_path(&self.dir, name);
+        let file = fs::File::open(&path)
+            .map_err(|e| io::Error::new(e.kind(), format!("no such profile: {name}")))?;
+        match serde_json::from_reader::<_, Value>(file).map_err(io::Error::from)? {
+            Value::Object(doc) => Ok(doc),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{path:?} is not a JSON object"),
+            )),
+        }
+    }
+
+    /// Write `doc` as the file of the profile `name`, creating its group
+    /// directory if needed.
+    fn write_document(&self, name: &str, doc: &Map<String, Value>) -> io::Result<()> {
+        let path = json_path(&self.dir, name);
+        if let Some(group_dir) = path.parent() {
+            fs::create_dir_all(group_dir)?;
+        }
+        serde_json::to_writer_pretty(fs::File::create(path)?, doc).map_err(SerdeError::into)
+    }
+
+    /// [`save`](Self::save) a profile that already exists; fails with
+    /// `NotFound` instead of creating a new one.
+    pub fn update(&self, profile: &Profile) -> io::Result<()> {
+        let name = profile.qualified_name();
+        if !json_path(&self.dir, &name).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no such profile: {name}"),
+            ));
+        }
+        self.save(profile)
+    }
+
+    /// Rename the profile `old` to `new`, both qualified names, so a profile
+    /// can also move between groups. Its key-ring secret moves along, and
+    /// the default profile and profiles inheriting from it follow. Fails
+    /// with `NotFound` if there is no `old` and with `AlreadyExists` if there
+    /// already is a `new`.
+    pub fn rename(&self, old: &str, new: &str) -> io::Result<()> {
+        check_name(new)?;
+        let mut doc = self.read_document(old)?;
+        if json_path(&self.dir, new).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("profile {new} already exists"),
+            ));
+        }
+        debug!("rename {old} -> {new}");
+
+        let (group, base_name) = Profile::split_qualified_name(new);
+        doc.insert("name".into(), Value::String(base_name.to_owned()));
+        match group {
+            Some(group) => doc.insert("group".into(), Value::String(group.to_owned())),
+            None => doc.remove("group"),
+        };
+        // Without a `keyring_id` the secrets of an SSH profile sit under the
+        // default id derived from its name, so they have to move just the
+        // same. A profile that inherits its kind may be one as well.
+        let keyring_id = doc.get("keyring_id").and_then(Value::as_str);
+        let under_default_id = match keyring_id {
+            Some(id) => id == key_id(old),
+            None => !matches!(
+                doc.get("kind").and_then(Value::as_str),
+                Some("Serial" | "Telnet")
+            ),
+        };
+        if under_default_id {
+            let had_keyring_id = keyring_id.is_some();
+            let moved = move_secret(PASSWORD_SERVICE, &key_id(old), &key_id(new))
+                .and_then(|()| move_secret(KEY_PASSPHRASE_SERVICE, &key_id(old), &key_id(new)));
+            match moved {
+                Ok(()) if had_keyring_id => {
+                    doc.insert("keyring_id".into(), Value::String(key_id(new)));
                 }
-                Err(e) => warn!("bad profile {path:?}: {e}"),
+                Ok(()) => {}
+                Err(e) => warn!("keeping the key-ring secret of {new} under {old}: {e}"),
             }
         }
-        Ok(out)
+        self.write_document(new, &doc)?;
+        self.remove_document(old)?;
+
+        let mut names = self.names_in(None)?;
+        for group in self.groups()? {
+            names.extend(self.names_in(Some(&group))?);
+        }
+        for name in names {
+            let mut doc = self.read_document(&name)?;
+            if doc.get("inherits").and_then(Value::as_str) == Some(old) {
+                doc.insert("inherits".into(), Value::String(new.to_owned()));
+                self.write_document(&name, &doc)?;
+            }
+        }
+
+        if self.default_profile()?.as_deref() == Some(old) {
+            let mut config = self.read_config()?;
+            config.default_profile = Some(new.to_owned());
+            self.write_config(&config)?;
+        }
+        Ok(())
     }
 
-    /// Removes the JSON file **and** the associated key-ring secret.
+    /// Removes the JSON file of the profile `name`, qualified with its group
+    /// if it has one, **and** the associated key-ring secrets.
     pub fn delete(&self, name: &str) -> io::Result<bool> {
         let id = key_id(name);
-        let _ = open_entry(&id)?.delete_credential();
+        for service in [PASSWORD_SERVICE, KEY_PASSPHRASE_SERVICE] {
+            match open_entry(service, &id) {
+                Ok(entry) => {
+                    let _ = entry.delete_credential();
+                }
+                Err(e) => debug!("key-ring unavailable: {e}"),
+            }
+        }
+
+        if self.default_profile()?.as_deref() == Some(name) {
+            self.clear_default_profile()?;
+        }
 
-        match fs::remove_file(json_path(&self.dir, name)) {
-            Ok(()) => Ok(true),
+        self.remove_document(name)
+    }
+
+    /// Remove the file of the profile `name`, and its group directory with
+    /// the group's last profile. `false` if there was no such file.
+    fn remove_document(&self, name: &str) -> io::Result<bool> {
+        let path = json_path(&self.dir, name);
+        match fs::remove_file(&path) {
+            Ok(()) => {
+                if let (Some(_), Some(group_dir)) =
+                    (Profile::split_qualified_name(name).0, path.parent())
+                {
+                    let _ = fs::remove_dir(group_dir);
+                }
+                Ok(true)
+            }
             Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
             Err(e) => Err(e),
         }
     }
 
+    /// Name of the profile opened by `putty-rs connect`, if one is set.
+    pub fn default_profile(&self) -> io::Result<Option<String>> {
+        Ok(self.read_config()?.default_profile)
+    }
+
+    /// Make `name` the default profile. Fails with `NotFound` if no such
+    /// profile is stored.
+    pub fn set_default_profile(&self, name: &str) -> io::Result<()> {
+        if !json_path(&self.dir, name).exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no such profile: {name}"),
+            ));
+        }
+        let mut config = self.read_config()?;
+        config.default_profile = Some(name.to_owned());
+        self.write_config(&config)
+    }
+
+    /// Forget the default profile.
+    pub fn clear_default_profile(&self) -> io::Result<()> {
+        let mut config = self.read_config()?;
+        config.default_profile = None;
+        self.write_config(&config)
+    }
+
+    fn read_config(&self) -> io::Result<StoreConfig> {
+        match fs::File::open(&self.config_path) {
+            Ok(f) => serde_json::from_reader(f).map_err(SerdeError::into),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(StoreConfig::default()),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn write_config(&self, config: &StoreConfig) -> io::Result<()> {
+        serde_json::to_writer_pretty(fs::File::create(&self.config_path)?, config)
+            .map_err(SerdeError::into)
+    }
+
     /// Create a store rooted at an explicit directory, useful for tests.
     pub fn in_dir(dir: PathBuf) -> io::Result<Self> {
         fs::create_dir_all(&dir)?;
-        Ok(Self { dir })
+        Ok(Self {
+            config_path: config_path(&dir),
+            dir,
+            passphrase: None,
+        })
     }
 }
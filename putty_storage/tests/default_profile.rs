@@ -0,0 +1,60 @@
+//! Set, get and clear the default profile kept in `config.json`.
+
+use putty_storage::{Profile, ProfileStore};
+use tempfile::TempDir;
+
+#[test]
+fn default_profile_set_get_clear() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    store.save(&Profile::Serial {
+        name: "lab".into(),
+        group: None,
+        port: "/dev/ttyUSB0".into(),
+        baud: 115_200,
+        data_bits: Default::default(),
+        parity: Default::default(),
+        stop_bits: Default::default(),
+        flow_control: Default::default(),
+        init_string: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    })?;
+
+    // ── Nothing set yet ──────────────────────────────────────────────────
+    assert_eq!(store.default_profile()?, None);
+
+    // ── Set ──────────────────────────────────────────────────────────────
+    store.set_default_profile("lab")?;
+    assert_eq!(store.default_profile()?.as_deref(), Some("lab"));
+    assert!(
+        sandbox.path().join("config.json").exists(),
+        "config.json should live next to the profiles dir"
+    );
+
+    // The config file must not be mistaken for a profile.
+    assert_eq!(store.list()?.len(), 1);
+
+    // ── Clear ────────────────────────────────────────────────────────────
+    store.clear_default_profile()?;
+    assert_eq!(store.default_profile()?, None);
+
+    Ok(())
+}
+
+#[test]
+fn default_profile_must_exist() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    let err = store
+        .set_default_profile("missing")
+        .expect_err("setting an unknown profile as default should fail");
+    assert_eq!(err.kind(), std::io::ErrorKind::NotFound);
+    assert_eq!(store.default_profile()?, None);
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Renaming a profile takes its key-ring secrets along, whether the profile
+//! names its `keyring_id` or relies on the default one derived from its name.
+//! Runs in its own process because it replaces the process-wide key-ring
+//! back-end.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::{Arc, Mutex};
+
+use keyring::credential::{Credential, CredentialApi, CredentialBuilderApi};
+use keyring::Error as KrError;
+use putty_storage::{Profile, ProfileStore};
+use serde_json::Value;
+use tempfile::TempDir;
+
+type Secrets = Arc<Mutex<HashMap<(String, String), Vec<u8>>>>;
+
+/// Back-end keeping the secrets in memory, by service and user.
+#[derive(Debug, Default)]
+struct MemoryKeyring(Secrets);
+
+#[derive(Debug)]
+struct MemoryCredential {
+    secrets: Secrets,
+    key: (String, String),
+}
+
+impl CredentialApi for MemoryCredential {
+    fn set_secret(&self, secret: &[u8]) -> keyring::Result<()> {
+        let mut secrets = self.secrets.lock().unwrap();
+        secrets.insert(self.key.clone(), secret.to_vec());
+        Ok(())
+    }
+
+    fn get_secret(&self) -> keyring::Result<Vec<u8>> {
+        let secrets = self.secrets.lock().unwrap();
+        secrets.get(&self.key).cloned().ok_or(KrError::NoEntry)
+    }
+
+    fn delete_credential(&self) -> keyring::Result<()> {
+        let mut secrets = self.secrets.lock().unwrap();
+        secrets.remove(&self.key).map(drop).ok_or(KrError::NoEntry)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+impl CredentialBuilderApi for MemoryKeyring {
+    fn build(
+        &self,
+        _target: Option<&str>,
+        service: &str,
+        user: &str,
+    ) -> keyring::Result<Box<Credential>> {
+        Ok(Box::new(MemoryCredential {
+            secrets: self.0.clone(),
+            key: (service.to_owned(), user.to_owned()),
+        }))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+fn ssh(name: &str, password: &str) -> Profile {
+    Profile::Ssh {
+        name: name.into(),
+        group: None,
+        host: "10.0.0.5".into(),
+        port: 22,
+        username: "ops".into(),
+        password: password.into(),
+        keyring_id: None,
+        key_path: None,
+        passphrase: None,
+        expected_host_key: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    }
+}
+
+fn password_of(store: &ProfileStore, name: &str) -> anyhow::Result<String> {
+    match store.resolve(name)? {
+        Profile::Ssh { password, .. } => Ok(password),
+        other => anyhow::bail!("not an SSH profile: {other:?}"),
+    }
+}
+
+#[test]
+fn rename_moves_the_keyring_secret() -> anyhow::Result<()> {
+    let secrets = Secrets::default();
+    keyring::set_default_credential_builder(Box::new(MemoryKeyring(secrets.clone())));
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+
+    store.save(&ssh("prodbox", "s3cr3t!"))?;
+    let on_disk = fs::read_to_string(dir.join("prodbox.json"))?;
+    assert!(!on_disk.contains("s3cr3t!"), "{on_disk}");
+    store.rename("prodbox", "prod/box")?;
+    assert_eq!(password_of(&store, "prod/box")?, "s3cr3t!");
+
+    // A profile file without a `keyring_id`, as written by hand or before
+    // there was one, keeps its secret under the default id.
+    store.save(&ssh("stagebox", "hunter2"))?;
+    let path = dir.join("stagebox.json");
+    let mut doc: serde_json::Map<String, Value> =
+        serde_json::from_str(&fs::read_to_string(&path)?)?;
+    doc.remove("keyring_id");
+    fs::write(&path, serde_json::to_string(&doc)?)?;
+    store.rename("stagebox", "stage/box")?;
+    assert_eq!(password_of(&store, "stage/box")?, "hunter2");
+
+    let users: Vec<String> = secrets
+        .lock()
+        .unwrap()
+        .keys()
+        .map(|(_, user)| user.clone())
+        .collect();
+    assert!(
+        !users
+            .iter()
+            .any(|user| user == "putty_rs:prodbox" || user == "putty_rs:stagebox"),
+        "{users:?}"
+    );
+    Ok(())
+}
@@ -0,0 +1,72 @@
+//! Without a usable key-ring, SSH profiles keep their password in plain text
+//! in the profile file rather than losing it. Runs in its own process because
+//! it replaces the process-wide key-ring back-end.
+
+use std::any::Any;
+
+use keyring::credential::{Credential, CredentialBuilderApi};
+use keyring::Error as KrError;
+use putty_storage::{Profile, ProfileStore};
+use tempfile::TempDir;
+
+/// Back-end that fails like a locked or missing Secret Service.
+#[derive(Debug)]
+struct NoKeyring;
+
+impl CredentialBuilderApi for NoKeyring {
+    fn build(
+        &self,
+        _target: Option<&str>,
+        _service: &str,
+        _user: &str,
+    ) -> keyring::Result<Box<Credential>> {
+        Err(KrError::NoStorageAccess("no key-ring in this test".into()))
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[test]
+fn secrets_are_kept_inline_without_a_keyring() -> anyhow::Result<()> {
+    keyring::set_default_credential_builder(Box::new(NoKeyring));
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+
+    let profile = |password: &str| Profile::Ssh {
+        name: "prodbox".into(),
+        group: None,
+        host: "10.0.0.5".into(),
+        port: 22,
+        username: "ops".into(),
+        password: password.into(),
+        keyring_id: None,
+        key_path: None,
+        passphrase: None,
+        expected_host_key: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    };
+    store.save(&profile("s3cr3t!"))?;
+    let on_disk = std::fs::read_to_string(dir.join("prodbox.json"))?;
+    assert!(on_disk.contains("s3cr3t!"), "{on_disk}");
+
+    // Saving again without a password keeps the one in the file.
+    store.save(&profile(""))?;
+    let loaded = store.resolve("prodbox")?;
+    assert!(
+        matches!(&loaded, Profile::Ssh { password, .. } if password == "s3cr3t!"),
+        "{loaded:?}"
+    );
+
+    let bundle = sandbox.path().join("bundle.json");
+    store.export(&bundle)?;
+    let exported = std::fs::read_to_string(&bundle)?;
+    assert!(!exported.contains("s3cr3t!"), "{exported}");
+    assert!(store.delete("prodbox")?);
+    Ok(())
+}
@@ -0,0 +1,198 @@
+//! Moving every profile between stores through one bundle file.
+
+use std::fs;
+
+use putty_storage::{ImportSummary, Profile, ProfileStore};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+fn serial(name: &str, group: Option<&str>, port: &str) -> Profile {
+    Profile::Serial {
+        name: name.into(),
+        group: group.map(Into::into),
+        port: port.into(),
+        baud: 115200,
+        data_bits: Default::default(),
+        parity: Default::default(),
+        stop_bits: Default::default(),
+        flow_control: Default::default(),
+        init_string: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    }
+}
+
+#[test]
+fn export_then_import_copies_every_profile() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let source = ProfileStore::in_dir(sandbox.path().join("a").join("profiles"))?;
+    source.save(&serial("lab", None, "/dev/ttyUSB0"))?;
+    source.save(&serial("console", Some("prod"), "/dev/ttyUSB1"))?;
+    // Hand-written inheritance travels as written.
+    fs::write(
+        sandbox.path().join("a/profiles/bench.json"),
+        json!({ "inherits": "lab", "baud": 9600 }).to_string(),
+    )?;
+
+    let bundle = sandbox.path().join("profiles.bundle.json");
+    assert_eq!(source.export(&bundle)?, 3);
+    let doc: Value = serde_json::from_str(&fs::read_to_string(&bundle)?)?;
+    assert_eq!(
+        doc["profiles"]["bench"],
+        json!({ "inherits": "lab", "baud": 9600 })
+    );
+    assert!(doc["profiles"]["prod/console"].is_object());
+
+    let target = ProfileStore::in_dir(sandbox.path().join("b").join("profiles"))?;
+    assert_eq!(
+        target.import(&bundle, false)?,
+        ImportSummary {
+            imported: 3,
+            skipped: 0
+        }
+    );
+    assert_eq!(
+        target.resolve("prod/console")?,
+        serial("console", Some("prod"), "/dev/ttyUSB1")
+    );
+    let Profile::Serial { baud, port, .. } = target.resolve("bench")? else {
+        panic!("bench should be a serial profile");
+    };
+    assert_eq!((baud, port.as_str()), (9600, "/dev/ttyUSB0"));
+    Ok(())
+}
+
+#[test]
+fn collisions_are_skipped_unless_overwriting() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let source = ProfileStore::in_dir(sandbox.path().join("a").join("profiles"))?;
+    source.save(&serial("lab", None, "/dev/ttyUSB0"))?;
+    source.save(&serial("new", None, "/dev/ttyUSB1"))?;
+    let bundle = sandbox.path().join("profiles.bundle.json");
+    source.export(&bundle)?;
+
+    let target = ProfileStore::in_dir(sandbox.path().join("b").join("profiles"))?;
+    target.save(&serial("lab", None, "/dev/ttyS9"))?;
+
+    assert_eq!(
+        target.import(&bundle, false)?,
+        ImportSummary {
+            imported: 1,
+            skipped: 1
+        }
+    );
+    assert_eq!(target.resolve("lab")?, serial("lab", None, "/dev/ttyS9"));
+
+    assert_eq!(
+        target.import(&bundle, true)?,
+        ImportSummary {
+            imported: 2,
+            skipped: 0
+        }
+    );
+    assert_eq!(target.resolve("lab")?, serial("lab", None, "/dev/ttyUSB0"));
+    Ok(())
+}
+
+#[test]
+fn sealed_passwords_stay_sealed() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let source = ProfileStore::in_dir(sandbox.path().join("a").join("profiles"))?
+        .with_passphrase("correct horse")?;
+    let prodbox = Profile::Ssh {
+        name: "prodbox".into(),
+        group: None,
+        host: "10.0.0.5".into(),
+        port: 22,
+        username: "ops".into(),
+        password: "s3cr3t!".into(),
+        keyring_id: None,
+        key_path: None,
+        passphrase: None,
+        expected_host_key: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    };
+    source.save(&prodbox)?;
+
+    let bundle = sandbox.path().join("profiles.bundle.json");
+    source.export(&bundle)?;
+    assert!(!fs::read_to_string(&bundle)?.contains("s3cr3t!"));
+
+    let target = ProfileStore::in_dir(sandbox.path().join("b").join("profiles"))?
+        .with_passphrase("correct horse")?;
+    target.import(&bundle, false)?;
+    assert_eq!(target.resolve("prodbox")?, prodbox);
+    Ok(())
+}
+
+#[test]
+fn malformed_bundles_write_nothing() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+    let bundle = sandbox.path().join("evil.json");
+    fs::write(
+        &bundle,
+        json!({ "profiles": {
+            "ok": { "kind": "Serial", "port": "/dev/ttyUSB0", "baud": 115200 },
+            "../escape": { "kind": "Serial", "port": "/dev/ttyUSB0", "baud": 115200 },
+        } })
+        .to_string(),
+    )?;
+
+    let err = store.import(&bundle, false).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    assert!(store.list()?.is_empty());
+    assert!(!sandbox.path().join("escape.json").exists());
+    Ok(())
+}
+
+#[test]
+fn invalid_profiles_in_a_bundle_write_nothing() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+    for profiles in [
+        // Not a profile at all.
+        json!({ "ok": { "kind": "Serial", "port": "/dev/ttyUSB0", "baud": 115200 },
+                "junk": { "hello": "world" } }),
+        // A profile with an impossible setting.
+        json!({ "ok": { "kind": "Serial", "port": "/dev/ttyUSB0", "baud": 115200 },
+                "fast": { "kind": "Serial", "port": "/dev/ttyUSB0", "baud": 1_000_000_000 } }),
+        // Inheriting from a base that is nowhere to be found.
+        json!({ "ok": { "kind": "Serial", "port": "/dev/ttyUSB0", "baud": 115200 },
+                "orphan": { "inherits": "missing", "baud": 9600 } }),
+    ] {
+        let bundle = sandbox.path().join("bad.json");
+        fs::write(&bundle, json!({ "profiles": profiles }).to_string())?;
+
+        let err = store.import(&bundle, false).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{err}");
+        assert!(store.list()?.is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn keyring_ids_are_not_exported() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+    fs::write(
+        dir.join("prodbox.json"),
+        json!({ "kind": "Ssh", "host": "10.0.0.5", "port": 22, "username": "ops",
+                "keyring_id": "putty_rs:prodbox" })
+        .to_string(),
+    )?;
+
+    let bundle = sandbox.path().join("profiles.bundle.json");
+    store.export(&bundle)?;
+    let doc: Value = serde_json::from_str(&fs::read_to_string(&bundle)?)?;
+    assert_eq!(doc["profiles"]["prodbox"]["host"], "10.0.0.5");
+    assert_eq!(doc["profiles"]["prodbox"].get("keyring_id"), None);
+    Ok(())
+}
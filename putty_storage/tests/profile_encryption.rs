@@ -0,0 +1,225 @@
+//! SSH passwords sealed into the profile file by a store opened with a
+//! passphrase, instead of going to the key-ring.
+
+use std::{fs, io::ErrorKind, path::Path};
+
+use putty_storage::{Profile, ProfileStore};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+fn ssh(name: &str, password: &str) -> Profile {
+    Profile::Ssh {
+        name: name.into(),
+        group: None,
+        host: "10.0.0.5".into(),
+        port: 22,
+        username: "ops".into(),
+        password: password.into(),
+        keyring_id: None,
+        key_path: None,
+        passphrase: None,
+        expected_host_key: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    }
+}
+
+fn read(dir: &Path, name: &str) -> Value {
+    serde_json::from_str(&fs::read_to_string(dir.join(format!("{name}.json"))).unwrap()).unwrap()
+}
+
+#[test]
+fn sealed_password_roundtrips_without_touching_the_keyring() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?.with_passphrase("correct horse")?;
+
+    store.save(&ssh("prodbox", "s3cr3t!"))?;
+
+    let doc = read(&dir, "prodbox");
+    let text = doc.to_string();
+    assert!(
+        !text.contains("s3cr3t!"),
+        "password leaked into JSON: {text}"
+    );
+    assert_eq!(doc["keyring_id"], Value::Null);
+    for field in ["salt", "nonce", "ciphertext"] {
+        assert!(doc["sealed_password"][field].is_string(), "{text}");
+    }
+
+    assert_eq!(store.resolve("prodbox")?, ssh("prodbox", "s3cr3t!"));
+    assert_eq!(store.list()?, vec![ssh("prodbox", "s3cr3t!")]);
+
+    assert!(store.delete("prodbox")?);
+    assert!(store.list()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn every_save_uses_a_fresh_nonce_under_the_store_salt() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?.with_passphrase("correct horse")?;
+
+    store.save(&ssh("a", "same"))?;
+    store.save(&ssh("b", "same"))?;
+
+    // One salt per store, so the key is derived once rather than per profile.
+    let (a, b) = (read(&dir, "a"), read(&dir, "b"));
+    assert_eq!(a["sealed_password"]["salt"], b["sealed_password"]["salt"]);
+    assert_ne!(a["sealed_password"]["nonce"], b["sealed_password"]["nonce"]);
+    assert_ne!(
+        a["sealed_password"]["ciphertext"],
+        b["sealed_password"]["ciphertext"]
+    );
+    Ok(())
+}
+
+#[test]
+fn a_new_store_seals_under_the_salt_it_opened() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    ProfileStore::in_dir(dir.clone())?
+        .with_passphrase("correct horse")?
+        .save(&ssh("a", "first"))?;
+
+    let store = ProfileStore::in_dir(dir.clone())?.with_passphrase("correct horse")?;
+    assert_eq!(store.resolve("a")?, ssh("a", "first"));
+    store.save(&ssh("b", "second"))?;
+
+    assert_eq!(
+        read(&dir, "a")["sealed_password"]["salt"],
+        read(&dir, "b")["sealed_password"]["salt"]
+    );
+    assert_eq!(store.list()?, vec![ssh("a", "first"), ssh("b", "second")]);
+    Ok(())
+}
+
+#[test]
+fn wrong_passphrase_is_rejected() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    ProfileStore::in_dir(dir.clone())?
+        .with_passphrase("correct horse")?
+        .save(&ssh("prodbox", "s3cr3t!"))?;
+
+    let err = ProfileStore::in_dir(dir)?
+        .with_passphrase("battery staple")?
+        .resolve("prodbox")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("wrong passphrase"), "{err}");
+    Ok(())
+}
+
+#[test]
+fn store_without_passphrase_leaves_sealed_password_empty() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    ProfileStore::in_dir(dir.clone())?
+        .with_passphrase("correct horse")?
+        .save(&ssh("prodbox", "s3cr3t!"))?;
+
+    assert_eq!(
+        ProfileStore::in_dir(dir)?.resolve("prodbox")?,
+        ssh("prodbox", "")
+    );
+    Ok(())
+}
+
+#[test]
+fn unencrypted_profiles_still_load() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        dir.join("serial.json"),
+        json!({ "kind": "Serial", "name": "serial", "port": "/dev/ttyUSB0", "baud": 115200 })
+            .to_string(),
+    )?;
+    let store = ProfileStore::in_dir(dir)?.with_passphrase("correct horse")?;
+
+    assert_eq!(
+        store.resolve("serial")?,
+        Profile::Serial {
+            name: "serial".into(),
+            group: None,
+            port: "/dev/ttyUSB0".into(),
+            baud: 115200,
+            data_bits: Default::default(),
+            parity: Default::default(),
+            stop_bits: Default::default(),
+            flow_control: Default::default(),
+            init_string: None,
+            max_session_secs: None,
+            banner: None,
+            escape_char: None,
+            escape_exit: None,
+        }
+    );
+    Ok(())
+}
+
+#[test]
+fn empty_passphrase_is_refused() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let err = ProfileStore::in_dir(sandbox.path().join("profiles"))?
+        .with_passphrase("")
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    Ok(())
+}
+
+#[test]
+fn list_skips_profiles_whose_secret_does_not_open() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    ProfileStore::in_dir(dir.clone())?
+        .with_passphrase("battery staple")?
+        .save(&ssh("other", "elsewhere"))?;
+    let store = ProfileStore::in_dir(dir)?.with_passphrase("correct horse")?;
+    store.save(&ssh("prodbox", "s3cr3t!"))?;
+
+    assert_eq!(store.list()?, vec![ssh("prodbox", "s3cr3t!")]);
+    assert_eq!(
+        store.resolve("other").unwrap_err().kind(),
+        ErrorKind::InvalidData
+    );
+    Ok(())
+}
+
+#[test]
+fn saving_without_secrets_keeps_the_sealed_ones() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store =
+        ProfileStore::in_dir(sandbox.path().join("profiles"))?.with_passphrase("correct horse")?;
+    let profile = |port: u16, password: &str, passphrase: Option<&str>| Profile::Ssh {
+        name: "prodbox".into(),
+        group: None,
+        host: "10.0.0.5".into(),
+        port,
+        username: "ops".into(),
+        password: password.into(),
+        keyring_id: None,
+        key_path: Some("/home/ops/.ssh/id_ed25519".into()),
+        passphrase: passphrase.map(Into::into),
+        expected_host_key: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    };
+    store.save(&profile(22, "s3cr3t!", Some("k3y-s3cr3t")))?;
+
+    // What a client that listed the profile without its secrets sends back
+    // after changing the port.
+    store.save(&profile(2222, "", None))?;
+
+    assert_eq!(
+        store.resolve("prodbox")?,
+        profile(2222, "s3cr3t!", Some("k3y-s3cr3t"))
+    );
+    Ok(())
+}
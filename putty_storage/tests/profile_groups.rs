@@ -0,0 +1,113 @@
+//! Profiles filed under a group live in a subdirectory named after it and
+//! are known by their qualified name `group/name`.
+
+use std::{fs, io::ErrorKind};
+
+use putty_storage::{Profile, ProfileStore};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn serial(name: &str, group: Option<&str>, port: &str) -> Profile {
+    Profile::Serial {
+        name: name.into(),
+        group: group.map(Into::into),
+        port: port.into(),
+        baud: 115200,
+        data_bits: Default::default(),
+        parity: Default::default(),
+        stop_bits: Default::default(),
+        flow_control: Default::default(),
+        init_string: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    }
+}
+
+fn sorted(mut profiles: Vec<Profile>) -> Vec<Profile> {
+    profiles.sort_by_key(Profile::qualified_name);
+    profiles
+}
+
+#[test]
+fn names_only_need_to_be_unique_within_a_group() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+
+    let plain = serial("console", None, "/dev/ttyUSB0");
+    let prod = serial("console", Some("prod"), "/dev/ttyUSB1");
+    let lab = serial("console", Some("lab"), "/dev/ttyUSB2");
+    for profile in [&plain, &prod, &lab] {
+        store.save(profile)?;
+    }
+    assert!(dir.join("console.json").is_file());
+    assert!(dir.join("prod").join("console.json").is_file());
+    assert_eq!(prod.qualified_name(), "prod/console");
+
+    assert_eq!(store.resolve("console")?, plain);
+    assert_eq!(store.resolve("prod/console")?, prod);
+    assert_eq!(store.groups()?, vec!["lab", "prod"]);
+    assert_eq!(
+        sorted(store.list()?),
+        vec![plain.clone(), lab.clone(), prod.clone()]
+    );
+    assert_eq!(store.list_group(None)?, vec![plain]);
+    assert_eq!(store.list_group(Some("lab"))?, vec![lab]);
+    assert!(store.list_group(Some("nope"))?.is_empty());
+
+    store.set_default_profile("prod/console")?;
+    assert_eq!(store.default_profile()?.as_deref(), Some("prod/console"));
+
+    // Deleting the last profile of a group drops its directory too.
+    assert!(store.delete("lab/console")?);
+    assert!(!dir.join("lab").exists());
+    assert_eq!(store.groups()?, vec!["prod"]);
+    Ok(())
+}
+
+#[test]
+fn the_directory_decides_the_group() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+
+    // A file moved by hand keeps the group of the directory it is in.
+    fs::create_dir_all(dir.join("lab"))?;
+    fs::write(
+        dir.join("lab").join("bench.json"),
+        json!({ "kind": "Serial", "name": "bench", "group": "prod", "port": "/dev/ttyS0", "baud": 115200 })
+            .to_string(),
+    )?;
+    assert_eq!(
+        store.resolve("lab/bench")?,
+        serial("bench", Some("lab"), "/dev/ttyS0")
+    );
+
+    // Bases are named by qualified name and do not pass on their group.
+    fs::write(
+        dir.join("solo.json"),
+        json!({ "inherits": "lab/bench", "port": "/dev/ttyS1" }).to_string(),
+    )?;
+    assert_eq!(store.resolve("solo")?, serial("solo", None, "/dev/ttyS1"));
+    Ok(())
+}
+
+#[test]
+fn groups_must_be_plain_directory_names() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    for group in ["", "..", "a/b"] {
+        let err = store
+            .save(&serial("console", Some(group), "/dev/ttyUSB0"))
+            .unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput, "{group:?}");
+    }
+    let err = store
+        .save(&serial("prod/console", None, "/dev/ttyUSB0"))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    Ok(())
+}
@@ -0,0 +1,157 @@
+//! Profiles that name a base profile in `"inherits"` only store what differs.
+
+use std::{fs, io::ErrorKind, path::Path};
+
+use putty_storage::{Profile, ProfileStore};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+/// Profiles with inheritance are written by hand, so bypass `save`.
+fn write(dir: &Path, name: &str, doc: Value) {
+    fs::write(dir.join(format!("{name}.json")), doc.to_string()).unwrap();
+}
+
+fn ssh(name: &str, host: &str, port: u16, username: &str) -> Profile {
+    Profile::Ssh {
+        name: name.into(),
+        group: None,
+        host: host.into(),
+        port,
+        username: username.into(),
+        password: String::new(),
+        keyring_id: None,
+        key_path: None,
+        passphrase: None,
+        expected_host_key: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    }
+}
+
+#[test]
+fn single_level_inheritance_overrides_only_what_is_set() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+
+    write(
+        &dir,
+        "fleet",
+        json!({ "kind": "Ssh", "name": "fleet", "host": "bastion", "port": 2222, "username": "ops" }),
+    );
+    write(
+        &dir,
+        "web1",
+        json!({ "inherits": "fleet", "host": "10.0.0.11" }),
+    );
+
+    assert_eq!(
+        store.resolve("web1")?,
+        ssh("web1", "10.0.0.11", 2222, "ops")
+    );
+
+    let mut listed = store.list()?;
+    listed.sort_by(|a, b| a.name().cmp(b.name()));
+    assert_eq!(
+        listed,
+        vec![
+            ssh("fleet", "bastion", 2222, "ops"),
+            ssh("web1", "10.0.0.11", 2222, "ops"),
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+fn multi_level_inheritance_applies_the_nearest_value() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+
+    write(
+        &dir,
+        "base",
+        json!({ "kind": "Ssh", "name": "base", "host": "bastion", "port": 22, "username": "root" }),
+    );
+    write(
+        &dir,
+        "team",
+        json!({ "inherits": "base", "username": "ops", "port": 2222 }),
+    );
+    write(
+        &dir,
+        "db1",
+        json!({ "inherits": "team", "host": "10.0.1.5" }),
+    );
+
+    assert_eq!(store.resolve("db1")?, ssh("db1", "10.0.1.5", 2222, "ops"));
+    assert_eq!(store.resolve("team")?, ssh("team", "bastion", 2222, "ops"));
+    Ok(())
+}
+
+#[test]
+fn inheritance_cycles_are_reported_and_skipped_by_list() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+
+    write(&dir, "a", json!({ "inherits": "b", "host": "a" }));
+    write(&dir, "b", json!({ "inherits": "c" }));
+    write(&dir, "c", json!({ "inherits": "a" }));
+    write(&dir, "self", json!({ "inherits": "self" }));
+
+    let err = store.resolve("a").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidData);
+    assert!(err.to_string().contains("a -> b -> c -> a"), "{err}");
+
+    let err = store.resolve("self").unwrap_err();
+    assert!(err.to_string().contains("self -> self"), "{err}");
+
+    assert!(store.list()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn missing_base_is_not_found() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+
+    write(&dir, "orphan", json!({ "inherits": "gone", "host": "h" }));
+
+    let err = store.resolve("orphan").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(err.to_string().contains("gone"), "{err}");
+
+    assert_eq!(
+        store.resolve("nothing").unwrap_err().kind(),
+        ErrorKind::NotFound
+    );
+    Ok(())
+}
+
+#[test]
+fn inheriting_profile_uses_the_base_password() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?.with_passphrase("correct horse")?;
+
+    let mut fleet = ssh("fleet", "bastion", 22, "ops");
+    if let Profile::Ssh { password, .. } = &mut fleet {
+        *password = "hunter2".into();
+    }
+    store.save(&fleet)?;
+    write(
+        &dir,
+        "web1",
+        json!({ "inherits": "fleet", "host": "10.0.0.11" }),
+    );
+
+    let Profile::Ssh { host, password, .. } = store.resolve("web1")? else {
+        panic!("web1 is an SSH profile");
+    };
+    assert_eq!((host.as_str(), password.as_str()), ("10.0.0.11", "hunter2"));
+    Ok(())
+}
@@ -0,0 +1,114 @@
+//! Renaming profiles and updating them in place.
+
+use std::{fs, io::ErrorKind};
+
+use putty_storage::{Profile, ProfileStore};
+use serde_json::json;
+use tempfile::TempDir;
+
+fn serial(name: &str, group: Option<&str>, port: &str) -> Profile {
+    Profile::Serial {
+        name: name.into(),
+        group: group.map(Into::into),
+        port: port.into(),
+        baud: 115200,
+        data_bits: Default::default(),
+        parity: Default::default(),
+        stop_bits: Default::default(),
+        flow_control: Default::default(),
+        init_string: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    }
+}
+
+#[test]
+fn rename_moves_the_file_and_what_refers_to_it() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+    store.save(&serial("lab", None, "/dev/ttyUSB0"))?;
+    store.set_default_profile("lab")?;
+    fs::write(
+        dir.join("bench.json"),
+        json!({ "inherits": "lab", "baud": 9600 }).to_string(),
+    )?;
+
+    store.rename("lab", "prod/console")?;
+
+    assert!(!dir.join("lab.json").exists());
+    assert_eq!(
+        store.resolve("prod/console")?,
+        serial("console", Some("prod"), "/dev/ttyUSB0")
+    );
+    assert_eq!(store.default_profile()?.as_deref(), Some("prod/console"));
+    let Profile::Serial { port, baud, .. } = store.resolve("bench")? else {
+        panic!("bench should be a serial profile");
+    };
+    assert_eq!((port.as_str(), baud), ("/dev/ttyUSB0", 9600));
+
+    // And back out of the group, which then disappears.
+    store.rename("prod/console", "lab")?;
+    assert!(!dir.join("prod").exists());
+    assert_eq!(store.resolve("lab")?, serial("lab", None, "/dev/ttyUSB0"));
+    Ok(())
+}
+
+#[test]
+fn rename_onto_an_existing_profile_fails() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+    store.save(&serial("lab", None, "/dev/ttyUSB0"))?;
+    store.save(&serial("bench", None, "/dev/ttyUSB1"))?;
+
+    let err = store.rename("lab", "bench").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+    assert_eq!(store.resolve("lab")?, serial("lab", None, "/dev/ttyUSB0"));
+    assert_eq!(
+        store.resolve("bench")?,
+        serial("bench", None, "/dev/ttyUSB1")
+    );
+    Ok(())
+}
+
+#[test]
+fn rename_of_a_missing_profile_fails() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    let err = store.rename("nope", "still-nope").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(store.list()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn rename_to_an_invalid_name_fails() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+    store.save(&serial("lab", None, "/dev/ttyUSB0"))?;
+
+    let err = store.rename("lab", "../lab").unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert_eq!(store.resolve("lab")?, serial("lab", None, "/dev/ttyUSB0"));
+    Ok(())
+}
+
+#[test]
+fn update_only_overwrites_existing_profiles() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    let err = store
+        .update(&serial("lab", None, "/dev/ttyUSB0"))
+        .unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert!(store.list()?.is_empty());
+
+    store.save(&serial("lab", None, "/dev/ttyUSB0"))?;
+    store.update(&serial("lab", None, "/dev/ttyUSB1"))?;
+    assert_eq!(store.resolve("lab")?, serial("lab", None, "/dev/ttyUSB1"));
+    Ok(())
+}
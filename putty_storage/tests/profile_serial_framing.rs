@@ -0,0 +1,73 @@
+//! Serial profiles keep their data bits, parity, stop bits and flow control.
+
+use std::fs;
+
+use putty_core::connections::{DataBits, FlowControl, Framing, Parity, StopBits};
+use putty_storage::{Profile, ProfileStore};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+#[test]
+fn framing_is_saved_and_loaded() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+    let modem = Profile::Serial {
+        name: "modem".into(),
+        group: None,
+        port: "/dev/ttyS0".into(),
+        baud: 9600,
+        data_bits: DataBits::Seven,
+        parity: Parity::Even,
+        stop_bits: StopBits::Two,
+        flow_control: FlowControl::Hardware,
+        init_string: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    };
+
+    store.save(&modem)?;
+
+    let doc: Value = serde_json::from_str(&fs::read_to_string(dir.join("modem.json"))?)?;
+    assert_eq!(doc["data_bits"], 7);
+    assert_eq!(doc["parity"], "even");
+    assert_eq!(doc["stop_bits"], 2);
+    assert_eq!(doc["flow_control"], "hardware");
+    assert_eq!(store.resolve("modem")?, modem);
+    Ok(())
+}
+
+#[test]
+fn files_without_framing_load_as_8n1() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        dir.join("lab.json"),
+        json!({ "kind": "Serial", "name": "lab", "port": "/dev/ttyUSB0", "baud": 115200 })
+            .to_string(),
+    )?;
+    let store = ProfileStore::in_dir(dir)?;
+
+    assert_eq!(store.resolve("lab")?.framing(), Some(Framing::default()));
+    Ok(())
+}
+
+#[test]
+fn impossible_framing_is_rejected_on_load() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        dir.join("lab.json"),
+        json!({ "kind": "Serial", "name": "lab", "port": "/dev/ttyUSB0", "baud": 115200, "data_bits": 9 })
+            .to_string(),
+    )?;
+    let store = ProfileStore::in_dir(dir)?;
+
+    let err = store.resolve("lab").unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    Ok(())
+}
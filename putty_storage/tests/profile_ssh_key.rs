@@ -0,0 +1,78 @@
+//! SSH profiles that log in with a private key: the key path is plain JSON,
+//! its passphrase is a secret like the password.
+
+use std::{fs, path::PathBuf};
+
+use putty_storage::{Profile, ProfileStore};
+use serde_json::{json, Value};
+use tempfile::TempDir;
+
+fn ssh(key_path: Option<&str>, passphrase: Option<&str>) -> Profile {
+    Profile::Ssh {
+        name: "prodbox".into(),
+        group: None,
+        host: "10.0.0.5".into(),
+        port: 22,
+        username: "ops".into(),
+        password: String::new(),
+        keyring_id: None,
+        key_path: key_path.map(PathBuf::from),
+        passphrase: passphrase.map(Into::into),
+        expected_host_key: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    }
+}
+
+#[test]
+fn key_path_is_stored_and_passphrase_is_sealed() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?.with_passphrase("correct horse")?;
+    let profile = ssh(Some("/home/ops/.ssh/id_ed25519"), Some("k3y-s3cr3t"));
+
+    store.save(&profile)?;
+
+    let text = fs::read_to_string(dir.join("prodbox.json"))?;
+    assert!(!text.contains("k3y-s3cr3t"), "passphrase leaked: {text}");
+    let doc: Value = serde_json::from_str(&text)?;
+    assert_eq!(doc["key_path"], "/home/ops/.ssh/id_ed25519");
+    assert!(doc["sealed_passphrase"]["ciphertext"].is_string(), "{text}");
+    assert_eq!(doc.get("sealed_password"), None);
+
+    assert_eq!(store.resolve("prodbox")?, profile);
+    Ok(())
+}
+
+#[test]
+fn key_without_passphrase_roundtrips() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store =
+        ProfileStore::in_dir(sandbox.path().join("profiles"))?.with_passphrase("correct horse")?;
+    let profile = ssh(Some("/home/ops/.ssh/id_ed25519"), None);
+
+    store.save(&profile)?;
+    assert_eq!(store.resolve("prodbox")?, profile);
+    Ok(())
+}
+
+#[test]
+fn password_only_profiles_still_load() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    fs::create_dir_all(&dir)?;
+    fs::write(
+        dir.join("prodbox.json"),
+        json!({
+            "kind": "Ssh", "name": "prodbox", "host": "10.0.0.5", "port": 22,
+            "username": "ops", "keyring_id": null,
+        })
+        .to_string(),
+    )?;
+    let store = ProfileStore::in_dir(dir)?.with_passphrase("correct horse")?;
+
+    assert_eq!(store.resolve("prodbox")?, ssh(None, None));
+    Ok(())
+}
@@ -23,11 +23,19 @@ fn profile_store_roundtrip_default_backend() -> anyhow::Result<()> {
 
     store.save(&Profile::Ssh {
         name: profile_name.clone(),
+        group: None,
         host: "host".into(),
         port: 22,
         username: "user".into(),
         password: pw.into(),
         keyring_id: None,
+        key_path: None,
+        passphrase: None,
+        expected_host_key: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
     })?;
 
     let json_path: PathBuf = profiles_dir.join(format!("{profile_name}.json"));
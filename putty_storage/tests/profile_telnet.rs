@@ -0,0 +1,36 @@
+//! Telnet profiles are plain JSON like serial ones; nothing goes to the
+//! key-ring.
+
+use std::fs;
+
+use putty_storage::{Profile, ProfileStore};
+use serde_json::Value;
+use tempfile::TempDir;
+
+#[test]
+fn telnet_profile_is_saved_and_loaded() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let dir = sandbox.path().join("profiles");
+    let store = ProfileStore::in_dir(dir.clone())?;
+    let switch = Profile::Telnet {
+        name: "switch".into(),
+        group: Some("lab".into()),
+        host: "10.0.0.9".into(),
+        port: 2023,
+        max_session_secs: None,
+        banner: None,
+        escape_char: Some('\u{2}'),
+        escape_exit: None,
+    };
+
+    store.save(&switch)?;
+
+    let doc: Value = serde_json::from_str(&fs::read_to_string(dir.join("lab/switch.json"))?)?;
+    assert_eq!(doc["kind"], "Telnet");
+    assert_eq!(doc["host"], "10.0.0.9");
+    assert_eq!(doc["port"], 2023);
+    assert_eq!(store.resolve("lab/switch")?, switch);
+    assert_eq!(switch.framing(), None);
+    assert_eq!(switch.escape_keys(), (Some('\u{2}'), None));
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Profiles with impossible settings are refused before they hit the disk.
+
+use putty_storage::{Profile, ProfileStore};
+use std::io::ErrorKind;
+use tempfile::TempDir;
+
+fn serial(baud: u32) -> Profile {
+    Profile::Serial {
+        name: "lab".into(),
+        group: None,
+        port: "/dev/ttyUSB0".into(),
+        baud,
+        data_bits: Default::default(),
+        parity: Default::default(),
+        stop_bits: Default::default(),
+        flow_control: Default::default(),
+        init_string: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    }
+}
+
+#[test]
+fn save_rejects_absurd_baud() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    let err = store.save(&serial(1_000_000_000)).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(store.list()?.is_empty());
+
+    store.save(&serial(9600))?;
+    assert_eq!(store.list()?, vec![serial(9600)]);
+    Ok(())
+}
+
+#[test]
+fn save_rejects_malformed_init_string() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    let profile = Profile::Serial {
+        name: "modem".into(),
+        group: None,
+        port: "/dev/ttyUSB0".into(),
+        baud: 9600,
+        data_bits: Default::default(),
+        parity: Default::default(),
+        stop_bits: Default::default(),
+        flow_control: Default::default(),
+        init_string: Some("ATZ\\q".into()),
+        max_session_secs: None,
+        banner: None,
+        escape_char: None,
+        escape_exit: None,
+    };
+    let err = store.save(&profile).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(store.list()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn save_rejects_malformed_banner() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    let profile = Profile::Telnet {
+        name: "switch".into(),
+        group: None,
+        host: "10.0.0.9".into(),
+        port: 23,
+        max_session_secs: None,
+        banner: Some("=== rack1 ===\\x4".into()),
+        escape_char: None,
+        escape_exit: None,
+    };
+    let err = store.save(&profile).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(store.list()?.is_empty());
+
+    let mut lab = serial(9600);
+    if let Profile::Serial { banner, .. } = &mut lab {
+        *banner = Some("=== rack1 ===\\r\\n".into());
+    }
+    store.save(&lab)?;
+    assert_eq!(store.list()?, vec![lab]);
+    Ok(())
+}
+
+#[test]
+fn save_rejects_non_ascii_escape_keys() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    let profile = Profile::Serial {
+        name: "lab".into(),
+        group: None,
+        port: "/dev/ttyUSB0".into(),
+        baud: 9600,
+        data_bits: Default::default(),
+        parity: Default::default(),
+        stop_bits: Default::default(),
+        flow_control: Default::default(),
+        init_string: None,
+        max_session_secs: None,
+        banner: None,
+        escape_char: Some('§'),
+        escape_exit: Some('x'),
+    };
+    let err = store.save(&profile).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(store.list()?.is_empty());
+    Ok(())
+}
+
+#[test]
+fn save_rejects_escape_key_equal_to_exit_key() -> anyhow::Result<()> {
+    let sandbox = TempDir::new()?;
+    let store = ProfileStore::in_dir(sandbox.path().join("profiles"))?;
+
+    let mut profile = serial(9600);
+    if let Profile::Serial { escape_char, .. } = &mut profile {
+        // Clashes with the default exit key.
+        *escape_char = Some('x');
+    }
+    let err = store.save(&profile).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    assert!(store.list()?.is_empty());
+    Ok(())
+}
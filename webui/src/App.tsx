@@ -22,6 +22,7 @@ export default function App() {
   const {
     connId, connecting, connect, stop, termRef,
     listProfiles, saveSerial, saveSsh, deleteProfile, connectProfile,
+    listSerialPorts,
   } = useGrpc();
 
   const [showProfiles, setShowProfiles] = useState(false);
@@ -37,6 +38,7 @@ export default function App() {
         connect={connect}
         stop={stop}                    /* NEW */
         openProfiles={() => setShowProfiles(true)}
+        listSerialPorts={listSerialPorts}
       />
 
       <div className="term-wrapper">
@@ -8,6 +8,13 @@ import type { ListProfilesFn,
               DeleteProfileFn,
               ConnectProfileFn } from "./useGrpc";
 
+/* Kind column, by ProfileReq.kind case */
+const KIND_LABELS: Record<string, string> = {
+  serial: "Serial",
+  ssh:    "SSH",
+  telnet: "Telnet",
+};
+
 /* ---------------- props ------------------------------------------- */
 interface Props {
   show: boolean;
@@ -94,7 +101,7 @@ export default function ProfilesModal(p: Props) {
           <tbody>
             {profiles.map(p=><tr key={p.name}>
               <td>{p.name}</td>
-              <td>{"serial" in p.kind! ? "Serial" : "SSH"}</td>
+              <td>{KIND_LABELS[p.kind.case ?? ""] ?? "?"}</td>
               <td className="actions">
                 <button onClick={()=>handleApply(p.name!)}>use</button>
                 <button onClick={()=>handleDelete(p.name!)}>del</button>
@@ -1,4 +1,8 @@
-import type { Mode, SerialCfg, SshCfg } from "./useGrpc";
+import { useEffect, useState } from "react";
+import { Code, ConnectError } from "@connectrpc/connect";
+
+import type { Mode, SerialCfg, SshCfg, ListSerialPortsFn } from "./useGrpc";
+import type { SerialPortInfo } from "../generated/putty_interface_pb";
 
 /* props contract */
 interface Props {
@@ -14,6 +18,15 @@ interface Props {
   connect: (mode: Mode, cfg: SerialCfg|SshCfg) => void;
   stop: () => void;                     /* NEW */
   openProfiles: () => void;
+  listSerialPorts: ListSerialPortsFn;
+}
+
+/* "FTDI FT232R (0403:6001)" for USB adapters, nothing for other ports */
+function portLabel(p: SerialPortInfo) {
+  if (!p.usb) return "";
+  const hex = (n: number) => n.toString(16).padStart(4, "0");
+  const text = [p.manufacturer, p.product].filter(Boolean).join(" ");
+  return `${text} (${hex(p.vid)}:${hex(p.pid)})`.trim();
 }
 
 /* presentational toolbar -------------------------------------------------- */
@@ -23,9 +36,32 @@ export default function Toolbar(p: Props) {
     serialCfg, setSerialCfg,
     sshCfg,    setSshCfg,
     connecting, connected,
-    connect, stop, openProfiles,
+    connect, stop, openProfiles, listSerialPorts,
   } = p;
 
+  /* ports for the dropdown; the note says why the list is empty ------ */
+  const [ports,     setPorts]     = useState<SerialPortInfo[]>([]);
+  const [portsNote, setPortsNote] = useState("");
+
+  function refreshPorts() {
+    listSerialPorts()
+      .then(found => {
+        setPorts(found);
+        setPortsNote(found.length ? "" : "no serial ports found");
+      })
+      .catch(e => {
+        setPorts([]);
+        setPortsNote(
+          e instanceof ConnectError && e.code === Code.PermissionDenied
+            ? "not allowed to list serial ports"
+            : `listing serial ports failed: ${e}`);
+      });
+  }
+
+  useEffect(()=>{
+    if (mode === "serial") refreshPorts();
+  },[mode]);
+
   return (
     <div className="toolbar">
       <label>Mode&nbsp;
@@ -37,9 +73,15 @@ export default function Toolbar(p: Props) {
 
       {mode==="serial" && <>
         <label>Port&nbsp;
-          <input value={serialCfg.port}
+          <input list="serial-ports" value={serialCfg.port} title={portsNote}
                  onChange={e=>setSerialCfg({...serialCfg, port:e.target.value})}/>
+          <datalist id="serial-ports">
+            {ports.map(port =>
+              <option key={port.name} value={port.name}>{portLabel(port)}</option>)}
+          </datalist>
         </label>
+        <button onClick={refreshPorts} title="rescan serial ports">↻</button>
+        {portsNote && <span>{portsNote}</span>}
         <label>Baud&nbsp;
           <input type="number" value={serialCfg.baud}
                  onChange={e=>setSerialCfg({...serialCfg,
@@ -4,7 +4,7 @@ import { createClient } from "@connectrpc/connect";
 
 import {
   CreateRequest, Serial, Ssh, WriteRequest,
-  ConnectionId, ProfileReq, ProfileName, Empty,
+  ConnectionId, ProfileReq, ProfileName, Empty, SerialPortInfo,
 } from "../generated/putty_interface_pb.ts";
 import { RemoteConnection } from "../generated/putty_interface_connect.ts";
 
@@ -19,6 +19,7 @@ export type SaveSerialFn     = (name:string,cfg:SerialCfg)=>Promise<void>;
 export type SaveSshFn        = (name:string,cfg:SshCfg)   =>Promise<void>;
 export type DeleteProfileFn  = (name:string)=>Promise<void>;
 export type ConnectProfileFn = (name:string)=>Promise<string|undefined>;
+export type ListSerialPortsFn = () => Promise<SerialPortInfo[]>;
 
 /* ---------- gRPC-Web transport ---------------------------------- */
 const GRPC_PORT = 50051;                          // keep in one place
@@ -107,6 +108,11 @@ export default function useGrpc() {
     await rpc.deleteProfile(new ConnectionId({ id:name }));
   }
 
+  /* serial ports of the server machine --------------------------- */
+  async function listSerialPorts() {
+    return (await rpc.listSerialPorts(new Empty())).ports;
+  }
+
   /* shared opener ------------------------------------------------ */
   async function openRequest(req: CreateRequest) {
     if (connecting || connId) return;
@@ -138,6 +144,6 @@ export default function useGrpc() {
 
   return {
     connId, connecting, connect, connectProfile, stop, write, termRef,
-    listProfiles, saveSerial, saveSsh, deleteProfile,
+    listProfiles, saveSerial, saveSsh, deleteProfile, listSerialPorts,
   };
 }